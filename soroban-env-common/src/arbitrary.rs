@@ -1,12 +1,33 @@
 //! Implementations of [`Arbitrary`] for contract types.
+//!
+//! The [`Arbitrary`] implementations of the XDR types generate values that
+//! the host rejects most of the time (e.g. maps with unsorted keys or symbols
+//! with invalid characters). The `Valid*` wrappers below only generate values
+//! that respect the host's validity constraints, for use by fuzz targets.
 
 extern crate alloc;
 
 use crate::symbol::MAX_SMALL_CHARS;
-use crate::xdr::ScError;
+use crate::xdr::{
+    Duration, InvokeContractArgs, ScBytes, ScError, ScMap, ScMapEntry, ScString, ScSymbol, ScVal,
+    ScVec, TimePoint, SCSYMBOL_LIMIT,
+};
 use crate::{Error, StorageType, Symbol, SymbolSmall, Val, Void};
+use alloc::vec::Vec;
 use arbitrary::{Arbitrary, Unstructured};
 
+const SYMBOL_CHARS: [char; 63] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L',
+    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4',
+    '5', '6', '7', '8', '9', '_',
+];
+
+// Nesting depth and length bounds of the generated containers, which keep the
+// generated values well within the host's limits.
+const MAX_DEPTH: usize = 4;
+const MAX_LEN: usize = 8;
+
 impl<'a> Arbitrary<'a> for Error {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let scerror = ScError::arbitrary(u)?;
@@ -34,12 +55,7 @@ impl<'a> Arbitrary<'a> for Symbol {
         let len: usize = u.int_in_range(0..=MAX_SMALL_CHARS)?;
         let mut buf = [0u8; MAX_SMALL_CHARS];
         for i in 0..len {
-            buf[i] = (*u.choose(&[
-                'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p',
-                'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F',
-                'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V',
-                'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '_',
-            ])?) as u8;
+            buf[i] = (*u.choose(&SYMBOL_CHARS)?) as u8;
         }
         let small =
             SymbolSmall::try_from(&buf[0..len]).map_err(|_| arbitrary::Error::IncorrectFormat)?;
@@ -61,3 +77,143 @@ impl<'a> Arbitrary<'a> for StorageType {
         <usize as Arbitrary>::size_hint(depth)
     }
 }
+
+/// An [`ScSymbol`] of valid characters and length.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidScSymbol(pub ScSymbol);
+
+/// An [`ScVal`] that can be converted to a host value: it contains no
+/// storage-only values (such as contract instances or ledger keys), and all
+/// its symbols and maps are valid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidScVal(pub ScVal);
+
+/// An [`ScVec`] of [`ValidScVal`]s.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidScVec(pub ScVec);
+
+/// An [`ScMap`] of [`ValidScVal`]s, with its keys sorted in the host's order
+/// and unique.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidScMap(pub ScMap);
+
+/// The [`InvokeContractArgs`] of a contract call with a valid function name
+/// and arguments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidInvokeContractArgs(pub InvokeContractArgs);
+
+fn arbitrary_scsymbol(u: &mut Unstructured<'_>) -> arbitrary::Result<ScSymbol> {
+    let len: usize = u.int_in_range(0..=SCSYMBOL_LIMIT as usize)?;
+    let mut buf = Vec::with_capacity(len);
+    for _ in 0..len {
+        buf.push((*u.choose(&SYMBOL_CHARS)?) as u8);
+    }
+    Ok(ScSymbol(
+        buf.try_into()
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+    ))
+}
+
+fn arbitrary_len(u: &mut Unstructured<'_>) -> arbitrary::Result<usize> {
+    u.int_in_range(0..=MAX_LEN)
+}
+
+fn arbitrary_scval(u: &mut Unstructured<'_>, depth: usize) -> arbitrary::Result<ScVal> {
+    // The containers come last, so they can be excluded at the maximum depth.
+    let max_kind = if depth == 0 { 16 } else { 18 };
+    Ok(match u.int_in_range(0..=max_kind)? {
+        0 => ScVal::Bool(u.arbitrary()?),
+        1 => ScVal::Void,
+        2 => ScVal::Error(u.arbitrary()?),
+        3 => ScVal::U32(u.arbitrary()?),
+        4 => ScVal::I32(u.arbitrary()?),
+        5 => ScVal::U64(u.arbitrary()?),
+        6 => ScVal::I64(u.arbitrary()?),
+        7 => ScVal::Timepoint(TimePoint(u.arbitrary()?)),
+        8 => ScVal::Duration(Duration(u.arbitrary()?)),
+        9 => ScVal::U128(u.arbitrary()?),
+        10 => ScVal::I128(u.arbitrary()?),
+        11 => ScVal::U256(u.arbitrary()?),
+        12 => ScVal::I256(u.arbitrary()?),
+        13 => ScVal::Bytes(ScBytes(
+            Vec::<u8>::arbitrary(u)?
+                .try_into()
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+        )),
+        14 => ScVal::String(ScString(
+            Vec::<u8>::arbitrary(u)?
+                .try_into()
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+        )),
+        15 => ScVal::Symbol(arbitrary_scsymbol(u)?),
+        16 => ScVal::Address(u.arbitrary()?),
+        17 => ScVal::Vec(Some(arbitrary_scvec(u, depth - 1)?)),
+        _ => ScVal::Map(Some(arbitrary_scmap(u, depth - 1)?)),
+    })
+}
+
+fn arbitrary_scvec(u: &mut Unstructured<'_>, depth: usize) -> arbitrary::Result<ScVec> {
+    let len = arbitrary_len(u)?;
+    let mut vals = Vec::with_capacity(len);
+    for _ in 0..len {
+        vals.push(arbitrary_scval(u, depth)?);
+    }
+    Ok(ScVec(
+        vals.try_into()
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+    ))
+}
+
+fn arbitrary_scmap(u: &mut Unstructured<'_>, depth: usize) -> arbitrary::Result<ScMap> {
+    let len = arbitrary_len(u)?;
+    let mut entries = Vec::with_capacity(len);
+    for _ in 0..len {
+        entries.push(ScMapEntry {
+            key: arbitrary_scval(u, depth)?,
+            val: arbitrary_scval(u, depth)?,
+        });
+    }
+    // The host orders the representable values the same way as the derived
+    // `Ord` of `ScVal`.
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    entries.dedup_by(|a, b| a.key == b.key);
+    Ok(ScMap(
+        entries
+            .try_into()
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+    ))
+}
+
+impl<'a> Arbitrary<'a> for ValidScSymbol {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_scsymbol(u).map(ValidScSymbol)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ValidScVal {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_scval(u, MAX_DEPTH).map(ValidScVal)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ValidScVec {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_scvec(u, MAX_DEPTH - 1).map(ValidScVec)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ValidScMap {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_scmap(u, MAX_DEPTH - 1).map(ValidScMap)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ValidInvokeContractArgs {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(ValidInvokeContractArgs(InvokeContractArgs {
+            contract_address: u.arbitrary()?,
+            function_name: arbitrary_scsymbol(u)?,
+            args: arbitrary_scvec(u, MAX_DEPTH - 1)?.0,
+        }))
+    }
+}
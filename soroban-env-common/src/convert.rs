@@ -296,6 +296,14 @@ impl<E: Env> TryFromVal<E, Val> for I256 {
     }
 }
 
+impl<E: Env> TryFromVal<E, I256Val> for I256 {
+    type Error = crate::Error;
+
+    fn try_from_val(env: &E, v: &I256Val) -> Result<Self, Self::Error> {
+        Self::try_from_val(env, &v.to_val())
+    }
+}
+
 impl<E: Env> TryFromVal<E, I256> for Val {
     type Error = crate::Error;
 
@@ -340,6 +348,14 @@ impl<E: Env> TryFromVal<E, Val> for U256 {
     }
 }
 
+impl<E: Env> TryFromVal<E, U256Val> for U256 {
+    type Error = crate::Error;
+
+    fn try_from_val(env: &E, v: &U256Val) -> Result<Self, Self::Error> {
+        Self::try_from_val(env, &v.to_val())
+    }
+}
+
 impl<E: Env> TryFromVal<E, U256> for Val {
     type Error = crate::Error;
 
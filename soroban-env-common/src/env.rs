@@ -4,9 +4,9 @@ use crate::Object;
 
 use super::Symbol;
 use super::{
-    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I256Object, I256Val,
-    I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject, U128Object,
-    U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
+    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I128Val, I256Object,
+    I256Val, I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject,
+    U128Object, U128Val, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
 };
 use crate::xdr::{ScErrorCode, ScErrorType};
 
@@ -250,7 +250,9 @@ impl_checkedenvarg_for_val_or_wrapper!(U128Object);
 impl_checkedenvarg_for_val_or_wrapper!(U256Object);
 
 impl_checkedenvarg_for_val_or_wrapper!(U64Val);
+impl_checkedenvarg_for_val_or_wrapper!(U128Val);
 impl_checkedenvarg_for_val_or_wrapper!(U256Val);
+impl_checkedenvarg_for_val_or_wrapper!(I128Val);
 impl_checkedenvarg_for_val_or_wrapper!(I256Val);
 
 impl_checkedenvarg_for_val_or_wrapper!(Void);
@@ -332,7 +334,7 @@ macro_rules! generate_env_trait {
                     // pattern-repetition matcher so that it will match all such
                     // descriptions.
                     $(#[$fn_attr:meta])*
-                    { $fn_str:literal, fn $fn_id:ident $args:tt -> $ret:ty }
+                    { $fn_str:literal, $($min_proto:literal)?, fn $fn_id:ident $args:tt -> $ret:ty }
                 )*
             }
         )*
@@ -152,6 +152,11 @@ impl From<ConversionError> for Error {
     }
 }
 
+// The codes produced here surface in contract-visible errors and in ledger
+// results, so they are part of the protocol: distinguishing more xdr::Error
+// variants (or mapping further Rust error types with `From`, which makes them
+// reachable through any `?`) would change the outcome of existing
+// transactions and needs a protocol-gated call site instead.
 impl From<crate::xdr::Error> for Error {
     fn from(e: crate::xdr::Error) -> Self {
         match e {
@@ -415,4 +420,38 @@ mod tests {
 
         assert_eq!(pairs_xdr_sorted, pairs_host_sorted);
     }
+
+    #[test]
+    fn xdr_errors_map_to_stable_codes() {
+        for (xdr_err, type_, code) in [
+            (
+                crate::xdr::Error::DepthLimitExceeded,
+                ScErrorType::Context,
+                ScErrorCode::ExceededLimit,
+            ),
+            (
+                crate::xdr::Error::LengthLimitExceeded,
+                ScErrorType::Context,
+                ScErrorCode::ExceededLimit,
+            ),
+            (
+                crate::xdr::Error::LengthExceedsMax,
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+            ),
+            (
+                crate::xdr::Error::LengthMismatch,
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+            ),
+            (
+                crate::xdr::Error::Invalid,
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+            ),
+        ] {
+            let err = Error::from(xdr_err);
+            assert!(err.is_type(type_) && err.is_code(code));
+        }
+    }
 }
@@ -0,0 +1,113 @@
+//! This module provides a machine-readable description of the [Env](crate::Env)
+//! interface: every host function, grouped by module, with its export name,
+//! argument and return types, documentation and the protocol version it was
+//! introduced in. It is generated from the same `env.json` file that defines
+//! the [Env](crate::Env) trait, so tools that generate bindings or display the
+//! interface (SDKs, explorers) can stay in sync with it without parsing that
+//! file themselves.
+
+use soroban_env_macros::generate_env_interface_description;
+
+/// A module of host functions, as imported by contracts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HostModuleDescription {
+    /// The name of the module, such as `"context"`.
+    pub name: &'static str,
+    /// The (short) name the module is imported under in WASM.
+    pub export: &'static str,
+    pub functions: &'static [HostFunctionDescription],
+}
+
+/// A single host function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HostFunctionDescription {
+    /// The name of the function, as it appears on the [Env](crate::Env) trait.
+    pub name: &'static str,
+    /// The (short) name the function is imported under in WASM, within its
+    /// module.
+    pub export: &'static str,
+    pub args: &'static [HostFunctionArgDescription],
+    /// The name of the return type, such as `"U32Val"`.
+    pub r#return: &'static str,
+    /// Documentation of the function, possibly empty.
+    pub docs: &'static str,
+    /// The first ledger protocol version providing this function, or `None`
+    /// if it has been available since the first protocol supporting soroban.
+    pub min_supported_protocol: Option<u32>,
+}
+
+/// A single argument of a host function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HostFunctionArgDescription {
+    pub name: &'static str,
+    /// The name of the argument type, such as `"Val"` or `"BytesObject"`.
+    pub r#type: &'static str,
+}
+
+impl HostModuleDescription {
+    /// Look up a function in this module by its name.
+    pub fn function(&self, name: &str) -> Option<&'static HostFunctionDescription> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+}
+
+impl HostFunctionDescription {
+    /// Whether this function is provided by a host running the given ledger
+    /// protocol version.
+    pub fn is_supported_in_protocol(&self, protocol: u32) -> bool {
+        self.min_supported_protocol.map_or(true, |p| p <= protocol)
+    }
+}
+
+const ENV_INTERFACE: &[HostModuleDescription] = generate_env_interface_description!("env.json");
+
+/// Returns the description of every module of host functions in the
+/// [Env](crate::Env) interface, in the order they are defined.
+pub fn env_interface() -> &'static [HostModuleDescription] {
+    ENV_INTERFACE
+}
+
+/// Look up a module of the [Env](crate::Env) interface by its name.
+pub fn env_interface_module(name: &str) -> Option<&'static HostModuleDescription> {
+    ENV_INTERFACE.iter().find(|m| m.name == name)
+}
+
+#[test]
+fn test_env_interface() {
+    let ctx = env_interface_module("context").unwrap();
+    assert_eq!(ctx.export, "x");
+    let f = ctx.function("get_ledger_version").unwrap();
+    assert_eq!(f.export, "2");
+    assert!(f.args.is_empty());
+    assert_eq!(f.r#return, "U32Val");
+    assert_eq!(
+        f.docs,
+        "Return the protocol version of the current ledger as a u32."
+    );
+    assert!(f.is_supported_in_protocol(20));
+
+    let f = env_interface_module("buf")
+        .unwrap()
+        .function("bytes_copy_from_linear_memory")
+        .unwrap();
+    assert!(f
+        .args
+        .iter()
+        .map(|a| a.name)
+        .eq(["b", "b_pos", "lm_pos", "len"]));
+
+    let f = env_interface_module("ledger")
+        .unwrap()
+        .function("take_contract_data")
+        .unwrap();
+    assert_eq!(f.min_supported_protocol, Some(21));
+    assert!(!f.is_supported_in_protocol(20));
+    assert!(f.is_supported_in_protocol(21));
+
+    // Export names are unique within each module.
+    for m in env_interface() {
+        for (i, f) in m.functions.iter().enumerate() {
+            assert!(m.functions[..i].iter().all(|g| g.export != f.export));
+        }
+    }
+}
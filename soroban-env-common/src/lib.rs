@@ -60,6 +60,8 @@ mod wrapper_macros;
 
 #[cfg(feature = "testutils")]
 mod arbitrary;
+#[cfg(feature = "testutils")]
+pub use arbitrary::{ValidInvokeContractArgs, ValidScMap, ValidScSymbol, ValidScVal, ValidScVec};
 mod bytes;
 mod compare;
 mod convert;
@@ -79,6 +81,7 @@ mod vmcaller_env;
 
 // We have some modules that we don't re-export everything
 // from because only specific users are likely to use them.
+pub mod interface;
 pub mod meta;
 pub mod num;
 pub use num::{
@@ -102,7 +105,7 @@ pub use stellar_xdr::curr as xdr;
 pub use stellar_xdr::next as xdr;
 
 // Val is the 64-bit transparent type.
-pub use val::{ConversionError, Tag, Val};
+pub use val::{ConversionError, ObjectType, Tag, Val, ValKind};
 
 #[cfg(feature = "wasmi")]
 pub use val::WasmiMarshal;
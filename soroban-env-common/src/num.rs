@@ -442,6 +442,116 @@ pub fn i256_into_pieces(i: I256) -> (i64, u64, u64, u64) {
     (hi_hi, hi_lo, lo_hi, lo_lo)
 }
 
+pub fn u256_from_parts(u: &UInt256Parts) -> U256 {
+    u256_from_pieces(u.hi_hi, u.hi_lo, u.lo_hi, u.lo_lo)
+}
+
+pub fn u256_into_parts(u: U256) -> UInt256Parts {
+    let (hi_hi, hi_lo, lo_hi, lo_lo) = u256_into_pieces(u);
+    UInt256Parts {
+        hi_hi,
+        hi_lo,
+        lo_hi,
+        lo_lo,
+    }
+}
+
+pub fn i256_from_parts(i: &Int256Parts) -> I256 {
+    i256_from_pieces(i.hi_hi, i.hi_lo, i.lo_hi, i.lo_lo)
+}
+
+pub fn i256_into_parts(i: I256) -> Int256Parts {
+    let (hi_hi, hi_lo, lo_hi, lo_lo) = i256_into_pieces(i);
+    Int256Parts {
+        hi_hi,
+        hi_lo,
+        lo_hi,
+        lo_lo,
+    }
+}
+
+// ScVal conversions. These can't be `From` impls since both `ScVal` and the
+// ethnum types are foreign to this crate.
+
+pub fn u256_into_scval(u: U256) -> ScVal {
+    ScVal::U256(u256_into_parts(u))
+}
+
+pub fn u256_from_scval(v: &ScVal) -> Result<U256, ConversionError> {
+    match v {
+        ScVal::U256(u) => Ok(u256_from_parts(u)),
+        _ => Err(ConversionError),
+    }
+}
+
+pub fn i256_into_scval(i: I256) -> ScVal {
+    ScVal::I256(i256_into_parts(i))
+}
+
+pub fn i256_from_scval(v: &ScVal) -> Result<I256, ConversionError> {
+    match v {
+        ScVal::I256(i) => Ok(i256_from_parts(i)),
+        _ => Err(ConversionError),
+    }
+}
+
+// String conversions. Both decimal and `0x`-prefixed hexadecimal are accepted
+// when parsing, with a leading `-` for negative signed values; no other signs,
+// whitespace or separators are.
+
+pub fn u256_from_str(s: &str) -> Result<U256, ConversionError> {
+    let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (s, 10),
+    };
+    if digits.starts_with(['+', '-']) {
+        return Err(ConversionError);
+    }
+    U256::from_str_radix(digits, radix).map_err(|_| ConversionError)
+}
+
+pub fn i256_from_str(s: &str) -> Result<I256, ConversionError> {
+    let (negative, magnitude) = match s.strip_prefix('-') {
+        Some(m) => (true, u256_from_str(m)?),
+        None => (false, u256_from_str(s)?),
+    };
+    if negative {
+        if magnitude > I256::MIN.unsigned_abs() {
+            return Err(ConversionError);
+        }
+        Ok(magnitude.as_i256().wrapping_neg())
+    } else {
+        if magnitude > I256::MAX.as_u256() {
+            return Err(ConversionError);
+        }
+        Ok(magnitude.as_i256())
+    }
+}
+
+/// Formats a [U256] as `0x`-prefixed lowercase hexadecimal, in the form
+/// accepted by [u256_from_str]. Decimal formatting is provided by [U256]'s
+/// own `Display` impl.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct U256Hex(pub U256);
+
+impl core::fmt::Display for U256Hex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+/// Formats an [I256] as `0x`-prefixed lowercase hexadecimal of its magnitude,
+/// preceded by `-` when negative, in the form accepted by [i256_from_str].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct I256Hex(pub I256);
+
+impl core::fmt::Display for I256Hex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let sign = if self.0.is_negative() { "-" } else { "" };
+        write!(f, "{sign}{:#x}", self.0.unsigned_abs())
+    }
+}
+
 pub const MIN_SMALL_U64: u64 = 0;
 pub const MAX_SMALL_U64: u64 = 0x00ff_ffff_ffff_ffff_u64;
 
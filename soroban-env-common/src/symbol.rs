@@ -134,19 +134,20 @@ impl<E: Env> Compare<Symbol> for E {
     fn compare(&self, a: &Symbol, b: &Symbol) -> Result<Ordering, Self::Error> {
         let taga = a.0.get_tag();
         let tagb = b.0.get_tag();
-        match taga.cmp(&tagb) {
-            Ordering::Equal => {
-                if taga == Tag::SymbolSmall {
-                    let ssa = unsafe { SymbolSmall::unchecked_from_val(a.0) };
-                    let ssb = unsafe { SymbolSmall::unchecked_from_val(b.0) };
-                    Ok(ssa.cmp(&ssb))
-                } else {
-                    let soa = unsafe { SymbolObject::unchecked_from_val(a.0) };
-                    let sob = unsafe { SymbolObject::unchecked_from_val(b.0) };
-                    self.compare(&soa, &sob)
-                }
-            }
-            other => Ok(other),
+        if taga != tagb {
+            // A small symbol and a symbol object: order them by their
+            // characters like any other pair of symbols, which only the
+            // environment can do since the object may hold any symbol.
+            return Ok(self.obj_cmp(a.0, b.0)?.cmp(&0));
+        }
+        if taga == Tag::SymbolSmall {
+            let ssa = unsafe { SymbolSmall::unchecked_from_val(a.0) };
+            let ssb = unsafe { SymbolSmall::unchecked_from_val(b.0) };
+            Ok(ssa.cmp(&ssb))
+        } else {
+            let soa = unsafe { SymbolObject::unchecked_from_val(a.0) };
+            let sob = unsafe { SymbolObject::unchecked_from_val(b.0) };
+            self.compare(&soa, &sob)
         }
     }
 }
@@ -80,6 +80,53 @@ impl_for_tuple! { 11_u32 11_usize T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T
 impl_for_tuple! { 12_u32 12_usize T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11 }
 impl_for_tuple! { 13_u32 13_usize T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11 T12 12 }
 
+// Conversions of fixed-size arrays to and from vectors. These go through
+// VecObject rather than Val, as the conversions of byte arrays to and from Val
+// already exist and map to BytesObject.
+
+impl<E: Env, T, const N: usize> TryFromVal<E, VecObject> for [T; N]
+where
+    T: TryFromVal<E, Val>,
+{
+    type Error = crate::Error;
+
+    fn try_from_val(env: &E, vec: &VecObject) -> Result<Self, Self::Error> {
+        let mut tmp: [Val; N] = [Val::VOID.to_val(); N];
+        env.vec_unpack_to_slice(*vec, &mut tmp)
+            .map_err(Into::into)?;
+        // Stop converting at the first failure, and only unwrap the elements
+        // once they all converted.
+        let mut res: Result<(), crate::Error> = Ok(());
+        let arr = tmp.map(|v| match res {
+            Ok(()) => match T::try_from_val(env, &v) {
+                Ok(t) => Some(t),
+                Err(e) => {
+                    res = Err(e.into());
+                    None
+                }
+            },
+            Err(_) => None,
+        });
+        res?;
+        Ok(arr.map(|t| t.expect("all elements converted")))
+    }
+}
+
+impl<E: Env, T, const N: usize> TryFromVal<E, [T; N]> for VecObject
+where
+    T: TryIntoVal<E, Val>,
+{
+    type Error = crate::Error;
+
+    fn try_from_val(env: &E, v: &[T; N]) -> Result<Self, Self::Error> {
+        let mut tmp: [Val; N] = [Val::VOID.to_val(); N];
+        for (dst, src) in tmp.iter_mut().zip(v.iter()) {
+            *dst = src.try_into_val(env).map_err(Into::into)?;
+        }
+        env.vec_new_from_slice(&tmp).map_err(Into::into)
+    }
+}
+
 // Unit types are not tuples, but when people write out what they think is an
 // empty tuple, it is a unit type. The following conversions on unit types
 // behave like tuples in some conversions that are safe to do so, like
@@ -5,7 +5,7 @@ use crate::xdr::{ScError, ScVal, ScValType};
 use crate::{
     declare_tag_based_object_wrapper, declare_tag_based_wrapper,
     impl_tryfroms_and_tryfromvals_delegating_to_valconvert, impl_val_wrapper_base, Compare, I32Val,
-    SymbolSmall, SymbolStr, U32Val,
+    SymbolSmall, SymbolStr, U32Val, I256, U256,
 };
 
 use super::{Env, Error, TryFromVal};
@@ -794,6 +794,131 @@ impl Val {
     pub const FALSE: Bool = Val::from_bool(false);
 }
 
+/// The type of host object a [Val] with an object [Tag] refers to. This
+/// is the stable, exhaustive counterpart of the object cases of [Tag].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectType {
+    U64,
+    I64,
+    Timepoint,
+    Duration,
+    U128,
+    I128,
+    U256,
+    I256,
+    Bytes,
+    String,
+    Symbol,
+    Vec,
+    Map,
+    Address,
+}
+
+impl ObjectType {
+    /// Get the object [Tag] that [Val]s referring to objects of this type
+    /// carry.
+    pub const fn tag(self) -> Tag {
+        match self {
+            ObjectType::U64 => Tag::U64Object,
+            ObjectType::I64 => Tag::I64Object,
+            ObjectType::Timepoint => Tag::TimepointObject,
+            ObjectType::Duration => Tag::DurationObject,
+            ObjectType::U128 => Tag::U128Object,
+            ObjectType::I128 => Tag::I128Object,
+            ObjectType::U256 => Tag::U256Object,
+            ObjectType::I256 => Tag::I256Object,
+            ObjectType::Bytes => Tag::BytesObject,
+            ObjectType::String => Tag::StringObject,
+            ObjectType::Symbol => Tag::SymbolObject,
+            ObjectType::Vec => Tag::VecObject,
+            ObjectType::Map => Tag::MapObject,
+            ObjectType::Address => Tag::AddressObject,
+        }
+    }
+}
+
+/// A decoded view of a [Val], as returned by [Val::kind]. Small values carry
+/// their payload; objects carry their [ObjectType] and host handle, which is
+/// only meaningful to the host that issued it.
+#[derive(Copy, Clone, Debug)]
+pub enum ValKind {
+    Bool(bool),
+    Void,
+    Error(Error),
+    U32(u32),
+    I32(i32),
+    U64Small(u64),
+    I64Small(i64),
+    TimepointSmall(u64),
+    DurationSmall(u64),
+    U128Small(u128),
+    I128Small(i128),
+    U256Small(U256),
+    I256Small(I256),
+    SymbolSmall(SymbolSmall),
+    Object(ObjectType, u32),
+    /// A [Val] that is not well-formed, see [Val::is_good].
+    Bad,
+}
+
+impl Tag {
+    /// Get the [ObjectType] of an object tag, or `None` for tags that
+    /// denote small values.
+    pub const fn object_type(self) -> Option<ObjectType> {
+        match self {
+            Tag::U64Object => Some(ObjectType::U64),
+            Tag::I64Object => Some(ObjectType::I64),
+            Tag::TimepointObject => Some(ObjectType::Timepoint),
+            Tag::DurationObject => Some(ObjectType::Duration),
+            Tag::U128Object => Some(ObjectType::U128),
+            Tag::I128Object => Some(ObjectType::I128),
+            Tag::U256Object => Some(ObjectType::U256),
+            Tag::I256Object => Some(ObjectType::I256),
+            Tag::BytesObject => Some(ObjectType::Bytes),
+            Tag::StringObject => Some(ObjectType::String),
+            Tag::SymbolObject => Some(ObjectType::Symbol),
+            Tag::VecObject => Some(ObjectType::Vec),
+            Tag::MapObject => Some(ObjectType::Map),
+            Tag::AddressObject => Some(ObjectType::Address),
+            _ => None,
+        }
+    }
+}
+
+impl Val {
+    /// Decode this [Val] into a [ValKind], extracting the payload of small
+    /// values and the type and handle of objects. Returns [ValKind::Bad] for
+    /// any [Val] that fails [Val::is_good].
+    pub fn kind(self) -> ValKind {
+        if !self.is_good() {
+            return ValKind::Bad;
+        }
+        match self.get_tag() {
+            Tag::False => ValKind::Bool(false),
+            Tag::True => ValKind::Bool(true),
+            Tag::Void => ValKind::Void,
+            Tag::Error => ValKind::Error(unsafe { Error::unchecked_from_val(self) }),
+            Tag::U32Val => ValKind::U32(self.get_major()),
+            Tag::I32Val => ValKind::I32(self.get_major() as i32),
+            Tag::U64Small => ValKind::U64Small(self.get_body()),
+            Tag::I64Small => ValKind::I64Small(self.get_signed_body()),
+            Tag::TimepointSmall => ValKind::TimepointSmall(self.get_body()),
+            Tag::DurationSmall => ValKind::DurationSmall(self.get_body()),
+            Tag::U128Small => ValKind::U128Small(self.get_body() as u128),
+            Tag::I128Small => ValKind::I128Small(self.get_signed_body() as i128),
+            Tag::U256Small => ValKind::U256Small(U256::from(self.get_body())),
+            Tag::I256Small => ValKind::I256Small(I256::from(self.get_signed_body())),
+            Tag::SymbolSmall => {
+                ValKind::SymbolSmall(unsafe { SymbolSmall::unchecked_from_val(self) })
+            }
+            tag => match tag.object_type() {
+                Some(ty) => ValKind::Object(ty, self.get_major()),
+                None => ValKind::Bad,
+            },
+        }
+    }
+}
+
 impl Debug for Val {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         fn fmt_obj(name: &str, r: &Val, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -920,3 +1045,51 @@ fn test_tag_from_u8() {
         }
     }
 }
+
+#[test]
+fn test_val_kind() {
+    use crate::xdr::{ScErrorCode, ScErrorType};
+
+    for i in 0_u8..=255 {
+        let tag = Tag::from_u8(i);
+        match tag.object_type() {
+            Some(ty) => {
+                assert!(tag.is_object());
+                assert_eq!(ty.tag(), tag);
+                let v = unsafe { Val::from_major_minor_and_tag(7, 0, tag) };
+                assert!(matches!(v.kind(), ValKind::Object(t, 7) if t == ty));
+            }
+            None => assert!(!tag.is_object()),
+        }
+    }
+
+    assert!(matches!(Val::TRUE.to_val().kind(), ValKind::Bool(true)));
+    assert!(matches!(Val::FALSE.to_val().kind(), ValKind::Bool(false)));
+    assert!(matches!(Val::VOID.to_val().kind(), ValKind::Void));
+    assert!(matches!(
+        Val::U32_MAX.to_val().kind(),
+        ValKind::U32(u32::MAX)
+    ));
+    assert!(matches!(
+        Val::I32_MIN.to_val().kind(),
+        ValKind::I32(i32::MIN)
+    ));
+    let e = Error::from_type_and_code(ScErrorType::Value, ScErrorCode::InvalidInput);
+    assert!(matches!(e.to_val().kind(), ValKind::Error(x) if x == e));
+    let v = unsafe { Val::from_body_and_tag(-5_i64 as u64 & 0x00ff_ffff_ffff_ffff, Tag::I64Small) };
+    assert!(matches!(v.kind(), ValKind::I64Small(-5)));
+    let v =
+        unsafe { Val::from_body_and_tag(-5_i64 as u64 & 0x00ff_ffff_ffff_ffff, Tag::I256Small) };
+    assert!(matches!(v.kind(), ValKind::I256Small(x) if x == I256::from(-5_i64)));
+    let v = unsafe { Val::from_body_and_tag(42, Tag::U128Small) };
+    assert!(matches!(v.kind(), ValKind::U128Small(42)));
+    let s = SymbolSmall::try_from_str("hello").unwrap();
+    assert!(
+        matches!(s.to_val().kind(), ValKind::SymbolSmall(x) if x.to_val().shallow_eq(&s.to_val()))
+    );
+
+    // Objects with a nonzero minor and unknown tags are bad.
+    let v = unsafe { Val::from_major_minor_and_tag(7, 1, Tag::VecObject) };
+    assert!(matches!(v.kind(), ValKind::Bad));
+    assert!(matches!(Val::from_payload(0xff).kind(), ValKind::Bad));
+}
@@ -1,7 +1,10 @@
 use crate::common::HostCostMeasurement;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
 use rand_chacha::ChaCha20Rng;
-use soroban_env_host::{cost_runner::ChaCha20DrawBytesRun, Host};
+use soroban_env_host::{
+    cost_runner::{ChaCha20DrawBytesRun, ChaCha20InitSeedRun},
+    Host, Seed,
+};
 
 pub(crate) struct ChaCha20DrawBytesMeasure;
 
@@ -16,3 +19,16 @@ impl HostCostMeasurement for ChaCha20DrawBytesMeasure {
         (rng, dest)
     }
 }
+
+// The input is ignored: seeding costs the same for every seed.
+pub(crate) struct ChaCha20InitSeedMeasure;
+
+impl HostCostMeasurement for ChaCha20InitSeedMeasure {
+    type Runner = ChaCha20InitSeedRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Seed {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        seed
+    }
+}
@@ -0,0 +1,78 @@
+use crate::common::HostCostMeasurement;
+use rand::{rngs::StdRng, Rng};
+use soroban_env_host::{
+    cost_runner::{ScMapFromHostMapRun, ScMapToHostMapRun, ScVecFromHostVecRun, ScVecToHostVecRun},
+    xdr::{ScMap, ScMapEntry, ScVal, ScVec},
+    Host, TryFromVal, Val,
+};
+
+// These measure the per-element costs of converting containers between their
+// ScVal and host object forms. The elements are small values, so the
+// measurements only include the container traversal and not any conversion of
+// nested objects.
+
+fn random_scvec(rng: &mut StdRng, len: u64) -> ScVal {
+    let elts: Vec<ScVal> = (0..len).map(|_| ScVal::U32(rng.gen())).collect();
+    ScVal::Vec(Some(ScVec(elts.try_into().unwrap())))
+}
+
+fn random_scmap(rng: &mut StdRng, len: u64) -> ScVal {
+    // Keys are generated in increasing order, as required of a valid ScMap.
+    let entries: Vec<ScMapEntry> = (0..len)
+        .map(|i| ScMapEntry {
+            key: ScVal::U32(i as u32),
+            val: ScVal::U32(rng.gen()),
+        })
+        .collect();
+    ScVal::Map(Some(ScMap(entries.try_into().unwrap())))
+}
+
+pub(crate) struct ScVecToHostVecMeasure;
+
+impl HostCostMeasurement for ScVecToHostVecMeasure {
+    type Runner = ScVecToHostVecRun;
+
+    const STEP_SIZE: u64 = 100;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> ScVal {
+        random_scvec(rng, input * Self::STEP_SIZE)
+    }
+}
+
+pub(crate) struct ScMapToHostMapMeasure;
+
+impl HostCostMeasurement for ScMapToHostMapMeasure {
+    type Runner = ScMapToHostMapRun;
+
+    const STEP_SIZE: u64 = 100;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> ScVal {
+        random_scmap(rng, input * Self::STEP_SIZE)
+    }
+}
+
+pub(crate) struct ScVecFromHostVecMeasure;
+
+impl HostCostMeasurement for ScVecFromHostVecMeasure {
+    type Runner = ScVecFromHostVecRun;
+
+    const STEP_SIZE: u64 = 100;
+
+    fn new_random_case(host: &Host, rng: &mut StdRng, input: u64) -> Val {
+        let scv = random_scvec(rng, input * Self::STEP_SIZE);
+        Val::try_from_val(host, &scv).unwrap()
+    }
+}
+
+pub(crate) struct ScMapFromHostMapMeasure;
+
+impl HostCostMeasurement for ScMapFromHostMapMeasure {
+    type Runner = ScMapFromHostMapRun;
+
+    const STEP_SIZE: u64 = 100;
+
+    fn new_random_case(host: &Host, rng: &mut StdRng, input: u64) -> Val {
+        let scm = random_scmap(rng, input * Self::STEP_SIZE);
+        Val::try_from_val(host, &scm).unwrap()
+    }
+}
@@ -1,5 +1,7 @@
+mod conversion;
 mod ed25519_scalar_mul;
 mod read_xdr;
 
+pub(crate) use conversion::*;
 pub(crate) use ed25519_scalar_mul::*;
 pub(crate) use read_xdr::*;
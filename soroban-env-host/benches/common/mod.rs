@@ -56,6 +56,11 @@ pub(crate) fn for_each_experimental_cost_measurement<B: Benchmark>(
     call_bench::<B, Ed25519ScalarMulMeasure>(&mut params)?;
     call_bench::<B, VerifyEd25519SigMeasure>(&mut params)?;
     call_bench::<B, ReadXdrByteArrayMeasure>(&mut params)?;
+    call_bench::<B, ChaCha20InitSeedMeasure>(&mut params)?;
+    call_bench::<B, ScVecToHostVecMeasure>(&mut params)?;
+    call_bench::<B, ScMapToHostMapMeasure>(&mut params)?;
+    call_bench::<B, ScVecFromHostVecMeasure>(&mut params)?;
+    call_bench::<B, ScMapFromHostMapMeasure>(&mut params)?;
     Ok(params)
 }
 
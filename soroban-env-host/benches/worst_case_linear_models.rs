@@ -274,7 +274,7 @@ fn extract_tier(
         BTreeMap::new();
     for ty in insn_tier {
         if let Some(res) = params_wasm.get(&CostType::Wasm(*ty)) {
-            params_tier.insert(ty.clone(), res.clone());
+            params_tier.insert(ty.clone(), *res);
         }
     }
 
@@ -149,10 +149,9 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use soroban_env_common::xdr::{
-    ContractDataEntry, CreateContractArgs, HashIdPreimage, HashIdPreimageSorobanAuthorization,
-    InvokeContractArgs, LedgerEntry, LedgerEntryData, LedgerEntryExt, ScAddress, ScErrorCode,
-    ScErrorType, ScNonceKey, ScVal, SorobanAuthorizationEntry, SorobanAuthorizedFunction,
-    SorobanCredentials,
+    ContractDataEntry, CreateContractArgs, InvokeContractArgs, LedgerEntry, LedgerEntryData,
+    LedgerEntryExt, ScAddress, ScErrorCode, ScErrorType, ScNonceKey, ScSymbol, ScVal, ScVec,
+    SorobanAuthorizationEntry, SorobanAuthorizedFunction, SorobanCredentials, StringM,
 };
 use soroban_env_common::{AddressObject, Compare, Symbol, TryFromVal, TryIntoVal, Val, VecObject};
 
@@ -161,13 +160,13 @@ use crate::builtin_contracts::account_contract::{
     check_account_authentication, check_account_contract_auth,
 };
 use crate::builtin_contracts::invoker_contract_auth::invoker_contract_auth_to_authorized_invocation;
+use crate::events::diagnostic::DiagnosticSeverity;
 use crate::host::metered_clone::{MeteredAlloc, MeteredClone, MeteredContainer, MeteredIterator};
 use crate::host::Frame;
 use crate::host_object::HostVec;
 use crate::{Host, HostError};
 
 use super::xdr;
-use super::xdr::Hash;
 
 #[cfg(any(test, feature = "recording_auth"))]
 use crate::host::error::TryBorrowOrErr;
@@ -202,6 +201,11 @@ pub struct AuthorizationManager {
     // Call stack of relevant host function and contract invocations, moves mostly
     // in lock step with context stack in the host.
     call_stack: RefCell<Vec<AuthStackFrame>>,
+    // When `false`, the authentication of the account trackers is emulated
+    // instead of being performed, see
+    // `new_enforcing_without_signature_verification`.
+    #[cfg(any(test, feature = "recording_auth"))]
+    verify_signatures: bool,
 }
 
 macro_rules! impl_checked_borrow_helpers {
@@ -267,6 +271,39 @@ pub struct RecordedAuthPayload {
     pub invocation: xdr::SorobanAuthorizedInvocation,
 }
 
+#[cfg(any(test, feature = "recording_auth"))]
+impl RecordedAuthPayload {
+    // Converts the payload into an authorization entry that is ready to be
+    // signed. Payloads of addresses get address credentials with the recorded
+    // nonce, the provided signature expiration ledger and a `Void` signature
+    // that the signer has to replace. Payloads without an address are
+    // authorized by the transaction source account.
+    // metering: free, recording mode
+    pub fn to_authorization_entry(
+        &self,
+        signature_expiration_ledger: u32,
+    ) -> Result<SorobanAuthorizationEntry, HostError> {
+        let credentials = match (&self.address, self.nonce) {
+            (Some(address), Some(nonce)) => {
+                SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+                    address: address.clone(),
+                    nonce,
+                    signature_expiration_ledger,
+                    signature: ScVal::Void,
+                })
+            }
+            (None, _) => SorobanCredentials::SourceAccount,
+            _ => {
+                return Err((ScErrorType::Auth, ScErrorCode::InternalError).into());
+            }
+        };
+        Ok(SorobanAuthorizationEntry {
+            credentials,
+            root_invocation: self.invocation.clone(),
+        })
+    }
+}
+
 // Snapshot of `AuthorizationManager` to use when performing the callstack
 // rollbacks.
 pub struct AuthorizationManagerSnapshot {
@@ -518,6 +555,17 @@ pub(crate) struct AuthorizedInvocation {
     is_exhausted: bool,
 }
 
+// How close an authorized invocation comes to matching a required
+// invocation, from the furthest to the closest. Used only for the
+// diagnostics of unauthorized calls.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum InvocationMismatch {
+    Contract,
+    Function,
+    Args,
+    None,
+}
+
 // Snapshot of `AuthorizedInvocation` that contains only mutable fields.
 pub(crate) struct AuthorizedInvocationSnapshot {
     is_exhausted: bool,
@@ -594,6 +642,20 @@ impl AuthStackFrame {
     }
 }
 
+// Builds an `ScVal::Vec` out of `vals` for the diagnostic events.
+// metering: covered by the host
+fn diagnostic_vec(host: &Host, vals: Vec<ScVal>) -> Result<ScVal, HostError> {
+    Ok(ScVal::Vec(Some(ScVec(host.map_err(vals.try_into())?))))
+}
+
+// Builds an `ScVal::Symbol` out of `sym` for the diagnostic events.
+// metering: covered by the host
+fn diagnostic_symbol(host: &Host, sym: &str) -> Result<ScVal, HostError> {
+    Ok(ScVal::Symbol(ScSymbol(host.map_err(StringM::try_from(
+        host.metered_slice_to_vec(sym.as_bytes())?,
+    ))?)))
+}
+
 impl AuthorizedFunction {
     // metering: covered by the host
     fn from_xdr(host: &Host, xdr_fn: SorobanAuthorizedFunction) -> Result<Self, HostError> {
@@ -611,6 +673,66 @@ impl AuthorizedFunction {
         })
     }
 
+    // Compares this function with `other` part by part and returns the first
+    // part that differs.
+    // metering: covered by the host
+    fn mismatch(
+        &self,
+        host: &Host,
+        other: &AuthorizedFunction,
+    ) -> Result<InvocationMismatch, HostError> {
+        Ok(match (self, other) {
+            (AuthorizedFunction::ContractFn(f1), AuthorizedFunction::ContractFn(f2)) => {
+                if !host
+                    .compare(&f1.contract_address, &f2.contract_address)?
+                    .is_eq()
+                {
+                    InvocationMismatch::Contract
+                } else if !host.compare(&f1.function_name, &f2.function_name)?.is_eq() {
+                    InvocationMismatch::Function
+                } else if !host.compare(&f1.args, &f2.args)?.is_eq() {
+                    InvocationMismatch::Args
+                } else {
+                    InvocationMismatch::None
+                }
+            }
+            (
+                AuthorizedFunction::CreateContractHostFn(c1),
+                AuthorizedFunction::CreateContractHostFn(c2),
+            ) => {
+                if host.compare(c1, c2)?.is_eq() {
+                    InvocationMismatch::None
+                } else {
+                    InvocationMismatch::Args
+                }
+            }
+            _ => InvocationMismatch::Contract,
+        })
+    }
+
+    // Represents this function as a vec of the contract address, the function
+    // name and the arguments, or as the `create_contract` symbol, for the
+    // diagnostic events.
+    // metering: covered by the host
+    fn to_diagnostic_scval(&self, host: &Host) -> Result<ScVal, HostError> {
+        match self {
+            AuthorizedFunction::ContractFn(contract_fn) => {
+                let args = host.vals_to_scval_vec(contract_fn.args.as_slice())?;
+                diagnostic_vec(
+                    host,
+                    vec![
+                        ScVal::Address(host.scaddress_from_address(contract_fn.contract_address)?),
+                        ScVal::Symbol(host.scsymbol_from_symbol(contract_fn.function_name)?),
+                        ScVal::Vec(Some(ScVec(args))),
+                    ],
+                )
+            }
+            AuthorizedFunction::CreateContractHostFn(_) => {
+                diagnostic_symbol(host, "create_contract")
+            }
+        }
+    }
+
     // metering: covered by the host
     fn to_xdr(&self, host: &Host) -> Result<SorobanAuthorizedFunction, HostError> {
         match self {
@@ -689,6 +811,24 @@ impl AuthorizedInvocation {
         })
     }
 
+    // Returns the node of this tree that comes the closest to matching
+    // `function`, along with how close it comes.
+    // metering: covered by the host
+    fn closest_match(
+        &self,
+        host: &Host,
+        function: &AuthorizedFunction,
+    ) -> Result<(InvocationMismatch, &AuthorizedInvocation), HostError> {
+        let mut closest = (self.function.mismatch(host, function)?, self);
+        for sub_invocation in &self.sub_invocations {
+            let sub_closest = sub_invocation.closest_match(host, function)?;
+            if sub_closest.0 > closest.0 {
+                closest = sub_closest;
+            }
+        }
+        Ok(closest)
+    }
+
     // Walks a path in the tree defined by `match_stack` and
     // returns the last visited authorized node.
     // metering: free
@@ -781,6 +921,53 @@ impl AuthorizationManager {
             call_stack: RefCell::new(vec![]),
             account_trackers: RefCell::new(trackers),
             invoker_contract_trackers: RefCell::new(vec![]),
+            #[cfg(any(test, feature = "recording_auth"))]
+            verify_signatures: true,
+        })
+    }
+
+    // Creates a new enforcing `AuthorizationManager` from the given
+    // authorization entries that matches the entries and verifies their
+    // nonces as usual, but doesn't verify their signatures. The budget is
+    // still charged for the signature verification of the classic accounts.
+    // This is meant for the enforcing mode preflight.
+    // metering: covered
+    #[cfg(any(test, feature = "recording_auth"))]
+    pub(crate) fn new_enforcing_without_signature_verification(
+        host: &Host,
+        auth_entries: Vec<SorobanAuthorizationEntry>,
+    ) -> Result<Self, HostError> {
+        Ok(Self {
+            verify_signatures: false,
+            ..Self::new_enforcing(host, auth_entries)?
+        })
+    }
+
+    // Creates a new enforcing `AuthorizationManager` that authorizes the
+    // provided invocation trees on behalf of the respective addresses without
+    // checking any signatures or nonces.
+    // metering: covered
+    #[cfg(any(test, feature = "testutils"))]
+    pub(crate) fn new_enforcing_mocked(
+        host: &Host,
+        mocked_auths: Vec<(ScAddress, xdr::SorobanAuthorizedInvocation)>,
+    ) -> Result<Self, HostError> {
+        Vec::<AccountAuthorizationTracker>::charge_bulk_init_cpy(mocked_auths.len() as u64, host)?;
+        let mut trackers = Vec::with_capacity(mocked_auths.len());
+        for (address, root_invocation) in mocked_auths {
+            trackers.push(RefCell::new(AccountAuthorizationTracker::new_mocked(
+                host,
+                address,
+                root_invocation,
+            )?));
+        }
+        Ok(Self {
+            mode: AuthorizationMode::Enforcing,
+            call_stack: RefCell::new(vec![]),
+            account_trackers: RefCell::new(trackers),
+            invoker_contract_trackers: RefCell::new(vec![]),
+            #[cfg(any(test, feature = "recording_auth"))]
+            verify_signatures: true,
         })
     }
 
@@ -794,6 +981,8 @@ impl AuthorizationManager {
             call_stack: RefCell::new(vec![]),
             account_trackers: RefCell::new(vec![]),
             invoker_contract_trackers: RefCell::new(vec![]),
+            #[cfg(any(test, feature = "recording_auth"))]
+            verify_signatures: true,
         }
     }
 
@@ -811,6 +1000,19 @@ impl AuthorizationManager {
             call_stack: RefCell::new(vec![]),
             account_trackers: RefCell::new(vec![]),
             invoker_contract_trackers: RefCell::new(vec![]),
+            #[cfg(any(test, feature = "recording_auth"))]
+            verify_signatures: true,
+        }
+    }
+
+    // Returns `Some(disable_non_root_auth)` in recording mode and `None` in
+    // enforcing mode.
+    // metering: free
+    #[cfg(any(test, feature = "recording_auth"))]
+    pub(crate) fn recording_mode_disables_non_root_auth(&self) -> Option<bool> {
+        match &self.mode {
+            AuthorizationMode::Enforcing => None,
+            AuthorizationMode::Recording(info) => Some(info.disable_non_root_auth),
         }
     }
 
@@ -952,7 +1154,12 @@ impl AuthorizationManager {
                 if !host.compare(&tracker.address, &address)?.is_eq() {
                     continue;
                 }
-                match tracker.maybe_authorize_invocation(host, function, !has_active_tracker) {
+                match tracker.maybe_authorize_invocation(
+                    host,
+                    function,
+                    !has_active_tracker,
+                    self.verifies_signatures(),
+                ) {
                     // If tracker doesn't have a matching invocation,
                     // just skip it (there could still be another
                     // tracker  that matches it).
@@ -968,6 +1175,7 @@ impl AuthorizationManager {
         }
         // No matching tracker found, hence the invocation isn't
         // authorized.
+        self.unauthorized_call_diagnostics(host, address, function);
         Err(host.err(
             ScErrorType::Auth,
             ScErrorCode::InvalidAction,
@@ -976,6 +1184,91 @@ impl AuthorizationManager {
         ))
     }
 
+    // metering: free
+    fn verifies_signatures(&self) -> bool {
+        #[cfg(any(test, feature = "recording_auth"))]
+        {
+            self.verify_signatures
+        }
+        #[cfg(not(any(test, feature = "recording_auth")))]
+        {
+            true
+        }
+    }
+
+    // Explains why `function` couldn't be authorized for `address`: emits the
+    // path to the invocation in the call stack and then, for every
+    // authorization entry of the address, the invocation in its tree that
+    // comes the closest to `function` and the first part of it that doesn't
+    // match (`contract`, `function` or `args`). A fully matching invocation is
+    // reported either as `exhausted` if it has already been used, or as
+    // `call_path` if it is authorized under a different path.
+    // metering: free, debug mode only
+    fn unauthorized_call_diagnostics(
+        &self,
+        host: &Host,
+        address: AddressObject,
+        function: &AuthorizedFunction,
+    ) {
+        host.with_debug_mode(|| {
+            let path = self
+                .try_borrow_call_stack(host)?
+                .iter()
+                .map(|frame| match frame {
+                    AuthStackFrame::Contract(contract_frame) => diagnostic_vec(
+                        host,
+                        vec![
+                            ScVal::Address(
+                                host.scaddress_from_address(contract_frame.contract_address)?,
+                            ),
+                            ScVal::Symbol(host.scsymbol_from_symbol(contract_frame.function_name)?),
+                        ],
+                    ),
+                    AuthStackFrame::CreateContractHostFn(_) => {
+                        diagnostic_symbol(host, "create_contract")
+                    }
+                })
+                .metered_collect::<Result<Vec<ScVal>, HostError>>(host)??;
+            host.log_xdr_diagnostics_with_severity(
+                DiagnosticSeverity::Error,
+                "no authorization entry matches the invocation at call path",
+                vec![
+                    ScVal::Address(host.scaddress_from_address(address)?),
+                    function.to_diagnostic_scval(host)?,
+                    diagnostic_vec(host, path)?,
+                ],
+            );
+            for tracker in self.try_borrow_account_trackers(host)?.iter() {
+                let Ok(tracker) = tracker.try_borrow() else {
+                    continue;
+                };
+                if !host.compare(&tracker.address, &address)?.is_eq() {
+                    continue;
+                }
+                let (mismatch, closest) = tracker
+                    .invocation_tracker
+                    .root_authorized_invocation
+                    .closest_match(host, function)?;
+                let mismatch = match mismatch {
+                    InvocationMismatch::Contract => "contract",
+                    InvocationMismatch::Function => "function",
+                    InvocationMismatch::Args => "args",
+                    InvocationMismatch::None if closest.is_exhausted => "exhausted",
+                    InvocationMismatch::None => "call_path",
+                };
+                host.log_xdr_diagnostics_with_severity(
+                    DiagnosticSeverity::Error,
+                    "closest authorized invocation of the address differs in",
+                    vec![
+                        diagnostic_symbol(host, mismatch)?,
+                        closest.function.to_diagnostic_scval(host)?,
+                    ],
+                );
+            }
+            Ok(())
+        })
+    }
+
     #[cfg(any(test, feature = "recording_auth"))]
     fn require_auth_recording(
         &self,
@@ -1763,6 +2056,26 @@ impl AccountAuthorizationTracker {
         })
     }
 
+    // Creates a tracker that authorizes `root_invocation` on behalf of
+    // `address` the same way as the transaction source account
+    // authorizations are handled, i.e. without a signature and a nonce.
+    // metering: covered by the host and components
+    #[cfg(any(test, feature = "testutils"))]
+    fn new_mocked(
+        host: &Host,
+        address: ScAddress,
+        root_invocation: xdr::SorobanAuthorizedInvocation,
+    ) -> Result<Self, HostError> {
+        Ok(Self {
+            address: host.add_host_object(address)?,
+            invocation_tracker: InvocationTracker::from_xdr(host, root_invocation)?,
+            signature: Val::VOID.into(),
+            verified: false,
+            is_transaction_source_account: true,
+            nonce: None,
+        })
+    }
+
     // metering: free, since this is recording mode only
     #[cfg(any(test, feature = "recording_auth"))]
     fn new_recording(
@@ -1814,12 +2127,14 @@ impl AccountAuthorizationTracker {
     // Returns true/false based on whether the invocation is found in the
     // tracker. Returns error if invocation has been found, but the tracker
     // itself is not valid (failed authentication or nonce check).
+    // When `verify_signature` is `false`, authentication is only emulated.
     // metering: covered
     fn maybe_authorize_invocation(
         &mut self,
         host: &Host,
         function: &AuthorizedFunction,
         allow_matching_root: bool,
+        verify_signature: bool,
     ) -> Result<bool, HostError> {
         if !self.invocation_tracker.maybe_extend_invocation_match(
             host,
@@ -1833,8 +2148,12 @@ impl AccountAuthorizationTracker {
             return Ok(false);
         }
         if !self.verified {
-            let authenticate_res = self
-                .authenticate(host)
+            let authenticate_res = if verify_signature {
+                self.authenticate(host)
+            } else {
+                self.emulate_authentication_for_simulation(host)
+            };
+            let authenticate_res = authenticate_res
                 .map_err(|err| {
                     // Convert any recoverable errors to auth errors so that it's
                     // not possible to confuse them for the errors of the
@@ -1980,15 +2299,11 @@ impl AccountAuthorizationTracker {
                 &[],
             )
         })?;
-        let payload_preimage =
-            HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
-                network_id: Hash(host.with_ledger_info(|li| li.network_id.metered_clone(host))?),
-                nonce,
-                signature_expiration_ledger: live_until_ledger,
-                invocation: self.root_invocation_to_xdr(host)?,
-            });
-
-        host.metered_hash_xdr(&payload_preimage)
+        host.soroban_authorization_payload(
+            nonce,
+            live_until_ledger,
+            self.root_invocation_to_xdr(host)?,
+        )
     }
 
     // metering: covered by the hsot
@@ -2039,6 +2354,47 @@ impl AccountAuthorizationTracker {
         Ok(())
     }
 
+    // Emulates authentication for the enforcing mode without signature
+    // verification. Classic accounts are emulated as in
+    // `emulate_authentication`, and the budget is charged for verifying every
+    // signature provided for them. Custom accounts are authenticated by
+    // invoking their `__check_auth` with the signature of the entry (likely a
+    // placeholder), so that the resources it uses are accounted for: a
+    // recoverable failure, such as the placeholder not verifying, is ignored,
+    // while the non-recoverable ones (e.g. exceeding the budget) are still
+    // propagated.
+    // metering: covered
+    #[cfg(any(test, feature = "recording_auth"))]
+    fn emulate_authentication_for_simulation(&self, host: &Host) -> Result<(), HostError> {
+        if self.is_transaction_source_account {
+            return Ok(());
+        }
+        match host.scaddress_from_address(self.address)? {
+            ScAddress::Account(_) => {
+                self.emulate_authentication(host)?;
+                if let Ok(signatures) = VecObject::try_from(self.signature) {
+                    let signature_count = host.visit_obj(signatures, |v: &HostVec| Ok(v.len()))?;
+                    for _ in 0..signature_count {
+                        // The signature payload is a 32-byte hash.
+                        host.charge_budget(xdr::ContractCostType::VerifyEd25519Sig, Some(32))?;
+                    }
+                }
+            }
+            ScAddress::Contract(_) => match self.authenticate(host) {
+                Err(err) if !err.is_recoverable() => return Err(err),
+                _ => (),
+            },
+        }
+        Ok(())
+    }
+
+    // Signature verification can only be disabled with `recording_auth`.
+    // metering: covered by the host
+    #[cfg(not(any(test, feature = "recording_auth")))]
+    fn emulate_authentication_for_simulation(&self, host: &Host) -> Result<(), HostError> {
+        self.authenticate(host)
+    }
+
     // metering: covered
     fn snapshot(&self, budget: &Budget) -> Result<AccountAuthorizationTrackerSnapshot, HostError> {
         Ok(AccountAuthorizationTrackerSnapshot {
@@ -2159,7 +2515,7 @@ impl Host {
                     ScErrorType::Auth,
                     ScErrorCode::ExistingValue,
                     "nonce already exists for address",
-                    &[address.into()],
+                    &[address.into(), nonce.try_into_val(self)?],
                 ));
             }
             let data = LedgerEntryData::ContractData(ContractDataEntry {
@@ -2265,6 +2621,121 @@ impl Host {
         Ok(())
     }
 
+    /// Switches the host to the mode where every `require_auth` call
+    /// succeeds, so that the contract logic can be tested without building
+    /// the signed authorization entries.
+    ///
+    /// As in the recording mode, the authorized invocations must be rooted
+    /// at the top-level contract invocation; use
+    /// `mock_all_auths_allowing_non_root_auth` to lift this restriction. The
+    /// authorizations required by the last invocation can be retrieved with
+    /// `get_authenticated_authorizations`.
+    pub fn mock_all_auths(&self) -> Result<(), HostError> {
+        self.switch_to_recording_auth(true)
+    }
+
+    /// Like `mock_all_auths`, but also allows the authorized invocations that
+    /// are not rooted at the top-level contract invocation.
+    pub fn mock_all_auths_allowing_non_root_auth(&self) -> Result<(), HostError> {
+        self.switch_to_recording_auth(false)
+    }
+
+    /// Authorizes the provided invocation trees on behalf of the respective
+    /// addresses without requiring any signatures or nonces.
+    ///
+    /// In contrast with `mock_all_auths`, every `require_auth` call has to be
+    /// matched exactly as with the real authorization entries (i.e. with the
+    /// same contract, function and arguments), so this can be used for
+    /// testing that a contract requires the expected authorizations.
+    pub fn mock_auths(
+        &self,
+        auths: Vec<(ScAddress, SorobanAuthorizedInvocation)>,
+    ) -> Result<(), HostError> {
+        let new_auth_manager = AuthorizationManager::new_enforcing_mocked(self, auths)?;
+        *self.try_borrow_authorization_manager_mut()? = new_auth_manager;
+        Ok(())
+    }
+
+    /// Returns the payload that has to be signed by the address of the
+    /// authorization `entry` for the current network, i.e. the hash of its
+    /// `HashIdPreimageSorobanAuthorization`.
+    pub fn authorization_entry_signature_payload(
+        &self,
+        entry: &SorobanAuthorizationEntry,
+    ) -> Result<[u8; 32], HostError> {
+        let SorobanCredentials::Address(credentials) = &entry.credentials else {
+            return Err(self.err(
+                ScErrorType::Auth,
+                ScErrorCode::InvalidInput,
+                "source account authorization entries are not signed",
+                &[],
+            ));
+        };
+        self.soroban_authorization_payload(
+            credentials.nonce,
+            credentials.signature_expiration_ledger,
+            entry.root_invocation.metered_clone(self)?,
+        )
+    }
+
+    /// Converts the `payload` recorded for a classic account into an
+    /// authorization entry signed by the `signers` of the account for the
+    /// current network. Payloads authorized by the transaction source account
+    /// don't need to be signed and are just converted.
+    ///
+    /// Custom accounts define their own signature format, so their entries
+    /// have to be signed using `authorization_entry_signature_payload`.
+    pub fn sign_recorded_auth_payload(
+        &self,
+        payload: &RecordedAuthPayload,
+        signers: &[&ed25519_dalek::SigningKey],
+        signature_expiration_ledger: u32,
+    ) -> Result<SorobanAuthorizationEntry, HostError> {
+        use crate::builtin_contracts::{
+            account_contract::AccountEd25519Signature,
+            base_types::{BytesN, Vec as ContractTypeVec},
+        };
+        use ed25519_dalek::Signer;
+        use soroban_env_common::EnvBase;
+
+        let mut entry = payload.to_authorization_entry(signature_expiration_ledger)?;
+        let signature_payload = match &entry.credentials {
+            SorobanCredentials::SourceAccount => return Ok(entry),
+            SorobanCredentials::Address(credentials) => {
+                if let ScAddress::Contract(_) = credentials.address {
+                    return Err(self.err(
+                        ScErrorType::Auth,
+                        ScErrorCode::InvalidInput,
+                        "custom account payloads can't be signed with ed25519 keys",
+                        &[],
+                    ));
+                }
+                self.authorization_entry_signature_payload(&entry)?
+            }
+        };
+        // The account signatures have to be ordered by the public key.
+        let mut signers = signers.to_vec();
+        signers.sort_by_key(|key| key.verifying_key().to_bytes());
+        let mut signatures = ContractTypeVec::new(self)?;
+        for signer in signers {
+            signatures.push(&AccountEd25519Signature {
+                public_key: BytesN::<32>::try_from_val(
+                    self,
+                    &self.bytes_new_from_slice(&signer.verifying_key().to_bytes())?,
+                )?,
+                signature: BytesN::<64>::try_from_val(
+                    self,
+                    &self.bytes_new_from_slice(&signer.sign(&signature_payload).to_bytes())?,
+                )?,
+            })?;
+        }
+        let signature = self.from_host_val(signatures.into())?;
+        if let SorobanCredentials::Address(credentials) = &mut entry.credentials {
+            credentials.signature = signature;
+        }
+        Ok(entry)
+    }
+
     // Returns the authorizations that have been authenticated for the last
     // contract invocation.
     //
@@ -2283,6 +2754,45 @@ impl Host {
             // taken place so return an empty vec.
             .unwrap_or_default())
     }
+
+    // Returns the ledger key of the nonce entry of `address`.
+    fn nonce_ledger_key(
+        &self,
+        address: AddressObject,
+        nonce: i64,
+    ) -> Result<Rc<xdr::LedgerKey>, HostError> {
+        self.storage_key_for_address(
+            self.scaddress_from_address(address)?,
+            ScVal::LedgerKeyNonce(ScNonceKey { nonce }),
+            xdr::ContractDataDurability::Temporary,
+        )
+    }
+
+    /// Returns the live_until ledger of the `nonce` consumed by `address`, or
+    /// `None` if the nonce has not been consumed.
+    pub fn get_nonce_live_until_ledger(
+        &self,
+        address: AddressObject,
+        nonce: i64,
+    ) -> Result<Option<u32>, HostError> {
+        let nonce_key = self.nonce_ledger_key(address, nonce)?;
+        self.with_mut_storage(|storage| {
+            if !storage.has(&nonce_key, self.budget_ref())? {
+                return Ok(None);
+            }
+            let (_, live_until_ledger) =
+                storage.get_with_live_until_ledger(&nonce_key, self.budget_ref())?;
+            Ok(live_until_ledger)
+        })
+    }
+
+    /// Removes the `nonce` consumed by `address`, so that the authorization
+    /// entries using it are accepted again, e.g. to replay an invocation in a
+    /// test. The nonce entry has to be in the read-write footprint.
+    pub fn reset_nonce(&self, address: AddressObject, nonce: i64) -> Result<(), HostError> {
+        let nonce_key = self.nonce_ledger_key(address, nonce)?;
+        self.with_mut_storage(|storage| storage.del(&nonce_key, self.budget_ref()))
+    }
 }
 
 // metering: free for testutils
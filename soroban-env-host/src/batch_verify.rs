@@ -0,0 +1,184 @@
+//! Verification of the signatures of authorization entries in batches, for
+//! the embedders (such as simulation services) that want to reject badly
+//! signed transactions before invoking them.
+//!
+//! This is not a part of the consensus execution: nothing is metered, and the
+//! host still verifies every signature (along with the signer weights and the
+//! nonces, which need the ledger state) when the entries are used by an
+//! invocation. The checks are verified in order on the calling thread; they
+//! don't depend on a host, so embedders can split a batch across their own
+//! threads.
+
+use k256::{ecdsa::signature::hazmat::PrehashVerifier, elliptic_curve::scalar::IsHigh};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    budget::DEFAULT_XDR_RW_LIMITS,
+    xdr::{
+        Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, ScAddress, ScErrorCode,
+        ScErrorType, ScVal, SorobanAuthorizationEntry, SorobanCredentials, WriteXdr,
+    },
+    Error,
+};
+
+/// A single signature to verify.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignatureCheck {
+    /// An ed25519 signature of `message`, verified as by the `verify_sig_ed25519`
+    /// host function.
+    Ed25519 {
+        public_key: [u8; 32],
+        message: Vec<u8>,
+        signature: [u8; 64],
+    },
+    /// An ECDSA secp256k1 signature of the 32-byte `msg_digest`, with the
+    /// SEC-1 encoded `public_key`. As in the `recover_key_ecdsa_secp256k1`
+    /// host function, the `s` part of the signature must be normalized to the
+    /// low form.
+    EcdsaSecp256k1 {
+        public_key: Vec<u8>,
+        msg_digest: [u8; 32],
+        signature: [u8; 64],
+    },
+}
+
+fn invalid_signature() -> Error {
+    Error::from_type_and_code(ScErrorType::Crypto, ScErrorCode::InvalidInput)
+}
+
+fn invalid_auth_entry() -> Error {
+    Error::from_type_and_code(ScErrorType::Auth, ScErrorCode::InvalidInput)
+}
+
+fn is_symbol(val: &ScVal, name: &str) -> bool {
+    matches!(val, ScVal::Symbol(s) if s.as_slice() == name.as_bytes())
+}
+
+impl SignatureCheck {
+    /// Verifies the signature, failing with `(Crypto, InvalidInput)` if the
+    /// key or the signature is malformed, or if the signature doesn't match.
+    pub fn verify(&self) -> Result<(), Error> {
+        match self {
+            SignatureCheck::Ed25519 {
+                public_key,
+                message,
+                signature,
+            } => {
+                let key = ed25519_dalek::VerifyingKey::from_bytes(public_key)
+                    .map_err(|_| invalid_signature())?;
+                key.verify_strict(message, &ed25519_dalek::Signature::from_bytes(signature))
+                    .map_err(|_| invalid_signature())
+            }
+            SignatureCheck::EcdsaSecp256k1 {
+                public_key,
+                msg_digest,
+                signature,
+            } => {
+                let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                    .map_err(|_| invalid_signature())?;
+                let sig = k256::ecdsa::Signature::try_from(signature.as_slice())
+                    .map_err(|_| invalid_signature())?;
+                if sig.s().is_high().into() {
+                    return Err(invalid_signature());
+                }
+                key.verify_prehash(msg_digest, &sig)
+                    .map_err(|_| invalid_signature())
+            }
+        }
+    }
+}
+
+/// Verifies the `checks`, returning the result of every check in the same
+/// order.
+pub fn verify_signatures(checks: &[SignatureCheck]) -> Vec<Result<(), Error>> {
+    checks.iter().map(SignatureCheck::verify).collect()
+}
+
+/// Returns the ed25519 signatures attached to an authorization `entry` of a
+/// Stellar account on the network with the given `network_id`.
+///
+/// The entries authorized by the source account aren't signed, and the
+/// entries of the custom accounts define their own signature format, so no
+/// signatures are returned for them; the embedders that know the format of
+/// their custom accounts can build the [SignatureCheck]s themselves.
+///
+/// Fails with `(Auth, InvalidInput)` if the signatures of an account entry
+/// are malformed.
+pub fn account_signature_checks(
+    network_id: &[u8; 32],
+    entry: &SorobanAuthorizationEntry,
+) -> Result<Vec<SignatureCheck>, Error> {
+    let SorobanCredentials::Address(credentials) = &entry.credentials else {
+        return Ok(vec![]);
+    };
+    if let ScAddress::Contract(_) = credentials.address {
+        return Ok(vec![]);
+    }
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(*network_id),
+        nonce: credentials.nonce,
+        signature_expiration_ledger: credentials.signature_expiration_ledger,
+        invocation: entry.root_invocation.clone(),
+    });
+    let preimage = preimage
+        .to_xdr(DEFAULT_XDR_RW_LIMITS)
+        .map_err(|_| invalid_auth_entry())?;
+    let payload: [u8; 32] = Sha256::digest(preimage).into();
+
+    // The signatures are a vector of `AccountEd25519Signature` maps, see
+    // `check_account_authentication`.
+    let ScVal::Vec(Some(signatures)) = &credentials.signature else {
+        return Err(invalid_auth_entry());
+    };
+    signatures
+        .iter()
+        .map(|signature| {
+            let ScVal::Map(Some(fields)) = signature else {
+                return Err(invalid_auth_entry());
+            };
+            let field = |name: &str| -> Result<&[u8], Error> {
+                fields
+                    .iter()
+                    .find(|e| is_symbol(&e.key, name))
+                    .and_then(|e| match &e.val {
+                        ScVal::Bytes(b) => Some(b.as_slice()),
+                        _ => None,
+                    })
+                    .ok_or_else(invalid_auth_entry)
+            };
+            if fields.len() != 2 {
+                return Err(invalid_auth_entry());
+            }
+            Ok(SignatureCheck::Ed25519 {
+                public_key: field("public_key")?
+                    .try_into()
+                    .map_err(|_| invalid_auth_entry())?,
+                message: payload.to_vec(),
+                signature: field("signature")?
+                    .try_into()
+                    .map_err(|_| invalid_auth_entry())?,
+            })
+        })
+        .collect()
+}
+
+/// Verifies the signatures of the Stellar account `entries` (see
+/// [account_signature_checks]), returning the result for every entry in the
+/// same order.
+///
+/// An entry passes if all of its signatures are valid, which doesn't imply
+/// that the invocation will authenticate it: the signers may still not belong
+/// to the account, or not have enough weight.
+pub fn verify_auth_entries(
+    network_id: &[u8; 32],
+    entries: &[SorobanAuthorizationEntry],
+) -> Vec<Result<(), Error>> {
+    entries
+        .iter()
+        .map(|entry| {
+            account_signature_checks(network_id, entry)?
+                .iter()
+                .try_for_each(SignatureCheck::verify)
+        })
+        .collect()
+}
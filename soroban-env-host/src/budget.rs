@@ -5,17 +5,18 @@ mod util;
 mod wasmi_helper;
 
 pub(crate) use limits::DepthLimiter;
-pub use limits::{DEFAULT_HOST_DEPTH_LIMIT, DEFAULT_XDR_RW_LIMITS};
+pub use limits::{
+    DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT, DEFAULT_HOST_DEPTH_LIMIT, DEFAULT_XDR_RW_LIMITS,
+};
 pub use model::{MeteredCostComponent, ScaledU64};
 
 use std::{
-    cell::{RefCell, RefMut},
+    cell::Cell,
     fmt::{Debug, Display},
     rc::Rc,
 };
 
 use crate::{
-    host::error::TryBorrowOrErr,
     xdr::{ContractCostParams, ContractCostType, ScErrorCode, ScErrorType},
     Error, Host, HostError,
 };
@@ -23,7 +24,7 @@ use crate::{
 use dimension::{BudgetDimension, IsCpu, IsShadowMode};
 use wasmi_helper::FuelConfig;
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CostTracker {
     pub iterations: u64,
     pub inputs: Option<u64>,
@@ -31,16 +32,31 @@ pub struct CostTracker {
     pub mem: u64,
 }
 
+/// The budget consumed by a single top-level invocation on a [Host], see
+/// [Host::invocation_budgets].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InvocationBudget {
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+}
+
 #[derive(Clone)]
 struct BudgetTracker {
     // Tracks the `(sum_of_iterations, total_input)` for each `CostType`
-    cost_tracker: [CostTracker; ContractCostType::variants().len()],
+    cost_tracker: [Cell<CostTracker>; ContractCostType::variants().len()],
     // Total number of times the meter is called
-    meter_count: u32,
-    #[cfg(any(test, feature = "testutils", feature = "bench"))]
-    wasm_memory: u64,
+    meter_count: Cell<u32>,
+    // Only tracked in tests and benchmarks, and reset along with the rest of
+    // the tracker wherever the budget can be reset.
+    #[cfg(any(
+        test,
+        feature = "testutils",
+        feature = "bench",
+        feature = "recording_auth"
+    ))]
+    wasm_memory: Cell<u64>,
     // Tracks the real time (in nsecs) spent on various `CostType`
-    time_tracker: [u64; ContractCostType::variants().len()],
+    time_tracker: [Cell<u64>; ContractCostType::variants().len()],
 }
 
 impl Default for BudgetTracker {
@@ -48,13 +64,18 @@ impl Default for BudgetTracker {
         let mut mt = Self {
             cost_tracker: Default::default(),
             meter_count: Default::default(),
-            #[cfg(any(test, feature = "testutils", feature = "bench"))]
+            #[cfg(any(
+                test,
+                feature = "testutils",
+                feature = "bench",
+                feature = "recording_auth"
+            ))]
             wasm_memory: Default::default(),
             time_tracker: Default::default(),
         };
         for (ct, tracker) in ContractCostType::variants()
             .iter()
-            .zip(mt.cost_tracker.iter_mut())
+            .zip(mt.cost_tracker.iter_mut().map(Cell::get_mut))
         {
             // Define what inputs actually mean. For any constant-cost types --
             // whether it is a true constant unit cost type, or empirically
@@ -99,46 +120,75 @@ impl Default for BudgetTracker {
 }
 
 impl BudgetTracker {
+    #[cfg(any(
+        test,
+        feature = "testutils",
+        feature = "bench",
+        feature = "recording_auth"
+    ))]
+    fn reset(&self) {
+        self.meter_count.set(0);
+        for tracker in &self.cost_tracker {
+            let inputs = tracker.get().inputs.map(|_| 0);
+            tracker.set(CostTracker {
+                iterations: 0,
+                inputs,
+                cpu: 0,
+                mem: 0,
+            });
+        }
+        self.wasm_memory.set(0);
+    }
+
     #[cfg(any(test, feature = "testutils", feature = "bench"))]
-    fn reset(&mut self) {
-        self.meter_count = 0;
-        for tracker in &mut self.cost_tracker {
-            tracker.iterations = 0;
-            tracker.inputs = tracker.inputs.map(|_| 0);
-            tracker.cpu = 0;
-            tracker.mem = 0;
+    fn swap(&self, other: &Self) {
+        for (t, o) in self.cost_tracker.iter().zip(other.cost_tracker.iter()) {
+            t.swap(o);
+        }
+        self.meter_count.swap(&other.meter_count);
+        self.wasm_memory.swap(&other.wasm_memory);
+        for (t, o) in self.time_tracker.iter().zip(other.time_tracker.iter()) {
+            t.swap(o);
         }
-        self.wasm_memory = 0;
     }
 
-    fn track_time(&mut self, ty: ContractCostType, duration: u64) -> Result<(), HostError> {
-        let t = self.time_tracker.get_mut(ty as usize).ok_or_else(|| {
+    fn track_time(&self, ty: ContractCostType, duration: u64) -> Result<(), HostError> {
+        let t = self.time_tracker.get(ty as usize).ok_or_else(|| {
             HostError::from(Error::from_type_and_code(
                 ScErrorType::Budget,
                 ScErrorCode::InternalError,
             ))
         })?;
-        *t += duration;
+        t.set(t.get() + duration);
         Ok(())
     }
 
     fn get_time(&self, ty: ContractCostType) -> Result<u64, HostError> {
         self.time_tracker
             .get(ty as usize)
-            .map(|t| *t)
+            .map(Cell::get)
             .ok_or_else(|| (ScErrorType::Budget, ScErrorCode::InternalError).into())
     }
 }
 
+// All the state is kept in `Cell`s rather than behind a single `RefCell`, so
+// that the charging path (which runs for nearly every host operation) is just
+// a few loads and stores, without borrow-flag checks that would otherwise
+// distort the calibration of the cheapest cost types.
 #[derive(Clone)]
 pub(crate) struct BudgetImpl {
     cpu_insns: BudgetDimension,
     mem_bytes: BudgetDimension,
     /// For the purpose of calibration and reporting; not used for budget-limiting nor does it affect consensus
     tracker: BudgetTracker,
-    is_in_shadow_mode: bool,
-    fuel_config: FuelConfig,
-    depth_limit: u32,
+    is_in_shadow_mode: Cell<bool>,
+    fuel_config: Cell<FuelConfig>,
+    depth_limit: Cell<u32>,
+    conversion_element_limit: Cell<u32>,
+    // Elements left for the current outermost `ScVal`<->`Val` conversion,
+    // `None` outside of the conversions.
+    conversion_elements_left: Cell<Option<u32>>,
+    conversion_limit_error: Cell<Option<Error>>,
 }
 
 impl BudgetImpl {
@@ -149,13 +199,16 @@ impl BudgetImpl {
         cpu_cost_params: ContractCostParams,
         mem_cost_params: ContractCostParams,
     ) -> Result<Self, HostError> {
-        let mut b = Self {
+        let b = Self {
             cpu_insns: BudgetDimension::try_from_config(cpu_cost_params)?,
             mem_bytes: BudgetDimension::try_from_config(mem_cost_params)?,
             tracker: Default::default(),
-            is_in_shadow_mode: false,
+            is_in_shadow_mode: Cell::new(false),
             fuel_config: Default::default(),
-            depth_limit: DEFAULT_HOST_DEPTH_LIMIT,
+            depth_limit: Cell::new(DEFAULT_HOST_DEPTH_LIMIT),
+            conversion_element_limit: Cell::new(DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT),
+            conversion_elements_left: Cell::new(None),
+            conversion_limit_error: Cell::new(None),
         };
 
         b.cpu_insns.reset(cpu_limit);
@@ -164,27 +217,31 @@ impl BudgetImpl {
     }
 
     pub fn charge(
-        &mut self,
+        &self,
         ty: ContractCostType,
         iterations: u64,
         input: Option<u64>,
     ) -> Result<(), HostError> {
-        let tracker = self
-            .tracker
-            .cost_tracker
-            .get_mut(ty as usize)
-            .ok_or_else(|| HostError::from((ScErrorType::Budget, ScErrorCode::InternalError)))?;
+        let tracker =
+            self.tracker.cost_tracker.get(ty as usize).ok_or_else(|| {
+                HostError::from((ScErrorType::Budget, ScErrorCode::InternalError))
+            })?;
+        let is_in_shadow_mode = self.is_in_shadow_mode.get();
 
-        if !self.is_in_shadow_mode {
+        if !is_in_shadow_mode {
             // update tracker for reporting
-            self.tracker.meter_count = self.tracker.meter_count.saturating_add(1);
-            tracker.iterations = tracker.iterations.saturating_add(iterations);
-            match (&mut tracker.inputs, input) {
+            self.tracker
+                .meter_count
+                .set(self.tracker.meter_count.get().saturating_add(1));
+            let mut ct = tracker.get();
+            ct.iterations = ct.iterations.saturating_add(iterations);
+            match (&mut ct.inputs, input) {
                 (None, None) => (),
                 (Some(t), Some(i)) => *t = t.saturating_add(i.saturating_mul(iterations)),
                 // internal logic error, a wrong cost type has been passed in
                 _ => return Err((ScErrorType::Budget, ScErrorCode::InternalError).into()),
             };
+            tracker.set(ct);
         }
 
         let cpu_charged = self.cpu_insns.charge(
@@ -192,26 +249,48 @@ impl BudgetImpl {
             iterations,
             input,
             IsCpu(true),
-            IsShadowMode(self.is_in_shadow_mode),
+            IsShadowMode(is_in_shadow_mode),
         )?;
-        if !self.is_in_shadow_mode {
-            tracker.cpu = tracker.cpu.saturating_add(cpu_charged);
+        if !is_in_shadow_mode {
+            let mut ct = tracker.get();
+            ct.cpu = ct.cpu.saturating_add(cpu_charged);
+            tracker.set(ct);
         }
         self.cpu_insns
-            .check_budget_limit(IsShadowMode(self.is_in_shadow_mode))?;
+            .check_budget_limit(IsShadowMode(is_in_shadow_mode))?;
 
         let mem_charged = self.mem_bytes.charge(
             ty,
             iterations,
             input,
             IsCpu(false),
-            IsShadowMode(self.is_in_shadow_mode),
+            IsShadowMode(is_in_shadow_mode),
         )?;
-        if !self.is_in_shadow_mode {
-            tracker.mem = tracker.mem.saturating_add(mem_charged);
+        if !is_in_shadow_mode {
+            let mut ct = tracker.get();
+            ct.mem = ct.mem.saturating_add(mem_charged);
+            tracker.set(ct);
         }
         self.mem_bytes
-            .check_budget_limit(IsShadowMode(self.is_in_shadow_mode))
+            .check_budget_limit(IsShadowMode(is_in_shadow_mode))
+    }
+
+    /// Replaces the whole state with `other` in place, as the budget is shared
+    /// and can't be swapped out from under its owners.
+    #[cfg(any(test, feature = "testutils", feature = "bench"))]
+    pub(crate) fn reset_to(&self, other: Self) {
+        self.cpu_insns.swap(&other.cpu_insns);
+        self.mem_bytes.swap(&other.mem_bytes);
+        self.tracker.swap(&other.tracker);
+        self.is_in_shadow_mode.swap(&other.is_in_shadow_mode);
+        self.fuel_config.swap(&other.fuel_config);
+        self.depth_limit.swap(&other.depth_limit);
+        self.conversion_element_limit
+            .swap(&other.conversion_element_limit);
+        self.conversion_elements_left
+            .swap(&other.conversion_elements_left);
+        self.conversion_limit_error
+            .swap(&other.conversion_limit_error);
     }
 
     fn get_wasmi_fuel_remaining(&self) -> Result<u64, HostError> {
@@ -245,9 +324,12 @@ impl Default for BudgetImpl {
             cpu_insns: BudgetDimension::default(),
             mem_bytes: BudgetDimension::default(),
             tracker: Default::default(),
-            is_in_shadow_mode: false,
+            is_in_shadow_mode: Cell::new(false),
             fuel_config: Default::default(),
-            depth_limit: DEFAULT_HOST_DEPTH_LIMIT,
+            depth_limit: Cell::new(DEFAULT_HOST_DEPTH_LIMIT),
+            conversion_element_limit: Cell::new(DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT),
+            conversion_elements_left: Cell::new(None),
+            conversion_limit_error: Cell::new(None),
         };
 
         for ct in ContractCostType::variants() {
@@ -487,12 +569,14 @@ impl Debug for BudgetImpl {
         writeln!(
             f,
             "Cpu limit: {}; used: {}",
-            self.cpu_insns.limit, self.cpu_insns.total_count
+            self.cpu_insns.limit.get(),
+            self.cpu_insns.total_count.get()
         )?;
         writeln!(
             f,
             "Mem limit: {}; used: {}",
-            self.mem_bytes.limit, self.mem_bytes.total_count
+            self.mem_bytes.limit.get(),
+            self.mem_bytes.total_count.get()
         )?;
         writeln!(f, "{:=<165}", "")?;
         writeln!(
@@ -514,14 +598,14 @@ impl Debug for BudgetImpl {
                 f,
                 "{:<25}{:<15}{:<15}{:<15}{:<15}{:<20}{:<20}{:<20}{:<20}",
                 format!("{:?}", ct),
-                self.tracker.cost_tracker[i].iterations,
-                format!("{:?}", self.tracker.cost_tracker[i].inputs),
-                self.tracker.cost_tracker[i].cpu,
-                self.tracker.cost_tracker[i].mem,
-                self.cpu_insns.cost_models[i].const_term,
-                format!("{}", self.cpu_insns.cost_models[i].lin_term),
-                self.mem_bytes.cost_models[i].const_term,
-                format!("{}", self.mem_bytes.cost_models[i].lin_term),
+                self.tracker.cost_tracker[i].get().iterations,
+                format!("{:?}", self.tracker.cost_tracker[i].get().inputs),
+                self.tracker.cost_tracker[i].get().cpu,
+                self.tracker.cost_tracker[i].get().mem,
+                self.cpu_insns.cost_models[i].get().const_term,
+                format!("{}", self.cpu_insns.cost_models[i].get().lin_term),
+                self.mem_bytes.cost_models[i].get().const_term,
+                format!("{}", self.mem_bytes.cost_models[i].get().lin_term),
             )?;
         }
         writeln!(f, "{:=<165}", "")?;
@@ -532,17 +616,19 @@ impl Debug for BudgetImpl {
         writeln!(
             f,
             "Total # times meter was called: {}",
-            self.tracker.meter_count,
+            self.tracker.meter_count.get(),
         )?;
         writeln!(
             f,
             "Shadow cpu limit: {}; used: {}",
-            self.cpu_insns.shadow_limit, self.cpu_insns.shadow_total_count
+            self.cpu_insns.shadow_limit.get(),
+            self.cpu_insns.shadow_total_count.get()
         )?;
         writeln!(
             f,
             "Shadow mem limit: {}; used: {}",
-            self.mem_bytes.shadow_limit, self.mem_bytes.shadow_total_count
+            self.mem_bytes.shadow_limit.get(),
+            self.mem_bytes.shadow_total_count.get()
         )?;
         writeln!(f, "{:=<165}", "")?;
         Ok(())
@@ -555,12 +641,14 @@ impl Display for BudgetImpl {
         writeln!(
             f,
             "Cpu limit: {}; used: {}",
-            self.cpu_insns.limit, self.cpu_insns.total_count
+            self.cpu_insns.limit.get(),
+            self.cpu_insns.total_count.get()
         )?;
         writeln!(
             f,
             "Mem limit: {}; used: {}",
-            self.mem_bytes.limit, self.mem_bytes.total_count
+            self.mem_bytes.limit.get(),
+            self.mem_bytes.total_count.get()
         )?;
         writeln!(f, "{:=<55}", "")?;
         writeln!(
@@ -574,8 +662,8 @@ impl Display for BudgetImpl {
                 f,
                 "{:<25}{:<15}{:<15}",
                 format!("{:?}", ct),
-                self.tracker.cost_tracker[i].cpu,
-                self.tracker.cost_tracker[i].mem,
+                self.tracker.cost_tracker[i].get().cpu,
+                self.tracker.cost_tracker[i].get().mem,
             )?;
         }
         writeln!(f, "{:=<55}", "")?;
@@ -635,7 +723,7 @@ impl BudgetImpl {
 }
 
 #[derive(Clone)]
-pub struct Budget(pub(crate) Rc<RefCell<BudgetImpl>>);
+pub struct Budget(pub(crate) Rc<BudgetImpl>);
 
 #[allow(clippy::derivable_impls)]
 impl Default for Budget {
@@ -648,13 +736,13 @@ impl Default for Budget {
 
 impl Debug for Budget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{:?}", self.0.try_borrow().map_err(|_| std::fmt::Error)?)
+        writeln!(f, "{:?}", self.0)
     }
 }
 
 impl Display for Budget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{}", self.0.try_borrow().map_err(|_| std::fmt::Error)?)
+        writeln!(f, "{}", self.0)
     }
 }
 
@@ -694,20 +782,23 @@ impl Budget {
         cpu_cost_params: ContractCostParams,
         mem_cost_params: ContractCostParams,
     ) -> Result<Self, HostError> {
-        Ok(Self(Rc::new(RefCell::new(BudgetImpl::try_from_configs(
+        Ok(Self(Rc::new(BudgetImpl::try_from_configs(
             cpu_limit,
             mem_limit,
             cpu_cost_params,
             mem_cost_params,
-        )?))))
+        )?)))
     }
 
-    // Helper function to avoid panics from multiple borrow_muts
-    fn with_mut_budget<T, F>(&self, f: F) -> Result<T, HostError>
-    where
-        F: FnOnce(RefMut<BudgetImpl>) -> Result<T, HostError>,
-    {
-        f(self.0.try_borrow_mut_or_err()?)
+    // Switches the budget to (or back from) shadow mode, returning whether it
+    // was in shadow mode before.
+    fn set_shadow_mode(&self, is_in_shadow_mode: bool) -> bool {
+        self.0.is_in_shadow_mode.replace(is_in_shadow_mode)
+    }
+
+    fn check_shadow_budget_limits(&self) -> Result<(), HostError> {
+        self.0.cpu_insns.check_budget_limit(IsShadowMode(true))?;
+        self.0.mem_bytes.check_budget_limit(IsShadowMode(true))
     }
 
     /// Performs a bulk charge to the budget under the specified [`CostType`].
@@ -722,9 +813,7 @@ impl Budget {
         iterations: u64,
         input: Option<u64>,
     ) -> Result<(), HostError> {
-        self.0
-            .try_borrow_mut_or_err()?
-            .charge(ty, iterations, input)
+        self.0.charge(ty, iterations, input)
     }
 
     /// Charges the budget under the specified [`CostType`]. The actual amount
@@ -733,7 +822,7 @@ impl Budget {
     /// Otherwise it is a linear model.  The caller needs to ensure the input
     /// passed is consistent with the inherent model underneath.
     pub fn charge(&self, ty: ContractCostType, input: Option<u64>) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.charge(ty, 1, input)
+        self.0.charge(ty, 1, input)
     }
 
     /// Runs a user provided closure in shadow mode -- all metering is done
@@ -748,80 +837,90 @@ impl Budget {
     where
         F: FnOnce() -> Result<T, HostError>,
     {
-        let mut prev = false;
-
-        if self
-            .with_mut_budget(|mut b| {
-                prev = b.is_in_shadow_mode;
-                b.is_in_shadow_mode = true;
-                b.cpu_insns.check_budget_limit(IsShadowMode(true))?;
-                b.mem_bytes.check_budget_limit(IsShadowMode(true))
-            })
-            .is_ok()
-        {
+        let prev = self.set_shadow_mode(true);
+        if self.check_shadow_budget_limits().is_ok() {
             let _ = f();
         }
-
-        let _ = self.with_mut_budget(|mut b| {
-            b.is_in_shadow_mode = prev;
-            Ok(())
-        });
+        self.set_shadow_mode(prev);
     }
 
     pub(crate) fn is_in_shadow_mode(&self) -> Result<bool, HostError> {
-        Ok(self.0.try_borrow_or_err()?.is_in_shadow_mode)
+        Ok(self.0.is_in_shadow_mode.get())
     }
 
     pub(crate) fn set_shadow_limits(&self, cpu: u64, mem: u64) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.cpu_insns.shadow_limit = cpu;
-        self.0.try_borrow_mut_or_err()?.mem_bytes.shadow_limit = mem;
+        self.0.cpu_insns.shadow_limit.set(cpu);
+        self.0.mem_bytes.shadow_limit.set(mem);
         Ok(())
     }
 
     pub fn get_tracker(&self, ty: ContractCostType) -> Result<CostTracker, HostError> {
         self.0
-            .try_borrow_or_err()?
             .tracker
             .cost_tracker
             .get(ty as usize)
-            .map(|x| x.clone())
+            .map(Cell::get)
             .ok_or_else(|| (ScErrorType::Budget, ScErrorCode::InternalError).into())
     }
 
     pub fn get_time(&self, ty: ContractCostType) -> Result<u64, HostError> {
-        self.0.try_borrow_or_err()?.tracker.get_time(ty)
+        self.0.tracker.get_time(ty)
     }
 
     pub fn track_time(&self, ty: ContractCostType, duration: u64) -> Result<(), HostError> {
-        self.0
-            .try_borrow_mut_or_err()?
-            .tracker
-            .track_time(ty, duration)
+        self.0.tracker.track_time(ty, duration)
     }
 
     pub fn get_cpu_insns_consumed(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.cpu_insns.get_total_count())
+        Ok(self.0.cpu_insns.get_total_count())
     }
 
     pub fn get_mem_bytes_consumed(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.mem_bytes.get_total_count())
+        Ok(self.0.mem_bytes.get_total_count())
     }
 
     pub fn get_cpu_insns_remaining(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.cpu_insns.get_remaining())
+        Ok(self.0.cpu_insns.get_remaining())
     }
 
     pub fn get_mem_bytes_remaining(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.mem_bytes.get_remaining())
+        Ok(self.0.mem_bytes.get_remaining())
+    }
+
+    /// Sets the maximum nesting depth of the host values, which bounds the
+    /// recursion of their conversion, comparison and cloning, see
+    /// [`DEFAULT_HOST_DEPTH_LIMIT`]. Exceeding it fails with
+    /// `(Context, ExceededLimit)`. This has to be set before running any
+    /// invocations.
+    pub fn set_depth_limit(&self, depth_limit: u32) -> Result<(), HostError> {
+        self.0.depth_limit.set(depth_limit);
+        Ok(())
+    }
+
+    pub fn get_depth_limit(&self) -> Result<u32, HostError> {
+        Ok(self.0.depth_limit.get())
+    }
+
+    /// Sets the maximum total number of vec and map elements converted by a
+    /// single `ScVal`<->`Val` conversion, see
+    /// [`DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT`]. Exceeding it fails with
+    /// `(Value, ExceededLimit)`.
+    pub fn set_conversion_element_limit(&self, element_limit: u32) -> Result<(), HostError> {
+        self.0.conversion_element_limit.set(element_limit);
+        Ok(())
+    }
+
+    pub fn get_conversion_element_limit(&self) -> Result<u32, HostError> {
+        Ok(self.0.conversion_element_limit.get())
     }
 
     pub(crate) fn get_wasmi_fuel_remaining(&self) -> Result<u64, HostError> {
-        self.0.try_borrow_mut_or_err()?.get_wasmi_fuel_remaining()
+        self.0.get_wasmi_fuel_remaining()
     }
 
     // generate a wasmi fuel cost schedule based on our calibration
     pub(crate) fn wasmi_fuel_costs(&self) -> Result<wasmi::FuelCosts, HostError> {
-        let config = &self.0.try_borrow_or_err()?.fuel_config;
+        let config = self.0.fuel_config.get();
         let mut costs = wasmi::FuelCosts::default();
         costs.base = config.base;
         costs.entity = config.entity;
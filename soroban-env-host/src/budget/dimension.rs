@@ -1,12 +1,17 @@
 use super::model::{HostCostModel, MeteredCostComponent};
+#[cfg(any(test, feature = "recording_auth"))]
+use crate::xdr::{ContractCostParamEntry, ExtensionPoint};
 use crate::xdr::{ContractCostParams, ContractCostType, ScErrorCode, ScErrorType};
 use crate::{Error, HostError};
 use core::fmt::Debug;
+use std::cell::Cell;
 
 /// Helper types to annotate boolean function arguments
 pub(crate) struct IsCpu(pub(crate) bool);
 pub(crate) struct IsShadowMode(pub(crate) bool);
 
+/// All the fields are `Cell`s so that charging only takes a shared reference
+/// to the dimension, see [`super::BudgetImpl`].
 #[derive(Clone, Default)]
 pub(crate) struct BudgetDimension {
     /// A set of cost models that map input values (eg. event counts, object
@@ -14,25 +19,25 @@ pub(crate) struct BudgetDimension {
     /// tracked by this dimension (eg. cpu or memory). CostType enum values are
     /// used as indexes into this vector, to make runtime lookups as cheap as
     /// possible.
-    pub(crate) cost_models: [MeteredCostComponent; ContractCostType::variants().len()],
+    pub(crate) cost_models: [Cell<MeteredCostComponent>; ContractCostType::variants().len()],
 
     /// The limit against-which the count is compared to decide if we're
     /// over budget.
-    pub(crate) limit: u64,
+    pub(crate) limit: Cell<u64>,
 
     /// Tracks the sum of _output_ values from the cost model, for purposes
     /// of comparing to limit.
-    pub(crate) total_count: u64,
+    pub(crate) total_count: Cell<u64>,
 
     /// The shadow limit tracks work done internally that is not exposed to the
     /// external user -- it does not affect fees or decide the invocation outcome
     /// in any way (no error due to exceeding the shadow limit). It exists solely
     /// for dos prevention. Such work include diagnostic logging, or work that
     /// exists only for preflight.
-    pub(crate) shadow_limit: u64,
+    pub(crate) shadow_limit: Cell<u64>,
 
     /// Similar to `total_count`, but towards the `shadow_limit`
-    pub(crate) shadow_total_count: u64,
+    pub(crate) shadow_total_count: Cell<u64>,
 }
 
 impl Debug for BudgetDimension {
@@ -40,18 +45,20 @@ impl Debug for BudgetDimension {
         writeln!(
             f,
             "limit: {}, total_count: {}",
-            self.limit, self.total_count
+            self.limit.get(),
+            self.total_count.get()
         )?;
 
         for ct in ContractCostType::variants() {
             writeln!(f, "CostType {:?}", ct)?;
-            writeln!(f, "model: {:?}", self.cost_models[ct as usize])?;
+            writeln!(f, "model: {:?}", self.cost_models[ct as usize].get())?;
         }
 
         writeln!(
             f,
             "shadow limit: {}, shadow_total_count: {}",
-            self.shadow_limit, self.shadow_total_count
+            self.shadow_limit.get(),
+            self.shadow_total_count.get()
         )?;
         Ok(())
     }
@@ -62,8 +69,8 @@ impl BudgetDimension {
         let cost_models = cost_params
             .0
             .iter()
-            .map(|p| MeteredCostComponent::try_from(p))
-            .collect::<Result<Vec<MeteredCostComponent>, HostError>>()?;
+            .map(|p| MeteredCostComponent::try_from(p).map(Cell::new))
+            .collect::<Result<Vec<Cell<MeteredCostComponent>>, HostError>>()?;
 
         Ok(Self {
             cost_models: cost_models.try_into().map_err(|_| {
@@ -80,37 +87,81 @@ impl BudgetDimension {
         })
     }
 
-    pub(crate) fn get_cost_model(&self, ty: ContractCostType) -> Option<&MeteredCostComponent> {
-        self.cost_models.get(ty as usize)
+    #[cfg(any(test, feature = "recording_auth"))]
+    pub(crate) fn to_config(&self) -> Result<ContractCostParams, HostError> {
+        let invalid = || HostError::from((ScErrorType::Budget, ScErrorCode::InternalError));
+        let entries = self
+            .cost_models
+            .iter()
+            .map(|m| {
+                let m = m.get();
+                Ok(ContractCostParamEntry {
+                    ext: ExtensionPoint::V0,
+                    const_term: i64::try_from(m.const_term).map_err(|_| invalid())?,
+                    linear_term: i64::try_from(m.lin_term.0).map_err(|_| invalid())?,
+                })
+            })
+            .collect::<Result<Vec<_>, HostError>>()?;
+        Ok(ContractCostParams(
+            entries.try_into().map_err(|_| invalid())?,
+        ))
+    }
+
+    pub(crate) fn get_cost_model(&self, ty: ContractCostType) -> Option<MeteredCostComponent> {
+        self.cost_models.get(ty as usize).map(Cell::get)
     }
 
     pub(crate) fn get_cost_model_mut(
         &mut self,
         ty: ContractCostType,
     ) -> Option<&mut MeteredCostComponent> {
-        self.cost_models.get_mut(ty as usize)
+        self.cost_models.get_mut(ty as usize).map(Cell::get_mut)
+    }
+
+    #[cfg(any(test, feature = "testutils"))]
+    pub(crate) fn set_cost_model(
+        &self,
+        ty: ContractCostType,
+        model: MeteredCostComponent,
+    ) -> Result<(), HostError> {
+        let Some(cm) = self.cost_models.get(ty as usize) else {
+            return Err((ScErrorType::Budget, ScErrorCode::InternalError).into());
+        };
+        cm.set(model);
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "testutils", feature = "bench"))]
+    pub(crate) fn swap(&self, other: &Self) {
+        for (m, o) in self.cost_models.iter().zip(other.cost_models.iter()) {
+            m.swap(o);
+        }
+        self.limit.swap(&other.limit);
+        self.total_count.swap(&other.total_count);
+        self.shadow_limit.swap(&other.shadow_limit);
+        self.shadow_total_count.swap(&other.shadow_total_count);
     }
 
     pub(crate) fn get_total_count(&self) -> u64 {
-        self.total_count
+        self.total_count.get()
     }
 
     pub(crate) fn get_remaining(&self) -> u64 {
-        self.limit.saturating_sub(self.total_count)
+        self.limit.get().saturating_sub(self.total_count.get())
     }
 
-    pub(crate) fn reset(&mut self, limit: u64) {
-        self.limit = limit;
-        self.total_count = 0;
-        self.shadow_limit = limit;
-        self.shadow_total_count = 0;
+    pub(crate) fn reset(&self, limit: u64) {
+        self.limit.set(limit);
+        self.total_count.set(0);
+        self.shadow_limit.set(limit);
+        self.shadow_total_count.set(0);
     }
 
     pub(crate) fn check_budget_limit(&self, is_shadow: IsShadowMode) -> Result<(), HostError> {
         let over_limit = if is_shadow.0 {
-            self.shadow_total_count > self.shadow_limit
+            self.shadow_total_count.get() > self.shadow_limit.get()
         } else {
-            self.total_count > self.limit
+            self.total_count.get() > self.limit.get()
         };
 
         if over_limit {
@@ -127,7 +178,7 @@ impl BudgetDimension {
     /// model, and amount charged is iterations * const_term.
     /// Returns the amount charged.
     pub(crate) fn charge(
-        &mut self,
+        &self,
         ty: ContractCostType,
         iterations: u64,
         input: Option<u64>,
@@ -147,9 +198,11 @@ impl BudgetDimension {
         }
 
         if is_shadow.0 {
-            self.shadow_total_count = self.shadow_total_count.saturating_add(amount);
+            self.shadow_total_count
+                .set(self.shadow_total_count.get().saturating_add(amount));
         } else {
-            self.total_count = self.total_count.saturating_add(amount);
+            self.total_count
+                .set(self.total_count.get().saturating_add(amount));
         }
 
         Ok(amount)
@@ -157,9 +210,11 @@ impl BudgetDimension {
 
     // Resets all model parameters to zero (so that we can override and test individual ones later).
     #[cfg(any(test, feature = "testutils", feature = "bench"))]
-    pub(crate) fn reset_models(&mut self) {
-        for model in &mut self.cost_models {
-            model.reset()
+    pub(crate) fn reset_models(&self) {
+        for model in &self.cost_models {
+            let mut m = model.get();
+            m.reset();
+            model.set(m);
         }
     }
 }
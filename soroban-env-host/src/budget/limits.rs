@@ -1,6 +1,5 @@
 use crate::{
-    budget::{Budget, BudgetImpl},
-    host::error::TryBorrowOrErr,
+    budget::Budget,
     xdr::{Limits, ScErrorCode, ScErrorType},
     Error, HostError,
 };
@@ -40,6 +39,13 @@ pub const DEFAULT_XDR_RW_LIMITS: Limits = Limits {
 ///   XDR limit.
 pub const DEFAULT_HOST_DEPTH_LIMIT: u32 = 100;
 
+/// - `DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT`: The maximum total number of vec
+///   and map elements converted by a single `ScVal`<->`Val` conversion. The
+///   conversions are metered, so by default this is not limited beyond what
+///   the budget allows. Embedders may lower it with
+///   `Budget::set_conversion_element_limit`.
+pub const DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT: u32 = u32::MAX;
+
 // These are some sane values, however the embedder should typically customize
 // these to match the network config.
 pub(crate) const DEFAULT_CPU_INSN_LIMIT: u64 = 100_000_000;
@@ -79,10 +85,11 @@ pub(crate) trait DepthLimiter {
     }
 }
 
-impl DepthLimiter for BudgetImpl {
+impl DepthLimiter for Budget {
     fn enter(&mut self) -> Result<(), HostError> {
-        if let Some(depth) = self.depth_limit.checked_sub(1) {
-            self.depth_limit = depth;
+        let depth_limit = &self.0.depth_limit;
+        if let Some(depth) = depth_limit.get().checked_sub(1) {
+            depth_limit.set(depth);
         } else {
             return Err(Error::from_type_and_code(
                 ScErrorType::Context,
@@ -96,19 +103,75 @@ impl DepthLimiter for BudgetImpl {
     // `leave` should be called in tandem with `enter` such that the depth
     // doesn't exceed the initial depth limit.
     fn leave(&mut self) -> Result<(), HostError> {
-        self.depth_limit = self.depth_limit.checked_add(1).ok_or_else(|| {
+        let depth_limit = &self.0.depth_limit;
+        depth_limit.set(depth_limit.get().checked_add(1).ok_or_else(|| {
             Error::from_type_and_code(ScErrorType::Context, ScErrorCode::InternalError)
-        })?;
+        })?);
         Ok(())
     }
 }
 
-impl DepthLimiter for Budget {
-    fn enter(&mut self) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.enter()
+impl Budget {
+    // Runs `f`, a `ScVal`<->`Val` conversion or a part of it, within the
+    // conversion limits. The outermost conversion starts counting the
+    // converted elements against the element limit.
+    //
+    // The nested conversions performed by the common crate replace the host
+    // errors with a `ConversionError`, which would make the limit errors
+    // indistinguishable from the malformed values. To prevent that, when
+    // `keep_limit_error` is set the first limit error hit by the conversion is
+    // kept aside and can be retrieved with `conversion_limit_error`.
+    pub(crate) fn with_conversion_limits<T, F>(
+        &self,
+        keep_limit_error: bool,
+        f: F,
+    ) -> Result<T, HostError>
+    where
+        F: FnOnce() -> Result<T, HostError>,
+    {
+        let b = &self.0;
+        let is_outermost = b.conversion_elements_left.get().is_none();
+        if is_outermost {
+            b.conversion_elements_left
+                .set(Some(b.conversion_element_limit.get()));
+            b.conversion_limit_error.set(None);
+        }
+        let res = f();
+        if let (Err(e), true) = (&res, keep_limit_error) {
+            if e.error.is_code(ScErrorCode::ExceededLimit)
+                && (e.error.is_type(ScErrorType::Context) || e.error.is_type(ScErrorType::Value))
+                && b.conversion_limit_error.get().is_none()
+            {
+                b.conversion_limit_error.set(Some(e.error));
+            }
+        }
+        if is_outermost {
+            b.conversion_elements_left.set(None);
+        }
+        res
     }
 
-    fn leave(&mut self) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.leave()
+    // Returns the limit error hit by the current conversion, if any.
+    pub(crate) fn conversion_limit_error(&self) -> Result<Option<Error>, HostError> {
+        Ok(self.0.conversion_limit_error.get())
+    }
+
+    // Counts `count` elements converted by the current conversion against
+    // the element limit. Fails with `(Value, ExceededLimit)` once the limit is
+    // exceeded.
+    pub(crate) fn charge_conversion_elements(&self, count: usize) -> Result<(), HostError> {
+        let elements_left = &self.0.conversion_elements_left;
+        if let Some(left) = elements_left.get() {
+            let count = u32::try_from(count).unwrap_or(u32::MAX);
+            let Some(left) = left.checked_sub(count) else {
+                return Err(Error::from_type_and_code(
+                    ScErrorType::Value,
+                    ScErrorCode::ExceededLimit,
+                )
+                .into());
+            };
+            elements_left.set(Some(left));
+        }
+        Ok(())
     }
 }
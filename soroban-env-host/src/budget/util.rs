@@ -6,81 +6,46 @@
 ))]
 use crate::{budget::Budget, HostError};
 
-#[cfg(any(test, feature = "testutils", feature = "bench"))]
-use crate::host::error::TryBorrowOrErr;
+#[cfg(any(test, feature = "recording_auth"))]
+use crate::xdr::ContractCostParams;
 
 #[cfg(any(test, feature = "testutils"))]
-use crate::{budget::model::ScaledU64, xdr::ContractCostType};
+use crate::{
+    budget::model::{MeteredCostComponent, ScaledU64},
+    xdr::ContractCostType,
+};
 
 #[cfg(any(test, feature = "testutils", feature = "bench"))]
 impl Budget {
     pub fn reset_models(&self) -> Result<(), HostError> {
-        self.with_mut_budget(|mut b| {
-            b.cpu_insns.reset_models();
-            b.mem_bytes.reset_models();
-            Ok(())
-        })
+        self.0.cpu_insns.reset_models();
+        self.0.mem_bytes.reset_models();
+        Ok(())
     }
 
     pub(crate) fn track_wasm_mem_alloc(&self, delta: u64) -> Result<(), HostError> {
-        let mut bgt = self.0.try_borrow_mut_or_err()?;
-        bgt.tracker.wasm_memory = bgt.tracker.wasm_memory.saturating_add(delta);
+        let wasm_memory = &self.0.tracker.wasm_memory;
+        wasm_memory.set(wasm_memory.get().saturating_add(delta));
         Ok(())
     }
 
     pub fn get_wasm_mem_alloc(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.tracker.wasm_memory)
+        Ok(self.0.tracker.wasm_memory.get())
     }
 
     pub fn reset_default(&self) -> Result<(), HostError> {
-        *self.0.try_borrow_mut_or_err()? = super::BudgetImpl::default();
-        Ok(())
-    }
-
-    pub fn reset_unlimited(&self) -> Result<(), HostError> {
-        self.reset_unlimited_cpu()?;
-        self.reset_unlimited_mem()?;
+        self.0.reset_to(super::BudgetImpl::default());
         Ok(())
     }
 
-    pub fn reset_unlimited_cpu(&self) -> Result<(), HostError> {
-        self.with_mut_budget(|mut b| {
-            b.cpu_insns.reset(u64::MAX);
-            Ok(())
-        })?; // panic means multiple-mut-borrow bug
-        self.reset_tracker()
-    }
-
-    pub fn reset_unlimited_mem(&self) -> Result<(), HostError> {
-        self.with_mut_budget(|mut b| {
-            b.mem_bytes.reset(u64::MAX);
-            Ok(())
-        })?;
-        self.reset_tracker()
-    }
-
     pub fn cpu_limit_exceeded(&self) -> Result<bool, HostError> {
-        let cpu = &self.0.try_borrow_or_err()?.cpu_insns;
-        Ok(cpu.total_count > cpu.limit)
+        let cpu = &self.0.cpu_insns;
+        Ok(cpu.total_count.get() > cpu.limit.get())
     }
 
     pub fn mem_limit_exceeded(&self) -> Result<bool, HostError> {
-        let mem = &self.0.try_borrow_or_err()?.mem_bytes;
-        Ok(mem.total_count > mem.limit)
-    }
-
-    pub fn reset_tracker(&self) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.tracker.reset();
-        Ok(())
-    }
-
-    pub fn reset_limits(&self, cpu: u64, mem: u64) -> Result<(), HostError> {
-        self.with_mut_budget(|mut b| {
-            b.cpu_insns.reset(cpu);
-            b.mem_bytes.reset(mem);
-            Ok(())
-        })?;
-        self.reset_tracker()
+        let mem = &self.0.mem_bytes;
+        Ok(mem.total_count.get() > mem.limit.get())
     }
 
     /// Resets the `FuelConfig` we pass into Wasmi before running calibration.
@@ -91,27 +56,85 @@ impl Budget {
     /// of a specific fuel category. In order to get the correct, unscaled fuel
     /// count, we have to preset all the `FuelConfig` entries to 1.
     pub fn reset_fuel_config(&self) -> Result<(), HostError> {
-        self.0.try_borrow_mut_or_err()?.fuel_config.reset();
+        let mut fuel_config = self.0.fuel_config.get();
+        fuel_config.reset();
+        self.0.fuel_config.set(fuel_config);
         Ok(())
     }
 
     pub fn get_shadow_cpu_insns_consumed(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.cpu_insns.shadow_total_count)
+        Ok(self.0.cpu_insns.shadow_total_count.get())
     }
 
     pub fn get_shadow_mem_bytes_consumed(&self) -> Result<u64, HostError> {
-        Ok(self.0.try_borrow_or_err()?.mem_bytes.shadow_total_count)
+        Ok(self.0.mem_bytes.shadow_total_count.get())
     }
 
     #[allow(unused)]
     pub fn shadow_cpu_limit_exceeded(&self) -> Result<bool, HostError> {
-        let cpu = &self.0.try_borrow_or_err()?.cpu_insns;
-        Ok(cpu.shadow_total_count > cpu.shadow_limit)
+        let cpu = &self.0.cpu_insns;
+        Ok(cpu.shadow_total_count.get() > cpu.shadow_limit.get())
     }
 
     pub fn shadow_mem_limit_exceeded(&self) -> Result<bool, HostError> {
-        let mem = &self.0.try_borrow_or_err()?.mem_bytes;
-        Ok(mem.shadow_total_count > mem.shadow_limit)
+        let mem = &self.0.mem_bytes;
+        Ok(mem.shadow_total_count.get() > mem.shadow_limit.get())
+    }
+}
+
+// These are also used in preflight (see `Host::preflight_invocation`), which
+// runs with an unlimited budget in order to measure the actual resource usage.
+#[cfg(any(
+    test,
+    feature = "testutils",
+    feature = "bench",
+    feature = "recording_auth"
+))]
+impl Budget {
+    pub fn reset_limits(&self, cpu: u64, mem: u64) -> Result<(), HostError> {
+        self.0.cpu_insns.reset(cpu);
+        self.0.mem_bytes.reset(mem);
+        self.reset_tracker()
+    }
+
+    pub fn reset_unlimited(&self) -> Result<(), HostError> {
+        self.reset_unlimited_cpu()?;
+        self.reset_unlimited_mem()?;
+        Ok(())
+    }
+
+    pub fn reset_unlimited_cpu(&self) -> Result<(), HostError> {
+        self.0.cpu_insns.reset(u64::MAX);
+        self.reset_tracker()
+    }
+
+    pub fn reset_unlimited_mem(&self) -> Result<(), HostError> {
+        self.0.mem_bytes.reset(u64::MAX);
+        self.reset_tracker()
+    }
+
+    pub fn reset_tracker(&self) -> Result<(), HostError> {
+        self.0.tracker.reset();
+        Ok(())
+    }
+}
+
+// This is also used to detach the state of a host, see
+// `Host::detach_state`.
+#[cfg(any(test, feature = "recording_auth"))]
+impl Budget {
+    /// Returns the CPU and memory limits and cost parameters of the budget,
+    /// in the form accepted by [`Budget::try_from_configs`].
+    pub fn to_configs(
+        &self,
+    ) -> Result<(u64, u64, ContractCostParams, ContractCostParams), HostError> {
+        let b = &self.0;
+        Ok((
+            b.cpu_insns.limit.get(),
+            b.mem_bytes.limit.get(),
+            b.cpu_insns.to_config()?,
+            b.mem_bytes.to_config()?,
+        ))
     }
 }
 
@@ -125,22 +148,20 @@ impl Budget {
         const_mem: u64,
         lin_mem: ScaledU64,
     ) -> Result<(), HostError> {
-        use crate::xdr::{ScErrorCode, ScErrorType};
-
-        let mut bgt = self.0.try_borrow_mut_or_err()?;
-
-        let Some(cpu_model) = bgt.cpu_insns.get_cost_model_mut(ty) else {
-            return Err((ScErrorType::Budget, ScErrorCode::InternalError).into());
-        };
-        cpu_model.const_term = const_cpu;
-        cpu_model.lin_term = lin_cpu;
-
-        let Some(mem_model) = bgt.mem_bytes.get_cost_model_mut(ty) else {
-            return Err((ScErrorType::Budget, ScErrorCode::InternalError).into());
-        };
-        mem_model.const_term = const_mem;
-        mem_model.lin_term = lin_mem;
-        Ok(())
+        self.0.cpu_insns.set_cost_model(
+            ty,
+            MeteredCostComponent {
+                const_term: const_cpu,
+                lin_term: lin_cpu,
+            },
+        )?;
+        self.0.mem_bytes.set_cost_model(
+            ty,
+            MeteredCostComponent {
+                const_term: const_mem,
+                lin_term: lin_mem,
+            },
+        )
     }
 
     pub(crate) fn override_model_with_unscaled_params(
@@ -186,26 +207,12 @@ impl Budget {
     where
         F: FnOnce() -> Result<T, HostError>,
     {
-        let mut prev = false;
-        let should_execute = self.with_mut_budget(|mut b| {
-            prev = b.is_in_shadow_mode;
-            b.is_in_shadow_mode = true;
-            b.cpu_insns
-                .check_budget_limit(super::dimension::IsShadowMode(true))?;
-            b.mem_bytes
-                .check_budget_limit(super::dimension::IsShadowMode(true))
-        });
-
-        let rt = match should_execute {
+        let prev = self.set_shadow_mode(true);
+        let rt = match self.check_shadow_budget_limits() {
             Ok(_) => f(),
             Err(e) => Err(e),
         };
-
-        self.with_mut_budget(|mut b| {
-            b.is_in_shadow_mode = prev;
-            Ok(())
-        })?;
-
+        self.set_shadow_mode(prev);
         rt
     }
 }
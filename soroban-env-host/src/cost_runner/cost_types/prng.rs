@@ -1,11 +1,14 @@
 use std::hint::black_box;
 
+use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 
 use crate::{
-    cost_runner::{CostRunner, CostType},
-    host::crypto::chacha20_fill_bytes,
+    budget::CostTracker,
+    cost_runner::{experimental::ExperimentalCostType, CostRunner, CostType},
+    host::crypto::{chacha20_fill_bytes, unbias_prng_seed},
     xdr::ContractCostType::ChaCha20DrawBytes,
+    Seed,
 };
 
 pub struct ChaCha20DrawBytesRun;
@@ -38,3 +41,42 @@ impl CostRunner for ChaCha20DrawBytesRun {
         black_box(sample)
     }
 }
+
+// Measures the fixed cost of setting up a PRNG from a seed (unbiasing the
+// seed and initializing the ChaCha20 state), which a `ChaCha20DrawBytes`
+// calibration over large draws cannot separate from the per-byte cost.
+pub struct ChaCha20InitSeedRun;
+
+impl CostRunner for ChaCha20InitSeedRun {
+    const COST_TYPE: CostType = CostType::Experimental(ExperimentalCostType::ChaCha20InitSeed);
+
+    const RUN_ITERATIONS: u64 = 100;
+
+    type SampleType = Seed;
+
+    type RecycledType = (Seed, Option<ChaCha20Rng>);
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let rng = unbias_prng_seed(&sample, host)
+            .map(ChaCha20Rng::from_seed)
+            .expect("chacha20 init seed");
+        black_box((sample, Some(rng)))
+    }
+
+    fn get_tracker(_host: &crate::Host) -> CostTracker {
+        CostTracker {
+            iterations: Self::RUN_ITERATIONS,
+            inputs: None,
+            cpu: 0,
+            mem: 0,
+        }
+    }
+
+    fn run_baseline_iter(
+        _host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box((sample, None))
+    }
+}
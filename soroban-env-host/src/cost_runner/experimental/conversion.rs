@@ -0,0 +1,110 @@
+use std::{cell::Cell, hint::black_box};
+
+use crate::{
+    budget::CostTracker,
+    cost_runner::{CostRunner, CostType},
+    xdr::ScVal,
+    Host, Val,
+};
+
+use super::ExperimentalCostType;
+
+// The input of the conversion cost types is the number of container elements
+// converted, which the host does not track: it only charges the memory the
+// conversion allocates and copies. So the runners count the elements they
+// convert here, and hand the count over in `get_tracker`.
+thread_local! {
+    static CONVERTED_ELEMENTS: Cell<u64> = const { Cell::new(0) };
+}
+
+fn count_converted_elements(v: &ScVal) {
+    let len = match v {
+        ScVal::Vec(Some(v)) => v.len(),
+        ScVal::Map(Some(m)) => m.len(),
+        _ => 0,
+    };
+    CONVERTED_ELEMENTS.with(|c| c.set(c.get() + len as u64));
+}
+
+fn take_converted_elements_tracker(iterations: u64) -> CostTracker {
+    CostTracker {
+        iterations,
+        inputs: Some(CONVERTED_ELEMENTS.with(|c| c.take())),
+        cpu: 0,
+        mem: 0,
+    }
+}
+
+macro_rules! impl_to_host_conversion_runner {
+    ($runner:ident, $cost:ident) => {
+        /// Measures converting an `ScVal` container into a host object, per
+        /// element of the container.
+        pub struct $runner;
+
+        impl CostRunner for $runner {
+            const COST_TYPE: CostType = CostType::Experimental(ExperimentalCostType::$cost);
+
+            type SampleType = ScVal;
+
+            type RecycledType = (Option<Val>, ScVal);
+
+            fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+                count_converted_elements(&sample);
+                let v = black_box(host.to_host_val(&sample).unwrap());
+                (Some(v), sample)
+            }
+
+            fn run_baseline_iter(
+                _host: &Host,
+                _iter: u64,
+                sample: Self::SampleType,
+            ) -> Self::RecycledType {
+                black_box((None, sample))
+            }
+
+            fn get_tracker(_host: &Host) -> CostTracker {
+                take_converted_elements_tracker(Self::RUN_ITERATIONS)
+            }
+        }
+    };
+}
+
+macro_rules! impl_from_host_conversion_runner {
+    ($runner:ident, $cost:ident) => {
+        /// Measures converting a host container object into an `ScVal`, per
+        /// element of the container.
+        pub struct $runner;
+
+        impl CostRunner for $runner {
+            const COST_TYPE: CostType = CostType::Experimental(ExperimentalCostType::$cost);
+
+            type SampleType = Val;
+
+            type RecycledType = Option<ScVal>;
+
+            fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+                let v = black_box(host.from_host_val(sample).unwrap());
+                count_converted_elements(&v);
+                Some(v)
+            }
+
+            fn run_baseline_iter(
+                _host: &Host,
+                _iter: u64,
+                sample: Self::SampleType,
+            ) -> Self::RecycledType {
+                black_box(sample);
+                None
+            }
+
+            fn get_tracker(_host: &Host) -> CostTracker {
+                take_converted_elements_tracker(Self::RUN_ITERATIONS)
+            }
+        }
+    };
+}
+
+impl_to_host_conversion_runner!(ScVecToHostVecRun, ScVecToHostVec);
+impl_to_host_conversion_runner!(ScMapToHostMapRun, ScMapToHostMap);
+impl_from_host_conversion_runner!(ScVecFromHostVecRun, ScVecFromHostVec);
+impl_from_host_conversion_runner!(ScMapFromHostMapRun, ScMapFromHostMap);
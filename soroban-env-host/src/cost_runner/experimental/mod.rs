@@ -1,6 +1,8 @@
+mod conversion;
 mod ed25519_scalar_mut;
 mod read_xdr;
 
+pub use conversion::*;
 pub use ed25519_scalar_mut::*;
 pub use read_xdr::*;
 
@@ -11,6 +13,11 @@ use core::fmt;
 pub enum ExperimentalCostType {
     EdwardsPointCurve25519ScalarMul,
     ReadXdrByteArray,
+    ScVecToHostVec,
+    ScMapToHostMap,
+    ScVecFromHostVec,
+    ScMapFromHostMap,
+    ChaCha20InitSeed,
 }
 
 impl Name for ExperimentalCostType {
@@ -20,6 +27,11 @@ impl Name for ExperimentalCostType {
                 "EdwardsPointCurve25519ScalarMul"
             }
             ExperimentalCostType::ReadXdrByteArray => "ReadXdrByteArray",
+            ExperimentalCostType::ScVecToHostVec => "ScVecToHostVec",
+            ExperimentalCostType::ScMapToHostMap => "ScMapToHostMap",
+            ExperimentalCostType::ScVecFromHostVec => "ScVecFromHostVec",
+            ExperimentalCostType::ScMapFromHostMap => "ScMapFromHostMap",
+            ExperimentalCostType::ChaCha20InitSeed => "ChaCha20InitSeed",
         }
     }
 }
@@ -83,7 +83,7 @@ pub struct LedgerEntryLiveUntilChange {
 /// Returns the difference between the `storage` and its initial snapshot as
 /// `LedgerEntryChanges`.
 /// Returns an entry for every item in `storage` footprint.
-pub fn get_ledger_changes<T: SnapshotSource>(
+pub fn get_ledger_changes<T: SnapshotSource + ?Sized>(
     budget: &Budget,
     storage: &Storage,
     init_storage_snapshot: &T,
@@ -336,6 +336,136 @@ fn extract_diagnostic_events(events: &Events, diagnostic_events: &mut Vec<Diagno
     }
 }
 
+/// A historical invocation to be replayed with [`replay_invocation`], as
+/// recorded in the ledger: the operation itself, the relevant parts of its
+/// transaction and the value it has returned.
+#[cfg(any(test, feature = "testutils"))]
+pub struct RecordedInvocation {
+    /// The invoked operation, with its authorization entries.
+    pub op: crate::xdr::InvokeHostFunctionOp,
+    /// Source account of the operation.
+    pub source_account: AccountId,
+    /// Resources declared by the transaction, including the footprint.
+    pub resources: SorobanResources,
+    /// PRNG seed the transaction has been applied with.
+    pub base_prng_seed: [u8; 32],
+    /// Returned value of the invocation, or `None` if it has failed.
+    pub recorded_result: Option<crate::xdr::ScVal>,
+}
+
+/// Divergence of a replayed invocation from its recorded outcome.
+#[cfg(any(test, feature = "testutils"))]
+#[derive(Debug)]
+pub enum ReplayDivergence {
+    /// Both invocations have succeeded, but with different values.
+    ResultMismatch {
+        recorded: crate::xdr::ScVal,
+        replayed: crate::xdr::ScVal,
+    },
+    /// The recorded invocation has failed, but the replayed one succeeded.
+    UnexpectedSuccess { replayed: crate::xdr::ScVal },
+    /// The recorded invocation has succeeded, but the replayed one failed.
+    UnexpectedFailure { recorded: crate::xdr::ScVal },
+}
+
+/// Result of [`replay_invocation`].
+#[cfg(any(test, feature = "testutils"))]
+pub struct ReplayResult {
+    /// Outcome of the replayed invocation, as returned by
+    /// [`invoke_host_function`].
+    pub invoke_result: InvokeHostFunctionResult,
+    /// Divergence from the recorded outcome, `None` if both match.
+    pub divergence: Option<ReplayDivergence>,
+}
+
+/// Replays a historical invocation on top of the ledger state it has been
+/// applied to, under the given protocol version and the cost parameters
+/// configured in `budget`, and reports whether its outcome diverges from the
+/// recorded one.
+///
+/// `snapshot` is typically read with
+/// [`LedgerSnapshot::read_file`](crate::testutils::LedgerSnapshot::read_file)
+/// and has to contain the footprint entries of the invocation; the remaining
+/// entries are ignored, so the whole ledger state may be provided. The ledger
+/// info is taken from the snapshot as well, except for its protocol version.
+///
+/// This may only fail under the same conditions as [`invoke_host_function`].
+#[cfg(any(test, feature = "testutils"))]
+pub fn replay_invocation(
+    budget: &Budget,
+    snapshot: &crate::testutils::LedgerSnapshot,
+    invocation: &RecordedInvocation,
+    protocol_version: u32,
+) -> Result<ReplayResult, HostError> {
+    use crate::xdr::{Hash, ReadXdr, ScVal, WriteXdr};
+    use crate::DEFAULT_XDR_RW_LIMITS;
+    use sha2::{Digest, Sha256};
+
+    // The inputs are prepared outside of the invocation, so they are encoded
+    // without metering in order to not affect the replayed budget.
+    fn encode(value: &impl WriteXdr) -> Result<Vec<u8>, HostError> {
+        Ok(value.to_xdr(DEFAULT_XDR_RW_LIMITS)?)
+    }
+
+    let footprint = &invocation.resources.footprint;
+    let mut encoded_ledger_entries = vec![];
+    let mut encoded_ttl_entries = vec![];
+    for (key, (entry, live_until)) in snapshot.entries.iter() {
+        if !footprint.read_only.as_vec().contains(key.as_ref())
+            && !footprint.read_write.as_vec().contains(key.as_ref())
+        {
+            continue;
+        }
+        encoded_ledger_entries.push(encode(entry.as_ref())?);
+        encoded_ttl_entries.push(match live_until {
+            Some(live_until_ledger_seq) => encode(&TtlEntry {
+                key_hash: Hash(Sha256::digest(encode(key.as_ref())?).into()),
+                live_until_ledger_seq: *live_until_ledger_seq,
+            })?,
+            None => vec![],
+        });
+    }
+    let encoded_auth_entries = invocation
+        .op
+        .auth
+        .as_vec()
+        .iter()
+        .map(encode)
+        .collect::<Result<Vec<_>, HostError>>()?;
+    let mut ledger_info = snapshot.ledger_info.clone();
+    ledger_info.protocol_version = protocol_version;
+
+    let invoke_result = invoke_host_function(
+        budget,
+        false,
+        encode(&invocation.op.host_function)?,
+        encode(&invocation.resources)?,
+        encode(&invocation.source_account)?,
+        encoded_auth_entries.into_iter(),
+        ledger_info,
+        encoded_ledger_entries.into_iter(),
+        encoded_ttl_entries.into_iter(),
+        invocation.base_prng_seed.to_vec(),
+        &mut vec![],
+    )?;
+    let replayed = match &invoke_result.encoded_invoke_result {
+        Ok(encoded) => Some(ScVal::from_xdr(encoded, DEFAULT_XDR_RW_LIMITS)?),
+        Err(_) => None,
+    };
+    let divergence = match (invocation.recorded_result.clone(), replayed) {
+        (Some(recorded), Some(replayed)) if recorded != replayed => {
+            Some(ReplayDivergence::ResultMismatch { recorded, replayed })
+        }
+        (None, Some(replayed)) => Some(ReplayDivergence::UnexpectedSuccess { replayed }),
+        (Some(recorded), None) => Some(ReplayDivergence::UnexpectedFailure { recorded }),
+        _ => None,
+    };
+    Ok(ReplayResult {
+        invoke_result,
+        divergence,
+    })
+}
+
 fn ledger_entry_to_ledger_key(le: &LedgerEntry, budget: &Budget) -> Result<LedgerKey, HostError> {
     match &le.data {
         LedgerEntryData::Account(a) => Ok(LedgerKey::Account(LedgerKeyAccount {
@@ -473,6 +603,279 @@ impl Host {
     }
 }
 
+/// Consolidated resource estimate for a host function invocation, produced
+/// by [`Host::preflight_invocation`]. This has all the information necessary
+/// for populating the Soroban resources and authorization entries of a
+/// transaction.
+#[cfg(any(test, feature = "recording_auth"))]
+pub struct PreflightInvocationResult {
+    /// Result value of the function, or error.
+    pub invoke_result: Result<crate::xdr::ScVal, HostError>,
+    /// Recorded footprint, consumed CPU instructions and the number of bytes
+    /// read from and written to the ledger.
+    pub resources: SorobanResources,
+    /// Memory consumed by the invocation, in bytes.
+    pub memory_bytes: u64,
+    /// Total size of the emitted contract events encoded as `ContractEvent`
+    /// XDR (events from the failed calls are not counted).
+    pub contract_events_size_bytes: u32,
+    /// Recorded authorization entries. Entries with `Address` credentials have
+    /// to be signed (and have their signature expiration ledger set) before
+    /// being used in a transaction.
+    pub auth_entries: Vec<SorobanAuthorizationEntry>,
+}
+
+#[cfg(any(test, feature = "recording_auth"))]
+impl Host {
+    /// Runs the host function in 'preflight' mode and returns the estimated
+    /// resources necessary for running it in a transaction.
+    ///
+    /// The host has to be set up with a storage in
+    /// [`FootprintMode::Recording`] and is expected to be fresh, as the
+    /// recorded footprint covers all the storage accesses made via it.
+    /// Preflight switches the host to the recording authorization mode and
+    /// resets its budget to be unlimited, so that the actual resource
+    /// consumption can be measured. The invocation result is returned within
+    /// the `Ok(PreflightInvocationResult)`, together with the estimates that
+    /// are still available in case of failure.
+    pub fn preflight_invocation(
+        &self,
+        hf: HostFunction,
+    ) -> Result<PreflightInvocationResult, HostError> {
+        self.preflight_invocation_with_auth(hf, None)
+    }
+
+    // Runs the preflight with either recording authorization (when
+    // `auth_entries` is `None`) or enforcing the provided entries, which are
+    // then returned as the authorization entries of the result.
+    fn preflight_invocation_with_auth(
+        &self,
+        hf: HostFunction,
+        auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    ) -> Result<PreflightInvocationResult, HostError> {
+        use crate::{
+            storage::FootprintMode,
+            xdr::{ScVal, SorobanAddressCredentials, SorobanCredentials},
+        };
+
+        let snapshot = match &self.try_borrow_storage()?.mode {
+            FootprintMode::Recording(src) => Rc::clone(src),
+            FootprintMode::Enforcing => {
+                return Err(self.err(
+                    ScErrorType::Storage,
+                    ScErrorCode::InvalidAction,
+                    "preflight requires storage in recording mode",
+                    &[],
+                ));
+            }
+        };
+        match &auth_entries {
+            Some(entries) => self.set_authorization_entries(entries.clone())?,
+            None => self.switch_to_recording_auth(true)?,
+        }
+        self.budget_ref().reset_unlimited()?;
+
+        let invoke_result = self.invoke_function(hf);
+        // Measure the budget before doing any preflight-only work.
+        let cpu_insns = self.budget_ref().get_cpu_insns_consumed()?;
+        let memory_bytes = self.budget_ref().get_mem_bytes_consumed()?;
+
+        let to_u32 = |v: u64| {
+            u32::try_from(v).map_err(|_| {
+                self.err(
+                    ScErrorType::Budget,
+                    ScErrorCode::ExceededLimit,
+                    "preflight resource estimate does not fit into u32",
+                    &[],
+                )
+            })
+        };
+        let mut read_only = vec![];
+        let mut read_write = vec![];
+        let mut read_bytes: u64 = 0;
+        let mut write_bytes: u64 = 0;
+        {
+            let storage = self.try_borrow_storage()?;
+            for (key, access_type) in storage.footprint.0.iter(self.as_budget())? {
+                if snapshot.has(key)? {
+                    let mut buf = vec![];
+                    metered_write_xdr(self.as_budget(), snapshot.get(key)?.0.as_ref(), &mut buf)?;
+                    read_bytes = read_bytes.saturating_add(buf.len() as u64);
+                }
+                match access_type {
+                    AccessType::ReadOnly => read_only.push(key.as_ref().metered_clone(self)?),
+                    AccessType::ReadWrite => {
+                        if let Some(Some((entry, _))) =
+                            storage.map.get::<Rc<LedgerKey>>(key, self.as_budget())?
+                        {
+                            let mut buf = vec![];
+                            metered_write_xdr(self.as_budget(), entry.as_ref(), &mut buf)?;
+                            write_bytes = write_bytes.saturating_add(buf.len() as u64);
+                        }
+                        read_write.push(key.as_ref().metered_clone(self)?)
+                    }
+                }
+            }
+        }
+        let resources = SorobanResources {
+            footprint: LedgerFootprint {
+                read_only: self.map_err(read_only.try_into())?,
+                read_write: self.map_err(read_write.try_into())?,
+            },
+            instructions: to_u32(cpu_insns)?,
+            read_bytes: to_u32(read_bytes)?,
+            write_bytes: to_u32(write_bytes)?,
+        };
+
+        let events = self.get_events()?;
+        let contract_events_size_bytes = encode_contract_events(self.as_budget(), &events)?
+            .iter()
+            .fold(0_u64, |acc, e| acc.saturating_add(e.len() as u64));
+
+        if let Some(auth_entries) = auth_entries {
+            return Ok(PreflightInvocationResult {
+                invoke_result,
+                resources,
+                memory_bytes,
+                contract_events_size_bytes: to_u32(contract_events_size_bytes)?,
+                auth_entries,
+            });
+        }
+        let auth_entries = self
+            .get_recorded_auth_payloads()?
+            .into_iter()
+            .map(|payload| {
+                let credentials = match (payload.address, payload.nonce) {
+                    (Some(address), Some(nonce)) => {
+                        SorobanCredentials::Address(SorobanAddressCredentials {
+                            address,
+                            nonce,
+                            signature_expiration_ledger: 0,
+                            signature: ScVal::Void,
+                        })
+                    }
+                    (None, None) => SorobanCredentials::SourceAccount,
+                    _ => {
+                        return Err(self.err(
+                            ScErrorType::Auth,
+                            ScErrorCode::InternalError,
+                            "recorded auth payload has inconsistent address and nonce",
+                            &[],
+                        ))
+                    }
+                };
+                Ok(SorobanAuthorizationEntry {
+                    credentials,
+                    root_invocation: payload.invocation,
+                })
+            })
+            .collect::<Result<Vec<SorobanAuthorizationEntry>, HostError>>()?;
+
+        Ok(PreflightInvocationResult {
+            invoke_result,
+            resources,
+            memory_bytes,
+            contract_events_size_bytes: to_u32(contract_events_size_bytes)?,
+            auth_entries,
+        })
+    }
+}
+
+/// Result of [`Host::simulate_transaction`]: the preflight result, together
+/// with the transaction resources and the fees they incur.
+#[cfg(any(test, feature = "recording_auth"))]
+pub struct SimulateTransactionResult {
+    pub preflight: PreflightInvocationResult,
+    /// Resources to declare in the transaction, as used for the fees.
+    pub transaction_resources: crate::fees::TransactionResources,
+    /// Non-refundable resource fee, see
+    /// [`compute_transaction_resource_fee`](crate::fees::compute_transaction_resource_fee).
+    pub non_refundable_fee: i64,
+    /// Refundable resource fee, excluding rent.
+    pub refundable_fee: i64,
+    /// Rent fee for the entries created, grown or extended by the invocation,
+    /// see [`compute_rent_fee`](crate::fees::compute_rent_fee). Rent is
+    /// refundable as well.
+    pub rent_fee: i64,
+}
+
+#[cfg(any(test, feature = "recording_auth"))]
+impl Host {
+    /// Simulates a transaction invoking `hf` on behalf of `source_account`,
+    /// mirroring what stellar-core does with it: records the footprint and,
+    /// unless `auth_entries` are provided (in which case they are enforced,
+    /// signatures included), the authorization entries, runs the invocation
+    /// and estimates the resources and fees of the transaction.
+    ///
+    /// The same requirements as for [`Host::preflight_invocation`] apply. As
+    /// the transaction envelope is not built here, its size has to be
+    /// provided as `transaction_size_bytes`.
+    pub fn simulate_transaction(
+        &self,
+        hf: HostFunction,
+        source_account: AccountId,
+        auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+        transaction_size_bytes: u32,
+        fee_config: &crate::fees::FeeConfiguration,
+        rent_fee_config: &crate::fees::RentFeeConfiguration,
+    ) -> Result<SimulateTransactionResult, HostError> {
+        use crate::{
+            fees::{compute_rent_fee, compute_transaction_resource_fee, TransactionResources},
+            storage::FootprintMode,
+        };
+
+        self.set_source_account(source_account)?;
+        let preflight = self.preflight_invocation_with_auth(hf, auth_entries)?;
+
+        let rent_changes = {
+            let storage = self.try_borrow_storage()?;
+            let snapshot = match &storage.mode {
+                FootprintMode::Recording(src) => Rc::clone(src),
+                FootprintMode::Enforcing => {
+                    return Err(self.err(
+                        ScErrorType::Storage,
+                        ScErrorCode::InternalError,
+                        "storage left recording mode during preflight",
+                        &[],
+                    ));
+                }
+            };
+            let changes = get_ledger_changes(
+                self.as_budget(),
+                &storage,
+                snapshot.as_ref(),
+                TtlEntryMap::new(),
+            )?;
+            extract_rent_changes(&changes)
+        };
+        let rent_fee = compute_rent_fee(
+            &rent_changes,
+            rent_fee_config,
+            self.with_ledger_info(|li| Ok(li.sequence_number))?,
+        );
+
+        let resources = &preflight.resources;
+        let transaction_resources = TransactionResources {
+            instructions: resources.instructions,
+            read_entries: resources.footprint.read_only.len() as u32,
+            write_entries: resources.footprint.read_write.len() as u32,
+            read_bytes: resources.read_bytes,
+            write_bytes: resources.write_bytes,
+            contract_events_size_bytes: preflight.contract_events_size_bytes,
+            transaction_size_bytes,
+        };
+        let (non_refundable_fee, refundable_fee) =
+            compute_transaction_resource_fee(&transaction_resources, fee_config);
+        Ok(SimulateTransactionResult {
+            preflight,
+            transaction_resources,
+            non_refundable_fee,
+            refundable_fee,
+            rent_fee,
+        })
+    }
+}
+
 struct StorageMapSnapshotSource<'a> {
     budget: &'a Budget,
     map: &'a StorageMap,
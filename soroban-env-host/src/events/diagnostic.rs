@@ -1,25 +1,81 @@
-use std::rc::Rc;
+use std::{collections::VecDeque, rc::Rc};
 
 use crate::{
+    budget::AsBudget,
     events::{
         internal::{InternalDiagnosticArg, InternalDiagnosticEvent},
         InternalEvent, InternalEventsBuffer,
     },
-    host::metered_clone::{MeteredAlloc, MeteredClone, MeteredContainer, MeteredIterator},
-    xdr::{Hash, ScBytes, ScString, ScVal, StringM},
+    host::{
+        frame::Frame,
+        metered_clone::{MeteredAlloc, MeteredClone, MeteredContainer, MeteredIterator},
+    },
+    xdr::{
+        ContractCostType, Hash, ScBytes, ScErrorCode, ScErrorType, ScString, ScSymbol, ScVal,
+        ScVec, StringM,
+    },
     Error, Host, HostError, Symbol, SymbolSmall, Val,
 };
 
+/// Number of host function calls retained for the post-mortem report of a
+/// budget exhaustion.
+const RECENT_HOST_CALLS: usize = 8;
+/// Number of cost types listed in the post-mortem report of a budget
+/// exhaustion.
+const TOP_COST_TYPES: usize = 5;
+
+/// Debug-mode state backing the post-mortem report of a budget exhaustion,
+/// see `Host::budget_exceeded_diagnostics`.
 #[derive(Clone, Default)]
+pub(crate) struct BudgetPostMortem {
+    // Names of the last few host functions called by contracts.
+    recent_host_calls: VecDeque<&'static str>,
+    // Whether the current invocation has already reported its exhaustion.
+    reported: bool,
+}
+
+/// Selects the diagnostics recorded by the [Host], see
+/// [Host::set_diagnostic_level]. Levels are ordered, each one recording
+/// everything the previous one does.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum DiagnosticLevel {
     #[default]
     None,
+    /// Records the diagnostic events.
+    DebugEvents,
+    /// Also captures debug info in every [HostError]: a backtrace, the events
+    /// recorded so far and the chain of contexts the error propagated through.
+    /// Debug info is only captured in test builds.
+    FullTrace,
+}
+
+#[allow(non_upper_case_globals)]
+impl DiagnosticLevel {
+    /// The single debug level of the previous releases, which records both
+    /// the diagnostic events and the debug info of errors. Prefer
+    /// [DiagnosticLevel::DebugEvents] when the debug info isn't needed.
+    pub const Debug: DiagnosticLevel = DiagnosticLevel::FullTrace;
+}
+
+/// Severity of a diagnostic event. Diagnostic events are only recorded when
+/// the [DiagnosticLevel] is at least [DiagnosticLevel::DebugEvents], the
+/// severity allows the embedder to filter them further.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DiagnosticSeverity {
+    /// Fine-grained tracing, such as the contract call and return events.
     Debug,
+    /// Informational messages, such as the messages logged by contracts.
+    Info,
+    /// Unexpected but recoverable conditions.
+    Warn,
+    /// Errors raised during the execution.
+    Error,
 }
 
 impl Host {
     fn record_diagnostic_event(
         &self,
+        severity: DiagnosticSeverity,
         contract_id: Option<Hash>,
         topics: Vec<InternalDiagnosticArg>,
         args: Vec<InternalDiagnosticArg>,
@@ -27,18 +83,34 @@ impl Host {
         self.with_debug_mode(|| {
             let de = Rc::metered_new(
                 InternalDiagnosticEvent {
+                    severity,
                     contract_id,
                     topics,
                     args,
                 },
                 self,
             )?;
-            self.with_events_mut(|events| events.record(InternalEvent::Diagnostic(de), self))
+            let event = InternalEvent::Diagnostic(de);
+            let sequence = self.with_events_mut(|events| {
+                let sequence = events.next_sequence();
+                events.record(event.clone(), self)?;
+                Ok(sequence)
+            })?;
+            self.call_event_callback(&event, sequence)
         });
         Ok(())
     }
 
     pub(crate) fn log_diagnostics(&self, msg: &str, args: &[Val]) {
+        self.log_diagnostics_with_severity(DiagnosticSeverity::Info, msg, args)
+    }
+
+    pub(crate) fn log_diagnostics_with_severity(
+        &self,
+        severity: DiagnosticSeverity,
+        msg: &str,
+        args: &[Val],
+    ) {
         self.with_debug_mode(|| {
             let calling_contract = self.get_current_contract_id_opt_internal()?;
             let log_sym = SymbolSmall::try_from_str("log")?;
@@ -50,17 +122,49 @@ impl Host {
             let args: Vec<_> = std::iter::once(InternalDiagnosticArg::XdrVal(msg))
                 .chain(args.iter().map(|rv| InternalDiagnosticArg::HostVal(*rv)))
                 .metered_collect(self)?;
-            self.record_diagnostic_event(calling_contract, topics, args)
+            self.record_diagnostic_event(severity, calling_contract, topics, args)
+        })
+    }
+
+    // Like `log_diagnostics_with_severity`, for arguments that are already in
+    // their XDR form, so that no host objects need to be created only to
+    // carry them.
+    pub(crate) fn log_xdr_diagnostics_with_severity(
+        &self,
+        severity: DiagnosticSeverity,
+        msg: &str,
+        args: Vec<ScVal>,
+    ) {
+        self.with_debug_mode(|| {
+            let calling_contract = self.get_current_contract_id_opt_internal()?;
+            let log_sym = SymbolSmall::try_from_str("log")?;
+            Vec::<InternalDiagnosticArg>::charge_bulk_init_cpy(1, self)?;
+            let topics = vec![InternalDiagnosticArg::HostVal(log_sym.to_val())];
+            let msg = ScVal::String(ScString::from(StringM::try_from(
+                self.metered_slice_to_vec(msg.as_bytes())?,
+            )?));
+            let args: Vec<_> = std::iter::once(msg)
+                .chain(args)
+                .map(InternalDiagnosticArg::XdrVal)
+                .metered_collect(self)?;
+            self.record_diagnostic_event(severity, calling_contract, topics, args)
         })
     }
 
+    // Records the diagnostic event of an error into the already-borrowed
+    // `events`, returning the event and its sequence number, so that the caller
+    // can report it to the event callback once the buffer is released. When
+    // `name_contract_error` is set and the error is named in the spec of the
+    // running contract, the name is appended to the data of the event.
     pub(crate) fn record_err_diagnostics(
         &self,
         events: &mut InternalEventsBuffer,
         error: Error,
         msg: &str,
         args: &[Val],
-    ) {
+        name_contract_error: bool,
+    ) -> Option<(InternalEvent, u32)> {
+        let mut recorded = None;
         self.with_debug_mode(|| {
             let error_sym = SymbolSmall::try_from_str("error")?;
             let contract_id = self.get_current_contract_id_opt_internal()?;
@@ -72,8 +176,19 @@ impl Host {
             let msg = ScVal::String(ScString::from(StringM::try_from(
                 self.metered_slice_to_vec(msg.as_bytes())?,
             )?));
+            let name = match name_contract_error {
+                true => self.contract_error_name(error)?,
+                false => None,
+            };
+            let name = match name {
+                Some(name) => Some(ScVal::String(ScString::from(StringM::try_from(
+                    self.metered_slice_to_vec(name.as_bytes())?,
+                )?))),
+                None => None,
+            };
             let args: Vec<_> = std::iter::once(InternalDiagnosticArg::XdrVal(msg))
                 .chain(args.iter().map(|rv| InternalDiagnosticArg::HostVal(*rv)))
+                .chain(name.into_iter().map(InternalDiagnosticArg::XdrVal))
                 .metered_collect(self)?;
 
             // We do the event-recording ourselves here rather than calling
@@ -82,14 +197,20 @@ impl Host {
             // insulate against double-faulting).
             let ce = Rc::metered_new(
                 InternalDiagnosticEvent {
+                    severity: DiagnosticSeverity::Error,
                     contract_id,
                     topics,
                     args,
                 },
                 self,
             )?;
-            events.record(InternalEvent::Diagnostic(ce), self)
-        })
+            let event = InternalEvent::Diagnostic(ce);
+            let sequence = events.next_sequence();
+            events.record(event.clone(), self)?;
+            recorded = Some((event, sequence));
+            Ok(())
+        });
+        recorded
     }
 
     // Emits an event with topic = ["fn_call", called_contract_id,
@@ -116,7 +237,7 @@ impl Host {
                 .iter()
                 .map(|rv| InternalDiagnosticArg::HostVal(*rv))
                 .metered_collect(self)?;
-            self.record_diagnostic_event(calling_contract, topics, args)
+            self.record_diagnostic_event(DiagnosticSeverity::Debug, calling_contract, topics, args)
         })
     }
 
@@ -131,7 +252,131 @@ impl Host {
             ];
             Vec::<InternalDiagnosticArg>::charge_bulk_init_cpy(1, self)?;
             let args = vec![InternalDiagnosticArg::HostVal(*res)];
-            self.record_diagnostic_event(Some(contract_id.metered_clone(self)?), topics, args)
+            self.record_diagnostic_event(
+                DiagnosticSeverity::Debug,
+                Some(contract_id.metered_clone(self)?),
+                topics,
+                args,
+            )
+        })
+    }
+
+    // Remembers a host function call made by a contract, in debug mode only.
+    pub(crate) fn record_recent_host_call(&self, name: &'static str) {
+        self.with_debug_mode(|| {
+            let calls = &mut self.try_borrow_budget_post_mortem_mut()?.recent_host_calls;
+            if calls.len() == RECENT_HOST_CALLS {
+                calls.pop_front();
+            }
+            calls.push_back(name);
+            Ok(())
         })
     }
+
+    pub(crate) fn reset_budget_post_mortem(&self) {
+        self.with_debug_mode(|| {
+            self.try_borrow_budget_post_mortem_mut()?.reported = false;
+            Ok(())
+        })
+    }
+
+    // If `error` is the budget exhaustion of the current invocation and it has
+    // not been reported yet, emits an event with topic = ["error", error] and
+    // data = ["budget exceeded", top_cost_types, frames, recent_host_calls], a
+    // post-mortem listing the cost types that consumed the most CPU, the
+    // frames on the stack (outermost first) and the last host functions called
+    // by contracts (oldest first). Called as the error crosses the host
+    // function and frame boundaries, so that the first one reports the stack
+    // at the failure.
+    pub(crate) fn budget_exceeded_diagnostics(&self, error: &HostError) {
+        if !(error.error.is_type(ScErrorType::Budget)
+            && error.error.is_code(ScErrorCode::ExceededLimit))
+        {
+            return;
+        }
+        self.with_debug_mode(|| {
+            if std::mem::replace(
+                &mut self.try_borrow_budget_post_mortem_mut()?.reported,
+                true,
+            ) {
+                return Ok(());
+            }
+            let string = |s: String| -> Result<ScVal, HostError> {
+                Ok(ScVal::String(ScString::from(StringM::try_from(
+                    self.metered_slice_to_vec(s.as_bytes())?,
+                )?)))
+            };
+            let vec = |v: Vec<ScVal>| -> Result<ScVal, HostError> {
+                Ok(ScVal::Vec(Some(ScVec::try_from(v)?)))
+            };
+
+            let mut costs = ContractCostType::variants()
+                .iter()
+                .map(|ty| Ok((*ty, self.as_budget().get_tracker(*ty)?)))
+                .collect::<Result<Vec<_>, HostError>>()?;
+            costs.sort_by_key(|(_, t)| std::cmp::Reverse(t.cpu));
+            let costs = costs
+                .into_iter()
+                .filter(|(_, t)| t.cpu > 0 || t.mem > 0)
+                .take(TOP_COST_TYPES)
+                .map(|(ty, t)| string(format!("{:?}: cpu {}, mem {}", ty, t.cpu, t.mem)))
+                .collect::<Result<Vec<_>, HostError>>()?;
+
+            let frames = self
+                .try_borrow_context_stack()?
+                .iter()
+                .map(
+                    |ctx| match (&ctx.frame, ctx.frame.contract_id(), ctx.frame.fn_name()) {
+                        (Frame::HostFunction(hf), _, _) => {
+                            string(format!("host function {:?}", hf))
+                        }
+                        (_, Some(id), Some(func)) => string(format!(
+                            "contract {} fn {}",
+                            id,
+                            self.scsymbol_from_symbol(func)?.to_utf8_string_lossy()
+                        )),
+                        _ => string("unknown frame".to_string()),
+                    },
+                )
+                .collect::<Result<Vec<_>, HostError>>()?;
+
+            let calls = self
+                .try_borrow_budget_post_mortem()?
+                .recent_host_calls
+                .iter()
+                .map(|name| Ok(ScVal::Symbol(ScSymbol(StringM::try_from(*name)?))))
+                .collect::<Result<Vec<_>, HostError>>()?;
+
+            let contract_id = self.get_current_contract_id_opt_internal()?;
+            Vec::<InternalDiagnosticArg>::charge_bulk_init_cpy(2, self)?;
+            let topics = vec![
+                InternalDiagnosticArg::HostVal(SymbolSmall::try_from_str("error")?.into()),
+                InternalDiagnosticArg::HostVal(error.error.to_val()),
+            ];
+            Vec::<InternalDiagnosticArg>::charge_bulk_init_cpy(4, self)?;
+            let args = vec![
+                InternalDiagnosticArg::XdrVal(string("budget exceeded".to_string())?),
+                InternalDiagnosticArg::XdrVal(vec(costs)?),
+                InternalDiagnosticArg::XdrVal(vec(frames)?),
+                InternalDiagnosticArg::XdrVal(vec(calls)?),
+            ];
+            self.record_diagnostic_event(DiagnosticSeverity::Error, contract_id, topics, args)
+        })
+    }
+
+    // If `error` is a contract error named in the spec of the running
+    // contract, returns its name formatted as `Enum::Case`.
+    fn contract_error_name(&self, error: Error) -> Result<Option<String>, HostError> {
+        if !error.is_type(ScErrorType::Contract) {
+            return Ok(None);
+        }
+        let vm = self.with_current_frame_opt(|frame| match frame {
+            Some(Frame::ContractVM { vm, .. }) => Ok(Some(Rc::clone(vm))),
+            _ => Ok(None),
+        })?;
+        match vm {
+            Some(vm) => vm.contract_error_name(self, error.get_code()),
+            None => Ok(None),
+        }
+    }
 }
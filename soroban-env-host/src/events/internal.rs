@@ -2,7 +2,9 @@ use std::rc::Rc;
 
 use crate::{
     budget::AsBudget,
-    events::{Events, HostEvent},
+    events::{
+        diagnostic::DiagnosticSeverity, ContractEventFilter, DiagnosticEvent, Events, HostEvent,
+    },
     host::metered_clone::{MeteredClone, MeteredContainer, MeteredIterator},
     xdr::{self, ScVal},
     BytesObject, Host, HostError, Val, VecObject,
@@ -55,6 +57,7 @@ impl InternalContractEvent {
 
 #[derive(Clone, Debug)]
 pub(crate) struct InternalDiagnosticEvent {
+    pub severity: DiagnosticSeverity,
     pub contract_id: Option<crate::xdr::Hash>,
     pub topics: Vec<InternalDiagnosticArg>,
     pub args: Vec<InternalDiagnosticArg>,
@@ -158,16 +161,32 @@ pub(crate) enum InternalEvent {
     Diagnostic(Rc<InternalDiagnosticEvent>),
 }
 
+impl InternalEvent {
+    pub(crate) fn to_xdr(&self, host: &Host) -> Result<xdr::ContractEvent, HostError> {
+        match self {
+            InternalEvent::Contract(c) => c.to_xdr(host),
+            InternalEvent::Diagnostic(d) => d.to_xdr(host),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum EventError {
     FromFailedCall,
     FromSuccessfulCall,
 }
 
-/// The events buffer. Stores `InternalEvent`s in the chronological order.
+/// The events buffer. Stores `InternalEvent`s in the chronological order,
+/// along with their sequence numbers.
 #[derive(Clone, Default)]
 pub(crate) struct InternalEventsBuffer {
-    pub(crate) vec: Vec<(InternalEvent, EventError)>,
+    pub(crate) vec: Vec<(InternalEvent, EventError, u32)>,
+    // Number of contract events in `vec` that are not from failed calls.
+    contract_events: usize,
+    // Sequence number of the next recorded event. Unlike the length of `vec`
+    // this is not rewound by `rollback`, so that the numbers of the dropped
+    // events are never reused.
+    next_sequence: u32,
 }
 
 impl InternalEventsBuffer {
@@ -180,12 +199,17 @@ impl InternalEventsBuffer {
             // and we need a deterministic cost across all instances, the cost
             // needs to be amortized and buffer size-independent.
             Vec::<(InternalEvent, EventError)>::charge_bulk_init_cpy(1, host)?;
-            self.vec.push((e, EventError::FromSuccessfulCall));
+            self.vec
+                .push((e, EventError::FromSuccessfulCall, self.next_sequence));
+            self.next_sequence = self.next_sequence.saturating_add(1);
             Ok(())
         };
 
         match &e {
-            InternalEvent::Contract(_) => metered_internal_event_push(e)?,
+            InternalEvent::Contract(_) => {
+                metered_internal_event_push(e)?;
+                self.contract_events += 1;
+            }
             InternalEvent::Diagnostic(_) => host.with_debug_mode(|| metered_internal_event_push(e)),
         }
 
@@ -195,8 +219,23 @@ impl InternalEventsBuffer {
     /// "Rolls back" the event buffer starting at `events` by marking all
     /// subsequent events as failed calls.
     pub(crate) fn rollback(&mut self, events: usize) -> Result<(), HostError> {
-        // note that we first skip the events that are not being rolled back
         // Metering: free (or conceptually: paid for when pushing the event)
+        self.contract_events -= self
+            .vec
+            .iter()
+            .skip(events)
+            .filter(|e| {
+                matches!(
+                    e,
+                    (
+                        InternalEvent::Contract(_),
+                        EventError::FromSuccessfulCall,
+                        _
+                    )
+                )
+            })
+            .count();
+        // note that we first skip the events that are not being rolled back
         for e in self.vec.iter_mut().skip(events) {
             e.1 = EventError::FromFailedCall;
         }
@@ -204,6 +243,16 @@ impl InternalEventsBuffer {
         Ok(())
     }
 
+    /// Returns the sequence number the next recorded event will get.
+    pub(crate) fn next_sequence(&self) -> u32 {
+        self.next_sequence
+    }
+
+    /// Returns the number of contract events recorded outside of failed calls.
+    pub(crate) fn num_contract_events(&self) -> usize {
+        self.contract_events
+    }
+
     /// Converts the internal events into their external representation. This
     /// should only be called either when the host is finished (via
     /// `try_finish`), or when an error occurs.
@@ -217,33 +266,125 @@ impl InternalEventsBuffer {
         // event-by-event basis.
         let mut vec = Vec::with_capacity(self.vec.len());
 
-        let mut metered_external_event_push =
-            |event: xdr::ContractEvent, status: &EventError| -> Result<(), HostError> {
-                // Metering: we use the cost of instantiating a size=1 `Vec` as
-                // an estimate for the cost collecting 1 `HostEvent` into the
-                // events buffer. Because the resulting buffer length may be
-                // different on different instances (due to diagnostic events)
-                // and we need a deterministic cost across all instances, the
-                // cost needs to be amortized and buffer size-independent.
-                Vec::<HostEvent>::charge_bulk_init_cpy(1, host)?;
-                vec.push(HostEvent {
-                    event,
-                    failed_call: *status == EventError::FromFailedCall,
-                });
-                Ok(())
-            };
+        let mut metered_external_event_push = |event: xdr::ContractEvent,
+                                               status: &EventError,
+                                               sequence: u32|
+         -> Result<(), HostError> {
+            // Metering: we use the cost of instantiating a size=1 `Vec` as
+            // an estimate for the cost collecting 1 `HostEvent` into the
+            // events buffer. Because the resulting buffer length may be
+            // different on different instances (due to diagnostic events)
+            // and we need a deterministic cost across all instances, the
+            // cost needs to be amortized and buffer size-independent.
+            Vec::<HostEvent>::charge_bulk_init_cpy(1, host)?;
+            vec.push(HostEvent {
+                event,
+                failed_call: *status == EventError::FromFailedCall,
+                sequence,
+            });
+            Ok(())
+        };
 
-        for (event, status) in self.vec.iter() {
+        let failed_call_events_are_diagnostics = host.failed_call_events_are_diagnostics()?;
+        for (event, status, sequence) in self.vec.iter() {
+            let sequence = *sequence;
             match event {
+                // Contract events of failed calls are only retained as
+                // diagnostics, see `FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION`.
+                InternalEvent::Contract(c)
+                    if failed_call_events_are_diagnostics
+                        && *status == EventError::FromFailedCall =>
+                {
+                    host.with_debug_mode(|| {
+                        metered_external_event_push(c.to_xdr(host)?, status, sequence)
+                    });
+                }
                 InternalEvent::Contract(c) => {
-                    metered_external_event_push(c.to_xdr(host)?, status)?;
+                    metered_external_event_push(c.to_xdr(host)?, status, sequence)?;
                 }
                 InternalEvent::Diagnostic(d) => {
-                    host.with_debug_mode(|| metered_external_event_push(d.to_xdr(host)?, status));
+                    host.with_debug_mode(|| {
+                        metered_external_event_push(d.to_xdr(host)?, status, sequence)
+                    });
                 }
             }
         }
 
         Ok(Events(vec))
     }
+
+    /// Converts the contract events of successful calls that match `filter`
+    /// into their XDR representation.
+    pub(crate) fn externalize_contract_events(
+        &self,
+        host: &Host,
+        filter: &ContractEventFilter,
+    ) -> Result<Vec<xdr::ContractEvent>, HostError> {
+        // Not charging for the whole buffer upfront, as it also contains
+        // diagnostic events, see `externalize`.
+        let mut vec = vec![];
+        for (event, status, _) in self.vec.iter() {
+            let InternalEvent::Contract(c) = event else {
+                continue;
+            };
+            if *status == EventError::FromFailedCall || !filter.matches_type(c.type_) {
+                continue;
+            }
+            let event = c.to_xdr(host)?;
+            if filter.matches_event(&event) {
+                Vec::<xdr::ContractEvent>::charge_bulk_init_cpy(1, host)?;
+                vec.push(event);
+            }
+        }
+        Ok(vec)
+    }
+
+    /// Converts the diagnostic events with at least `min_severity` into their
+    /// structured external representation. Contract events are skipped.
+    ///
+    /// The conversion is charged to the shadow budget, and fails with
+    /// `(Budget, ExceededLimit)` if the shadow budget runs out rather than
+    /// returning a partial list.
+    pub(crate) fn externalize_diagnostics(
+        &self,
+        host: &Host,
+        min_severity: DiagnosticSeverity,
+    ) -> Result<Vec<DiagnosticEvent>, HostError> {
+        // Diagnostic events are only recorded in debug mode, so there is
+        // nothing to convert otherwise.
+        let mut res = None;
+        host.as_budget().with_shadow_mode(|| {
+            res = Some(self.diagnostics_to_external(host, min_severity));
+            Ok(())
+        });
+        // The closure is skipped if the shadow budget is already exhausted.
+        res.unwrap_or_else(|| {
+            Err((xdr::ScErrorType::Budget, xdr::ScErrorCode::ExceededLimit).into())
+        })
+    }
+
+    fn diagnostics_to_external(
+        &self,
+        host: &Host,
+        min_severity: DiagnosticSeverity,
+    ) -> Result<Vec<DiagnosticEvent>, HostError> {
+        let mut vec = vec![];
+        for (event, status, sequence) in self.vec.iter() {
+            let InternalEvent::Diagnostic(d) = event else {
+                continue;
+            };
+            if d.severity < min_severity {
+                continue;
+            }
+            vec.push(DiagnosticEvent {
+                severity: d.severity,
+                contract_id: d.contract_id.metered_clone(host)?,
+                topics: externalize_args(host, &d.topics)?,
+                args: externalize_args(host, &d.args)?,
+                failed_call: *status == EventError::FromFailedCall,
+                sequence: *sequence,
+            });
+        }
+        Ok(vec)
+    }
 }
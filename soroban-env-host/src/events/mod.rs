@@ -1,17 +1,24 @@
 pub(crate) mod diagnostic;
+pub use diagnostic::DiagnosticSeverity;
 mod internal;
 pub(crate) mod system_events;
 pub(crate) use internal::{
     EventError, InternalDiagnosticArg, InternalDiagnosticEvent, InternalEventsBuffer,
 };
 // expose them as pub use for benches
+use std::rc::Rc;
+
 use crate::{
+    budget::AsBudget,
+    host::metered_xdr::metered_write_xdr,
+    host_object::HostVec,
     num::{i256_from_pieces, u256_from_pieces},
     xdr::{
-        ContractEventBody, ContractEventType, ContractExecutable, PublicKey::PublicKeyTypeEd25519,
-        ScAddress, ScContractInstance, ScVal,
+        ContractEvent, ContractEventBody, ContractEventType, ContractExecutable, Hash,
+        PublicKey::PublicKeyTypeEd25519, ScAddress, ScContractInstance, ScErrorCode, ScErrorType,
+        ScVal,
     },
-    Error, Host, HostError, Val, VecObject,
+    Error, Host, HostError, U32Val, Val, VecObject,
 };
 pub(crate) use internal::{InternalContractEvent, InternalEvent};
 
@@ -21,6 +28,9 @@ pub struct HostEvent {
     pub event: crate::xdr::ContractEvent,
     // failed_call keeps track of if the call this event was emitted in failed
     pub failed_call: bool,
+    /// The sequence number of the event among all the events emitted across the
+    /// call tree, see [Host::get_events].
+    pub sequence: u32,
 }
 
 fn display_address(addr: &ScAddress, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -100,6 +110,15 @@ fn display_scval(scv: &ScVal, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Resu
     }
 }
 
+// Adapts [display_scval] to the [std::fmt::Display] interface.
+struct DisplayScVal<'a>(&'a ScVal);
+
+impl core::fmt::Display for DisplayScVal<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        display_scval(self.0, f)
+    }
+}
+
 impl core::fmt::Display for HostEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.failed_call {
@@ -127,11 +146,169 @@ impl core::fmt::Display for HostEvent {
     }
 }
 
+/// The structured external representation of a diagnostic event, see
+/// [Host::get_diagnostic_events].
+#[derive(Clone, Debug)]
+pub struct DiagnosticEvent {
+    pub severity: DiagnosticSeverity,
+    /// The contract that was running when the event was emitted, if any.
+    pub contract_id: Option<Hash>,
+    pub topics: Vec<ScVal>,
+    pub args: Vec<ScVal>,
+    // failed_call keeps track of if the call this event was emitted in failed
+    pub failed_call: bool,
+    /// The sequence number of the event among all the events emitted across the
+    /// call tree, see [Host::get_events].
+    pub sequence: u32,
+}
+
+/// The first ledger protocol version in which the contract events emitted by
+/// failed calls are rolled back from the event stream. They are still retained
+/// in the events buffer, marked as failed calls, but only surface as
+/// diagnostics, i.e. in debug mode and charged to the shadow budget. In
+/// earlier protocols they are part of the event stream, marked as failed
+/// calls.
+pub(crate) const FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION: u32 = 21;
+
 /// The external representation of events in the chronological order.
 #[derive(Clone, Debug, Default)]
 pub struct Events(pub Vec<HostEvent>);
 
+/// Selects the events returned by [Host::get_contract_events]. An unset field
+/// matches every event.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContractEventFilter {
+    /// Only return the events emitted by this contract.
+    pub contract_id: Option<Hash>,
+    /// Only return the events of one of these types.
+    pub event_types: Option<Vec<ContractEventType>>,
+    /// Only return the events whose topics start with these topics.
+    pub topic_prefix: Option<Vec<ScVal>>,
+}
+
+impl ContractEventFilter {
+    pub(crate) fn matches_type(&self, type_: ContractEventType) -> bool {
+        match &self.event_types {
+            Some(types) => types.contains(&type_),
+            None => true,
+        }
+    }
+
+    pub(crate) fn matches_contract(&self, contract_id: &Option<Hash>) -> bool {
+        match &self.contract_id {
+            Some(id) => contract_id.as_ref() == Some(id),
+            None => true,
+        }
+    }
+
+    pub(crate) fn matches_topics(&self, topics: &[ScVal]) -> bool {
+        match &self.topic_prefix {
+            Some(prefix) => topics.starts_with(prefix),
+            None => true,
+        }
+    }
+
+    pub(crate) fn matches_event(&self, event: &ContractEvent) -> bool {
+        let ContractEventBody::V0(body) = &event.body;
+        self.matches_type(event.type_)
+            && self.matches_contract(&event.contract_id)
+            && self.matches_topics(body.topics.as_slice())
+    }
+}
+
+/// Limits enforced by the [Host] on the contract events emitted via the
+/// `contract_event` host function, mirroring the network settings. `None`
+/// means that the corresponding limit is not enforced, which is the default.
+///
+/// The sizes are the sizes of the XDR-serialized `ScVal`s.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EventLimits {
+    /// Maximum number of contract and system events per invocation, not
+    /// counting the events of the calls that failed.
+    pub max_events: Option<u32>,
+    pub max_topics_per_event: Option<u32>,
+    pub max_topic_size_bytes: Option<u32>,
+    pub max_data_size_bytes: Option<u32>,
+}
+
+/// Callback invoked with every event as it is emitted, see
+/// [Host::set_event_callback].
+pub type EventCallback = Rc<dyn for<'a> Fn(&'a Host, &'a HostEvent) -> Result<(), HostError>>;
+
 impl Host {
+    /// Renders `val` (recursing into vectors and maps) in the compact
+    /// human-readable form used for the values in the event log of a
+    /// [HostError], e.g. `[sym, {1: "str"}, Address(Contract(..))]`, for
+    /// logging by embedders.
+    ///
+    /// Rendering is charged to the shadow budget, so it does not affect the
+    /// metering. If the value can't be rendered (e.g. the shadow budget is
+    /// exhausted) it is rendered as its [Debug](std::fmt::Debug) form
+    /// instead.
+    pub fn render_val(&self, val: Val) -> String {
+        let mut rendered = None;
+        self.as_budget().with_shadow_mode(|| {
+            let scv = self.from_host_val(val)?;
+            rendered = Some(DisplayScVal(&scv).to_string());
+            Ok(())
+        });
+        rendered.unwrap_or_else(|| format!("{:?}", val))
+    }
+
+    /// Sets a callback that is invoked synchronously with every contract,
+    /// system and diagnostic event as it is emitted, rather than only once the
+    /// invocation is over.
+    ///
+    /// An error returned by the callback for a contract or system event fails
+    /// the invocation that emitted it, which allows enforcing custom policies
+    /// mid-invocation. Errors returned for diagnostic events are ignored, as
+    /// diagnostics must not affect the outcome of the invocation.
+    ///
+    /// The callback is invoked right after the event is recorded. Converting
+    /// the events for the callback is charged to the shadow budget, so
+    /// installing a callback does not change the metering; delivery is
+    /// best-effort, and an event that can't be converted within the shadow
+    /// budget is not reported. Events emitted while the callback runs (such as
+    /// the diagnostics of the errors it raises) are not reported to it either.
+    /// Note that the events of calls that fail later on are reported as well
+    /// (with `failed_call` unset).
+    pub fn set_event_callback(&self, callback: Option<EventCallback>) -> Result<(), HostError> {
+        *self.try_borrow_event_callback_mut()? = callback;
+        Ok(())
+    }
+
+    // Passes the event to the event callback, if one is set.
+    pub(crate) fn call_event_callback(
+        &self,
+        event: &InternalEvent,
+        sequence: u32,
+    ) -> Result<(), HostError> {
+        // Take the callback out for the duration of the call, so that the
+        // events it emits itself aren't reported back to it.
+        let callback = match self.try_borrow_event_callback_mut()?.take() {
+            Some(callback) => callback,
+            None => return Ok(()),
+        };
+        let mut res = Ok(());
+        // If the conversion fails (e.g. the shadow budget is exhausted) the
+        // event is skipped, as the shadow budget must not affect the outcome
+        // of the invocation.
+        self.as_budget().with_shadow_mode(|| {
+            let event = HostEvent {
+                event: event.to_xdr(self)?,
+                failed_call: false,
+                sequence,
+            };
+            res = callback(self, &event);
+            Ok(())
+        });
+        let mut slot = self.try_borrow_event_callback_mut()?;
+        if slot.is_none() {
+            *slot = Some(callback);
+        }
+        res
+    }
+
     pub(crate) fn with_events_mut<F, U>(&self, f: F) -> Result<U, HostError>
     where
         F: FnOnce(&mut InternalEventsBuffer) -> Result<U, HostError>,
@@ -139,10 +316,126 @@ impl Host {
         f(&mut *self.try_borrow_events_mut()?)
     }
 
+    // Returns whether the contract events of failed calls only surface as
+    // diagnostics, see `FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION`.
+    // Hosts without ledger info keep the behavior of earlier protocols.
+    pub(crate) fn failed_call_events_are_diagnostics(&self) -> Result<bool, HostError> {
+        Ok(match self.try_borrow_ledger()?.as_ref() {
+            Some(li) => li.protocol_version >= FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION,
+            None => false,
+        })
+    }
+
+    /// Returns all the events recorded so far, in the order they were emitted
+    /// across the whole call tree.
+    ///
+    /// Every event carries a `sequence` number reflecting that order: sequence
+    /// numbers are deterministic and strictly increasing, and the same number
+    /// is reported to the event callback when the event is emitted. When the
+    /// events of failed calls are dropped, see
+    /// [FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION], this leaves gaps
+    /// in the numbers, but the numbers are never reused.
     pub fn get_events(&self) -> Result<Events, HostError> {
         self.try_borrow_events()?.externalize(self)
     }
 
+    /// Returns the contract and system events of the invocation matching
+    /// `filter` as `ContractEvent` XDR, in the chronological order. Diagnostic
+    /// events and the events of failed calls are never returned, so with the
+    /// default filter this is the event stream that is published on the
+    /// network.
+    pub fn get_contract_events(
+        &self,
+        filter: &ContractEventFilter,
+    ) -> Result<Vec<ContractEvent>, HostError> {
+        self.try_borrow_events()?
+            .externalize_contract_events(self, filter)
+    }
+
+    /// Returns the diagnostic events with at least `min_severity` in the
+    /// chronological order, separately from the contract events. Diagnostic
+    /// events are only recorded when the [DiagnosticLevel](diagnostic::DiagnosticLevel)
+    /// is at least `DebugEvents`. The conversion is charged to the shadow
+    /// budget and fails with `(Budget, ExceededLimit)` if it runs out.
+    pub fn get_diagnostic_events(
+        &self,
+        min_severity: DiagnosticSeverity,
+    ) -> Result<Vec<DiagnosticEvent>, HostError> {
+        self.try_borrow_events()?
+            .externalize_diagnostics(self, min_severity)
+    }
+
+    /// Sets the [EventLimits] enforced on the contract events.
+    pub fn set_event_limits(&self, limits: EventLimits) -> Result<(), HostError> {
+        *self.try_borrow_event_limits_mut()? = limits;
+        Ok(())
+    }
+
+    // Size of the XDR serialization of `val`, charging for the conversion
+    // and the serialization.
+    pub(crate) fn metered_xdr_size(&self, val: &ScVal) -> Result<usize, HostError> {
+        let mut buf = vec![];
+        metered_write_xdr(self.budget_ref(), val, &mut buf)?;
+        Ok(buf.len())
+    }
+
+    // Checks the event about to be emitted by a contract against the
+    // configured [EventLimits]. Only the configured limits are checked (and
+    // charged for).
+    pub(crate) fn check_contract_event_limits(
+        &self,
+        topics: VecObject,
+        data: Val,
+    ) -> Result<(), HostError> {
+        let limits = *self.try_borrow_event_limits()?;
+        if let Some(max_events) = limits.max_events {
+            let num_events = self.try_borrow_events()?.num_contract_events();
+            if num_events >= max_events as usize {
+                return Err(self.err(
+                    ScErrorType::Events,
+                    ScErrorCode::ExceededLimit,
+                    "too many events emitted",
+                    &[U32Val::from(max_events).to_val()],
+                ));
+            }
+        }
+        if let Some(max_topics) = limits.max_topics_per_event {
+            let num_topics = self.visit_obj(topics, |hv: &HostVec| Ok(hv.len()))?;
+            if num_topics > max_topics as usize {
+                return Err(self.err(
+                    ScErrorType::Events,
+                    ScErrorCode::IndexBounds,
+                    "too many event topics",
+                    &[U32Val::from(max_topics).to_val()],
+                ));
+            }
+        }
+        if let Some(max_topic_size) = limits.max_topic_size_bytes {
+            for topic in self.vecobject_to_scval_vec(topics)?.iter() {
+                if self.metered_xdr_size(topic)? > max_topic_size as usize {
+                    return Err(self.err(
+                        ScErrorType::Events,
+                        ScErrorCode::InvalidInput,
+                        "event topic too large",
+                        &[U32Val::from(max_topic_size).to_val()],
+                    ));
+                }
+            }
+        }
+        if let Some(max_data_size) = limits.max_data_size_bytes {
+            let data = self.from_host_val(data)?;
+            if self.metered_xdr_size(&data)? > max_data_size as usize {
+                return Err(self.err(
+                    ScErrorType::Events,
+                    ScErrorCode::UnexpectedSize,
+                    "event data too large",
+                    &[U32Val::from(max_data_size).to_val()],
+                ));
+            }
+        }
+        Ok(())
+    }
+
     // Records a contract event.
     pub(crate) fn record_contract_event(
         &self,
@@ -156,6 +449,12 @@ impl Host {
             topics,
             data,
         };
-        self.with_events_mut(|events| Ok(events.record(InternalEvent::Contract(ce), self)))?
+        let event = InternalEvent::Contract(ce);
+        let sequence = self.with_events_mut(|events| {
+            let sequence = events.next_sequence();
+            events.record(event.clone(), self)?;
+            Ok(sequence)
+        })?;
+        self.call_event_callback(&event, sequence)
     }
 }
@@ -3,13 +3,16 @@ use std::rc::Rc;
 
 use crate::{
     auth::AuthorizationManager,
-    budget::{AsBudget, Budget},
-    events::{diagnostic::DiagnosticLevel, Events, InternalEventsBuffer},
+    budget::{AsBudget, Budget, InvocationBudget},
+    events::{
+        diagnostic::{BudgetPostMortem, DiagnosticLevel},
+        EventCallback, EventLimits, Events, InternalEventsBuffer,
+    },
     host_object::{HostMap, HostObject, HostVec},
     impl_bignum_host_fns, impl_bignum_host_fns_rhs_u32, impl_wrapping_obj_from_num,
     impl_wrapping_obj_to_num,
     num::*,
-    storage::Storage,
+    storage::{CrossContractStoragePolicy, Storage},
     xdr::{
         int128_helpers, AccountId, Asset, ContractCostType, ContractEventType, ContractExecutable,
         ContractIdPreimage, ContractIdPreimageFromAddress, CreateContractArgs, Duration, Hash,
@@ -17,18 +20,25 @@ use crate::{
         ScSymbol, ScVal, TimePoint, Uint256,
     },
     AddressObject, Bool, BytesObject, Compare, ConversionError, EnvBase, Error, I128Object,
-    I256Object, MapObject, Object, StorageType, StringObject, Symbol, SymbolObject, SymbolSmall,
-    TryFromVal, U128Object, U256Object, U32Val, U64Val, Val, VecObject, VmCaller, VmCallerEnv,
-    Void, I256, U256,
+    I128Val, I256Object, MapObject, Object, StorageType, StringObject, Symbol, SymbolObject,
+    SymbolSmall, SymbolStr, TryFromVal, TryIntoVal, U128Object, U128Val, U256Object, U32Val,
+    U64Val, Val, VecObject, VmCaller, VmCallerEnv, Void, I256, U256,
 };
 
+#[cfg(feature = "testutils")]
+pub(crate) mod call_log;
 mod comparison;
-mod conversion;
+pub(crate) mod conversion;
 pub(crate) mod crypto;
 mod data_helper;
+mod debug_render;
 mod declared_size;
+#[cfg(any(test, feature = "recording_auth"))]
+mod detached_state;
 pub(crate) mod error;
 pub(crate) mod frame;
+#[cfg(any(test, feature = "testutils"))]
+mod invocation_report;
 pub(crate) mod ledger_info_helper;
 mod lifecycle;
 mod mem_helper;
@@ -38,14 +48,22 @@ pub(crate) mod metered_vector;
 pub(crate) mod metered_xdr;
 mod num;
 mod prng;
+#[cfg(feature = "tracing")]
+pub(crate) mod trace;
 mod validity;
 
-pub use error::HostError;
-pub use prng::{Seed, SEED_BYTES};
+pub use conversion::ScMapKeyOrderPolicy;
+pub use debug_render::DebugRenderLimits;
+#[cfg(any(test, feature = "recording_auth"))]
+pub use detached_state::{DetachedHostState, OwnedEntryWithLiveUntil};
+pub use error::{ErrorContext, HostError};
+pub use frame::CallLimits;
+pub(crate) use prng::BasePrng;
+pub use prng::{derive_frame_prng_seed, Seed, FRAME_PRNG_DERIVATION_PROTOCOL_VERSION, SEED_BYTES};
 
 use self::{
     frame::{Context, ContractReentryMode},
-    mem_helper::MemFnArgs,
+    mem_helper::{MemFnArgs, SYMBOL_CHARS_CHECK_PROTOCOL_VERSION},
     metered_clone::{MeteredClone, MeteredContainer},
     metered_xdr::metered_write_xdr,
     prng::Prng,
@@ -54,6 +72,8 @@ use self::{
 #[cfg(any(test, feature = "testutils"))]
 pub use frame::ContractFunctionSet;
 pub(crate) use frame::Frame;
+#[cfg(any(test, feature = "testutils"))]
+pub use invocation_report::{BudgetSummary, InvocationReport, StorageChange};
 #[cfg(any(test, feature = "recording_auth"))]
 use rand_chacha::ChaCha20Rng;
 
@@ -92,6 +112,14 @@ pub enum ContractInvocationEvent {
 #[cfg(any(test, feature = "testutils"))]
 pub type ContractInvocationHook = Rc<dyn for<'a> Fn(&'a Host, ContractInvocationEvent) -> ()>;
 
+#[cfg(any(test, feature = "testutils"))]
+pub type StorageAccessHook =
+    Rc<dyn for<'a> Fn(&'a Host, crate::storage::StorageAccessEvent<'a>) -> ()>;
+
+#[cfg(any(test, feature = "testutils"))]
+pub type StorageKeysByContract =
+    std::collections::BTreeMap<Hash, crate::storage::ContractStorageKeys>;
+
 #[derive(Clone, Default)]
 struct HostImpl {
     source_account: RefCell<Option<AccountId>>,
@@ -99,6 +127,13 @@ struct HostImpl {
     objects: RefCell<Vec<HostObject>>,
     storage: RefCell<Storage>,
     context_stack: RefCell<Vec<Context>>,
+    // Set by `allow_reentry_for_next_call` and consumed by the next contract
+    // call, see `take_next_call_reentry_mode`.
+    allow_reentry_for_next_call: RefCell<bool>,
+    // Scratch spaces of the frames that have used `scratch_put` or
+    // `scratch_del`, by depth of the frame in the context stack. Kept out of
+    // `Context` so that frames don't grow for contracts that don't use them.
+    frame_scratch: RefCell<std::collections::BTreeMap<usize, HostMap>>,
     // Note: budget is refcounted and is _not_ deep-cloned when you call HostImpl::deep_clone,
     // mainly because it's not really possible to achieve (the same budget is connected to many
     // metered sub-objects) but also because it's plausible that the person calling deep_clone
@@ -113,7 +148,20 @@ struct HostImpl {
     // helpers for it and the only method to use it is inside the
     // `with_debug_mode` callback that switches to the shadow budget.
     diagnostic_level: RefCell<DiagnosticLevel>,
-    base_prng: RefCell<Option<Prng>>,
+    cross_contract_storage_policy: RefCell<CrossContractStoragePolicy>,
+    event_limits: RefCell<EventLimits>,
+    call_limits: RefCell<CallLimits>,
+    event_callback: RefCell<Option<EventCallback>>,
+    scmap_key_order_policy: RefCell<ScMapKeyOrderPolicy>,
+    // Hashes of the objects already hashed by `hash_val`, by handle.
+    val_hash_cache: RefCell<crypto::ValHashCache>,
+    // Derived from the network id of `ledger`, see `NetworkPreimagePrefixes`.
+    network_preimage_prefixes: RefCell<Option<crypto::NetworkPreimagePrefixes>>,
+    // Only used in debug mode, see `budget_exceeded_diagnostics`.
+    budget_post_mortem: RefCell<BudgetPostMortem>,
+    // Budget consumed by every top-level invocation, see `invocation_budgets`.
+    invocation_budgets: RefCell<Vec<InvocationBudget>>,
+    base_prng: RefCell<Option<BasePrng>>,
     // Auth-recording mode generates pseudorandom nonces to populate its output.
     // We'd like these to be deterministic from one run to the next, but also
     // completely isolated from any use of the user-accessible PRNGs (either
@@ -152,6 +200,29 @@ struct HostImpl {
     #[doc(hidden)]
     #[cfg(any(test, feature = "testutils"))]
     top_contract_invocation_hook: RefCell<Option<ContractInvocationHook>>,
+    // Store a hook that observes all the storage accesses, see
+    // `set_storage_access_hook`.
+    #[doc(hidden)]
+    #[cfg(any(test, feature = "testutils"))]
+    storage_access_hook: RefCell<Option<StorageAccessHook>>,
+    // Storage keys accessed by every contract that has run on this host since
+    // the tracking has been enabled, see `enable_storage_keys_tracking`.
+    #[cfg(any(test, feature = "testutils"))]
+    storage_keys_by_contract: RefCell<Option<StorageKeysByContract>>,
+    // Log of the host function calls made since the logging has been enabled,
+    // see `enable_host_call_log`.
+    #[cfg(feature = "testutils")]
+    host_call_log: RefCell<Option<call_log::HostCallLog>>,
+    // Failures pending injection, see `inject_fault`.
+    #[cfg(any(test, feature = "testutils"))]
+    fault_injector: RefCell<crate::testutils::FaultInjector>,
+    // State of the last top-level invocation, see `invocation_report`.
+    #[cfg(any(test, feature = "testutils"))]
+    invocation_snapshot: RefCell<Option<invocation_report::InvocationSnapshot>>,
+    // Prints the budget usage when the host is dropped, see
+    // `print_budget_usage_on_drop`.
+    #[cfg(any(test, feature = "testutils"))]
+    budget_usage_printer: RefCell<Option<Rc<crate::testutils::BudgetUsagePrinter>>>,
 }
 
 // Host is a newtype on Rc<HostImpl> so we can impl Env for it below.
@@ -215,18 +286,84 @@ impl_checked_borrow_helpers!(
     try_borrow_context_stack,
     try_borrow_context_stack_mut
 );
+impl_checked_borrow_helpers!(
+    allow_reentry_for_next_call,
+    bool,
+    try_borrow_allow_reentry_for_next_call,
+    try_borrow_allow_reentry_for_next_call_mut
+);
+impl_checked_borrow_helpers!(
+    frame_scratch,
+    std::collections::BTreeMap<usize, HostMap>,
+    try_borrow_frame_scratch,
+    try_borrow_frame_scratch_mut
+);
 impl_checked_borrow_helpers!(
     events,
     InternalEventsBuffer,
     try_borrow_events,
     try_borrow_events_mut
 );
+impl_checked_borrow_helpers!(
+    invocation_budgets,
+    Vec<InvocationBudget>,
+    try_borrow_invocation_budgets,
+    try_borrow_invocation_budgets_mut
+);
 impl_checked_borrow_helpers!(
     authorization_manager,
     AuthorizationManager,
     try_borrow_authorization_manager,
     try_borrow_authorization_manager_mut
 );
+impl_checked_borrow_helpers!(
+    cross_contract_storage_policy,
+    CrossContractStoragePolicy,
+    try_borrow_cross_contract_storage_policy,
+    try_borrow_cross_contract_storage_policy_mut
+);
+impl_checked_borrow_helpers!(
+    event_limits,
+    EventLimits,
+    try_borrow_event_limits,
+    try_borrow_event_limits_mut
+);
+impl_checked_borrow_helpers!(
+    call_limits,
+    CallLimits,
+    try_borrow_call_limits,
+    try_borrow_call_limits_mut
+);
+impl_checked_borrow_helpers!(
+    event_callback,
+    Option<EventCallback>,
+    try_borrow_event_callback,
+    try_borrow_event_callback_mut
+);
+impl_checked_borrow_helpers!(
+    scmap_key_order_policy,
+    ScMapKeyOrderPolicy,
+    try_borrow_scmap_key_order_policy,
+    try_borrow_scmap_key_order_policy_mut
+);
+impl_checked_borrow_helpers!(
+    val_hash_cache,
+    crypto::ValHashCache,
+    try_borrow_val_hash_cache,
+    try_borrow_val_hash_cache_mut
+);
+impl_checked_borrow_helpers!(
+    network_preimage_prefixes,
+    Option<crypto::NetworkPreimagePrefixes>,
+    try_borrow_network_preimage_prefixes,
+    try_borrow_network_preimage_prefixes_mut
+);
+impl_checked_borrow_helpers!(
+    budget_post_mortem,
+    BudgetPostMortem,
+    try_borrow_budget_post_mortem,
+    try_borrow_budget_post_mortem_mut
+);
 
 // Note: diagnostic_mode borrow helpers are _not_ defined here to reduce the
 // risk of future maintainers accidentally revealing any way of observing the
@@ -235,7 +372,7 @@ impl_checked_borrow_helpers!(
 
 impl_checked_borrow_helpers!(
     base_prng,
-    Option<Prng>,
+    Option<BasePrng>,
     try_borrow_base_prng,
     try_borrow_base_prng_mut
 );
@@ -283,6 +420,54 @@ impl_checked_borrow_helpers!(
     try_borrow_top_contract_invocation_hook_mut
 );
 
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    storage_access_hook,
+    Option<StorageAccessHook>,
+    try_borrow_storage_access_hook,
+    try_borrow_storage_access_hook_mut
+);
+
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    storage_keys_by_contract,
+    Option<StorageKeysByContract>,
+    try_borrow_storage_keys_by_contract,
+    try_borrow_storage_keys_by_contract_mut
+);
+
+#[cfg(feature = "testutils")]
+impl_checked_borrow_helpers!(
+    host_call_log,
+    Option<call_log::HostCallLog>,
+    try_borrow_host_call_log,
+    try_borrow_host_call_log_mut
+);
+
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    fault_injector,
+    crate::testutils::FaultInjector,
+    try_borrow_fault_injector,
+    try_borrow_fault_injector_mut
+);
+
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    budget_usage_printer,
+    Option<Rc<crate::testutils::BudgetUsagePrinter>>,
+    try_borrow_budget_usage_printer,
+    try_borrow_budget_usage_printer_mut
+);
+
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    invocation_snapshot,
+    Option<invocation_report::InvocationSnapshot>,
+    try_borrow_invocation_snapshot,
+    try_borrow_invocation_snapshot_mut
+);
+
 impl Debug for HostImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "HostImpl(...)")
@@ -308,12 +493,23 @@ impl Host {
             objects: Default::default(),
             storage: RefCell::new(storage),
             context_stack: Default::default(),
+            allow_reentry_for_next_call: Default::default(),
+            frame_scratch: Default::default(),
             budget,
             events: Default::default(),
             authorization_manager: RefCell::new(
                 AuthorizationManager::new_enforcing_without_authorizations(),
             ),
             diagnostic_level: Default::default(),
+            cross_contract_storage_policy: Default::default(),
+            event_limits: Default::default(),
+            call_limits: Default::default(),
+            event_callback: RefCell::new(None),
+            scmap_key_order_policy: Default::default(),
+            val_hash_cache: Default::default(),
+            network_preimage_prefixes: Default::default(),
+            budget_post_mortem: Default::default(),
+            invocation_budgets: Default::default(),
             base_prng: RefCell::new(None),
             #[cfg(any(test, feature = "recording_auth"))]
             recording_auth_nonce_prng: RefCell::new(None),
@@ -327,6 +523,18 @@ impl Host {
             lifecycle_event_hook: RefCell::new(None),
             #[cfg(any(test, feature = "testutils"))]
             top_contract_invocation_hook: RefCell::new(None),
+            #[cfg(any(test, feature = "testutils"))]
+            storage_access_hook: RefCell::new(None),
+            #[cfg(any(test, feature = "testutils"))]
+            storage_keys_by_contract: Default::default(),
+            #[cfg(feature = "testutils")]
+            host_call_log: RefCell::new(None),
+            #[cfg(any(test, feature = "testutils"))]
+            fault_injector: Default::default(),
+            #[cfg(any(test, feature = "testutils"))]
+            invocation_snapshot: RefCell::new(None),
+            #[cfg(any(test, feature = "testutils"))]
+            budget_usage_printer: RefCell::new(None),
         }))
     }
 
@@ -408,6 +616,27 @@ impl Host {
         Ok(())
     }
 
+    /// Like `set_authorization_entries`, but the signatures of the entries
+    /// are not verified, while the entries still have to match the
+    /// invocations and have valid nonces. The budget is charged as if the
+    /// signatures of the classic accounts were verified, so this can be used
+    /// for estimating the resources of an invocation with the authorization
+    /// entries that haven't been signed yet.
+    #[cfg(any(test, feature = "recording_auth"))]
+    pub fn set_authorization_entries_without_signature_verification(
+        &self,
+        auth_entries: Vec<soroban_env_common::xdr::SorobanAuthorizationEntry>,
+    ) -> Result<(), HostError> {
+        let new_auth_manager =
+            AuthorizationManager::new_enforcing_without_signature_verification(self, auth_entries)?;
+        *self.try_borrow_authorization_manager_mut()? = new_auth_manager;
+        Ok(())
+    }
+
+    /// Sets the seed that every frame PRNG is derived from (see
+    /// [`derive_frame_prng_seed`]), and that seeds the host's base and
+    /// auxiliary PRNGs. Embedders must call this before running any contract that uses
+    /// the PRNG; tests can use it to make randomized contracts reproducible.
     #[allow(unused_variables)]
     pub fn set_base_prng_seed(&self, seed: prng::Seed) -> Result<(), HostError> {
         let mut base_prng = Prng::new_from_seed(seed, self.budget_ref())?;
@@ -424,12 +653,17 @@ impl Host {
         {
             *self.try_borrow_recording_auth_nonce_prng_mut()? = Some(recording_auth_nonce_prng);
         }
-        *self.try_borrow_base_prng_mut()? = Some(base_prng);
+        let protocol_version = self
+            .try_borrow_ledger()?
+            .as_ref()
+            .map(|li| li.protocol_version);
+        *self.try_borrow_base_prng_mut()? = Some(BasePrng::new(seed, base_prng, protocol_version));
         Ok(())
     }
 
     pub fn set_ledger_info(&self, info: LedgerInfo) -> Result<(), HostError> {
         *self.try_borrow_ledger_mut()? = Some(info);
+        *self.try_borrow_network_preimage_prefixes_mut()? = None;
         Ok(())
     }
 
@@ -461,6 +695,7 @@ impl Host {
             )),
             Some(li) => {
                 f(li);
+                *self.try_borrow_network_preimage_prefixes_mut()? = None;
                 Ok(())
             }
         }
@@ -478,6 +713,32 @@ impl Host {
         self.0.budget.clone()
     }
 
+    /// Returns the budget consumed by each top-level invocation on the host so
+    /// far, in order.
+    ///
+    /// All the invocations on a host share its budget, which keeps being
+    /// consumed from one invocation to the next (e.g. when running all the
+    /// operations of a transaction on one host), so this breaks the total
+    /// consumption down per invocation.
+    pub fn invocation_budgets(&self) -> Result<Vec<InvocationBudget>, HostError> {
+        Ok(self.try_borrow_invocation_budgets()?.clone())
+    }
+
+    // Stores `error` in the current frame if it is a `TestContract` one, so
+    // that the error can be recovered once the panic about to be raised with
+    // it is caught, see `escalate_error_to_panic`.
+    #[cfg(any(test, feature = "testutils"))]
+    pub(crate) fn record_test_contract_panic(&self, error: Error) {
+        let _ = self.with_current_frame_opt(|f| {
+            if let Some(Frame::TestContract(frame)) = f {
+                if let Ok(mut panic) = frame.panic.try_borrow_mut() {
+                    *panic = Some(error);
+                }
+            }
+            Ok(())
+        });
+    }
+
     pub fn charge_budget(&self, ty: ContractCostType, input: Option<u64>) -> Result<(), HostError> {
         self.0.budget.charge(ty, input)
     }
@@ -486,6 +747,10 @@ impl Host {
         self.0.budget.set_shadow_limits(cpu, mem)
     }
 
+    /// Sets the [DiagnosticLevel]. The level may be changed between
+    /// invocations on the same host, e.g. to re-run a failing invocation with
+    /// diagnostics enabled: diagnostics are charged to the shadow budget and
+    /// never change the results or the metering of an invocation.
     pub fn set_diagnostic_level(&self, diagnostic_level: DiagnosticLevel) -> Result<(), HostError> {
         use crate::host::error::TryBorrowOrErr;
         *self.0.diagnostic_level.try_borrow_mut_or_err()? = diagnostic_level;
@@ -494,7 +759,17 @@ impl Host {
 
     // As above, avoids having to import DiagnosticLevel.
     pub fn enable_debug(&self) -> Result<(), HostError> {
-        self.set_diagnostic_level(DiagnosticLevel::Debug)
+        self.set_diagnostic_level(DiagnosticLevel::FullTrace)
+    }
+
+    /// Sets the [`CrossContractStoragePolicy`] that controls whether a contract
+    /// may read the data of other contracts and extend their TTL.
+    pub fn set_cross_contract_storage_policy(
+        &self,
+        policy: CrossContractStoragePolicy,
+    ) -> Result<(), HostError> {
+        *self.try_borrow_cross_contract_storage_policy_mut()? = policy;
+        Ok(())
     }
 
     /// Wraps a `budget.with_shadow_mode` call with a check against the
@@ -517,12 +792,28 @@ impl Host {
     /// debug-mode-guarded and/or only write results into debug state (eg.
     /// diagnostic events).
     pub(crate) fn with_debug_mode<F>(&self, f: F)
+    where
+        F: FnOnce() -> Result<(), HostError>,
+    {
+        self.with_diagnostic_level_at_least(DiagnosticLevel::DebugEvents, f)
+    }
+
+    /// Like [Host::with_debug_mode], but only runs the closure when the
+    /// diagnostic level is [DiagnosticLevel::FullTrace].
+    pub(crate) fn with_full_trace_mode<F>(&self, f: F)
+    where
+        F: FnOnce() -> Result<(), HostError>,
+    {
+        self.with_diagnostic_level_at_least(DiagnosticLevel::FullTrace, f)
+    }
+
+    fn with_diagnostic_level_at_least<F>(&self, min_level: DiagnosticLevel, f: F)
     where
         F: FnOnce() -> Result<(), HostError>,
     {
         use crate::host::error::TryBorrowOrErr;
         if let Ok(cell) = self.0.diagnostic_level.try_borrow_or_err() {
-            if matches!(*cell, DiagnosticLevel::Debug) {
+            if *cell >= min_level {
                 return self.budget_ref().with_shadow_mode(f);
             }
         }
@@ -546,7 +837,12 @@ impl Host {
         let events = self.try_borrow_events()?.externalize(&self)?;
         Rc::try_unwrap(self.0)
             .map(|host_impl| {
-                let storage = host_impl.storage.into_inner();
+                #[allow(unused_mut)]
+                let mut storage = host_impl.storage.into_inner();
+                #[cfg(any(test, feature = "testutils"))]
+                {
+                    storage.access_callback = None;
+                }
                 (storage, events)
             })
             .map_err(|_| {
@@ -665,14 +961,7 @@ impl EnvBase for Host {
     // not in a position to improve it.
     #[cfg(feature = "testutils")]
     fn escalate_error_to_panic(&self, e: Self::Error) -> ! {
-        let _ = self.with_current_frame_opt(|f| {
-            if let Some(Frame::TestContract(frame)) = f {
-                if let Ok(mut panic) = frame.panic.try_borrow_mut() {
-                    *panic = Some(e.error);
-                }
-            }
-            Ok(())
-        });
+        self.record_test_contract_panic(e.error);
         let escalation = self.error(e.error, "escalating error to panic", &[]);
         panic!("{:?}", escalation)
     }
@@ -682,12 +971,21 @@ impl EnvBase for Host {
             if e.info.is_none() {
                 e.info = self.maybe_get_debug_info()
             }
+            self.budget_exceeded_diagnostics(e);
         }
         x
     }
 
     #[cfg(feature = "testutils")]
     fn env_call_hook(&self, fname: &'static str, args: &[String]) -> Result<(), HostError> {
+        if let Some(log) = self.try_borrow_host_call_log_mut()?.as_mut() {
+            log.record_call(
+                fname,
+                args,
+                self.budget_ref().get_cpu_insns_consumed()?,
+                self.budget_ref().get_mem_bytes_consumed()?,
+            );
+        }
         self.call_any_lifecycle_hook(HostLifecycleEvent::EnvCall(fname, args))
     }
 
@@ -701,6 +999,14 @@ impl EnvBase for Host {
         // not know enough about the structure of errors (in particular that we
         // do _not_ want to format debuginfo into the lifecycle-hook string).
         let res = res.clone().map_err(|he| format!("{:?}", he.error));
+        if let Some(log) = self.try_borrow_host_call_log_mut()?.as_mut() {
+            log.record_ret(
+                fname,
+                res.clone(),
+                self.budget_ref().get_cpu_insns_consumed()?,
+                self.budget_ref().get_mem_bytes_consumed()?,
+            );
+        }
         self.call_any_lifecycle_hook(HostLifecycleEvent::EnvRet(fname, &res))
     }
 
@@ -1032,6 +1338,7 @@ impl VmCallerEnv for Host {
         topics: VecObject,
         data: Val,
     ) -> Result<Void, HostError> {
+        self.check_contract_event_limits(topics, data)?;
         self.record_contract_event(ContractEventType::Contract, topics, data)?;
         Ok(Val::VOID)
     }
@@ -1054,7 +1361,7 @@ impl VmCallerEnv for Host {
         error: Error,
     ) -> Result<Void, Self::Error> {
         if error.is_type(ScErrorType::Contract) {
-            Err(self.error(
+            Err(self.contract_error(
                 error,
                 "failing with contract error",
                 &[U32Val::from(error.get_code()).to_val()],
@@ -1073,8 +1380,10 @@ impl VmCallerEnv for Host {
         &self,
         _vmcaller: &mut VmCaller<Host>,
     ) -> Result<BytesObject, Self::Error> {
+        if self.network_preimage_prefixes_enabled()? {
+            return self.add_host_object(self.scbytes_from_slice(self.network_id()?.0.as_slice())?);
+        }
         self.with_ledger_info(|li| {
-            // FIXME: cache this and a few other such IDs: https://github.com/stellar/rs-soroban-env/issues/681
             self.add_host_object(self.scbytes_from_slice(li.network_id.as_slice())?)
         })
     }
@@ -1084,7 +1393,6 @@ impl VmCallerEnv for Host {
         &self,
         _vmcaller: &mut VmCaller<Host>,
     ) -> Result<AddressObject, HostError> {
-        // FIXME: cache this and a few other such IDs: https://github.com/stellar/rs-soroban-env/issues/681
         self.add_host_object(ScAddress::Contract(
             self.get_current_contract_id_internal()?,
         ))
@@ -1097,6 +1405,89 @@ impl VmCallerEnv for Host {
         Ok(self.max_live_until_ledger()?.into())
     }
 
+    // Notes on metering: the frame ids are metered clones, and the returned
+    // vectors are charged as they're created.
+    fn get_current_call_stack(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+    ) -> Result<VecObject, HostError> {
+        let mut frames: Vec<(Hash, Symbol)> = Vec::new();
+        for ctx in self.try_borrow_context_stack()?.iter() {
+            if let (Some(id), Some(func)) = (ctx.frame.contract_id(), ctx.frame.fn_name()) {
+                frames.push((id.metered_clone(self)?, func));
+            }
+        }
+        Vec::<Val>::charge_bulk_init_cpy(frames.len() as u64, self)?;
+        let mut entries: Vec<Val> = Vec::with_capacity(frames.len());
+        for (id, func) in frames {
+            let addr = self.add_host_object(ScAddress::Contract(id))?;
+            let entry = self.vec_new_from_slice(&[addr.to_val(), func.to_val()])?;
+            entries.push(entry.to_val());
+        }
+        self.vec_new_from_slice(&entries)
+    }
+
+    // Notes on metering: covered by the components.
+    fn get_invoker(&self, _vmcaller: &mut VmCaller<Host>) -> Result<VecObject, HostError> {
+        let (kind, invoker) = match self.get_invoker_contract_id_internal()? {
+            Some(id) => (1_u32, self.add_host_object(ScAddress::Contract(id))?),
+            None => {
+                let source = self.source_account_address()?.ok_or_else(|| {
+                    self.err(
+                        ScErrorType::Context,
+                        ScErrorCode::MissingValue,
+                        "transaction source account is not set",
+                        &[],
+                    )
+                })?;
+                (0_u32, source)
+            }
+        };
+        self.vec_new_from_slice(&[U32Val::from(kind).to_val(), invoker.to_val()])
+    }
+
+    // Notes on metering: covered by the map operations.
+    fn scratch_put(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        k: Val,
+        v: Val,
+    ) -> Result<Void, HostError> {
+        self.with_current_scratch_mut(|scratch| {
+            *scratch = scratch.insert(k, v, self)?;
+            Ok(())
+        })?;
+        Ok(Val::VOID)
+    }
+
+    // Notes on metering: covered by the map operations.
+    fn scratch_has(&self, _vmcaller: &mut VmCaller<Host>, k: Val) -> Result<Bool, HostError> {
+        Ok(Val::from_bool(self.scratch_lookup(k)?.is_some()))
+    }
+
+    // Notes on metering: covered by the map operations.
+    fn scratch_get(&self, _vmcaller: &mut VmCaller<Host>, k: Val) -> Result<Val, HostError> {
+        self.scratch_lookup(k)?.ok_or_else(|| {
+            self.err(
+                ScErrorType::Context,
+                ScErrorCode::MissingValue,
+                "key is missing from scratch space",
+                &[k],
+            )
+        })
+    }
+
+    // Notes on metering: covered by the map operations.
+    fn scratch_del(&self, _vmcaller: &mut VmCaller<Host>, k: Val) -> Result<Void, HostError> {
+        self.with_current_scratch_mut(|scratch| {
+            if let Some((new_map, _)) = scratch.remove(&k, self)? {
+                *scratch = new_map;
+            }
+            Ok(())
+        })?;
+        Ok(Val::VOID)
+    }
+
     // endregion: "context" module functions
 
     // region: "int" module functions
@@ -1887,8 +2278,9 @@ impl VmCallerEnv for Host {
         let res = match t {
             StorageType::Temporary | StorageType::Persistent => {
                 let key = self.storage_key_from_val(k, t.try_into()?)?;
-                self.try_borrow_storage_mut()?
-                    .has(&key, self.as_budget())
+                #[cfg(any(test, feature = "testutils"))]
+                self.check_injected_fault("storage_read")?;
+                self.has_contract_data_entry(&key)
                     .map_err(|e| self.decorate_contract_data_storage_error(e, k))?
             }
             StorageType::Instance => {
@@ -1909,9 +2301,10 @@ impl VmCallerEnv for Host {
         match t {
             StorageType::Temporary | StorageType::Persistent => {
                 let key = self.storage_key_from_val(k, t.try_into()?)?;
+                #[cfg(any(test, feature = "testutils"))]
+                self.check_injected_fault("storage_read")?;
                 let entry = self
-                    .try_borrow_storage_mut()?
-                    .get(&key, self.as_budget())
+                    .get_contract_data_entry(&key)
                     .map_err(|e| self.decorate_contract_data_storage_error(e, k))?;
                 match &entry.data {
                     LedgerEntryData::ContractData(e) => Ok(self.to_host_val(&e.val)?),
@@ -1949,6 +2342,8 @@ impl VmCallerEnv for Host {
         match t {
             StorageType::Temporary | StorageType::Persistent => {
                 let key = self.contract_data_key_from_val(k, t.try_into()?)?;
+                #[cfg(any(test, feature = "testutils"))]
+                self.check_injected_fault("storage_write")?;
                 self.try_borrow_storage_mut()?
                     .del(&key, self.as_budget())
                     .map_err(|e| self.decorate_contract_data_storage_error(e, k))?;
@@ -1966,6 +2361,49 @@ impl VmCallerEnv for Host {
         Ok(Val::VOID)
     }
 
+    // Notes on metering: covered by components
+    fn take_contract_data(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        k: Val,
+        t: StorageType,
+    ) -> Result<Val, HostError> {
+        match t {
+            StorageType::Temporary | StorageType::Persistent => {
+                let key = self.contract_data_key_from_val(k, t.try_into()?)?;
+                #[cfg(any(test, feature = "testutils"))]
+                self.check_injected_fault("storage_write")?;
+                let entry = self
+                    .try_borrow_storage_mut()?
+                    .take(&key, self.as_budget())
+                    .map_err(|e| self.decorate_contract_data_storage_error(e, k))?;
+                match &entry.data {
+                    LedgerEntryData::ContractData(e) => Ok(self.to_host_val(&e.val)?),
+                    _ => Err(self.err(
+                        ScErrorType::Storage,
+                        ScErrorCode::InternalError,
+                        "expected contract data ledger entry",
+                        &[],
+                    )),
+                }
+            }
+            StorageType::Instance => {
+                self.with_mut_instance_storage(|s| match s.map.remove(&k, self)? {
+                    Some((new_map, v)) => {
+                        s.map = new_map;
+                        Ok(v)
+                    }
+                    None => Err(self.err(
+                        ScErrorType::Storage,
+                        ScErrorCode::MissingValue,
+                        "key is missing from instance storage",
+                        &[k],
+                    )),
+                })
+            }
+        }
+    }
+
     // Notes on metering: covered by components
     fn extend_contract_data_ttl(
         &self,
@@ -2013,6 +2451,7 @@ impl VmCallerEnv for Host {
         extend_to: U32Val,
     ) -> Result<Void, Self::Error> {
         let contract_id = self.contract_id_from_address(contract)?;
+        self.check_cross_contract_ttl_extension(&contract_id)?;
         self.extend_contract_instance_and_code_ttl_from_contract_id(
             &contract_id,
             threshold.into(),
@@ -2039,7 +2478,30 @@ impl VmCallerEnv for Host {
             contract_id_preimage,
             executable,
         };
-        self.create_contract_internal(Some(deployer), args)
+        self.create_contract_internal(Some(deployer), args, None)
+    }
+
+    // Notes on metering: covered by the components.
+    fn create_contract_with_constructor(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        deployer: AddressObject,
+        wasm_hash: BytesObject,
+        salt: BytesObject,
+        constructor_args: VecObject,
+    ) -> Result<AddressObject, HostError> {
+        let contract_id_preimage = ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+            address: self.visit_obj(deployer, |addr: &ScAddress| addr.metered_clone(self))?,
+            salt: self.u256_from_bytesobj_input("contract_id_salt", salt)?,
+        });
+        let executable =
+            ContractExecutable::Wasm(self.hash_from_bytesobj_input("wasm_hash", wasm_hash)?);
+        let args = CreateContractArgs {
+            contract_id_preimage,
+            executable,
+        };
+        let constructor_args = self.call_args_from_obj(constructor_args)?;
+        self.create_contract_internal(Some(deployer), args, Some(constructor_args.as_slice()))
     }
 
     // Notes on metering: covered by the components.
@@ -2057,7 +2519,7 @@ impl VmCallerEnv for Host {
         };
         // Asset contracts don't need any deployer authorization (they're tied
         // to the asset issuers instead).
-        self.create_contract_internal(None, args)
+        self.create_contract_internal(None, args, None)
     }
 
     // Notes on metering: covered by the components.
@@ -2127,13 +2589,14 @@ impl VmCallerEnv for Host {
         args: VecObject,
     ) -> Result<Val, HostError> {
         let argvec = self.call_args_from_obj(args)?;
-        // this is the recommended path of calling a contract, with `reentry`
-        // always set `ContractReentryMode::Prohibited`
+        // this is the recommended path of calling a contract, with reentry
+        // prohibited unless the caller has explicitly allowed it for this call
+        let reentry_mode = self.take_next_call_reentry_mode()?;
         let res = self.call_n_internal(
             &self.contract_id_from_address(contract_address)?,
             func,
             argvec.as_slice(),
-            ContractReentryMode::Prohibited,
+            reentry_mode,
             false,
         );
         if let Err(e) = &res {
@@ -2155,15 +2618,14 @@ impl VmCallerEnv for Host {
         args: VecObject,
     ) -> Result<Val, HostError> {
         let argvec = self.call_args_from_obj(args)?;
-        // this is the "loosened" path of calling a contract.
-        // TODO: A `reentry` flag will be passed from `try_call` into here.
-        // For now, we are passing in `ContractReentryMode::Prohibited` to disable
-        // reentry.
+        // this is the "loosened" path of calling a contract, with the same
+        // reentry rules as `call`.
+        let reentry_mode = self.take_next_call_reentry_mode()?;
         let res = self.call_n_internal(
             &self.contract_id_from_address(contract_address)?,
             func,
             argvec.as_slice(),
-            ContractReentryMode::Prohibited,
+            reentry_mode,
             false,
         );
         match res {
@@ -2205,6 +2667,14 @@ impl VmCallerEnv for Host {
         }
     }
 
+    fn allow_reentry_for_next_call(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+    ) -> Result<Void, HostError> {
+        *self.try_borrow_allow_reentry_for_next_call_mut()? = true;
+        Ok(Val::VOID)
+    }
+
     // endregion: "call" module functions
     // region: "buf" module functions
 
@@ -2265,6 +2735,38 @@ impl VmCallerEnv for Host {
         Ok(Val::VOID)
     }
 
+    fn symbol_val_copy_to_linear_memory(
+        &self,
+        vmcaller: &mut VmCaller<Host>,
+        s: Symbol,
+        s_pos: U32Val,
+        lm_pos: U32Val,
+        len: U32Val,
+    ) -> Result<Void, HostError> {
+        let Ok(ss) = SymbolSmall::try_from(s) else {
+            let sobj: SymbolObject = s.try_into()?;
+            self.memobj_copy_to_linear_memory::<ScSymbol>(vmcaller, sobj, s_pos, lm_pos, len)?;
+            return Ok(Val::VOID);
+        };
+        let MemFnArgs { vm, pos, len } = self.get_mem_fn_args(lm_pos, len)?;
+        let sstr: SymbolStr = ss.into();
+        let s_pos: u32 = s_pos.into();
+        let s_end = s_pos
+            .checked_add(len)
+            .ok_or_else(|| self.err_arith_overflow())?;
+        let bytes: &[u8] = sstr.as_ref();
+        let buf = bytes.get(s_pos as usize..s_end as usize).ok_or_else(|| {
+            self.err(
+                ScErrorType::Object,
+                ScErrorCode::IndexBounds,
+                "out-of-bounds read from small symbol",
+                &[],
+            )
+        })?;
+        self.metered_vm_write_bytes_to_linear_memory(vmcaller, &vm, pos, buf)?;
+        Ok(Val::VOID)
+    }
+
     fn bytes_copy_to_linear_memory(
         &self,
         vmcaller: &mut VmCaller<Host>,
@@ -2312,7 +2814,17 @@ impl VmCallerEnv for Host {
         lm_pos: U32Val,
         len: U32Val,
     ) -> Result<SymbolObject, HostError> {
-        self.memobj_new_from_linear_memory::<ScSymbol>(vmcaller, lm_pos, len)
+        // Before SYMBOL_CHARS_CHECK_PROTOCOL_VERSION, the new symbol may hold
+        // any bytes.
+        if self.get_ledger_protocol_version()? < SYMBOL_CHARS_CHECK_PROTOCOL_VERSION {
+            return self.memobj_new_from_linear_memory::<ScSymbol>(vmcaller, lm_pos, len);
+        }
+        let MemFnArgs { vm, pos, len } = self.get_mem_fn_args(lm_pos, len)?;
+        self.charge_budget(ContractCostType::MemAlloc, Some(len as u64))?;
+        let mut vnew: Vec<u8> = vec![0; len as usize];
+        self.metered_vm_read_bytes_from_linear_memory(vmcaller, &vm, pos, &mut vnew)?;
+        self.check_symbol_chars(&vnew)?;
+        self.add_host_object(ScSymbol(vnew.try_into()?))
     }
 
     // Metering: covered by `metered_vm_scan_slices_in_linear_memory` and `symbol_matches`.
@@ -2458,6 +2970,21 @@ impl VmCallerEnv for Host {
         self.usize_to_u32val(len)
     }
 
+    // Notes on metering: `len` is free
+    fn symbol_val_len(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: Symbol,
+    ) -> Result<U32Val, HostError> {
+        let len = if let Ok(ss) = SymbolSmall::try_from(s) {
+            SymbolStr::from(ss).len()
+        } else {
+            let sobj: SymbolObject = s.try_into()?;
+            self.visit_obj(sobj, |hv: &ScSymbol| Ok(hv.len()))?
+        };
+        self.usize_to_u32val(len)
+    }
+
     // Notes on metering: `push` is free
     fn bytes_push(
         &self,
@@ -2619,6 +3146,16 @@ impl VmCallerEnv for Host {
         self.add_host_object(self.scbytes_from_vec(hash)?)
     }
 
+    // Notes on metering: covered by components.
+    fn compute_hash_sha256_val(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        v: Val,
+    ) -> Result<BytesObject, HostError> {
+        let hash = self.hash_val(v)?;
+        self.add_host_object(self.scbytes_from_hash(&hash)?)
+    }
+
     // Notes on metering: covered by components.
     fn compute_hash_keccak256(
         &self,
@@ -2819,6 +3356,59 @@ impl VmCallerEnv for Host {
         self.add_host_object(sc_addr)
     }
 
+    fn account_address_from_ed25519_public_key(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        public_key: BytesObject,
+    ) -> Result<AddressObject, Self::Error> {
+        // Decoding the key validates that it's a point on the curve, so that
+        // we never construct an account address nobody could sign for.
+        let _ = self.ed25519_pub_key_from_bytesobj_input(public_key)?;
+        let account_id = self.account_id_from_bytesobj(public_key)?;
+        self.add_host_object(ScAddress::Account(account_id))
+    }
+
+    fn contract_address_from_id(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        contract_id: BytesObject,
+    ) -> Result<AddressObject, Self::Error> {
+        let hash = self.hash_from_bytesobj_input("contract_id", contract_id)?;
+        self.add_host_object(ScAddress::Contract(hash))
+    }
+
+    fn address_to_ed25519_public_key(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        address: AddressObject,
+    ) -> Result<Val, Self::Error> {
+        let key = self.visit_obj(address, |addr: &ScAddress| match addr {
+            ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(ed25519)))) => {
+                Ok(Some(self.scbytes_from_slice(ed25519)?))
+            }
+            ScAddress::Contract(_) => Ok(None),
+        })?;
+        match key {
+            Some(bytes) => Ok(self.add_host_object(bytes)?.into()),
+            None => Ok(Val::VOID.into()),
+        }
+    }
+
+    fn address_to_contract_id(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        address: AddressObject,
+    ) -> Result<Val, Self::Error> {
+        let id = self.visit_obj(address, |addr: &ScAddress| match addr {
+            ScAddress::Contract(hash) => Ok(Some(self.scbytes_from_hash(hash)?)),
+            ScAddress::Account(_) => Ok(None),
+        })?;
+        match id {
+            Some(bytes) => Ok(self.add_host_object(bytes)?.into()),
+            None => Ok(Val::VOID.into()),
+        }
+    }
+
     // endregion: "address" module functions
     // region: "prng" module functions
 
@@ -2873,6 +3463,32 @@ impl VmCallerEnv for Host {
         self.with_current_prng(|prng| prng.u64_in_inclusive_range(lo..=hi, self.as_budget()))
     }
 
+    fn prng_u128_in_inclusive_range(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        lo: U128Val,
+        hi: U128Val,
+    ) -> Result<U128Val, Self::Error> {
+        let lo: u128 = lo.to_val().try_into_val(self)?;
+        let hi: u128 = hi.to_val().try_into_val(self)?;
+        let res =
+            self.with_current_prng(|prng| prng.u128_in_inclusive_range(lo..=hi, self.as_budget()))?;
+        Ok(res.try_into_val(self)?)
+    }
+
+    fn prng_i128_in_inclusive_range(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        lo: I128Val,
+        hi: I128Val,
+    ) -> Result<I128Val, Self::Error> {
+        let lo: i128 = lo.to_val().try_into_val(self)?;
+        let hi: i128 = hi.to_val().try_into_val(self)?;
+        let res =
+            self.with_current_prng(|prng| prng.i128_in_inclusive_range(lo..=hi, self.as_budget()))?;
+        Ok(res.try_into_val(self)?)
+    }
+
     fn prng_vec_shuffle(
         &self,
         _vmcaller: &mut VmCaller<Self::VmUserState>,
@@ -2883,6 +3499,21 @@ impl VmCallerEnv for Host {
         })?;
         self.add_host_object(vnew)
     }
+
+    fn prng_weighted_pick(
+        &self,
+        _vmcaller: &mut VmCaller<Self::VmUserState>,
+        weights: VecObject,
+    ) -> Result<U32Val, Self::Error> {
+        let weights = self.visit_obj(weights, |v: &HostVec| v.metered_clone(self))?;
+        Vec::<u64>::charge_bulk_init_cpy(weights.len() as u64, self)?;
+        let weights = weights
+            .iter()
+            .map(|w| u64::try_from_val(self, w))
+            .collect::<Result<Vec<u64>, _>>()?;
+        let idx = self.with_current_prng(|prng| prng.weighted_pick(&weights, self.as_budget()))?;
+        Ok(U32Val::from(idx))
+    }
     // endregion: "prng" module functions
 }
 
@@ -2913,6 +3544,132 @@ impl Host {
         Ok(())
     }
 
+    /// Sets a hook that is called on every access to the host [`Storage`]:
+    /// reads, writes, deletions and TTL extensions, including the accesses
+    /// performed by the host itself (e.g. while loading contract instances).
+    /// The reported event contains the accessed key, the entry before and
+    /// after the access, and the contract running in the current frame.
+    ///
+    /// The hook is called as the accesses happen, so it also observes the
+    /// accesses from frames that are later rolled back. The storage is
+    /// borrowed while the hook runs, hence the hook may not access it via the
+    /// provided [`Host`]. The hook isn't metered.
+    pub fn set_storage_access_hook(
+        &self,
+        hook: Option<StorageAccessHook>,
+    ) -> Result<(), HostError> {
+        let install = hook.is_some();
+        *self.try_borrow_storage_access_hook_mut()? = hook;
+        if install {
+            self.install_storage_access_callback()?;
+        }
+        Ok(())
+    }
+
+    /// Starts tracking the storage keys read and written by every contract
+    /// that runs on this host, see [`Host::get_storage_keys_by_contract`].
+    /// Does nothing if the tracking is already enabled.
+    ///
+    /// The tracking isn't metered.
+    pub fn enable_storage_keys_tracking(&self) -> Result<(), HostError> {
+        {
+            let mut keys_by_contract = self.try_borrow_storage_keys_by_contract_mut()?;
+            if keys_by_contract.is_some() {
+                return Ok(());
+            }
+            *keys_by_contract = Some(Default::default());
+        }
+        self.install_storage_access_callback()
+    }
+
+    /// Returns the storage keys that every contract that has run on this host
+    /// since [`Host::enable_storage_keys_tracking`] has read and written,
+    /// keyed by the contract id. Returns an empty map if the tracking hasn't
+    /// been enabled.
+    ///
+    /// Accesses are attributed to the contract running in the current frame,
+    /// so the accesses the host performs on behalf of a contract (such as
+    /// loading the instance of a contract it calls) are attributed to the
+    /// caller. Accesses from frames that have been rolled back are included
+    /// as well.
+    pub fn get_storage_keys_by_contract(&self) -> Result<StorageKeysByContract, HostError> {
+        Ok(self
+            .try_borrow_storage_keys_by_contract()?
+            .clone()
+            .unwrap_or_default())
+    }
+
+    /// Starts logging the host function calls made on this host through the
+    /// [Env](crate::Env) interface, along with the budget consumed by every
+    /// call, see [`Host::get_host_call_log`]. Clears the log if it is already
+    /// enabled.
+    ///
+    /// The logging isn't metered.
+    #[cfg(feature = "testutils")]
+    pub fn enable_host_call_log(&self) -> Result<(), HostError> {
+        *self.try_borrow_host_call_log_mut()? = Some(Default::default());
+        Ok(())
+    }
+
+    /// Returns the log of the host function calls made since
+    /// [`Host::enable_host_call_log`], or an empty log if the logging hasn't
+    /// been enabled.
+    #[cfg(feature = "testutils")]
+    pub fn get_host_call_log(&self) -> Result<call_log::HostCallLog, HostError> {
+        Ok(self.try_borrow_host_call_log()?.clone().unwrap_or_default())
+    }
+
+    // Installs the storage callback that tracks the keys accessed by every
+    // contract and forwards the accesses to the storage access hook, unless
+    // it's already installed. The callback is only installed on demand, as it
+    // runs unmetered on every storage access.
+    fn install_storage_access_callback(&self) -> Result<(), HostError> {
+        let mut storage = self.try_borrow_storage_mut()?;
+        if storage.access_callback.is_some() {
+            return Ok(());
+        }
+        // Use a weak reference to avoid the reference cycle between the host
+        // and its storage.
+        let weak_host = Rc::downgrade(&self.0);
+        let callback: crate::storage::StorageAccessCallback = Rc::new(move |mut event| {
+            let Some(host_impl) = weak_host.upgrade() else {
+                return;
+            };
+            let host = Host(host_impl);
+            event.contract = host.try_borrow_context_stack().ok().and_then(|stack| {
+                stack
+                    .last()
+                    .and_then(|ctx| ctx.frame.contract_id().cloned())
+            });
+            if let (Some(contract), Ok(Some(keys_by_contract))) = (
+                &event.contract,
+                host.try_borrow_storage_keys_by_contract_mut()
+                    .as_deref_mut(),
+            ) {
+                let keys = keys_by_contract.entry(contract.clone()).or_default();
+                match event.kind {
+                    crate::storage::StorageAccessKind::Read
+                    | crate::storage::StorageAccessKind::ExtendTtl => {
+                        keys.read.insert(Rc::clone(event.key));
+                    }
+                    crate::storage::StorageAccessKind::Write
+                    | crate::storage::StorageAccessKind::Delete => {
+                        keys.written.insert(Rc::clone(event.key));
+                    }
+                }
+            }
+            let hook = host
+                .try_borrow_storage_access_hook()
+                .ok()
+                .and_then(|hook| hook.clone());
+            if let Some(hook) = hook {
+                hook(&host, event);
+            }
+        });
+        storage.access_callback = Some(callback);
+        Ok(())
+    }
+
     /// Helper for mutating the [`Budget`] held in this [`Host`], either to
     /// allocate it on contract creation or to deplete it on callbacks from
     /// the VM or host functions.
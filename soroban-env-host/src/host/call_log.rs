@@ -0,0 +1,104 @@
+use std::fmt::Display;
+
+/// The entry to or the exit from a host function called through the [Env]
+/// interface, with the arguments and the result formatted with `Debug`.
+///
+/// [Env]: crate::Env
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostCallStep {
+    Call(&'static str, Vec<String>),
+    Ret(&'static str, Result<String, String>),
+}
+
+impl Display for HostCallStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostCallStep::Call(hostfn, args) => {
+                write!(f, "call {}(", hostfn)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            HostCallStep::Ret(hostfn, Ok(ok)) => write!(f, "ret {} -> Ok({})", hostfn, ok),
+            HostCallStep::Ret(hostfn, Err(err)) => write!(f, "ret {} -> Err({})", hostfn, err),
+        }
+    }
+}
+
+/// A host function call entry or exit along with the nesting depth of the
+/// call and, on exit, the budget consumed between the entry and the exit of
+/// the call (including any nested call).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostCallRecord {
+    pub depth: usize,
+    pub step: HostCallStep,
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+}
+
+impl Display for HostCallRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:indent$}{}", "", self.step, indent = self.depth * 2)?;
+        if let HostCallStep::Ret(..) = self.step {
+            write!(f, " [cpu:{}, mem:{}]", self.cpu_insns, self.mem_bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// The ordered log of the host function calls made on a [Host](crate::Host)
+/// since [Host::enable_host_call_log](crate::Host::enable_host_call_log),
+/// meant for diffing the behavior of different host versions.
+#[derive(Clone, Debug, Default)]
+pub struct HostCallLog {
+    records: Vec<HostCallRecord>,
+    // Budget consumed at the entry of each call in progress.
+    in_progress: Vec<(u64, u64)>,
+}
+
+impl HostCallLog {
+    pub(crate) fn record_call(
+        &mut self,
+        fname: &'static str,
+        args: &[String],
+        cpu_insns: u64,
+        mem_bytes: u64,
+    ) {
+        self.records.push(HostCallRecord {
+            depth: self.in_progress.len(),
+            step: HostCallStep::Call(fname, args.to_vec()),
+            cpu_insns: 0,
+            mem_bytes: 0,
+        });
+        self.in_progress.push((cpu_insns, mem_bytes));
+    }
+
+    pub(crate) fn record_ret(
+        &mut self,
+        fname: &'static str,
+        res: Result<String, String>,
+        cpu_insns: u64,
+        mem_bytes: u64,
+    ) {
+        let (cpu_insns_start, mem_bytes_start) = self.in_progress.pop().unwrap_or_default();
+        self.records.push(HostCallRecord {
+            depth: self.in_progress.len(),
+            step: HostCallStep::Ret(fname, res),
+            cpu_insns: cpu_insns.saturating_sub(cpu_insns_start),
+            mem_bytes: mem_bytes.saturating_sub(mem_bytes_start),
+        });
+    }
+
+    pub fn records(&self) -> &[HostCallRecord] {
+        &self.records
+    }
+
+    /// Renders the log one record per line, nested calls being indented.
+    pub fn render(&self) -> String {
+        self.records.iter().map(|r| format!("{r}\n")).collect()
+    }
+}
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{cmp::Ordering, rc::Rc};
 
 use crate::{
     budget::{AsBudget, DepthLimiter},
@@ -14,11 +14,85 @@ use crate::{
         ScErrorCode, ScErrorType, ScMap, ScMapEntry, ScSymbol, ScVal, ScVec, UInt128Parts,
         UInt256Parts, Uint256, VecM,
     },
-    AddressObject, BytesObject, Convert, Host, HostError, Object, ScValObjRef, ScValObject, Symbol,
-    SymbolObject, TryFromVal, TryIntoVal, U32Val, Val, VecObject,
+    AddressObject, BytesObject, Compare, Convert, Host, HostError, Object, ScValObjRef,
+    ScValObject, Symbol, SymbolObject, TryFromVal, TryIntoVal, U32Val, Val, VecObject,
 };
 
+/// The first ledger protocol version in which a conversion between `ScVal` and
+/// `Val` exceeding the depth or the element limit fails with the limit error,
+/// i.e. `(Context, ExceededLimit)` or `(Value, ExceededLimit)`. In earlier
+/// protocols the limit errors hit by the nested conversions are reported as
+/// the generic `(Value, UnexpectedType)` conversion error.
+pub(crate) const CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION: u32 = 21;
+
+/// How the host treats the entries of an incoming [ScMap] (e.g. an argument of
+/// a host function or a value loaded from storage) whose keys are not sorted
+/// in the host's order, see [Host::set_scmap_key_order_policy].
+///
+/// Maps with duplicate keys are always rejected, as there is no canonical way
+/// to pick one of their values.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ScMapKeyOrderPolicy {
+    /// Reject the maps whose keys are not sorted. This is what the network
+    /// does.
+    #[default]
+    Strict,
+    /// Sort the entries of the maps whose keys are not sorted. Sorting is
+    /// metered.
+    Canonicalize,
+}
+
 impl Host {
+    /// Sets the [ScMapKeyOrderPolicy] applied when converting [ScMap]s to
+    /// host maps. Embedders accepting values from less strict sources may
+    /// canonicalize them instead of rejecting them.
+    pub fn set_scmap_key_order_policy(&self, policy: ScMapKeyOrderPolicy) -> Result<(), HostError> {
+        *self.try_borrow_scmap_key_order_policy_mut()? = policy;
+        Ok(())
+    }
+
+    // Builds a host map from the entries converted from an [ScMap], which
+    // must have sorted and unique keys, or be sorted first under the
+    // lenient policy.
+    pub(crate) fn host_map_from_scmap_entries(
+        &self,
+        mut entries: Vec<(Val, Val)>,
+    ) -> Result<HostMap, HostError> {
+        if *self.try_borrow_scmap_key_order_policy()? == ScMapKeyOrderPolicy::Canonicalize {
+            let mut err: Option<HostError> = None;
+            entries.sort_by(|a, b| {
+                // Once an error has been hit, return Ordering::Equal to avoid
+                // any further (metered) comparisons.
+                if err.is_some() {
+                    return Ordering::Equal;
+                }
+                match self.compare(&a.0, &b.0) {
+                    Ok(ord) => ord,
+                    Err(he) => {
+                        err = Some(he);
+                        Ordering::Equal
+                    }
+                }
+            });
+            if let Some(he) = err {
+                return Err(he);
+            }
+        }
+        HostMap::from_map(entries, self).map_err(|he| {
+            if he.error.is_type(ScErrorType::Object) && he.error.is_code(ScErrorCode::InvalidInput)
+            {
+                self.err(
+                    ScErrorType::Object,
+                    ScErrorCode::InvalidInput,
+                    "ScMap keys are not sorted in the host order or not unique",
+                    &[],
+                )
+            } else {
+                he
+            }
+        })
+    }
+
     // Notes on metering: free
     pub(crate) fn usize_to_u32(&self, u: usize) -> Result<u32, HostError> {
         match u32::try_from(u) {
@@ -270,6 +344,7 @@ impl Host {
     }
 
     pub(crate) fn host_map_to_scmap(&self, map: &HostMap) -> Result<ScMap, HostError> {
+        self.budget_ref().charge_conversion_elements(map.len())?;
         Vec::<ScMapEntry>::charge_bulk_init_cpy(map.len() as u64, self)?;
         let mut mv = Vec::with_capacity(map.len());
         for (k, v) in map.iter(self)? {
@@ -323,16 +398,42 @@ impl Host {
         }
     }
 
+    // Returns whether the conversions report the limit errors they hit, see
+    // `CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION`. Hosts without ledger info
+    // keep the behavior of earlier protocols.
+    fn reports_conversion_limit_errors(&self) -> Result<bool, HostError> {
+        Ok(match self.try_borrow_ledger()?.as_ref() {
+            Some(li) => li.protocol_version >= CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION,
+            None => false,
+        })
+    }
+
+    // Converts a `ConversionError` produced by the common crate into a host
+    // error, restoring the limit error the conversion may have hit.
+    fn conversion_error(&self, cerr: crate::ConversionError, msg: &str) -> HostError {
+        if let Ok(true) = self.reports_conversion_limit_errors() {
+            if let Ok(Some(limit_err)) = self.budget_ref().conversion_limit_error() {
+                return self.error(limit_err, "conversion limit exceeded", &[]);
+            }
+        }
+        self.error(cerr.into(), msg, &[])
+    }
+
     pub(crate) fn from_host_val(&self, val: Val) -> Result<ScVal, HostError> {
         // This is the depth limit checkpoint for `Val`->`ScVal` conversion.
         // Metering of val conversion happens only if an object is encountered,
         // and is done inside `from_host_obj`.
         let _span = tracy_span!("Val to ScVal");
-        let scval = self.budget_cloned().with_limited_depth(|_| {
-            ScVal::try_from_val(self, &val).map_err(|cerr: crate::ConversionError| {
-                self.error(cerr.into(), "failed to convert host value to ScVal", &[])
-            })
-        })?;
+        let keep_limit_error = self.reports_conversion_limit_errors()?;
+        let scval = self
+            .budget_ref()
+            .with_conversion_limits(keep_limit_error, || {
+                self.budget_cloned().with_limited_depth(|_| {
+                    ScVal::try_from_val(self, &val).map_err(|cerr: crate::ConversionError| {
+                        self.conversion_error(cerr, "failed to convert host value to ScVal")
+                    })
+                })
+            })?;
         // This is a check of internal logical consistency: we came _from_ a Val
         // so the ScVal definitely should have been representable.
         self.check_val_representable_scval(&scval)?;
@@ -347,20 +448,32 @@ impl Host {
         // This is the depth limit checkpoint for `ScVal`->`Val` conversion.
         // Metering of val conversion happens only if an object is encountered,
         // and is done inside `to_host_obj`.
-        self.budget_cloned().with_limited_depth(|_| {
-            v.try_into_val(self)
-                .map_err(|cerr: crate::ConversionError| {
-                    self.error(cerr.into(), "failed to convert ScVal to host value", &[])
+        let keep_limit_error = self.reports_conversion_limit_errors()?;
+        self.budget_ref()
+            .with_conversion_limits(keep_limit_error, || {
+                self.budget_cloned().with_limited_depth(|_| {
+                    v.try_into_val(self)
+                        .map_err(|cerr: crate::ConversionError| {
+                            self.conversion_error(cerr, "failed to convert ScVal to host value")
+                        })
                 })
-        })
+            })
     }
 
     pub(crate) fn from_host_obj(&self, ob: impl Into<Object>) -> Result<ScValObject, HostError> {
+        let keep_limit_error = self.reports_conversion_limit_errors()?;
+        self.budget_ref()
+            .with_conversion_limits(keep_limit_error, || {
+                self.from_host_obj_within_limits(ob.into())
+            })
+    }
+
+    fn from_host_obj_within_limits(&self, objref: Object) -> Result<ScValObject, HostError> {
         unsafe {
-            let objref: Object = ob.into();
             self.visit_obj_untyped(objref, |ho| {
                 let val = match ho {
                     HostObject::Vec(vv) => {
+                        self.budget_ref().charge_conversion_elements(vv.len())?;
                         Vec::<ScVal>::charge_bulk_init_cpy(vv.len() as u64, self)?;
                         let sv = vv.iter().map(|e| self.from_host_val(*e)).collect::<Result<
                             Vec<ScVal>,
@@ -425,6 +538,12 @@ impl Host {
     }
 
     pub(crate) fn to_host_obj(&self, ob: &ScValObjRef<'_>) -> Result<Object, HostError> {
+        let keep_limit_error = self.reports_conversion_limit_errors()?;
+        self.budget_ref()
+            .with_conversion_limits(keep_limit_error, || self.to_host_obj_within_limits(ob))
+    }
+
+    fn to_host_obj_within_limits(&self, ob: &ScValObjRef<'_>) -> Result<Object, HostError> {
         let val: &ScVal = (*ob).into();
         // This is an internal consistency check: this is an internal method and any
         // caller should have previously rejected non-representable ScVals.
@@ -434,6 +553,7 @@ impl Host {
             // below. There is no otherwise ubiquitous metering for ScVal->Val conversion,
             // since most of them happen in the "common" crate with no access to the host.
             ScVal::Vec(Some(v)) => {
+                self.budget_ref().charge_conversion_elements(v.len())?;
                 Vec::<Val>::charge_bulk_init_cpy(v.len() as u64, self)?;
                 let mut vv = Vec::with_capacity(v.len());
                 for e in v.iter() {
@@ -442,6 +562,7 @@ impl Host {
                 Ok(self.add_host_object(HostVec::from_vec(vv)?)?.into())
             }
             ScVal::Map(Some(m)) => {
+                self.budget_ref().charge_conversion_elements(m.len())?;
                 Vec::<(Val, Val)>::charge_bulk_init_cpy(m.len() as u64, self)?;
                 let mut mm = Vec::with_capacity(m.len());
                 for pair in m.iter() {
@@ -449,7 +570,9 @@ impl Host {
                     let v = self.to_host_val(&pair.val)?;
                     mm.push((k, v))
                 }
-                Ok(self.add_host_object(HostMap::from_map(mm, self)?)?.into())
+                Ok(self
+                    .add_host_object(self.host_map_from_scmap_entries(mm)?)?
+                    .into())
             }
             ScVal::Vec(None) => Err(self.err(
                 ScErrorType::Value,
@@ -1,9 +1,13 @@
 use crate::host::prng::SEED_BYTES;
 use crate::{
-    budget::AsBudget,
+    budget::{AsBudget, Budget},
     err,
-    xdr::{ContractCostType, Hash, ScBytes, ScErrorCode, ScErrorType},
-    BytesObject, Error, Host, HostError, U32Val, Val,
+    xdr::{
+        ContractCostType, ContractIdPreimage, EnvelopeType, Hash, HashIdPreimage,
+        HashIdPreimageSorobanAuthorization, ScBytes, ScErrorCode, ScErrorType,
+        SorobanAuthorizedInvocation,
+    },
+    BytesObject, Error, Host, HostError, Object, U32Val, Val,
 };
 use hex_literal::hex;
 use hmac::{Hmac, Mac};
@@ -12,7 +16,56 @@ use rand_chacha::ChaCha20Rng;
 use sha2::Sha256;
 use sha3::Keccak256;
 
-use super::metered_clone::MeteredContainer;
+use std::collections::BTreeMap;
+
+use super::{
+    metered_clone::{MeteredClone, MeteredContainer},
+    metered_xdr::metered_write_xdr,
+};
+
+/// The hashes computed by [Host::hash_val], by the handle of the hashed
+/// object. Host objects are immutable, so a hash stays valid for as long as
+/// the handle does.
+pub(crate) type ValHashCache = BTreeMap<u32, Hash>;
+
+/// The first ledger protocol version in which the network id and the hashes
+/// of the `HashIdPreimage`s that include it are computed from the cached
+/// [NetworkPreimagePrefixes]. Earlier protocols encode the full preimages, as
+/// they are replayed with the same costs and object handles.
+pub(crate) const NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION: u32 = 21;
+
+/// The SHA-256 states after hashing the XDR encodings of the
+/// `HashIdPreimage` prefixes that only depend on the network (the envelope
+/// type followed by the network id), so that hashing the authorization
+/// payloads and the contract id preimages only has to encode, hash and charge
+/// the rest of the preimage. Cleared whenever the ledger info changes.
+#[derive(Clone)]
+pub(crate) struct NetworkPreimagePrefixes {
+    network_id: Hash,
+    soroban_authorization: Sha256,
+    contract_id: Sha256,
+}
+
+impl NetworkPreimagePrefixes {
+    // metering: covered
+    fn new(network_id: &[u8; 32], budget: &Budget) -> Result<Self, HostError> {
+        let network_id = Hash(network_id.metered_clone(budget)?);
+        let prefix_state = |ty: EnvelopeType| -> Result<Sha256, HostError> {
+            let mut buf = vec![];
+            metered_write_xdr(budget, &ty, &mut buf)?;
+            metered_write_xdr(budget, &network_id, &mut buf)?;
+            budget.charge(ContractCostType::ComputeSha256Hash, Some(buf.len() as u64))?;
+            let mut state = <Sha256 as sha2::Digest>::new();
+            sha2::Digest::update(&mut state, &buf);
+            Ok(state)
+        };
+        Ok(Self {
+            soroban_authorization: prefix_state(EnvelopeType::SorobanAuthorization)?,
+            contract_id: prefix_state(EnvelopeType::ContractId)?,
+            network_id,
+        })
+    }
+}
 
 impl Host {
     // Ed25519 functions
@@ -67,6 +120,8 @@ impl Host {
             ContractCostType::VerifyEd25519Sig,
             Some(payload.len() as u64),
         )?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_injected_fault("ed25519_verify")?;
         verifying_key.verify_strict(payload, sig).map_err(|_| {
             self.err(
                 ScErrorType::Crypto,
@@ -148,6 +203,8 @@ impl Host {
     ) -> Result<BytesObject, HostError> {
         let _span = tracy_span!("secp256k1 recover");
         self.charge_budget(ContractCostType::RecoverEcdsaSecp256k1Key, None)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_injected_fault("secp256k1_recover")?;
         let recovered_key =
             k256::ecdsa::VerifyingKey::recover_from_prehash(hash.as_slice(), &sig, rid).map_err(
                 |_| {
@@ -187,6 +244,128 @@ impl Host {
         })
     }
 
+    /// Returns the SHA-256 hash of the XDR encoding of `val` as an [ScVal],
+    /// which only depends on the value (e.g. not on the handles of the
+    /// objects it contains), so it can be used to commit to complex
+    /// structures.
+    ///
+    /// Hashing is fully metered. The hashes of objects are cached by handle,
+    /// so hashing the same object again only costs a lookup.
+    pub fn hash_val(&self, val: Val) -> Result<Hash, HostError> {
+        let handle = Object::try_from(val).ok().map(|obj| obj.get_handle());
+        if let Some(handle) = handle {
+            if let Some(hash) = self.try_borrow_val_hash_cache()?.get(&handle) {
+                return hash.metered_clone(self);
+            }
+        }
+        let hash = Hash(self.metered_hash_xdr(&self.from_host_val(val)?)?);
+        if let Some(handle) = handle {
+            self.try_borrow_val_hash_cache_mut()?
+                .insert(handle, hash.metered_clone(self)?);
+        }
+        Ok(hash)
+    }
+
+    fn with_network_preimage_prefixes<T>(
+        &self,
+        f: impl FnOnce(&NetworkPreimagePrefixes) -> Result<T, HostError>,
+    ) -> Result<T, HostError> {
+        if self.try_borrow_network_preimage_prefixes()?.is_none() {
+            let prefixes = self.with_ledger_info(|li| {
+                NetworkPreimagePrefixes::new(&li.network_id, self.budget_ref())
+            })?;
+            *self.try_borrow_network_preimage_prefixes_mut()? = Some(prefixes);
+        }
+        match self.try_borrow_network_preimage_prefixes()?.as_ref() {
+            Some(prefixes) => f(prefixes),
+            None => Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InternalError,
+                "missing network preimage prefixes",
+                &[],
+            )),
+        }
+    }
+
+    // Finishes hashing the preimage whose prefix has been hashed into
+    // `prefix_state` with the XDR encoding written by `write_rest`, which is
+    // the only part charged. Copying the fixed-size state is covered by the
+    // constant cost of the hash.
+    // metering: covered
+    fn metered_hash_prefixed_xdr(
+        &self,
+        prefix_state: &Sha256,
+        write_rest: impl FnOnce(&mut Vec<u8>) -> Result<(), HostError>,
+    ) -> Result<[u8; 32], HostError> {
+        let _span = tracy_span!("hash prefixed xdr");
+        let mut buf = vec![];
+        write_rest(&mut buf)?;
+        self.charge_budget(ContractCostType::ComputeSha256Hash, Some(buf.len() as u64))?;
+        let mut state = prefix_state.clone();
+        sha2::Digest::update(&mut state, &buf);
+        Ok(sha2::Digest::finalize(state).into())
+    }
+
+    pub(crate) fn network_preimage_prefixes_enabled(&self) -> Result<bool, HostError> {
+        Ok(self.get_ledger_protocol_version()? >= NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION)
+    }
+
+    /// Returns the network id of the current ledger, i.e. the SHA-256 hash of
+    /// the network passphrase.
+    pub(crate) fn network_id(&self) -> Result<Hash, HostError> {
+        self.with_network_preimage_prefixes(|p| p.network_id.metered_clone(self))
+    }
+
+    /// Returns the hash of the `HashIdPreimageSorobanAuthorization` for the
+    /// current network, i.e. the payload that the address authorizing
+    /// `invocation` has to sign.
+    // metering: covered by components
+    pub(crate) fn soroban_authorization_payload(
+        &self,
+        nonce: i64,
+        signature_expiration_ledger: u32,
+        invocation: SorobanAuthorizedInvocation,
+    ) -> Result<[u8; 32], HostError> {
+        if !self.network_preimage_prefixes_enabled()? {
+            let payload_preimage =
+                HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+                    network_id: Hash(
+                        self.with_ledger_info(|li| li.network_id.metered_clone(self))?,
+                    ),
+                    nonce,
+                    signature_expiration_ledger,
+                    invocation,
+                });
+            return self.metered_hash_xdr(&payload_preimage);
+        }
+        self.with_network_preimage_prefixes(|p| {
+            self.metered_hash_prefixed_xdr(&p.soroban_authorization, |buf| {
+                metered_write_xdr(self.budget_ref(), &nonce, buf)?;
+                metered_write_xdr(self.budget_ref(), &signature_expiration_ledger, buf)?;
+                metered_write_xdr(self.budget_ref(), &invocation, buf)
+            })
+        })
+    }
+
+    /// Returns the hash of the `HashIdPreimageContractId` for the current
+    /// network, i.e. the id of the contract created from `preimage`.
+    // metering: covered by components
+    pub(crate) fn contract_id_preimage_hash(
+        &self,
+        preimage: ContractIdPreimage,
+    ) -> Result<Hash, HostError> {
+        if !self.network_preimage_prefixes_enabled()? {
+            let id_preimage = self.get_full_contract_id_preimage(preimage)?;
+            return Ok(Hash(self.metered_hash_xdr(&id_preimage)?));
+        }
+        self.with_network_preimage_prefixes(|p| {
+            self.metered_hash_prefixed_xdr(&p.contract_id, |buf| {
+                metered_write_xdr(self.budget_ref(), &preimage, buf)
+            })
+        })
+        .map(Hash)
+    }
+
     // Keccak256/SHA3 functions
     pub(crate) fn keccak256_hash_from_bytes_raw(
         &self,
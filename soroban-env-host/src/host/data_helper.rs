@@ -71,6 +71,82 @@ impl Host {
         })
     }
 
+    // Returns whether `owner` is a contract other than the one running in the
+    // current frame. Accesses performed outside of a contract frame are never
+    // considered foreign.
+    // Notes on metering: the contract ids are compared in place, which is a
+    // small fixed-size comparison that we don't charge for.
+    fn is_foreign_contract(&self, owner: &Hash) -> Result<bool, HostError> {
+        self.with_current_frame_opt(|frame| {
+            Ok(frame
+                .and_then(|frame| frame.contract_id())
+                .is_some_and(|id| id != owner))
+        })
+    }
+
+    /// Enforces the [CrossContractStoragePolicy](crate::storage::CrossContractStoragePolicy)
+    /// for the TTL extension of the instance and code of the contract `owner`
+    /// performed by the currently running contract. Extensions performed
+    /// outside of a contract frame are not restricted.
+    pub(crate) fn check_cross_contract_ttl_extension(&self, owner: &Hash) -> Result<(), HostError> {
+        if self.is_foreign_contract(owner)?
+            && !self
+                .try_borrow_cross_contract_storage_policy()?
+                .allow_extend_ttl
+        {
+            return Err(self.err(
+                ScErrorType::Storage,
+                ScErrorCode::InvalidAction,
+                "contract is not allowed to extend the TTL of another contract",
+                &[],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Enforces the [CrossContractStoragePolicy](crate::storage::CrossContractStoragePolicy)
+    /// for the read of the contract data entry under `key` performed by the
+    /// currently running contract. Reads of any other kind of entry are not
+    /// restricted.
+    fn check_cross_contract_read(&self, key: &LedgerKey) -> Result<(), HostError> {
+        if let LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(owner),
+            ..
+        }) = key
+        {
+            if self.is_foreign_contract(owner)?
+                && !self.try_borrow_cross_contract_storage_policy()?.allow_read
+            {
+                return Err(self.err(
+                    ScErrorType::Storage,
+                    ScErrorCode::InvalidAction,
+                    "contract is not allowed to read the storage of another contract",
+                    &[],
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the contract data entry under `key`, read on behalf of the
+    /// currently running contract.
+    // Notes on metering: covered by the storage.
+    pub(crate) fn get_contract_data_entry(
+        &self,
+        key: &Rc<LedgerKey>,
+    ) -> Result<Rc<LedgerEntry>, HostError> {
+        self.check_cross_contract_read(key)?;
+        self.try_borrow_storage_mut()?.get(key, self.as_budget())
+    }
+
+    /// Returns whether the contract data entry under `key` exists, as checked
+    /// on behalf of the currently running contract.
+    // Notes on metering: covered by the storage.
+    pub(crate) fn has_contract_data_entry(&self, key: &Rc<LedgerKey>) -> Result<bool, HostError> {
+        self.check_cross_contract_read(key)?;
+        self.try_borrow_storage_mut()?.has(key, self.as_budget())
+    }
+
     pub(crate) fn contract_instance_ledger_key(
         &self,
         contract_id: &Hash,
@@ -456,6 +532,8 @@ impl Host {
     ) -> Result<(), HostError> {
         let durability: ContractDataDurability = t.try_into()?;
         let key = self.contract_data_key_from_val(k, durability)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_injected_fault("storage_write")?;
         // Currently the storage stores the whole ledger entries, while this
         // operation might only modify only the internal `ScVal` value. Thus we
         // need to only overwrite the value in case if there is already an
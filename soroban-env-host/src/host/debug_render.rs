@@ -0,0 +1,304 @@
+use crate::{
+    budget::AsBudget,
+    host_object::HostObject,
+    storage::{FootprintMode, Storage},
+    xdr::{
+        AccountId, ContractCostType, LedgerKey, PublicKey, ScAddress, ScErrorCode, ScErrorType,
+        ScVal,
+    },
+    Host, HostError, Object, Val,
+};
+
+/// Bounds on how much the `debug_render*` functions on [Host] print. Anything
+/// past a bound is elided with `...`, so that logging a pathologically large
+/// or deep value can neither hang nor exhaust memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DebugRenderLimits {
+    /// Containers nested deeper than this are printed as `[...]` or `{...}`.
+    pub max_depth: u32,
+    /// Containers print at most this many elements, and bytes and strings at
+    /// most this many bytes.
+    pub max_elements: usize,
+    /// Rendering stops once the output reaches this many bytes.
+    pub max_len: usize,
+}
+
+impl Default for DebugRenderLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            max_elements: 32,
+            max_len: 4096,
+        }
+    }
+}
+
+// Accumulates bounded output, charging the (shadow) budget for every write.
+// Any error, including reaching `max_len`, ends the rendering.
+struct Renderer<'a> {
+    host: &'a Host,
+    limits: DebugRenderLimits,
+    out: String,
+}
+
+impl<'a> Renderer<'a> {
+    fn write(&mut self, s: &str) -> Result<(), HostError> {
+        self.host
+            .charge_budget(ContractCostType::MemCpy, Some(s.len() as u64))?;
+        let room = self.limits.max_len.saturating_sub(self.out.len());
+        if s.len() <= room {
+            self.out.push_str(s);
+            return Ok(());
+        }
+        let mut end = room;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.out.push_str(&s[..end]);
+        Err((ScErrorType::Context, ScErrorCode::ExceededLimit).into())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), HostError> {
+        for b in bytes.iter().take(self.limits.max_elements) {
+            self.write(&format!("{:02x}", b))?;
+        }
+        if bytes.len() > self.limits.max_elements {
+            self.write("...")?;
+        }
+        Ok(())
+    }
+
+    fn write_str_bytes(&mut self, bytes: &[u8]) -> Result<(), HostError> {
+        let shown = &bytes[..bytes.len().min(self.limits.max_elements)];
+        self.write(&String::from_utf8_lossy(shown))?;
+        if bytes.len() > self.limits.max_elements {
+            self.write("...")?;
+        }
+        Ok(())
+    }
+
+    // Writes the elements yielded by `items` between `open` and `close`,
+    // eliding the ones past `max_elements` and the whole contents past
+    // `max_depth`.
+    fn write_seq<T, F>(
+        &mut self,
+        open: &str,
+        close: &str,
+        depth: u32,
+        items: impl ExactSizeIterator<Item = T>,
+        mut f: F,
+    ) -> Result<(), HostError>
+    where
+        F: FnMut(&mut Self, T) -> Result<(), HostError>,
+    {
+        self.write(open)?;
+        if items.len() != 0 && depth >= self.limits.max_depth {
+            self.write("...")?;
+            return self.write(close);
+        }
+        let len = items.len();
+        for (i, item) in items.take(self.limits.max_elements).enumerate() {
+            if i != 0 {
+                self.write(", ")?;
+            }
+            f(self, item)?;
+        }
+        if len > self.limits.max_elements {
+            self.write(", ...")?;
+        }
+        self.write(close)
+    }
+
+    fn render_val(&mut self, val: Val, depth: u32) -> Result<(), HostError> {
+        match Object::try_from(val) {
+            Ok(obj) => {
+                let host = self.host;
+                host.visit_obj_untyped(obj, |hobj| self.render_obj(hobj, depth))
+            }
+            Err(_) => self.write(&format!("{:?}", val)),
+        }
+    }
+
+    fn render_obj(&mut self, hobj: &HostObject, depth: u32) -> Result<(), HostError> {
+        match hobj {
+            HostObject::Vec(v) => self.write_seq("[", "]", depth, v.iter(), |r, e| {
+                r.render_val(*e, depth + 1)
+            }),
+            HostObject::Map(m) => self.write_seq("{", "}", depth, m.map.iter(), |r, (k, v)| {
+                r.render_val(*k, depth + 1)?;
+                r.write(": ")?;
+                r.render_val(*v, depth + 1)
+            }),
+            HostObject::U64(u) => self.write(&format!("U64({})", u)),
+            HostObject::I64(i) => self.write(&format!("I64({})", i)),
+            HostObject::TimePoint(t) => self.write(&format!("Timepoint({})", t.0)),
+            HostObject::Duration(d) => self.write(&format!("Duration({})", d.0)),
+            HostObject::U128(u) => self.write(&format!("U128({})", u)),
+            HostObject::I128(i) => self.write(&format!("I128({})", i)),
+            HostObject::U256(u) => self.write(&format!("U256({})", u)),
+            HostObject::I256(i) => self.write(&format!("I256({})", i)),
+            HostObject::Bytes(b) => {
+                self.write("Bytes(")?;
+                self.write_bytes(b.as_slice())?;
+                self.write(")")
+            }
+            HostObject::String(s) => {
+                self.write("String(\"")?;
+                self.write_str_bytes(s.as_slice())?;
+                self.write("\")")
+            }
+            HostObject::Symbol(s) => {
+                self.write("Symbol(")?;
+                self.write_str_bytes(s.as_slice())?;
+                self.write(")")
+            }
+            HostObject::Address(a) => self.render_address(a),
+        }
+    }
+
+    fn render_address(&mut self, addr: &ScAddress) -> Result<(), HostError> {
+        match addr {
+            ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(k))) => {
+                self.write("Address(Account(")?;
+                self.write_bytes(&k.0)?;
+            }
+            ScAddress::Contract(h) => {
+                self.write("Address(Contract(")?;
+                self.write_bytes(&h.0)?;
+            }
+        }
+        self.write("))")
+    }
+
+    fn render_scval(&mut self, scv: &ScVal, depth: u32) -> Result<(), HostError> {
+        match scv {
+            ScVal::Vec(Some(v)) => self.write_seq("[", "]", depth, v.iter(), |r, e| {
+                r.render_scval(e, depth + 1)
+            }),
+            ScVal::Vec(None) => self.write("[]"),
+            ScVal::Map(Some(m)) => self.write_seq("{", "}", depth, m.iter(), |r, e| {
+                r.render_scval(&e.key, depth + 1)?;
+                r.write(": ")?;
+                r.render_scval(&e.val, depth + 1)
+            }),
+            ScVal::Map(None) => self.write("{}"),
+            ScVal::Bytes(b) => {
+                self.write("Bytes(")?;
+                self.write_bytes(b.as_slice())?;
+                self.write(")")
+            }
+            ScVal::String(s) => {
+                self.write("String(\"")?;
+                self.write_str_bytes(s.as_slice())?;
+                self.write("\")")
+            }
+            ScVal::Symbol(s) => {
+                self.write("Symbol(")?;
+                self.write_str_bytes(s.as_slice())?;
+                self.write(")")
+            }
+            ScVal::Address(a) => self.render_address(a),
+            ScVal::ContractInstance(_) => self.write("ContractInstance(...)"),
+            // The remaining cases are all fixed-size.
+            _ => self.write(&format!("{:?}", scv)),
+        }
+    }
+
+    fn render_ledger_key(&mut self, key: &LedgerKey) -> Result<(), HostError> {
+        match key {
+            LedgerKey::Account(k) => {
+                let AccountId(PublicKey::PublicKeyTypeEd25519(pk)) = &k.account_id;
+                self.write("Account(")?;
+                self.write_bytes(&pk.0)?;
+                self.write(")")
+            }
+            LedgerKey::ContractData(k) => {
+                self.write("ContractData(")?;
+                self.render_address(&k.contract)?;
+                self.write(&format!(", {:?}, ", k.durability))?;
+                self.render_scval(&k.key, 1)?;
+                self.write(")")
+            }
+            LedgerKey::ContractCode(k) => {
+                self.write("ContractCode(")?;
+                self.write_bytes(&k.hash.0)?;
+                self.write(")")
+            }
+            _ => self.write(key.name()),
+        }
+    }
+
+    fn render_storage(&mut self, storage: &Storage) -> Result<(), HostError> {
+        let mode = match storage.mode {
+            FootprintMode::Recording(_) => "Recording",
+            FootprintMode::Enforcing => "Enforcing",
+        };
+        self.write(&format!("Storage({}, footprint: ", mode))?;
+        self.write_seq("[", "]", 0, storage.footprint.0.map.iter(), |r, (k, a)| {
+            r.render_ledger_key(k)?;
+            r.write(&format!(": {:?}", a))
+        })?;
+        self.write(&format!(", entries: {})", storage.map.len()))
+    }
+
+    fn render_host(&mut self) -> Result<(), HostError> {
+        let host = self.host;
+        self.write(&format!(
+            "Host(objects: {}, frames: {}, events: {}, cpu: {}, mem: {}, ",
+            host.try_borrow_objects()?.len(),
+            host.try_borrow_context_stack()?.len(),
+            host.try_borrow_events()?.vec.len(),
+            host.as_budget().get_cpu_insns_consumed()?,
+            host.as_budget().get_mem_bytes_consumed()?,
+        ))?;
+        self.render_storage(&*host.try_borrow_storage()?)?;
+        self.write(")")
+    }
+}
+
+impl Host {
+    // Runs `f` over a fresh renderer in shadow mode, so that rendering is
+    // charged to the shadow budget and never affects the real one. If `f`
+    // stops early, for any reason, the output is marked as truncated.
+    fn debug_render_with<F>(&self, limits: DebugRenderLimits, f: F) -> String
+    where
+        F: FnOnce(&mut Renderer) -> Result<(), HostError>,
+    {
+        let mut renderer = Renderer {
+            host: self,
+            limits,
+            out: String::new(),
+        };
+        let mut complete = false;
+        self.as_budget().with_shadow_mode(|| {
+            f(&mut renderer)?;
+            complete = true;
+            Ok(())
+        });
+        if !complete {
+            renderer.out.push_str("...");
+        }
+        renderer.out
+    }
+
+    /// Renders `val` for logging, including the contents of any host objects
+    /// it refers to, within the given `limits`. Rendering is charged to the
+    /// shadow budget; if that runs out the output is truncated.
+    pub fn debug_render_val(&self, val: Val, limits: DebugRenderLimits) -> String {
+        self.debug_render_with(limits, |r| r.render_val(val, 0))
+    }
+
+    /// Renders the footprint of the host's [Storage] for logging, within the
+    /// given `limits`.
+    pub fn debug_render_storage(&self, limits: DebugRenderLimits) -> String {
+        self.debug_render_with(limits, |r| r.render_storage(&*r.host.try_borrow_storage()?))
+    }
+
+    /// Renders a summary of the host's state for logging, within the given
+    /// `limits`. Unlike the [std::fmt::Debug] impl, which only identifies the
+    /// host, this includes object, frame and event counts, budget consumption
+    /// and the storage footprint.
+    pub fn debug_render(&self, limits: DebugRenderLimits) -> String {
+        self.debug_render_with(limits, |r| r.render_host())
+    }
+}
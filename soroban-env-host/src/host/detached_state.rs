@@ -0,0 +1,219 @@
+use std::rc::Rc;
+
+use crate::{
+    budget::Budget,
+    events::EventLimits,
+    host::{error::TryBorrowOrErr, metered_map::MeteredOrdMap, prng::BasePrng},
+    storage::{
+        AccessType, CrossContractStoragePolicy, Footprint, FootprintMode, SnapshotSource, Storage,
+    },
+    xdr::{AccountId, ContractCostParams, LedgerEntry, LedgerKey, ScErrorCode, ScErrorType},
+    CallLimits, DiagnosticLevel, Host, HostError, LedgerInfo, ScMapKeyOrderPolicy,
+};
+
+/// A ledger entry of a [DetachedHostState], along with its live-until ledger.
+pub type OwnedEntryWithLiveUntil = (LedgerEntry, Option<u32>);
+
+/// The state of a [Host] between invocations, detached from it via
+/// [Host::detach_state] in order to be sent to another thread and attached to
+/// a new host there via [Host::attach_state].
+///
+/// [Host] itself is built around `Rc` and `RefCell` and is not `Send`, so
+/// multi-threaded embedders (such as simulation services) move this state
+/// instead. Unlike the host, the state owns all of its values, so detaching
+/// copies the ledger entries; the storage snapshot source can't be moved
+/// between threads and has to be provided again when attaching.
+///
+/// Only the state that persists between invocations is captured, along with
+/// the configuration of the host (the diagnostic level, limits and policies):
+/// the host objects, events and the authorization entries are not, and
+/// neither are the hooks and callbacks, which can't be moved between threads
+/// and have to be installed on the new host again.
+#[derive(Clone)]
+pub struct DetachedHostState {
+    pub ledger_info: Option<LedgerInfo>,
+    /// The storage footprint and map, including the entries deleted so far
+    /// (mapped to `None`).
+    pub footprint: Vec<(LedgerKey, AccessType)>,
+    pub entries: Vec<(LedgerKey, Option<OwnedEntryWithLiveUntil>)>,
+    /// The entries archived via [Host::archive_expired_entries].
+    #[cfg(any(test, feature = "testutils"))]
+    pub archived: Vec<(LedgerKey, LedgerEntry)>,
+    /// Whether the storage is in recording mode, in which case a
+    /// [SnapshotSource] has to be provided when attaching the state.
+    pub recording_footprint: bool,
+    /// The budget configuration, see [Budget::try_from_configs]. Attached
+    /// hosts start with nothing consumed.
+    pub cpu_limit: u64,
+    pub mem_limit: u64,
+    pub cpu_cost_params: ContractCostParams,
+    pub mem_cost_params: ContractCostParams,
+    /// See [Budget::set_depth_limit] and [Budget::set_conversion_element_limit].
+    pub depth_limit: u32,
+    pub conversion_element_limit: u32,
+    pub diagnostic_level: DiagnosticLevel,
+    pub cross_contract_storage_policy: CrossContractStoragePolicy,
+    pub scmap_key_order_policy: ScMapKeyOrderPolicy,
+    pub event_limits: EventLimits,
+    pub call_limits: CallLimits,
+    pub source_account: Option<AccountId>,
+    /// `Some(disable_non_root_auth)` if the host records authorizations,
+    /// `None` if it enforces them.
+    pub recording_auth: Option<bool>,
+    pub(crate) base_prng: Option<BasePrng>,
+}
+
+static_assertions::assert_impl_all!(DetachedHostState: Send);
+
+impl Host {
+    /// Detaches the state of the host, so that it can be sent to another
+    /// thread and attached to a new host there via [Host::attach_state]. The
+    /// host itself is left unchanged.
+    ///
+    /// Fails if an invocation is in progress.
+    pub fn detach_state(&self) -> Result<DetachedHostState, HostError> {
+        if !self.try_borrow_context_stack()?.is_empty() {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidAction,
+                "cannot detach the state of a host during an invocation",
+                &[],
+            ));
+        }
+        let (cpu_limit, mem_limit, cpu_cost_params, mem_cost_params) =
+            self.budget_ref().to_configs()?;
+        let storage = self.try_borrow_storage()?;
+        Ok(DetachedHostState {
+            ledger_info: self.try_borrow_ledger()?.clone(),
+            footprint: storage
+                .footprint
+                .0
+                .map
+                .iter()
+                .map(|(key, access)| (key.as_ref().clone(), *access))
+                .collect(),
+            entries: storage
+                .map
+                .map
+                .iter()
+                .map(|(key, entry)| {
+                    (
+                        key.as_ref().clone(),
+                        entry
+                            .as_ref()
+                            .map(|(entry, live_until)| (entry.as_ref().clone(), *live_until)),
+                    )
+                })
+                .collect(),
+            #[cfg(any(test, feature = "testutils"))]
+            archived: storage
+                .archived
+                .map
+                .iter()
+                .map(|(key, entry)| (key.as_ref().clone(), entry.as_ref().clone()))
+                .collect(),
+            recording_footprint: matches!(storage.mode, FootprintMode::Recording(_)),
+            cpu_limit,
+            mem_limit,
+            cpu_cost_params,
+            mem_cost_params,
+            depth_limit: self.budget_ref().get_depth_limit()?,
+            conversion_element_limit: self.budget_ref().get_conversion_element_limit()?,
+            // The level is only copied over to the new host here, never
+            // observed, see the note on `HostImpl::diagnostic_level`.
+            diagnostic_level: *self.0.diagnostic_level.try_borrow_or_err()?,
+            cross_contract_storage_policy: *self.try_borrow_cross_contract_storage_policy()?,
+            scmap_key_order_policy: *self.try_borrow_scmap_key_order_policy()?,
+            event_limits: *self.try_borrow_event_limits()?,
+            call_limits: *self.try_borrow_call_limits()?,
+            source_account: self.try_borrow_source_account()?.clone(),
+            recording_auth: self
+                .try_borrow_authorization_manager()?
+                .recording_mode_disables_non_root_auth(),
+            base_prng: self.try_borrow_base_prng()?.clone(),
+        })
+    }
+
+    /// Constructs a host in the state detached from another host via
+    /// [Host::detach_state], with nothing consumed from its budget.
+    ///
+    /// `snapshot_source` has to be provided if and only if the state was
+    /// detached from a host with a storage in recording mode.
+    pub fn attach_state(
+        state: DetachedHostState,
+        snapshot_source: Option<Rc<dyn SnapshotSource>>,
+    ) -> Result<Self, HostError> {
+        let mut storage = match (snapshot_source, state.recording_footprint) {
+            (Some(src), true) => Storage::with_recording_footprint(src),
+            (None, false) => Storage::default(),
+            _ => {
+                return Err(HostError::from((
+                    ScErrorType::Storage,
+                    ScErrorCode::InvalidInput,
+                )))
+            }
+        };
+        let budget = Budget::try_from_configs(
+            state.cpu_limit,
+            state.mem_limit,
+            state.cpu_cost_params,
+            state.mem_cost_params,
+        )?;
+        storage.footprint = Footprint(MeteredOrdMap::from_map(
+            state
+                .footprint
+                .into_iter()
+                .map(|(key, access)| (Rc::new(key), access))
+                .collect(),
+            &budget,
+        )?);
+        storage.map = MeteredOrdMap::from_map(
+            state
+                .entries
+                .into_iter()
+                .map(|(key, entry)| {
+                    (
+                        Rc::new(key),
+                        entry.map(|(entry, live_until)| (Rc::new(entry), live_until)),
+                    )
+                })
+                .collect(),
+            &budget,
+        )?;
+        #[cfg(any(test, feature = "testutils"))]
+        {
+            storage.archived = MeteredOrdMap::from_map(
+                state
+                    .archived
+                    .into_iter()
+                    .map(|(key, entry)| (Rc::new(key), Rc::new(entry)))
+                    .collect(),
+                &budget,
+            )?;
+        }
+        budget.set_depth_limit(state.depth_limit)?;
+        budget.set_conversion_element_limit(state.conversion_element_limit)?;
+        let host = Host::with_storage_and_budget(storage, budget);
+        host.set_diagnostic_level(state.diagnostic_level)?;
+        host.set_cross_contract_storage_policy(state.cross_contract_storage_policy)?;
+        host.set_scmap_key_order_policy(state.scmap_key_order_policy)?;
+        host.set_event_limits(state.event_limits)?;
+        host.set_call_limits(state.call_limits)?;
+        if let Some(base_prng) = state.base_prng {
+            host.set_base_prng_seed(base_prng.seed())?;
+            *host.try_borrow_base_prng_mut()? = Some(base_prng);
+        }
+        if let Some(ledger_info) = state.ledger_info {
+            host.set_ledger_info(ledger_info)?;
+        }
+        if let Some(source_account) = state.source_account {
+            host.set_source_account(source_account)?;
+        }
+        if let Some(disable_non_root_auth) = state.recording_auth {
+            host.switch_to_recording_auth(disable_non_root_auth)?;
+        }
+        host.budget_ref()
+            .reset_limits(state.cpu_limit, state.mem_limit)?;
+        Ok(host)
+    }
+}
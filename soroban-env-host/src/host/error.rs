@@ -11,14 +11,40 @@ use core::fmt::Debug;
 use std::{
     cell::{Ref, RefCell, RefMut},
     ops::DerefMut,
+    panic::Location,
     rc::Rc,
 };
 
 use super::metered_clone::MeteredClone;
 
+/// A frame of context attached to a [HostError], see [HostError::context].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorContext {
+    /// The location in the host code where the error was raised.
+    HostLocation(&'static Location<'static>),
+    /// The host function that failed with the error.
+    HostFunction(&'static str),
+    /// The contract function that failed with the error.
+    ContractFunction { contract: Hash, function: String },
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorContext::HostLocation(loc) => write!(f, "raised at {}", loc),
+            ErrorContext::HostFunction(name) => write!(f, "in host function {}", name),
+            ErrorContext::ContractFunction { contract, function } => {
+                write!(f, "in contract {} function {}", contract, function)
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct DebugInfo {
     events: Events,
+    // Innermost first.
+    context: Vec<ErrorContext>,
     #[cfg(any(test, feature = "testutils"))]
     backtrace: Backtrace,
 }
@@ -38,6 +64,17 @@ impl Into<Error> for HostError {
 }
 
 impl DebugInfo {
+    fn write_context(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.context.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Context (innermost first):")?;
+            for (i, c) in self.context.iter().enumerate() {
+                writeln!(f, "   {}: {}", i, c)?;
+            }
+        }
+        Ok(())
+    }
+
     fn write_events(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // TODO: maybe make this something users can adjust?
         // https://github.com/stellar/rs-soroban-env/issues/1288
@@ -117,6 +154,7 @@ impl Debug for HostError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "HostError: {:?}", self.error)?;
         if let Some(info) = &self.info {
+            info.write_context(f)?;
             info.write_events(f)?;
             info.write_backtrace(f)
         } else {
@@ -140,6 +178,32 @@ impl HostError {
         }
     }
 
+    /// Returns the chain of context frames accumulated by the error as it
+    /// propagated, innermost first. The chain is only recorded along with the
+    /// rest of the debug info, i.e. at
+    /// [DiagnosticLevel::FullTrace](crate::DiagnosticLevel::FullTrace).
+    pub fn context(&self) -> &[ErrorContext] {
+        match &self.info {
+            Some(info) => &info.context,
+            None => &[],
+        }
+    }
+
+    // Appends `context` to the chain, if the error has debug info.
+    pub(crate) fn push_context(&mut self, context: ErrorContext) {
+        if let Some(info) = &mut self.info {
+            info.context.push(context);
+        }
+    }
+
+    // Carries the chain of `cause` over to this error, which was raised in
+    // response to it.
+    pub(crate) fn inherit_context(&mut self, cause: &HostError) {
+        if let Some(info) = &mut self.info {
+            info.context = cause.context().to_vec();
+        }
+    }
+
     /// Identifies whether the error can be meaningfully recovered from.
     ///
     /// We consider errors that occur due to broken execution preconditions (
@@ -228,6 +292,7 @@ impl<T> TryBorrowOrErr<T> for RefCell<T> {
 
 impl Host {
     /// Convenience function to construct an [Error] and pass to [Host::error].
+    #[track_caller]
     pub(crate) fn err(
         &self,
         type_: ScErrorType,
@@ -244,7 +309,27 @@ impl Host {
     /// records a diagnostic event with the provided `msg` and `args` and then
     /// enriches the returned [Error] with [DebugInfo] in the form of a
     /// [Backtrace] and snapshot of the [Events] buffer.
+    #[track_caller]
     pub(crate) fn error(&self, error: Error, msg: &str, args: &[Val]) -> HostError {
+        self.error_at(error, msg, args, Location::caller(), false)
+    }
+
+    /// Like [Host::error], for a contract error raised by the running contract
+    /// itself: when the error is named in the spec of the contract, the name
+    /// is added to the data of the diagnostic event.
+    #[track_caller]
+    pub(crate) fn contract_error(&self, error: Error, msg: &str, args: &[Val]) -> HostError {
+        self.error_at(error, msg, args, Location::caller(), true)
+    }
+
+    fn error_at(
+        &self,
+        error: Error,
+        msg: &str,
+        args: &[Val],
+        location: &'static Location<'static>,
+        name_contract_error: bool,
+    ) -> HostError {
         let mut he = HostError::from(error);
         self.with_debug_mode(|| {
             // We _try_ to take a mutable borrow of the events buffer refcell
@@ -254,13 +339,26 @@ impl Host {
             // the refcell. This is to handle the "double fault" case where we
             // get an error _while performing_ any of the steps needed to record
             // an error as an event, below.
-            if let Ok(mut events_refmut) = self.0.events.try_borrow_mut() {
-                self.record_err_diagnostics(events_refmut.deref_mut(), error, msg, args);
+            let recorded = match self.0.events.try_borrow_mut() {
+                Ok(mut events_refmut) => self.record_err_diagnostics(
+                    events_refmut.deref_mut(),
+                    error,
+                    msg,
+                    args,
+                    name_contract_error,
+                ),
+                Err(_) => None,
+            };
+            // Errors returned by the callback for diagnostic events are
+            // ignored, see `set_event_callback`.
+            if let Some((event, sequence)) = recorded {
+                let _ = self.call_event_callback(&event, sequence);
             }
             he = HostError {
                 error,
                 info: self.maybe_get_debug_info(),
             };
+            he.push_context(ErrorContext::HostLocation(location));
             Ok(())
         });
         he
@@ -275,11 +373,15 @@ impl Host {
         // users doing local testing to get nice backtraces on their console.
         #[cfg(any(test, feature = "testutils"))]
         {
-            self.with_debug_mode(|| {
+            self.with_full_trace_mode(|| {
                 if let Ok(events_ref) = self.0.events.try_borrow() {
                     let events = events_ref.externalize(self)?;
                     let backtrace = Backtrace::new_unresolved();
-                    res = Some(Box::new(DebugInfo { backtrace, events }));
+                    res = Some(Box::new(DebugInfo {
+                        backtrace,
+                        events,
+                        context: vec![],
+                    }));
                 }
                 Ok(())
             });
@@ -289,6 +391,7 @@ impl Host {
 
     // Some common error patterns here.
 
+    #[track_caller]
     pub(crate) fn err_arith_overflow(&self) -> HostError {
         self.err(
             ScErrorType::Value,
@@ -298,6 +401,7 @@ impl Host {
         )
     }
 
+    #[track_caller]
     pub(crate) fn err_oob_linear_memory(&self) -> HostError {
         self.err(
             ScErrorType::WasmVm,
@@ -307,6 +411,7 @@ impl Host {
         )
     }
 
+    #[track_caller]
     pub(crate) fn err_oob_object_index(&self, index: Option<u32>) -> HostError {
         let type_ = ScErrorType::Object;
         let code = ScErrorCode::IndexBounds;
@@ -334,11 +439,13 @@ impl Host {
     /// will wind up writing `host.map_err(...)?` a bunch in code that you used
     /// to be able to get away with just writing `...?`, there's no way around
     /// this if we want to record the diagnostic information.
+    #[track_caller]
     pub(crate) fn map_err<T, E>(&self, res: Result<T, E>) -> Result<T, HostError>
     where
         Error: From<E>,
         E: Debug,
     {
+        let location = Location::caller();
         res.map_err(|e| {
             use std::borrow::Cow;
             let mut msg: Cow<'_, str> = Cow::Borrowed(&"");
@@ -349,7 +456,7 @@ impl Host {
                 msg = Cow::Owned(format!("{:?}", e));
                 Ok(())
             });
-            self.error(e.into(), &msg, &[])
+            self.error_at(e.into(), &msg, &[], location, false)
         })
     }
 
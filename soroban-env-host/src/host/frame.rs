@@ -1,20 +1,24 @@
 use crate::{
     auth::AuthorizationManagerSnapshot,
-    budget::AsBudget,
+    budget::{AsBudget, InvocationBudget},
     err,
     host::{
+        error::ErrorContext,
         metered_clone::{MeteredClone, MeteredContainer, MeteredIterator},
         prng::Prng,
     },
+    host_object::HostMap,
     storage::{InstanceStorageMap, StorageMap},
     xdr::{
         ContractExecutable, ContractIdPreimage, Hash, HostFunction, HostFunctionType, ScAddress,
         ScContractInstance, ScErrorCode, ScErrorType, ScVal,
     },
-    AddressObject, Error, Host, HostError, Object, Symbol, SymbolStr, TryFromVal, TryIntoVal, Val,
-    Vm, DEFAULT_HOST_DEPTH_LIMIT,
+    AddressObject, Error, Host, HostError, Object, Symbol, SymbolStr, TryFromVal, TryIntoVal,
+    U32Val, Val, Vm, DEFAULT_HOST_DEPTH_LIMIT,
 };
 
+#[cfg(any(test, feature = "testutils"))]
+use crate::events::DiagnosticSeverity;
 #[cfg(any(test, feature = "testutils"))]
 use core::cell::RefCell;
 use std::rc::Rc;
@@ -27,7 +31,6 @@ pub(crate) enum ContractReentryMode {
     /// possible for a contract to do a self-call via host).
     SelfAllowed,
     /// Re-entry is fully allowed.
-    #[allow(dead_code)]
     Allowed,
 }
 
@@ -36,6 +39,30 @@ pub(crate) enum ContractReentryMode {
 /// contracts.
 const RESERVED_CONTRACT_FN_PREFIX: &str = "__";
 
+/// The optional contract function that the host invokes exactly once, when
+/// the contract instance is created.
+const CONSTRUCTOR_FN_NAME: &str = "__constructor";
+
+/// The first ledger protocol version in which every contract creation path
+/// invokes the `__constructor` of the new contract (with no arguments unless
+/// created by `create_contract_with_constructor`). Earlier protocols only
+/// invoke it from `create_contract_with_constructor`.
+pub(crate) const CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION: u32 = 21;
+
+/// Limits enforced by the [Host] on the arguments and the return values of
+/// contract calls, bounding the cost of marshaling values across contract
+/// boundaries. `None` means that the corresponding limit is not enforced,
+/// which is the default.
+///
+/// The sizes are the sizes of the XDR-serialized `ScVal`s; for arguments, the
+/// total over all the arguments of a call.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CallLimits {
+    pub max_args: Option<u32>,
+    pub max_args_size_bytes: Option<u32>,
+    pub max_return_size_bytes: Option<u32>,
+}
+
 /// Saves host state (storage and objects) for rolling back a (sub-)transaction
 /// on error. A helper type used by [`FrameGuard`].
 // Notes on metering: `RollbackPoint` are metered under Frame operations
@@ -125,7 +152,7 @@ pub(crate) enum Frame {
 }
 
 impl Frame {
-    fn contract_id(&self) -> Option<&Hash> {
+    pub(crate) fn contract_id(&self) -> Option<&Hash> {
         match self {
             Frame::ContractVM { vm, .. } => Some(&vm.contract_id),
             Frame::HostFunction(_) => None,
@@ -135,6 +162,16 @@ impl Frame {
         }
     }
 
+    pub(crate) fn fn_name(&self) -> Option<Symbol> {
+        match self {
+            Frame::ContractVM { fn_name, .. } => Some(*fn_name),
+            Frame::HostFunction(_) => None,
+            Frame::StellarAssetContract(_, fn_name, ..) => Some(*fn_name),
+            #[cfg(any(test, feature = "testutils"))]
+            Frame::TestContract(tc) => Some(tc.func),
+        }
+    }
+
     fn instance(&self) -> Option<&ScContractInstance> {
         match self {
             Frame::ContractVM { instance, .. } => Some(instance),
@@ -183,6 +220,11 @@ impl Host {
         let _span = tracy_span!("pop context");
 
         let ctx = self.try_borrow_context_stack_mut()?.pop();
+        // An allowance the popped frame didn't use must not leak to its caller.
+        *self.try_borrow_allow_reentry_for_next_call_mut()? = false;
+        // The scratch space of the popped frame goes with it.
+        let depth = self.try_borrow_context_stack()?.len();
+        self.try_borrow_frame_scratch_mut()?.remove(&depth);
 
         #[cfg(any(test, feature = "recording_auth"))]
         if self.try_borrow_context_stack()?.is_empty() {
@@ -272,6 +314,60 @@ impl Host {
         }
     }
 
+    /// Returns the reentry mode for the next contract call:
+    /// [`ContractReentryMode::Allowed`] if `allow_reentry_for_next_call` has
+    /// been called since the last call (consuming that allowance),
+    /// [`ContractReentryMode::Prohibited`] otherwise.
+    pub(crate) fn take_next_call_reentry_mode(&self) -> Result<ContractReentryMode, HostError> {
+        let allowed = std::mem::take(&mut *self.try_borrow_allow_reentry_for_next_call_mut()?);
+        if allowed {
+            Ok(ContractReentryMode::Allowed)
+        } else {
+            Ok(ContractReentryMode::Prohibited)
+        }
+    }
+
+    /// Applies `f` to the scratch space of the current frame, creating it if
+    /// needed. Scratch spaces live in a side table of the host, under the
+    /// depth of their frame in the context stack.
+    pub(crate) fn with_current_scratch_mut<F, U>(&self, f: F) -> Result<U, HostError>
+    where
+        F: FnOnce(&mut HostMap) -> Result<U, HostError>,
+    {
+        let Some(depth) = self.try_borrow_context_stack()?.len().checked_sub(1) else {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InternalError,
+                "no contract running",
+                &[],
+            ));
+        };
+        let mut scratch_table = self.try_borrow_frame_scratch_mut()?;
+        f(scratch_table.entry(depth).or_insert_with(HostMap::new))
+    }
+
+    /// Looks `k` up in the scratch space of the current frame and then in
+    /// those of the enclosing frames of the same contract, nearest first.
+    pub(crate) fn scratch_lookup(&self, k: Val) -> Result<Option<Val>, HostError> {
+        let stack = self.try_borrow_context_stack()?;
+        let Some(curr) = stack.last() else {
+            return Ok(None);
+        };
+        let curr_id = curr.frame.contract_id();
+        let scratch_table = self.try_borrow_frame_scratch()?;
+        for (depth, ctx) in stack.iter().enumerate().rev() {
+            if ctx.frame.contract_id() != curr_id {
+                continue;
+            }
+            if let Some(scratch) = scratch_table.get(&depth) {
+                if let Some(v) = scratch.get(&k, self)? {
+                    return Ok(Some(*v));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Same as [`Self::with_current_frame`] but passes `None` when there is no current
     /// frame, rather than failing with an error.
     pub(crate) fn with_current_frame_opt<F, U>(&self, f: F) -> Result<U, HostError>
@@ -334,12 +430,17 @@ impl Host {
             // There's already a context PRNG, so use it.
             Some(prng) => prng,
 
-            // There's no context PRNG yet, seed one from the base PRNG (unless
+            // There's no context PRNG yet, make one from the base PRNG (unless
             // the base PRNG itself hasn't been seeded).
             None => {
+                let protocol_version = self.get_ledger_protocol_version()?;
                 let mut base_guard = self.try_borrow_base_prng_mut()?;
                 if let Some(base) = base_guard.as_mut() {
-                    base.sub_prng(self.as_budget())?
+                    base.frame_prng(
+                        protocol_version,
+                        || self.get_current_contract_id_opt_internal(),
+                        self.as_budget(),
+                    )?
                 } else {
                     return Err(self.err(
                         ScErrorType::Context,
@@ -385,6 +486,21 @@ impl Host {
             )
             .into());
         }
+        // Budget consumed by the invocations before this one, if it is a
+        // top-level one.
+        let mut invocation_budget_start = None;
+        if start_depth == 0 {
+            invocation_budget_start = Some((
+                self.as_budget().get_cpu_insns_consumed()?,
+                self.as_budget().get_mem_bytes_consumed()?,
+            ));
+            // A new invocation reports its own budget exhaustion, if any.
+            self.reset_budget_post_mortem();
+            #[cfg(any(test, feature = "testutils"))]
+            self.start_invocation_snapshot()?;
+        }
+        #[cfg(feature = "tracing")]
+        let _span = frame.trace_span().entered();
         let ctx = Context {
             frame,
             prng: None,
@@ -462,7 +578,7 @@ impl Host {
                 // contract failing to live up to a postcondition we're
                 // enforcing of "never returning this sort of error code".
                 if err.is_type(ScErrorType::Contract) {
-                    Err(self.error(
+                    Err(self.contract_error(
                         err,
                         "escalating Ok(ScErrorType::Contract) frame-exit to Err",
                         &[],
@@ -499,6 +615,22 @@ impl Host {
                 self.call_any_lifecycle_hook(crate::host::HostLifecycleEvent::PopCtx(&ctx, &res))?;
             }
         }
+        if let Err(e) = &mut res {
+            self.budget_exceeded_diagnostics(e);
+            // Record the contract function the error propagates out of, while
+            // the frame is still on the stack.
+            self.with_full_trace_mode(|| {
+                self.with_current_frame(|frame| {
+                    if let (Some(id), Some(func)) = (frame.contract_id(), frame.fn_name()) {
+                        e.push_context(ErrorContext::ContractFunction {
+                            contract: id.metered_clone(self)?,
+                            function: self.scsymbol_from_symbol(func)?.to_utf8_string_lossy(),
+                        });
+                    }
+                    Ok(())
+                })
+            });
+        }
         if res.is_err() {
             // Pop and rollback on error.
             self.pop_context(Some(rp))?
@@ -517,6 +649,21 @@ impl Host {
                 end_depth
             ));
         }
+        if let Some((cpu_insns_start, mem_bytes_start)) = invocation_budget_start {
+            // metering: free
+            let invocation_budget = InvocationBudget {
+                cpu_insns: self
+                    .as_budget()
+                    .get_cpu_insns_consumed()?
+                    .saturating_sub(cpu_insns_start),
+                mem_bytes: self
+                    .as_budget()
+                    .get_mem_bytes_consumed()?
+                    .saturating_sub(mem_bytes_start),
+            };
+            self.try_borrow_invocation_budgets_mut()?
+                .push(invocation_budget);
+        }
         #[cfg(any(test, feature = "testutils"))]
         if end_depth == 0 {
             // Empty call stack in tests means that some contract function call
@@ -527,6 +674,7 @@ impl Host {
             *self.try_borrow_previous_authorization_manager_mut()? =
                 Some(self.try_borrow_authorization_manager()?.clone());
             self.try_borrow_authorization_manager_mut()?.reset();
+            self.finish_invocation_snapshot(&res)?;
 
             // Call the contract invocation hook for contract invocations only.
             if is_top_contract_invocation {
@@ -556,6 +704,27 @@ impl Host {
         })
     }
 
+    /// Returns the contract ID of the immediate invoker of the contract frame
+    /// at the top of the context stack, or `None` if it was invoked directly
+    /// by the transaction (i.e. the frame below it is not a contract frame).
+    pub(crate) fn get_invoker_contract_id_internal(&self) -> Result<Option<Hash>, HostError> {
+        let stack = self.try_borrow_context_stack()?;
+        let mut frames = stack.iter().rev();
+        if frames.next().and_then(|c| c.frame.contract_id()).is_none() {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidAction,
+                "no contract running",
+                &[],
+            ));
+        }
+        frames
+            .next()
+            .and_then(|c| c.frame.contract_id())
+            .map(|id| id.metered_clone(self))
+            .transpose()
+    }
+
     /// Returns [`Hash`] contract ID from the VM frame at the top of the context
     /// stack, or a [`HostError`] if the context stack is empty or has a non-VM
     /// frame at its top.
@@ -597,6 +766,14 @@ impl Host {
         )
     }
 
+    /// Returns a digest of the state of the PRNG of the frame at the top of
+    /// the context stack, or `None` if that frame hasn't used its PRNG yet.
+    /// Used for testing.
+    #[cfg(any(test, feature = "testutils"))]
+    pub fn current_frame_prng_digest(&self) -> Result<Option<[u8; 32]>, HostError> {
+        self.with_current_context_mut(|ctx| Ok(ctx.prng.as_ref().map(|prng| prng.state_digest())))
+    }
+
     #[cfg(any(test, feature = "testutils"))]
     fn create_test_contract_frame(
         &self,
@@ -649,6 +826,115 @@ impl Host {
         }
     }
 
+    // Invokes the constructor of the freshly created contract `id`, if it has
+    // one. Contracts without a constructor can only be created without
+    // constructor arguments.
+    // Notes on metering: this is covered by the called components.
+    pub(crate) fn call_constructor(&self, id: &Hash, args: &[Val]) -> Result<(), HostError> {
+        let storage_key = self.contract_instance_ledger_key(id)?;
+        let instance = self
+            .retrieve_contract_instance_from_storage(&storage_key)
+            .map_err(|e| self.decorate_contract_instance_storage_error(e, &id))?;
+        let vm = match &instance.executable {
+            // Native test contracts are only registered after they have been
+            // created, so they never have a constructor.
+            #[cfg(any(test, feature = "testutils"))]
+            ContractExecutable::Wasm(_) if self.is_test_contract_executable(id)? => None,
+            ContractExecutable::Wasm(wasm_hash) => {
+                let code_entry = self.retrieve_wasm_from_storage(&wasm_hash)?;
+                let vm = Vm::new(self, id.metered_clone(self)?, code_entry.as_slice())?;
+                if vm.has_exported_function(CONSTRUCTOR_FN_NAME)? {
+                    Some(vm)
+                } else {
+                    None
+                }
+            }
+            // Built-in contracts are initialized by the host directly.
+            ContractExecutable::StellarAsset => None,
+        };
+        let Some(vm) = vm else {
+            if args.is_empty() {
+                return Ok(());
+            }
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidAction,
+                "constructor arguments provided for a contract without a constructor",
+                &[],
+            ));
+        };
+        let func = Symbol::try_from_val(self, &CONSTRUCTOR_FN_NAME)?;
+        self.fn_call_diagnostics(id, &func, args);
+        Vec::<Val>::charge_bulk_init_cpy(args.len() as u64, self.as_budget())?;
+        let res = self.with_frame(
+            Frame::ContractVM {
+                vm: Rc::clone(&vm),
+                fn_name: func,
+                args: args.to_vec(),
+                instance,
+                relative_objects: Vec::new(),
+            },
+            || vm.invoke_function_raw(self, &func, args),
+        )?;
+        self.fn_return_diagnostics(id, &func, &res);
+        Ok(())
+    }
+
+    /// Sets the [CallLimits] enforced on every contract call.
+    pub fn set_call_limits(&self, limits: CallLimits) -> Result<(), HostError> {
+        *self.try_borrow_call_limits_mut()? = limits;
+        Ok(())
+    }
+
+    // Checks the arguments of a call to `func` against the configured
+    // [CallLimits]. Only the configured limits are checked (and charged for).
+    fn check_call_args_limits(&self, func: Symbol, args: &[Val]) -> Result<(), HostError> {
+        let limits = *self.try_borrow_call_limits()?;
+        if let Some(max_args) = limits.max_args {
+            if args.len() > max_args as usize {
+                return Err(self.err(
+                    ScErrorType::Context,
+                    ScErrorCode::ExceededLimit,
+                    "too many call arguments",
+                    &[func.to_val(), U32Val::from(max_args).to_val()],
+                ));
+            }
+        }
+        if let Some(max_size) = limits.max_args_size_bytes {
+            let mut size: usize = 0;
+            for arg in args.iter() {
+                let arg = self.from_host_val(*arg)?;
+                size = size.saturating_add(self.metered_xdr_size(&arg)?);
+                if size > max_size as usize {
+                    return Err(self.err(
+                        ScErrorType::Context,
+                        ScErrorCode::ExceededLimit,
+                        "call arguments too large",
+                        &[func.to_val(), U32Val::from(max_size).to_val()],
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Checks the value returned from a call to `func` against the configured
+    // [CallLimits].
+    fn check_call_return_limits(&self, func: Symbol, ret: Val) -> Result<(), HostError> {
+        if let Some(max_size) = self.try_borrow_call_limits()?.max_return_size_bytes {
+            let ret = self.from_host_val(ret)?;
+            if self.metered_xdr_size(&ret)? > max_size as usize {
+                return Err(self.err(
+                    ScErrorType::Context,
+                    ScErrorCode::ExceededLimit,
+                    "call return value too large",
+                    &[func.to_val(), U32Val::from(max_size).to_val()],
+                ));
+            }
+        }
+        Ok(())
+    }
+
     // Notes on metering: this is covered by the called components.
     pub(crate) fn call_n_internal(
         &self,
@@ -704,6 +990,7 @@ impl Host {
             }
         }
 
+        self.check_call_args_limits(func, args)?;
         self.fn_call_diagnostics(id, &func, args);
 
         // Try dispatching the contract to the compiled-in registred
@@ -724,7 +1011,7 @@ impl Host {
             if let Some(cfs) = cfs_option {
                 let frame = self.create_test_contract_frame(id.clone(), func, args.to_vec())?;
                 let panic = frame.panic.clone();
-                return self.with_frame(Frame::TestContract(frame), || {
+                let res = self.with_frame(Frame::TestContract(frame), || {
                     use std::any::Any;
                     use std::panic::AssertUnwindSafe;
                     type PanicVal = Box<dyn Any + Send>;
@@ -806,14 +1093,22 @@ impl Host {
                                             "caught panic '{}' from contract function '{:?}'",
                                             str, func
                                         );
-                                        let _ = self.log_diagnostics(&msg, args);
+                                        let _ = self.log_diagnostics_with_severity(
+                                            DiagnosticSeverity::Warn,
+                                            &msg,
+                                            args,
+                                        );
                                     } else if let Some(str) = panic_payload.downcast_ref::<String>()
                                     {
                                         let msg: String = format!(
                                             "caught panic '{}' from contract function '{:?}'",
                                             str, func
                                         );
-                                        let _ = self.log_diagnostics(&msg, args);
+                                        let _ = self.log_diagnostics_with_severity(
+                                            DiagnosticSeverity::Warn,
+                                            &msg,
+                                            args,
+                                        );
                                     };
                                     Ok(())
                                 })
@@ -821,18 +1116,16 @@ impl Host {
                             Err(self.error(error, "caught error from function", &[]))
                         }
                     }
-                });
+                })?;
+                self.check_call_return_limits(func, res)?;
+                return Ok(res);
             }
         }
 
-        let res = self.call_contract_fn(id, &func, args);
-
-        match &res {
-            Ok(res) => self.fn_return_diagnostics(id, &func, res),
-            Err(_err) => {}
-        }
-
-        res
+        let res = self.call_contract_fn(id, &func, args)?;
+        self.check_call_return_limits(func, res)?;
+        self.fn_return_diagnostics(id, &func, &res);
+        Ok(res)
     }
 
     // Notes on metering: covered by the called components.
@@ -871,7 +1164,7 @@ impl Host {
                     }
                     ContractIdPreimage::Asset(_) => None,
                 };
-                self.create_contract_internal(deployer, args)
+                self.create_contract_internal(deployer, args, None)
                     .map(<Val>::from)
             }),
             HostFunction::UploadContractWasm(wasm) => self.with_frame(frame, || {
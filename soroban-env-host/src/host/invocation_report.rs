@@ -0,0 +1,160 @@
+//! A consolidated report of the last top-level invocation on a [Host], see
+//! [Host::invocation_report].
+
+use std::rc::Rc;
+
+use crate::{
+    budget::{AsBudget, InvocationBudget},
+    events::{DiagnosticEvent, DiagnosticSeverity, Events},
+    storage::{EntryWithLiveUntil, Footprint, FootprintMode, StorageMap},
+    xdr::{LedgerKey, ScAddress, ScVal, SorobanAuthorizedInvocation},
+    Host, HostError, Val,
+};
+
+/// The state captured when a top-level frame is pushed, and completed with the
+/// result once it is popped.
+#[derive(Clone)]
+pub(crate) struct InvocationSnapshot {
+    pub(crate) storage: StorageMap,
+    pub(crate) result: Option<Result<Val, HostError>>,
+}
+
+/// The budget consumed by the invocations on a [Host] so far, and what remains
+/// of its limits.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BudgetSummary {
+    pub cpu_insns_consumed: u64,
+    pub mem_bytes_consumed: u64,
+    pub cpu_insns_remaining: u64,
+    pub mem_bytes_remaining: u64,
+}
+
+/// A storage entry modified by the last top-level invocation.
+#[derive(Clone, Debug)]
+pub struct StorageChange {
+    pub key: Rc<LedgerKey>,
+    /// Entry and its live_until ledger before the invocation, `None` if the
+    /// entry didn't exist.
+    pub old_value: Option<EntryWithLiveUntil>,
+    /// Entry and its live_until ledger after the invocation, `None` if the
+    /// entry has been deleted.
+    pub new_value: Option<EntryWithLiveUntil>,
+}
+
+/// Everything observable about the last top-level invocation on a [Host],
+/// gathered in one place, see [Host::invocation_report].
+#[derive(Clone)]
+pub struct InvocationReport {
+    /// The value returned by the invocation, or its error. `None` if no
+    /// invocation has finished on the host yet.
+    pub result: Option<Result<ScVal, HostError>>,
+    /// All the events recorded by the host, see [Host::get_events].
+    pub events: Events,
+    /// The diagnostic events of every severity, see
+    /// [Host::get_diagnostic_events].
+    pub diagnostic_events: Vec<DiagnosticEvent>,
+    pub budget: BudgetSummary,
+    /// The budget consumed by the last invocation alone, `None` if no
+    /// invocation has finished on the host yet.
+    pub invocation_budget: Option<InvocationBudget>,
+    /// The entries whose value or live_until ledger changed during the
+    /// invocation, ordered by key.
+    pub storage_changes: Vec<StorageChange>,
+    pub footprint: Footprint,
+    /// The authorizations consumed by the invocation, see
+    /// [Host::get_authenticated_authorizations].
+    pub authorizations: Vec<(ScAddress, SorobanAuthorizedInvocation)>,
+}
+
+impl Host {
+    // Captures the storage state at the start of a top-level invocation.
+    // metering: free for testutils
+    pub(crate) fn start_invocation_snapshot(&self) -> Result<(), HostError> {
+        *self.try_borrow_invocation_snapshot_mut()? = Some(InvocationSnapshot {
+            storage: self.try_borrow_storage()?.map.clone(),
+            result: None,
+        });
+        Ok(())
+    }
+
+    // Records the result of the top-level invocation started last.
+    // metering: free for testutils
+    pub(crate) fn finish_invocation_snapshot(
+        &self,
+        result: &Result<Val, HostError>,
+    ) -> Result<(), HostError> {
+        if let Some(snapshot) = self.try_borrow_invocation_snapshot_mut()?.as_mut() {
+            snapshot.result = Some(result.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns a report of the last top-level invocation on the host (either
+    /// a host function or a direct contract call): its result, the events,
+    /// the budget, the storage changes it made, the footprint and the
+    /// authorizations it consumed.
+    ///
+    /// The events, budget and footprint accumulate over all the invocations
+    /// on the host, as with the individual accessors, while
+    /// `invocation_budget` only covers the last invocation. Building the report is
+    /// charged to the shadow budget, so it does not affect the metering.
+    pub fn invocation_report(&self) -> Result<InvocationReport, HostError> {
+        let budget = self.as_budget();
+        let budget_summary = BudgetSummary {
+            cpu_insns_consumed: budget.get_cpu_insns_consumed()?,
+            mem_bytes_consumed: budget.get_mem_bytes_consumed()?,
+            cpu_insns_remaining: budget.get_cpu_insns_remaining()?,
+            mem_bytes_remaining: budget.get_mem_bytes_remaining()?,
+        };
+        budget.with_observable_shadow_mode(|| {
+            let snapshot = self.try_borrow_invocation_snapshot()?.clone();
+            let result = match snapshot.as_ref().and_then(|s| s.result.as_ref()) {
+                Some(Ok(val)) => Some(self.from_host_val(*val)),
+                Some(Err(err)) => Some(Err(err.clone())),
+                None => None,
+            };
+            Ok(InvocationReport {
+                result,
+                events: self.get_events()?,
+                diagnostic_events: self.get_diagnostic_events(DiagnosticSeverity::Debug)?,
+                budget: budget_summary,
+                invocation_budget: self.try_borrow_invocation_budgets()?.last().copied(),
+                storage_changes: self.storage_changes(snapshot.map(|s| s.storage))?,
+                footprint: self.try_borrow_storage()?.footprint.clone(),
+                authorizations: self.get_authenticated_authorizations()?,
+            })
+        })
+    }
+
+    // Compares the current storage with its state at the start of the last
+    // top-level invocation. In recording mode, the entries that were not yet
+    // loaded at that point are compared with the snapshot source.
+    fn storage_changes(
+        &self,
+        initial: Option<StorageMap>,
+    ) -> Result<Vec<StorageChange>, HostError> {
+        let Some(initial) = initial else {
+            return Ok(vec![]);
+        };
+        let budget = self.as_budget();
+        let storage = self.try_borrow_storage()?;
+        let mut changes = vec![];
+        for (key, new_value) in storage.map.iter(budget)? {
+            let old_value = match initial.get::<Rc<LedgerKey>>(key, budget)? {
+                Some(old_value) => old_value.clone(),
+                None => match &storage.mode {
+                    FootprintMode::Recording(src) if src.has(key)? => Some(src.get(key)?),
+                    _ => None,
+                },
+            };
+            if old_value != *new_value {
+                changes.push(StorageChange {
+                    key: Rc::clone(key),
+                    old_value,
+                    new_value: new_value.clone(),
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
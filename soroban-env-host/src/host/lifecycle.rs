@@ -2,6 +2,7 @@ use crate::{
     budget::AsBudget,
     err,
     host::{
+        frame::CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION,
         metered_clone::{MeteredAlloc, MeteredClone},
         metered_write_xdr, ContractReentryMode, CreateContractArgs,
     },
@@ -10,11 +11,21 @@ use crate::{
         ContractIdPreimageFromAddress, ExtensionPoint, Hash, LedgerKey, LedgerKeyContractCode,
         ScAddress, ScErrorCode, ScErrorType,
     },
-    AddressObject, BytesObject, Host, HostError, Symbol, TryFromVal, Vm,
+    AddressObject, BytesObject, Host, HostError, Symbol, TryFromVal, Val, Vm,
 };
 use std::rc::Rc;
 
 impl Host {
+    // Returns whether every creation path invokes the constructor, see
+    // `CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION`. Hosts without ledger
+    // info keep the behavior of earlier protocols.
+    fn constructor_runs_on_all_creations(&self) -> Result<bool, HostError> {
+        Ok(match self.try_borrow_ledger()?.as_ref() {
+            Some(li) => li.protocol_version >= CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION,
+            None => false,
+        })
+    }
+
     // Notes on metering: this is covered by the called components.
     fn create_contract_with_id(
         &self,
@@ -80,6 +91,7 @@ impl Host {
         &self,
         deployer: Option<AddressObject>,
         args: CreateContractArgs,
+        constructor_args: Option<&[Val]>,
     ) -> Result<AddressObject, HostError> {
         let has_deployer = deployer.is_some();
         if has_deployer {
@@ -92,7 +104,7 @@ impl Host {
         // to manually manage auth manager frames (we don't need to authorize
         // any other host fns and it doesn't seem useful to create extra frames
         // for them just to make auth work in a single case).
-        let res = self.create_contract_with_optional_auth(deployer, args);
+        let res = self.create_contract_with_optional_auth(deployer, args, constructor_args);
         if has_deployer {
             self.try_borrow_authorization_manager()?
                 .pop_frame(self, None)?;
@@ -104,6 +116,7 @@ impl Host {
         &self,
         deployer: Option<AddressObject>,
         args: CreateContractArgs,
+        constructor_args: Option<&[Val]>,
     ) -> Result<AddressObject, HostError> {
         if let Some(deployer_address) = deployer {
             self.try_borrow_authorization_manager()?.require_auth(
@@ -113,14 +126,38 @@ impl Host {
             )?;
         }
 
-        let id_preimage =
-            self.get_full_contract_id_preimage(args.contract_id_preimage.metered_clone(self)?)?;
-        let hash_id = Hash(self.metered_hash_xdr(&id_preimage)?);
+        let hash_id =
+            self.contract_id_from_preimage(args.contract_id_preimage.metered_clone(self)?)?;
         self.create_contract_with_id(hash_id.metered_clone(self)?, args.executable)?;
+        // Before CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION only
+        // `create_contract_with_constructor` looks for a constructor, so that
+        // the other creation paths keep their cost. From that version on they
+        // run it too, with no arguments, so that no contract that exports a
+        // constructor is left unconstructed. If the constructor fails, the
+        // error is propagated and the frame of the caller rolls back the
+        // instance written above.
+        let constructor_args = match constructor_args {
+            None if self.constructor_runs_on_all_creations()? => Some(&[][..]),
+            constructor_args => constructor_args,
+        };
+        if let Some(constructor_args) = constructor_args {
+            self.call_constructor(&hash_id, constructor_args)?;
+        }
         self.maybe_initialize_stellar_asset_contract(&hash_id, &args.contract_id_preimage)?;
         self.add_host_object(ScAddress::Contract(hash_id))
     }
 
+    /// Computes the id of the contract that would be created from `preimage`
+    /// on the current network, i.e. the id for the given deployer address
+    /// (account or contract) and salt, or for the given Stellar asset. This
+    /// is the same derivation that the contract creation functions use.
+    pub fn contract_id_from_preimage(
+        &self,
+        preimage: ContractIdPreimage,
+    ) -> Result<Hash, HostError> {
+        self.contract_id_preimage_hash(preimage)
+    }
+
     pub(crate) fn get_contract_id_hash(
         &self,
         deployer: AddressObject,
@@ -130,16 +167,11 @@ impl Host {
             address: self.visit_obj(deployer, |addr: &ScAddress| addr.metered_clone(self))?,
             salt: self.u256_from_bytesobj_input("contract_id_salt", salt)?,
         });
-
-        let id_preimage =
-            self.get_full_contract_id_preimage(contract_id_preimage.metered_clone(self)?)?;
-        Ok(Hash(self.metered_hash_xdr(&id_preimage)?))
+        self.contract_id_from_preimage(contract_id_preimage)
     }
 
     pub(crate) fn get_asset_contract_id_hash(&self, asset: Asset) -> Result<Hash, HostError> {
-        let id_preimage = self.get_full_contract_id_preimage(ContractIdPreimage::Asset(asset))?;
-        let id_arr: [u8; 32] = self.metered_hash_xdr(&id_preimage)?;
-        Ok(Hash(id_arr))
+        self.contract_id_from_preimage(ContractIdPreimage::Asset(asset))
     }
 
     pub(crate) fn upload_contract_wasm(&self, wasm: Vec<u8>) -> Result<BytesObject, HostError> {
@@ -8,6 +8,11 @@ use crate::{
 
 use std::{cmp::Ordering, rc::Rc};
 
+/// The first ledger protocol version in which `symbol_new_from_linear_memory`
+/// checks that the new symbol only holds valid symbol characters. Earlier
+/// protocols accept any bytes.
+pub(crate) const SYMBOL_CHARS_CHECK_PROTOCOL_VERSION: u32 = 21;
+
 /// Helper type for host functions that receive a position and length pair and
 /// expect to operate on a VM. Pos and len are not validated and len may be a
 /// count of bytes, Vals or slices depending on the host function.
@@ -375,6 +380,18 @@ impl Host {
         self.add_host_object::<HOT>(vnew.try_into()?)
     }
 
+    // Checks that `s` only holds valid symbol characters `[a-zA-Z0-9_]`,
+    // charging for the scan like a comparison of its bytes. Only used by
+    // `symbol_new_from_linear_memory` from SYMBOL_CHARS_CHECK_PROTOCOL_VERSION
+    // on, see there.
+    pub(crate) fn check_symbol_chars(&self, s: &[u8]) -> Result<(), HostError> {
+        self.charge_budget(ContractCostType::MemCmp, Some(s.len() as u64))?;
+        for b in s {
+            SymbolSmall::validate_char(*b as char)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn symbol_matches(&self, s: &[u8], sym: Symbol) -> Result<bool, HostError> {
         if let Ok(ss) = SymbolSmall::try_from(sym) {
             let sstr: SymbolStr = ss.into();
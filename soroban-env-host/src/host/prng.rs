@@ -7,11 +7,17 @@ use crate::{
     budget::Budget,
     host::metered_clone::MeteredClone,
     host_object::HostVec,
-    xdr::{ContractCostType, ScBytes, ScErrorCode, ScErrorType},
+    xdr::{ContractCostType, Hash, ScBytes, ScErrorCode, ScErrorType},
     HostError,
 };
-use rand::{distributions::Uniform, prelude::Distribution, seq::SliceRandom, RngCore};
+use rand::{
+    distributions::{uniform::SampleUniform, Uniform},
+    prelude::Distribution,
+    seq::SliceRandom,
+    RngCore,
+};
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use sha2::{Digest, Sha256};
 use std::ops::RangeInclusive;
 
 /// PRNG subsystem in the host, which provides best-effort pseudo-randomness to
@@ -35,14 +41,17 @@ use std::ops::RangeInclusive;
 ///     hard to guess or control.
 ///
 ///   - Each frame (which is to say: each contract invocation or sub-invocation)
-///     will get a new PRNG instance separately seeded from the host's "base"
-///     PRNG, and guest code can only access the frame's PRNG, not the "base"
-///     PRNG or those of any other frame. This doesn't eliminate _all_ attack
-///     vectors or mechanisms for misuse, but it's the best we can give the user
-///     for buiding on. In particular it means that a "random" contract will not
-///     behave the same way from one call to the next inside the same txset, nor
-///     can a caller control the seed for a "random" callee (since they can't
-///     observe the state of the "base" PRNG).
+///     will get a new PRNG instance with a seed derived from the host's "base"
+///     seed, the frame's contract ID and a count of the frame PRNGs derived so
+///     far (see [`derive_frame_prng_seed`]), and guest code can only access the
+///     frame's PRNG, not the "base" seed or the PRNGs of any other frame. Before
+///     protocol [`FRAME_PRNG_DERIVATION_PROTOCOL_VERSION`], the frame PRNGs are
+///     instead seeded from successive draws of the "base" PRNG. This doesn't
+///     eliminate _all_ attack vectors or mechanisms for misuse, but it's the
+///     best we can give the user for buiding on. In particular it means that a
+///     "random" contract will not behave the same way from one call to the next
+///     inside the same txset, nor can a caller control the seed for a "random"
+///     callee (since they can't observe the "base" seed).
 ///
 ///   - Users _can_ reseed their frame-local PRNG if they want, which is a
 ///     useful building block for random-commitment schemes. In particular if a
@@ -68,12 +77,12 @@ use std::ops::RangeInclusive;
 ///     longer influence its state. The contract can then be re-invoked in the
 ///     next ledger to load and execute the commitment, in tx2.
 ///
-///   - We also include 3 building blocks for _using_ the PRNG: one basic one
+///   - We also include building blocks for _using_ the PRNG: one basic one
 ///     that just "generates a random BytesObject" (that the user can do
 ///     anything they like with, including copying to guest linear memory), and
-///     two slightly more subtle but very standard operations that are easy to
-///     get wrong: an inclusive-range uniform u64 sampler and a Fisher-Yates
-///     vector shuffle. The latter is also hard to do cheaply in guest code
+///     slightly more subtle but very standard operations that are easy to get
+///     wrong: inclusive-range uniform u64, u128 and i128 samplers and a
+///     Fisher-Yates vector shuffle. The latter is also hard to do cheaply in guest code
 ///     without copying the vector into the guest and copying it back.
 ///
 ///   - All these PRNGs are ChaCha20: a strong, cheap, standard CSPRNG.
@@ -85,6 +94,145 @@ pub type Seed = <rand_chacha::ChaCha20Rng as rand::SeedableRng>::Seed;
 pub const SEED_BYTES: u64 = <Seed as DeclaredSizeForMetering>::DECLARED_SIZE;
 static_assertions::const_assert_eq!(SEED_BYTES, 32);
 
+/// The first ledger protocol version in which frame PRNG seeds are derived
+/// with [`derive_frame_prng_seed`]. Earlier protocols seed every frame PRNG
+/// from the next draw of the base PRNG stream, and keep doing so in order to
+/// replay their ledgers with the same random streams and costs.
+pub const FRAME_PRNG_DERIVATION_PROTOCOL_VERSION: u32 = 21;
+
+/// The host's "base" PRNG state: the seed and the number of frame PRNGs
+/// derived from it so far (see [`derive_frame_prng_seed`]), along with the
+/// stream that frame PRNGs are drawn from before protocol
+/// [`FRAME_PRNG_DERIVATION_PROTOCOL_VERSION`].
+#[derive(Debug, Clone)]
+pub(crate) struct BasePrng {
+    seed: Seed,
+    frame_prng_count: u64,
+    stream: Prng,
+    // The ledger protocol version the PRNG was last seeded or used in, if
+    // known. It decides which part of the state above is in use.
+    protocol_version: Option<u32>,
+}
+
+// The seed and the count are only hashed once they are in use, so that the
+// observations of hosts that draw their frame PRNGs from the stream are the
+// same as before the derivation was introduced.
+#[cfg(any(feature = "testutils", test))]
+impl std::hash::Hash for BasePrng {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.stream.hash(state);
+        if self
+            .protocol_version
+            .is_some_and(|v| v >= FRAME_PRNG_DERIVATION_PROTOCOL_VERSION)
+        {
+            self.seed.hash(state);
+            self.frame_prng_count.hash(state);
+        }
+    }
+}
+
+const FRAME_PRNG_SEED_DOMAIN: &[u8] = b"soroban frame prng seed";
+
+/// Derives the seed of a frame-local PRNG from the `base_seed` passed to
+/// [`Host::set_base_prng_seed`](crate::Host::set_base_prng_seed), the id of
+/// the contract running in the frame (or `None` for a non-contract frame) and
+/// `counter`, the number of frame PRNGs the host derived before this one.
+///
+/// The seed is the SHA-256 hash of a fixed domain-separation string followed
+/// by the base seed, the big-endian counter and, for contract frames, the
+/// contract id. Embedders can use this to reproduce the random stream any
+/// given invocation observed.
+pub fn derive_frame_prng_seed(base_seed: &Seed, contract_id: Option<&Hash>, counter: u64) -> Seed {
+    let mut hasher = Sha256::new();
+    hasher.update(FRAME_PRNG_SEED_DOMAIN);
+    hasher.update(base_seed);
+    hasher.update(counter.to_be_bytes());
+    if let Some(id) = contract_id {
+        hasher.update(id.0);
+    }
+    hasher.finalize().into()
+}
+
+impl BasePrng {
+    pub(crate) fn new(seed: Seed, stream: Prng, protocol_version: Option<u32>) -> Self {
+        Self {
+            seed,
+            frame_prng_count: 0,
+            stream,
+            protocol_version,
+        }
+    }
+
+    #[cfg(any(test, feature = "recording_auth"))]
+    pub(crate) fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// Creates the PRNG of a new frame in a ledger with the given
+    /// `protocol_version`. The id of the contract running in the frame is only
+    /// looked up with `contract_id` (which is metered) when it's needed to
+    /// derive the seed.
+    pub(crate) fn frame_prng(
+        &mut self,
+        protocol_version: u32,
+        contract_id: impl FnOnce() -> Result<Option<Hash>, HostError>,
+        budget: &Budget,
+    ) -> Result<Prng, HostError> {
+        self.protocol_version = Some(protocol_version);
+        if protocol_version < FRAME_PRNG_DERIVATION_PROTOCOL_VERSION {
+            return self.stream.sub_prng(budget);
+        }
+        let contract_id = contract_id()?;
+        let contract_id = contract_id.as_ref();
+        let preimage_len =
+            FRAME_PRNG_SEED_DOMAIN.len() as u64 + SEED_BYTES + 8 + contract_id.map_or(0, |_| 32);
+        budget.charge(ContractCostType::ComputeSha256Hash, Some(preimage_len))?;
+        let seed = derive_frame_prng_seed(&self.seed, contract_id, self.frame_prng_count);
+        self.frame_prng_count = self
+            .frame_prng_count
+            .checked_add(1)
+            .ok_or_else(|| HostError::from((ScErrorType::Context, ScErrorCode::ArithDomain)))?;
+        Ok(Prng(ChaCha20Rng::from_seed(seed)))
+    }
+}
+
+#[cfg(any(feature = "testutils", test))]
+impl BasePrng {
+    /// Returns the seed, the number of frame PRNGs derived so far, the seed,
+    /// stream and word position of the stream and the protocol version of the
+    /// last derivation, which together determine the state of the PRNG, see
+    /// [BasePrng::from_parts].
+    pub(crate) fn to_parts(&self) -> (Seed, u64, Seed, u64, u128, Option<u32>) {
+        (
+            self.seed,
+            self.frame_prng_count,
+            self.stream.0.get_seed(),
+            self.stream.0.get_stream(),
+            self.stream.0.get_word_pos(),
+            self.protocol_version,
+        )
+    }
+
+    pub(crate) fn from_parts(
+        seed: Seed,
+        frame_prng_count: u64,
+        stream_seed: Seed,
+        stream: u64,
+        word_pos: u128,
+        protocol_version: Option<u32>,
+    ) -> Self {
+        let mut rng = ChaCha20Rng::from_seed(stream_seed);
+        rng.set_stream(stream);
+        rng.set_word_pos(word_pos);
+        Self {
+            seed,
+            frame_prng_count,
+            stream: Prng(rng),
+            protocol_version,
+        }
+    }
+}
+
 #[cfg(any(feature = "testutils", test))]
 impl std::hash::Hash for Prng {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
@@ -94,6 +242,19 @@ impl std::hash::Hash for Prng {
     }
 }
 
+#[cfg(any(feature = "testutils", test))]
+impl Prng {
+    /// SHA-256 of the PRNG's seed, stream and word position, which together
+    /// determine every value it will draw next.
+    pub(crate) fn state_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.0.get_seed());
+        hasher.update(self.0.get_stream().to_be_bytes());
+        hasher.update(self.0.get_word_pos().to_be_bytes());
+        hasher.finalize().into()
+    }
+}
+
 impl Prng {
     fn charge_prng_bytes(&self, budget: &Budget, count: u64) -> Result<(), HostError> {
         budget.charge(ContractCostType::ChaCha20DrawBytes, Some(count))
@@ -104,11 +265,15 @@ impl Prng {
         Ok(Self(ChaCha20Rng::from_seed(seed)))
     }
 
-    pub(crate) fn u64_in_inclusive_range(
+    // Uniformly samples `range`, charging for the PRNG draws of a `T`.
+    fn sample_in_inclusive_range<T>(
         &mut self,
-        range: RangeInclusive<u64>,
+        range: RangeInclusive<T>,
         budget: &Budget,
-    ) -> Result<u64, HostError> {
+    ) -> Result<T, HostError>
+    where
+        T: SampleUniform + PartialOrd + DeclaredSizeForMetering,
+    {
         // rand::Uniform panics if start > end.
         if range.start() > range.end() {
             return Err((ScErrorType::Value, ScErrorCode::InvalidInput).into());
@@ -117,11 +282,35 @@ impl Prng {
         // We over-estimate the number of bytes drawn by a factor of 2, to
         // account for the fact that a range sample is rejection-sampling which
         // is expected to only do one draw but might do more than one.
-        self.charge_prng_bytes(budget, 2 * <u64 as DeclaredSizeForMetering>::DECLARED_SIZE)?;
+        self.charge_prng_bytes(budget, 2 * <T as DeclaredSizeForMetering>::DECLARED_SIZE)?;
         let u = Uniform::from(range);
         Ok(u.sample(&mut self.0))
     }
 
+    pub(crate) fn u64_in_inclusive_range(
+        &mut self,
+        range: RangeInclusive<u64>,
+        budget: &Budget,
+    ) -> Result<u64, HostError> {
+        self.sample_in_inclusive_range(range, budget)
+    }
+
+    pub(crate) fn u128_in_inclusive_range(
+        &mut self,
+        range: RangeInclusive<u128>,
+        budget: &Budget,
+    ) -> Result<u128, HostError> {
+        self.sample_in_inclusive_range(range, budget)
+    }
+
+    pub(crate) fn i128_in_inclusive_range(
+        &mut self,
+        range: RangeInclusive<i128>,
+        budget: &Budget,
+    ) -> Result<i128, HostError> {
+        self.sample_in_inclusive_range(range, budget)
+    }
+
     pub(crate) fn vec_shuffle(
         &mut self,
         v: &HostVec,
@@ -143,6 +332,35 @@ impl Prng {
         Ok(v2)
     }
 
+    pub(crate) fn weighted_pick(
+        &mut self,
+        weights: &[u64],
+        budget: &Budget,
+    ) -> Result<u32, HostError> {
+        // We sum the weights, draw one value below the sum (charged by the
+        // range sampler) and walk the weights again to find the index whose
+        // cumulative weight exceeds it. The sum is a u128, so it cannot
+        // overflow for any realistic number of weights. Each pass is charged
+        // as one wide integer addition per weight, the walk for all of them
+        // since it may have to reach the last one.
+        budget.bulk_charge(ContractCostType::Int256AddSub, weights.len() as u64, None)?;
+        let total: u128 = weights.iter().map(|w| *w as u128).sum();
+        if total == 0 {
+            return Err((ScErrorType::Value, ScErrorCode::InvalidInput).into());
+        }
+        let target = self.sample_in_inclusive_range(0..=total - 1, budget)?;
+        budget.bulk_charge(ContractCostType::Int256AddSub, weights.len() as u64, None)?;
+        let mut cumulative: u128 = 0;
+        for (i, w) in weights.iter().enumerate() {
+            cumulative += *w as u128;
+            if target < cumulative {
+                return u32::try_from(i)
+                    .map_err(|_| (ScErrorType::Value, ScErrorCode::InvalidInput).into());
+            }
+        }
+        Err((ScErrorType::Context, ScErrorCode::InternalError).into())
+    }
+
     pub(crate) fn bytes_new(&mut self, size: u32, budget: &Budget) -> Result<ScBytes, HostError> {
         Vec::<u8>::charge_bulk_init_cpy(size as u64, budget)?;
         let mut vec = vec![0u8; size as usize];
@@ -0,0 +1,89 @@
+//! Integration with the [tracing](https://docs.rs/tracing) crate, enabled by
+//! the `tracing` feature.
+//!
+//! When enabled, the host enters a span for every frame it pushes on its
+//! context stack (contract invocations and top-level host functions), and
+//! emits an event for every host function called by a Wasm contract, carrying
+//! the CPU and memory budget consumed by the call. None of this is metered:
+//! spans and events are only observed by the subscriber installed by the
+//! embedder, and the field values are only computed when that subscriber is
+//! interested in them.
+
+use super::frame::Frame;
+use crate::{budget::AsBudget, Host};
+
+/// Target of the spans entered for each frame.
+pub const FRAME_TARGET: &str = "soroban_env_host::frame";
+/// Target of the events emitted for each host function call.
+pub const HOST_FN_TARGET: &str = "soroban_env_host::host_fn";
+
+impl Frame {
+    pub(crate) fn trace_span(&self) -> tracing::Span {
+        match self {
+            Frame::ContractVM { vm, fn_name, .. } => tracing::debug_span!(
+                target: FRAME_TARGET,
+                "frame",
+                kind = "contract_vm",
+                contract = ?vm.contract_id,
+                function = ?fn_name
+            ),
+            Frame::HostFunction(hf) => tracing::debug_span!(
+                target: FRAME_TARGET,
+                "frame",
+                kind = "host_function",
+                function = ?hf
+            ),
+            Frame::StellarAssetContract(id, fn_name, ..) => tracing::debug_span!(
+                target: FRAME_TARGET,
+                "frame",
+                kind = "stellar_asset_contract",
+                contract = ?id,
+                function = ?fn_name
+            ),
+            #[cfg(any(test, feature = "testutils"))]
+            Frame::TestContract(tc) => tracing::debug_span!(
+                target: FRAME_TARGET,
+                "frame",
+                kind = "test_contract",
+                contract = ?tc.id,
+                function = ?tc.func
+            ),
+        }
+    }
+}
+
+/// Records the budget consumed at the start of a host function call, so that
+/// the delta can be reported in the event emitted once the call returns.
+pub(crate) struct HostFnTrace {
+    name: &'static str,
+    cpu_insns: u64,
+    mem_bytes: u64,
+}
+
+impl HostFnTrace {
+    pub(crate) fn start(host: &Host, name: &'static str) -> Self {
+        let budget = host.as_budget();
+        Self {
+            name,
+            cpu_insns: budget.get_cpu_insns_consumed().unwrap_or_default(),
+            mem_bytes: budget.get_mem_bytes_consumed().unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn finish(self, host: &Host, ok: bool) {
+        let budget = host.as_budget();
+        tracing::trace!(
+            target: HOST_FN_TARGET,
+            host_fn = self.name,
+            cpu_insns = budget
+                .get_cpu_insns_consumed()
+                .unwrap_or_default()
+                .saturating_sub(self.cpu_insns),
+            mem_bytes = budget
+                .get_mem_bytes_consumed()
+                .unwrap_or_default()
+                .saturating_sub(self.mem_bytes),
+            ok
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! A canonical JSON representation of [ScVal], for the services (such as RPC
+//! and simulation) that need to present contract values to their users and
+//! read them back.
+//!
+//! Every value is rendered as a JSON object with a single field, named after
+//! its type:
+//!
+//! | `ScVal`                       | JSON                                                   |
+//! |-------------------------------|--------------------------------------------------------|
+//! | `Bool`                        | `{"bool": true}`                                       |
+//! | `Void`                        | `{"void": null}`                                       |
+//! | `Error`                       | `{"error": {"type": "Contract", "code": 1}}` or `{"error": {"type": "Auth", "code": "InvalidAction"}}` |
+//! | `U32`, `I32`                  | `{"u32": 1}`, `{"i32": -1}`                            |
+//! | `U64`, `I64`, `Timepoint`, `Duration`, `U128`, `I128`, `U256`, `I256` | `{"u64": "1"}`, ... as decimal strings |
+//! | `Bytes`                       | `{"bytes": "0aff"}` as lowercase hex                   |
+//! | `String`                      | `{"string": "text"}`, or `{"string": {"hex": "ff"}}` if it is not valid UTF-8 |
+//! | `Symbol`                      | `{"symbol": "name"}`                                   |
+//! | `Vec`                         | `{"vec": [...]}`, or `{"vec": null}` if missing        |
+//! | `Map`                         | `{"map": [{"key": ..., "val": ...}, ...]}`, or `{"map": null}` if missing |
+//! | `Address`                     | `{"address": "G..."}` or `{"address": "C..."}` as strkey |
+//! | `LedgerKeyContractInstance`   | `{"ledger_key_contract_instance": null}`               |
+//! | `LedgerKeyNonce`              | `{"ledger_key_nonce": "1"}`                            |
+//! | `ContractInstance`            | `{"contract_instance": {"executable": {"wasm": "<hex hash>"}, "storage": <map or null>}}`, with `"stellar_asset"` as the executable of the Stellar Asset Contract |
+//!
+//! 64-bit and wider integers are rendered as strings, as many JSON parsers
+//! can't represent them exactly. The errors and integer types use the names
+//! of the XDR definitions.
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    num::{i256_from_parts, i256_into_scval, u256_from_parts, u256_into_scval, I256, U256},
+    xdr::{
+        AccountId, ContractExecutable, Duration, Hash, Int128Parts, PublicKey, ScAddress, ScBytes,
+        ScContractInstance, ScError, ScErrorCode, ScErrorType, ScMap, ScMapEntry, ScNonceKey,
+        ScString, ScSymbol, ScVal, ScVec, TimePoint, UInt128Parts, Uint256,
+    },
+    Error, Host, HostError, SymbolSmall, Val,
+};
+
+fn invalid_json() -> HostError {
+    Error::from_type_and_code(ScErrorType::Value, ScErrorCode::InvalidInput).into()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>, HostError> {
+    if s.len() % 2 != 0 {
+        return Err(invalid_json());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(invalid_json)
+        })
+        .collect()
+}
+
+fn error_to_json(e: &ScError) -> Value {
+    let code = match e {
+        ScError::Contract(code) => json!(code),
+        ScError::WasmVm(code)
+        | ScError::Context(code)
+        | ScError::Storage(code)
+        | ScError::Object(code)
+        | ScError::Crypto(code)
+        | ScError::Events(code)
+        | ScError::Budget(code)
+        | ScError::Value(code)
+        | ScError::Auth(code) => json!(code.name()),
+    };
+    json!({"type": e.discriminant().name(), "code": code})
+}
+
+fn error_from_json(v: &Value) -> Result<ScError, HostError> {
+    let ty = v
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(invalid_json)?;
+    let ty = ScErrorType::VARIANTS
+        .into_iter()
+        .find(|t| t.name() == ty)
+        .ok_or_else(invalid_json)?;
+    let code = v.get("code").ok_or_else(invalid_json)?;
+    if ty == ScErrorType::Contract {
+        let code = code.as_u64().ok_or_else(invalid_json)?;
+        return Ok(ScError::Contract(
+            u32::try_from(code).map_err(|_| invalid_json())?,
+        ));
+    }
+    let code = code.as_str().ok_or_else(invalid_json)?;
+    let code = ScErrorCode::VARIANTS
+        .into_iter()
+        .find(|c| c.name() == code)
+        .ok_or_else(invalid_json)?;
+    Ok(match ty {
+        ScErrorType::Contract => unreachable!(),
+        ScErrorType::WasmVm => ScError::WasmVm(code),
+        ScErrorType::Context => ScError::Context(code),
+        ScErrorType::Storage => ScError::Storage(code),
+        ScErrorType::Object => ScError::Object(code),
+        ScErrorType::Crypto => ScError::Crypto(code),
+        ScErrorType::Events => ScError::Events(code),
+        ScErrorType::Budget => ScError::Budget(code),
+        ScErrorType::Value => ScError::Value(code),
+        ScErrorType::Auth => ScError::Auth(code),
+    })
+}
+
+fn address_to_json(address: &ScAddress) -> Value {
+    let strkey = match address {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(ed25519)))) => {
+            stellar_strkey::Strkey::PublicKeyEd25519(stellar_strkey::ed25519::PublicKey(*ed25519))
+        }
+        ScAddress::Contract(Hash(h)) => {
+            stellar_strkey::Strkey::Contract(stellar_strkey::Contract(*h))
+        }
+    };
+    json!(strkey.to_string())
+}
+
+fn address_from_json(v: &Value) -> Result<ScAddress, HostError> {
+    let s = v.as_str().ok_or_else(invalid_json)?;
+    match stellar_strkey::Strkey::from_string(s).map_err(|_| invalid_json())? {
+        stellar_strkey::Strkey::PublicKeyEd25519(pk) => Ok(ScAddress::Account(AccountId(
+            PublicKey::PublicKeyTypeEd25519(Uint256(pk.0)),
+        ))),
+        stellar_strkey::Strkey::Contract(c) => Ok(ScAddress::Contract(Hash(c.0))),
+        _ => Err(invalid_json()),
+    }
+}
+
+fn map_to_json(map: &ScMap) -> Result<Value, HostError> {
+    Ok(Value::Array(
+        map.iter()
+            .map(|e| Ok(json!({"key": scval_to_json(&e.key)?, "val": scval_to_json(&e.val)?})))
+            .collect::<Result<Vec<Value>, HostError>>()?,
+    ))
+}
+
+fn map_from_json(v: &Value) -> Result<ScMap, HostError> {
+    let entries = v
+        .as_array()
+        .ok_or_else(invalid_json)?
+        .iter()
+        .map(|e| {
+            Ok(ScMapEntry {
+                key: scval_from_json(e.get("key").ok_or_else(invalid_json)?)?,
+                val: scval_from_json(e.get("val").ok_or_else(invalid_json)?)?,
+            })
+        })
+        .collect::<Result<Vec<ScMapEntry>, HostError>>()?;
+    Ok(ScMap(entries.try_into()?))
+}
+
+fn decimal<T: std::str::FromStr>(v: &Value) -> Result<T, HostError> {
+    v.as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(invalid_json)
+}
+
+/// Renders `v` in the canonical JSON form described in the [module
+/// documentation](self).
+pub fn scval_to_json(v: &ScVal) -> Result<Value, HostError> {
+    let (ty, body) = match v {
+        ScVal::Bool(b) => ("bool", json!(b)),
+        ScVal::Void => ("void", Value::Null),
+        ScVal::Error(e) => ("error", error_to_json(e)),
+        ScVal::U32(u) => ("u32", json!(u)),
+        ScVal::I32(i) => ("i32", json!(i)),
+        ScVal::U64(u) => ("u64", json!(u.to_string())),
+        ScVal::I64(i) => ("i64", json!(i.to_string())),
+        ScVal::Timepoint(TimePoint(t)) => ("timepoint", json!(t.to_string())),
+        ScVal::Duration(Duration(d)) => ("duration", json!(d.to_string())),
+        ScVal::U128(UInt128Parts { hi, lo }) => (
+            "u128",
+            json!((u128::from(*hi) << 64 | u128::from(*lo)).to_string()),
+        ),
+        ScVal::I128(Int128Parts { hi, lo }) => (
+            "i128",
+            json!((i128::from(*hi) << 64 | i128::from(*lo)).to_string()),
+        ),
+        ScVal::U256(u) => ("u256", json!(u256_from_parts(u).to_string())),
+        ScVal::I256(i) => ("i256", json!(i256_from_parts(i).to_string())),
+        ScVal::Bytes(b) => ("bytes", json!(hex_encode(b.as_slice()))),
+        ScVal::String(s) => (
+            "string",
+            match std::str::from_utf8(s.as_slice()) {
+                Ok(s) => json!(s),
+                Err(_) => json!({"hex": hex_encode(s.as_slice())}),
+            },
+        ),
+        ScVal::Symbol(s) => (
+            "symbol",
+            json!(std::str::from_utf8(s.as_slice()).map_err(|_| invalid_json())?),
+        ),
+        ScVal::Vec(None) => ("vec", Value::Null),
+        ScVal::Vec(Some(v)) => (
+            "vec",
+            Value::Array(
+                v.iter()
+                    .map(scval_to_json)
+                    .collect::<Result<Vec<Value>, HostError>>()?,
+            ),
+        ),
+        ScVal::Map(None) => ("map", Value::Null),
+        ScVal::Map(Some(m)) => ("map", map_to_json(m)?),
+        ScVal::Address(a) => ("address", address_to_json(a)),
+        ScVal::LedgerKeyContractInstance => ("ledger_key_contract_instance", Value::Null),
+        ScVal::LedgerKeyNonce(ScNonceKey { nonce }) => {
+            ("ledger_key_nonce", json!(nonce.to_string()))
+        }
+        ScVal::ContractInstance(instance) => (
+            "contract_instance",
+            json!({
+                "executable": match &instance.executable {
+                    ContractExecutable::Wasm(Hash(h)) => json!({"wasm": hex_encode(h)}),
+                    ContractExecutable::StellarAsset => json!("stellar_asset"),
+                },
+                "storage": match &instance.storage {
+                    Some(m) => map_to_json(m)?,
+                    None => Value::Null,
+                },
+            }),
+        ),
+    };
+    let mut obj = Map::new();
+    obj.insert(ty.to_string(), body);
+    Ok(Value::Object(obj))
+}
+
+/// Parses a value rendered by [scval_to_json].
+pub fn scval_from_json(v: &Value) -> Result<ScVal, HostError> {
+    let obj = v.as_object().ok_or_else(invalid_json)?;
+    if obj.len() != 1 {
+        return Err(invalid_json());
+    }
+    let Some((ty, body)) = obj.iter().next() else {
+        return Err(invalid_json());
+    };
+    Ok(match ty.as_str() {
+        "bool" => ScVal::Bool(body.as_bool().ok_or_else(invalid_json)?),
+        "void" if body.is_null() => ScVal::Void,
+        "error" => ScVal::Error(error_from_json(body)?),
+        "u32" => ScVal::U32(
+            body.as_u64()
+                .and_then(|u| u32::try_from(u).ok())
+                .ok_or_else(invalid_json)?,
+        ),
+        "i32" => ScVal::I32(
+            body.as_i64()
+                .and_then(|i| i32::try_from(i).ok())
+                .ok_or_else(invalid_json)?,
+        ),
+        "u64" => ScVal::U64(decimal(body)?),
+        "i64" => ScVal::I64(decimal(body)?),
+        "timepoint" => ScVal::Timepoint(TimePoint(decimal(body)?)),
+        "duration" => ScVal::Duration(Duration(decimal(body)?)),
+        "u128" => {
+            let u: u128 = decimal(body)?;
+            ScVal::U128(UInt128Parts {
+                hi: (u >> 64) as u64,
+                lo: u as u64,
+            })
+        }
+        "i128" => {
+            let i: i128 = decimal(body)?;
+            ScVal::I128(Int128Parts {
+                hi: (i >> 64) as i64,
+                lo: i as u64,
+            })
+        }
+        "u256" => u256_into_scval(decimal::<U256>(body)?),
+        "i256" => i256_into_scval(decimal::<I256>(body)?),
+        "bytes" => ScVal::Bytes(ScBytes(
+            hex_decode(body.as_str().ok_or_else(invalid_json)?)?.try_into()?,
+        )),
+        "string" => {
+            let bytes = match body {
+                Value::String(s) => s.as_bytes().to_vec(),
+                _ => hex_decode(
+                    body.get("hex")
+                        .and_then(Value::as_str)
+                        .ok_or_else(invalid_json)?,
+                )?,
+            };
+            ScVal::String(ScString(bytes.try_into()?))
+        }
+        "symbol" => {
+            let s = body.as_str().ok_or_else(invalid_json)?;
+            if s.chars().any(|c| SymbolSmall::validate_char(c).is_err()) {
+                return Err(invalid_json());
+            }
+            ScVal::Symbol(ScSymbol(s.try_into()?))
+        }
+        "vec" if body.is_null() => ScVal::Vec(None),
+        "vec" => ScVal::Vec(Some(ScVec(
+            body.as_array()
+                .ok_or_else(invalid_json)?
+                .iter()
+                .map(scval_from_json)
+                .collect::<Result<Vec<ScVal>, HostError>>()?
+                .try_into()?,
+        ))),
+        "map" if body.is_null() => ScVal::Map(None),
+        "map" => ScVal::Map(Some(map_from_json(body)?)),
+        "address" => ScVal::Address(address_from_json(body)?),
+        "ledger_key_contract_instance" if body.is_null() => ScVal::LedgerKeyContractInstance,
+        "ledger_key_nonce" => ScVal::LedgerKeyNonce(ScNonceKey {
+            nonce: decimal(body)?,
+        }),
+        "contract_instance" => {
+            let executable = match body.get("executable").ok_or_else(invalid_json)? {
+                Value::String(s) if s == "stellar_asset" => ContractExecutable::StellarAsset,
+                executable => {
+                    let hash = hex_decode(
+                        executable
+                            .get("wasm")
+                            .and_then(Value::as_str)
+                            .ok_or_else(invalid_json)?,
+                    )?;
+                    ContractExecutable::Wasm(Hash(hash.try_into().map_err(|_| invalid_json())?))
+                }
+            };
+            let storage = match body.get("storage").ok_or_else(invalid_json)? {
+                Value::Null => None,
+                storage => Some(map_from_json(storage)?),
+            };
+            ScVal::ContractInstance(ScContractInstance {
+                executable,
+                storage,
+            })
+        }
+        _ => return Err(invalid_json()),
+    })
+}
+
+impl Host {
+    /// Renders the host value `val` in the canonical JSON form, see
+    /// [scval_to_json]. The conversion of `val` to [ScVal] is metered.
+    pub fn val_to_json(&self, val: Val) -> Result<Value, HostError> {
+        scval_to_json(&self.from_host_val(val)?)
+    }
+
+    /// Parses a value in the canonical JSON form into a host value, see
+    /// [scval_from_json]. The conversion to the host value is metered.
+    pub fn val_from_json(&self, json: &Value) -> Result<Val, HostError> {
+        let scval = scval_from_json(json)?;
+        if !Val::can_represent_scval(&scval) {
+            return Err(self.err(
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+                "value can't be represented as a host value",
+                &[],
+            ));
+        }
+        self.to_host_val(&scval)
+    }
+}
@@ -10,6 +10,15 @@
 //! wish to enable the `"testutils"` feature, which enables an interface on
 //! [Host] for registering other test contracts by ID.
 //!
+//! Embedders may enable the `"tracing"` feature to have the host report each
+//! contract invocation frame as a span, and each host function called by a
+//! contract as an event carrying its budget cost, to the
+//! [tracing](https://docs.rs/tracing) subscriber of their choice.
+//!
+//! Services presenting contract values to their users (such as RPC and
+//! simulation) may enable the `"json"` feature for the [json] module, which
+//! renders values into a canonical JSON form and parses them back.
+//!
 //! The [Host] type provides some facilities above and beyond just the [Env]
 //! trait, including:
 //!
@@ -25,7 +34,7 @@ mod macros;
 
 pub mod budget;
 pub mod events;
-pub use events::diagnostic::DiagnosticLevel;
+pub use events::diagnostic::{DiagnosticLevel, DiagnosticSeverity};
 mod host;
 pub(crate) mod host_object;
 
@@ -35,22 +44,38 @@ pub mod auth;
 pub mod vm;
 pub use vm::Vm;
 pub mod storage;
-pub use budget::{DEFAULT_HOST_DEPTH_LIMIT, DEFAULT_XDR_RW_LIMITS};
+pub use budget::{
+    DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT, DEFAULT_HOST_DEPTH_LIMIT, DEFAULT_XDR_RW_LIMITS,
+};
 pub use host::{
-    metered_map::MeteredOrdMap, metered_vector::MeteredVector, Host, HostError, LedgerInfo, Seed,
-    SEED_BYTES,
+    derive_frame_prng_seed, metered_map::MeteredOrdMap, metered_vector::MeteredVector, CallLimits,
+    DebugRenderLimits, ErrorContext, Host, HostError, LedgerInfo, ScMapKeyOrderPolicy, Seed,
+    FRAME_PRNG_DERIVATION_PROTOCOL_VERSION, SEED_BYTES,
 };
 pub use soroban_env_common::*;
 
+pub mod batch_verify;
 pub mod e2e_invoke;
 pub mod fees;
 
+#[cfg(any(test, feature = "json"))]
+pub mod json;
+
 #[cfg(feature = "bench")]
 #[doc(hidden)]
 pub mod cost_runner;
 
+#[cfg(feature = "testutils")]
+pub use host::call_log::{HostCallLog, HostCallRecord, HostCallStep};
+
+#[cfg(any(test, feature = "recording_auth"))]
+pub use host::{DetachedHostState, OwnedEntryWithLiveUntil};
+
 #[cfg(any(test, feature = "testutils"))]
-pub use host::{ContractFunctionSet, ContractInvocationEvent};
+pub use host::{
+    BudgetSummary, ContractFunctionSet, ContractInvocationEvent, InvocationReport,
+    StorageAccessHook, StorageChange, StorageKeysByContract,
+};
 
 #[cfg(any(test, feature = "testutils"))]
 #[doc(hidden)]
@@ -6,19 +6,66 @@
 //!   - [Env::get_contract_data](crate::Env::get_contract_data)
 //!   - [Env::put_contract_data](crate::Env::put_contract_data)
 //!   - [Env::del_contract_data](crate::Env::del_contract_data)
+//!   - [Env::take_contract_data](crate::Env::take_contract_data)
 
-use std::rc::Rc;
+use std::{cmp::Ordering, rc::Rc};
 
 use crate::{
     budget::Budget,
     host::{ledger_info_helper::get_key_durability, metered_map::MeteredOrdMap},
-    xdr::{ContractDataDurability, LedgerEntry, LedgerKey, ScErrorCode, ScErrorType},
-    Env, Error, Host, HostError, Val,
+    xdr::{ContractDataDurability, LedgerEntry, LedgerKey, ScErrorCode, ScErrorType, ScVal},
+    Compare, Env, Error, Host, HostError, Val,
 };
 
 pub type FootprintMap = MeteredOrdMap<Rc<LedgerKey>, AccessType, Budget>;
 pub type EntryWithLiveUntil = (Rc<LedgerEntry>, Option<u32>);
 pub type StorageMap = MeteredOrdMap<Rc<LedgerKey>, Option<EntryWithLiveUntil>, Budget>;
+#[cfg(any(test, feature = "testutils"))]
+pub type ArchivedMap = MeteredOrdMap<Rc<LedgerKey>, Rc<LedgerEntry>, Budget>;
+#[cfg(any(test, feature = "testutils"))]
+pub(crate) type StorageAccessCallback = Rc<dyn for<'a> Fn(StorageAccessEvent<'a>)>;
+
+/// The kind of access reported in a [StorageAccessEvent].
+#[cfg(any(test, feature = "testutils"))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StorageAccessKind {
+    /// The entry has been read or checked for presence.
+    Read,
+    /// The entry has been created or updated.
+    Write,
+    /// The entry has been deleted.
+    Delete,
+    /// The live_until ledger of the entry has been extended.
+    ExtendTtl,
+}
+
+/// Describes a single access to the [Storage], as reported to the hook set
+/// via `Host::set_storage_access_hook`.
+#[cfg(any(test, feature = "testutils"))]
+pub struct StorageAccessEvent<'a> {
+    pub kind: StorageAccessKind,
+    pub key: &'a Rc<LedgerKey>,
+    /// Entry and its live_until ledger before the access, `None` if the entry
+    /// didn't exist.
+    pub old_value: Option<&'a EntryWithLiveUntil>,
+    /// Entry and its live_until ledger after the access, `None` if the entry
+    /// doesn't exist.
+    pub new_value: Option<&'a EntryWithLiveUntil>,
+    /// The contract running in the frame that performed the access, if any.
+    pub contract: Option<crate::xdr::Hash>,
+}
+
+/// Storage keys accessed by a single contract, as reported by
+/// `Host::get_storage_keys_by_contract`.
+#[cfg(any(test, feature = "testutils"))]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContractStorageKeys {
+    /// Keys that have been read, checked for presence or had their TTL
+    /// extended.
+    pub read: std::collections::BTreeSet<Rc<LedgerKey>>,
+    /// Keys that have been written or deleted.
+    pub written: std::collections::BTreeSet<Rc<LedgerKey>>,
+}
 
 /// The in-memory instance storage of the current running contract. Initially
 /// contains entries from the `ScMap` of the corresponding `ScContractInstance`
@@ -38,9 +85,11 @@ impl std::hash::Hash for StorageMap {
 }
 
 impl InstanceStorageMap {
+    // Builds the map from the entries of the [ScMap] stored in a contract
+    // instance, see [Host::host_map_from_scmap_entries].
     pub(crate) fn from_map(map: Vec<(Val, Val)>, host: &Host) -> Result<Self, HostError> {
         Ok(Self {
-            map: MeteredOrdMap::from_map(map, host)?,
+            map: host.host_map_from_scmap_entries(map)?,
             is_modified: false,
         })
     }
@@ -60,6 +109,28 @@ pub enum AccessType {
     ReadWrite,
 }
 
+/// Policy enforced by the [Host] on the accesses that the currently running
+/// contract performs on the storage entries owned by another contract. Both
+/// kinds of access are allowed by default.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CrossContractStoragePolicy {
+    /// Whether the contract may extend the TTL of the instance and code of
+    /// another contract.
+    pub allow_extend_ttl: bool,
+    /// Whether the contract data reads (`get_contract_data` and
+    /// `has_contract_data`) may access the entries of another contract.
+    pub allow_read: bool,
+}
+
+impl Default for CrossContractStoragePolicy {
+    fn default() -> Self {
+        Self {
+            allow_extend_ttl: true,
+            allow_read: true,
+        }
+    }
+}
+
 /// A helper type used by [FootprintMode::Recording] to provide access
 /// to a stable read-snapshot of a ledger.
 pub trait SnapshotSource {
@@ -68,6 +139,23 @@ pub trait SnapshotSource {
     fn has(&self, key: &Rc<LedgerKey>) -> Result<bool, HostError>;
 }
 
+/// A durable, embedder-provided store of committed ledger state.
+///
+/// A [StorageBackend] is a [SnapshotSource] that can also accept writes, which
+/// allows long-running environments (such as local test networks or
+/// multi-transaction test harnesses) to keep committed state outside of the
+/// host, e.g. in an on-disk database. The host itself never writes to the
+/// backend during execution: all contract IO still goes through the metered
+/// [Storage] map, and the resulting changes are only applied to the backend
+/// via [Storage::commit_to_backend] once the embedder decides to commit them.
+pub trait StorageBackend: SnapshotSource {
+    /// Writes the ledger entry and its live_until ledger for the key,
+    /// replacing any existing entry.
+    fn put(&self, key: &Rc<LedgerKey>, entry: &EntryWithLiveUntil) -> Result<(), HostError>;
+    /// Removes the ledger entry for the key, if present.
+    fn del(&self, key: &Rc<LedgerKey>) -> Result<(), HostError>;
+}
+
 /// Describes the total set of [LedgerKey]s that a given transaction
 /// will access, as well as the [AccessType] governing each key.
 ///
@@ -165,6 +253,13 @@ pub struct Storage {
     pub footprint: Footprint,
     pub mode: FootprintMode,
     pub map: StorageMap,
+    /// Persistent entries that have been archived in tests, see
+    /// [Storage::archive]. Any access to these fails until they are restored
+    /// via [Storage::restore].
+    #[cfg(any(test, feature = "testutils"))]
+    pub archived: ArchivedMap,
+    #[cfg(any(test, feature = "testutils"))]
+    pub(crate) access_callback: Option<StorageAccessCallback>,
 }
 
 // Notes on metering: all storage operations: `put`, `get`, `del`, `has` are
@@ -198,6 +293,56 @@ impl Storage {
         }
     }
 
+    /// Compares two [LedgerKey]s using the same metered ordering the
+    /// [Storage] and [Footprint] maps use internally. Unsupported key types
+    /// are rejected as in [Storage::check_supported_ledger_key_type].
+    pub fn compare_keys(
+        a: &LedgerKey,
+        b: &LedgerKey,
+        budget: &Budget,
+    ) -> Result<Ordering, HostError> {
+        <Budget as Compare<LedgerKey>>::compare(budget, a, b)
+    }
+
+    /// Compares two contract data keys in their [ScVal] form. This is the
+    /// ordering the host uses for storage keys and agrees with the ordering
+    /// of the corresponding host objects, so two keys comparing
+    /// [Ordering::Equal] here address the same storage entry.
+    pub fn compare_scval_keys(
+        a: &ScVal,
+        b: &ScVal,
+        budget: &Budget,
+    ) -> Result<Ordering, HostError> {
+        <Budget as Compare<ScVal>>::compare(budget, a, b)
+    }
+
+    /// Normalizes a set of keys (e.g. a footprint assembled by an embedder)
+    /// into the canonical order used by the host, removing any keys that are
+    /// equivalent to a preceding one. All comparisons are metered.
+    pub fn sort_keys(keys: &mut Vec<Rc<LedgerKey>>, budget: &Budget) -> Result<(), HostError> {
+        let mut err: Option<HostError> = None;
+        let mut cmp = |a: &Rc<LedgerKey>, b: &Rc<LedgerKey>| {
+            // Once an error has been hit, return Ordering::Equal to avoid any
+            // further (metered) comparisons.
+            if err.is_some() {
+                return Ordering::Equal;
+            }
+            match Self::compare_keys(a, b, budget) {
+                Ok(ord) => ord,
+                Err(he) => {
+                    err = Some(he);
+                    Ordering::Equal
+                }
+            }
+        };
+        keys.sort_by(|a, b| cmp(a, b));
+        keys.dedup_by(|a, b| cmp(a, b) == Ordering::Equal);
+        match err {
+            Some(he) => Err(he),
+            None => Ok(()),
+        }
+    }
+
     /// Constructs a new [Storage] in [FootprintMode::Enforcing] using a
     /// given [Footprint] and a storage map populated with all the keys
     /// listed in the [Footprint].
@@ -206,6 +351,10 @@ impl Storage {
             mode: FootprintMode::Enforcing,
             footprint,
             map,
+            #[cfg(any(test, feature = "testutils"))]
+            archived: Default::default(),
+            #[cfg(any(test, feature = "testutils"))]
+            access_callback: None,
         }
     }
 
@@ -216,9 +365,36 @@ impl Storage {
             mode: FootprintMode::Recording(src),
             footprint: Footprint::default(),
             map: Default::default(),
+            #[cfg(any(test, feature = "testutils"))]
+            archived: Default::default(),
+            #[cfg(any(test, feature = "testutils"))]
+            access_callback: None,
         }
     }
 
+    /// Applies the state accumulated in the storage map to the provided
+    /// [StorageBackend]: every live entry is written (which also captures any
+    /// TTL extensions of read-only entries) and every deleted or missing
+    /// entry is removed.
+    ///
+    /// This is meant to be called on the [Storage] returned from
+    /// [Host::try_finish](crate::Host::try_finish) after a successful
+    /// invocation, so that the next [Host] can observe the committed state by
+    /// using the same backend as its [SnapshotSource].
+    pub fn commit_to_backend(
+        &self,
+        backend: &dyn StorageBackend,
+        budget: &Budget,
+    ) -> Result<(), HostError> {
+        for (key, entry) in self.map.iter(budget)? {
+            match entry {
+                Some(entry) => backend.put(key, entry)?,
+                None => backend.del(key)?,
+            }
+        }
+        Ok(())
+    }
+
     // Helper function the next 3 `get`-variants funnel into.
     fn try_get_full(
         &mut self,
@@ -227,13 +403,20 @@ impl Storage {
     ) -> Result<Option<EntryWithLiveUntil>, HostError> {
         let _span = tracy_span!("storage get");
         Self::check_supported_ledger_key_type(key)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_not_archived(key)?;
         self.prepare_read_only_access(key, budget)?;
-        match self.map.get::<Rc<LedgerKey>>(key, budget)? {
+        let res = match self.map.get::<Rc<LedgerKey>>(key, budget)? {
             // Key has to be in the storage map at this point due to
             // `prepare_read_only_access`.
             None => Err((ScErrorType::Storage, ScErrorCode::InternalError).into()),
             Some(pair_option) => Ok(pair_option.clone()),
+        };
+        #[cfg(any(test, feature = "testutils"))]
+        if let Ok(value) = &res {
+            self.notify_access(StorageAccessKind::Read, key, value.as_ref(), value.as_ref());
         }
+        res
     }
 
     /// Attempts to retrieve the [LedgerEntry] associated with a given
@@ -301,6 +484,8 @@ impl Storage {
         if let Some(le) = &val {
             Self::check_supported_ledger_entry_type(&le.0)?;
         }
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_not_archived(key)?;
         let ty = AccessType::ReadWrite;
         match self.mode {
             FootprintMode::Recording(_) => {
@@ -310,7 +495,16 @@ impl Storage {
                 self.footprint.enforce_access(key, ty, budget)?;
             }
         };
+        #[cfg(any(test, feature = "testutils"))]
+        let old_value = self.peek_unmetered(key).cloned();
+        #[cfg(any(test, feature = "testutils"))]
+        let (kind, new_value) = match &val {
+            Some(v) => (StorageAccessKind::Write, Some(v.clone())),
+            None => (StorageAccessKind::Delete, None),
+        };
         self.map = self.map.insert(Rc::clone(key), val, budget)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.notify_access(kind, key, old_value.as_ref(), new_value.as_ref());
         Ok(())
     }
 
@@ -348,6 +542,42 @@ impl Storage {
         self.put_opt(key, None, budget)
     }
 
+    /// Attempts to delete the [LedgerEntry] associated with a given [LedgerKey]
+    /// in the [Storage], returning the deleted entry or an error if there is
+    /// no such entry.
+    ///
+    /// The footprint requirements are the same as for [Storage::del]: the
+    /// previous entry is retrieved as a part of the same read-write access,
+    /// so there is no need for a separate [Storage::get].
+    pub fn take(
+        &mut self,
+        key: &Rc<LedgerKey>,
+        budget: &Budget,
+    ) -> Result<Rc<LedgerEntry>, HostError> {
+        let _span = tracy_span!("storage take");
+        Self::check_supported_ledger_key_type(key)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_not_archived(key)?;
+        let ty = AccessType::ReadWrite;
+        match self.mode {
+            FootprintMode::Recording(_) => {
+                self.footprint.record_access(key, ty, budget)?;
+                self.read_through_snapshot(key, budget)?;
+            }
+            FootprintMode::Enforcing => {
+                self.footprint.enforce_access(key, ty, budget)?;
+            }
+        };
+        let old_value = match self.map.get::<Rc<LedgerKey>>(key, budget)? {
+            Some(Some(pair)) => pair.clone(),
+            _ => return Err((ScErrorType::Storage, ScErrorCode::MissingValue).into()),
+        };
+        self.map = self.map.insert(Rc::clone(key), None, budget)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.notify_access(StorageAccessKind::Delete, key, Some(&old_value), None);
+        Ok(old_value.0)
+    }
+
     /// Attempts to determine the presence of a [LedgerEntry] associated with a
     /// given [LedgerKey] in the [Storage], returning `Ok(true)` if an entry
     /// with the key exists and `Ok(false)` if it does not.
@@ -360,14 +590,18 @@ impl Storage {
     pub fn has(&mut self, key: &Rc<LedgerKey>, budget: &Budget) -> Result<bool, HostError> {
         let _span = tracy_span!("storage has");
         Self::check_supported_ledger_key_type(key)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.check_not_archived(key)?;
         self.prepare_read_only_access(key, budget)?;
-        Ok(self
+        let value = self
             .map
             .get::<Rc<LedgerKey>>(key, budget)?
             // Key has to be present in storage at this point, so not having it
             // would be an internal error.
-            .ok_or_else(|| HostError::from((ScErrorType::Storage, ScErrorCode::InternalError)))?
-            .is_some())
+            .ok_or_else(|| HostError::from((ScErrorType::Storage, ScErrorCode::InternalError)))?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.notify_access(StorageAccessKind::Read, key, value.as_ref(), value.as_ref());
+        Ok(value.is_some())
     }
 
     /// Extends `key` to live `extend_to` ledgers from now (not counting the
@@ -458,10 +692,17 @@ impl Storage {
         if new_live_until > old_live_until && old_live_until.saturating_sub(ledger_seq) <= threshold
         {
             self.map = self.map.insert(
-                key,
+                Rc::clone(&key),
                 Some((entry.clone(), Some(new_live_until))),
                 host.budget_ref(),
             )?;
+            #[cfg(any(test, feature = "testutils"))]
+            self.notify_access(
+                StorageAccessKind::ExtendTtl,
+                &key,
+                Some(&(entry.clone(), Some(old_live_until))),
+                Some(&(entry, Some(new_live_until))),
+            );
         }
         Ok(())
     }
@@ -473,18 +714,9 @@ impl Storage {
     ) -> Result<(), HostError> {
         let ty = AccessType::ReadOnly;
         match self.mode {
-            FootprintMode::Recording(ref src) => {
+            FootprintMode::Recording(_) => {
                 self.footprint.record_access(key, ty, budget)?;
-                // In recording mode we treat the map as a cache
-                // that misses read-through to the underlying src.
-                if !self.map.contains_key::<Rc<LedgerKey>>(key, budget)? {
-                    let value = if src.has(&key)? {
-                        Some(src.get(key)?)
-                    } else {
-                        None
-                    };
-                    self.map = self.map.insert(key.clone(), value, budget)?;
-                }
+                self.read_through_snapshot(key, budget)?;
             }
             FootprintMode::Enforcing => {
                 self.footprint.enforce_access(key, ty, budget)?;
@@ -492,4 +724,288 @@ impl Storage {
         };
         Ok(())
     }
+
+    // In recording mode we treat the map as a cache that misses read-through
+    // to the underlying src. This is a no-op in enforcing mode.
+    fn read_through_snapshot(
+        &mut self,
+        key: &Rc<LedgerKey>,
+        budget: &Budget,
+    ) -> Result<(), HostError> {
+        if let FootprintMode::Recording(src) = &self.mode {
+            if !self.map.contains_key::<Rc<LedgerKey>>(key, budget)? {
+                let value = if src.has(&key)? {
+                    Some(src.get(key)?)
+                } else {
+                    None
+                };
+                self.map = self.map.insert(key.clone(), value, budget)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(test, feature = "testutils"))]
+impl Storage {
+    // Notes on metering: storage access hooks are a test-only facility, so
+    // neither the lookup of the current entry nor the hook call are metered.
+    fn peek_unmetered(&self, key: &Rc<LedgerKey>) -> Option<&EntryWithLiveUntil> {
+        self.map
+            .map
+            .binary_search_by(|(k, _)| k.as_ref().cmp(key.as_ref()))
+            .ok()
+            .and_then(|i| self.map.map[i].1.as_ref())
+    }
+
+    fn notify_access(
+        &self,
+        kind: StorageAccessKind,
+        key: &Rc<LedgerKey>,
+        old_value: Option<&EntryWithLiveUntil>,
+        new_value: Option<&EntryWithLiveUntil>,
+    ) {
+        if let Some(callback) = &self.access_callback {
+            callback(StorageAccessEvent {
+                kind,
+                key,
+                old_value,
+                new_value,
+                contract: None,
+            });
+        }
+    }
+
+    // Notes on metering: archival is a test-only facility and this check runs
+    // on every storage access, so it is deliberately not metered in order to
+    // not affect the budget of tests that don't use archival.
+    fn check_not_archived(&self, key: &Rc<LedgerKey>) -> Result<(), HostError> {
+        if self
+            .archived
+            .map
+            .binary_search_by(|(k, _)| k.as_ref().cmp(key.as_ref()))
+            .is_ok()
+        {
+            Err((ScErrorType::Storage, ScErrorCode::InvalidAction).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns `true` if the entry for the given key has been archived and
+    /// not yet restored.
+    pub fn is_archived(&self, key: &Rc<LedgerKey>, budget: &Budget) -> Result<bool, HostError> {
+        self.archived.contains_key::<Rc<LedgerKey>>(key, budget)
+    }
+
+    /// Simulates the archival of a persistent entry, i.e. what happens to a
+    /// persistent entry on-chain once its TTL runs out. The entry is removed
+    /// from the live state and any further access to it (reads, writes,
+    /// deletions and TTL extensions) fails with
+    /// `(ScErrorType::Storage, ScErrorCode::InvalidAction)` until it is
+    /// brought back via [Storage::restore].
+    ///
+    /// Only persistent `ContractData` and `ContractCode` entries can be
+    /// archived, and the entry has to exist.
+    pub fn archive(&mut self, key: &Rc<LedgerKey>, budget: &Budget) -> Result<(), HostError> {
+        if !matches!(
+            get_key_durability(key),
+            Some(ContractDataDurability::Persistent)
+        ) {
+            return Err((ScErrorType::Storage, ScErrorCode::InvalidInput).into());
+        }
+        let (entry, _) = self
+            .try_get_full(key, budget)?
+            .ok_or_else(|| HostError::from((ScErrorType::Storage, ScErrorCode::MissingValue)))?;
+        // Archival is performed by the network and not by the transaction, so
+        // it bypasses the footprint checks.
+        self.map = self.map.insert(Rc::clone(key), None, budget)?;
+        self.archived = self.archived.insert(Rc::clone(key), entry, budget)?;
+        Ok(())
+    }
+
+    /// Brings back a previously archived entry with the provided
+    /// `live_until_ledger`, mirroring the effect of a restore operation.
+    ///
+    /// Similarly to the on-chain restore, this counts as a write of the key:
+    /// in [FootprintMode::Enforcing] mode the key has to be declared in the
+    /// [Footprint] as [AccessType::ReadWrite].
+    pub fn restore(
+        &mut self,
+        key: &Rc<LedgerKey>,
+        live_until_ledger: u32,
+        budget: &Budget,
+    ) -> Result<(), HostError> {
+        let Some((archived, entry)) = self.archived.remove::<Rc<LedgerKey>>(key, budget)? else {
+            return Err((ScErrorType::Storage, ScErrorCode::MissingValue).into());
+        };
+        // Keep the entry archived if the write is not allowed.
+        let prev_archived = std::mem::replace(&mut self.archived, archived);
+        if let Err(e) = self.put_opt(key, Some((entry, Some(live_until_ledger))), budget) {
+            self.archived = prev_archived;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// A single step of a [MerkleProof]: the hash of the sibling node and the side
+/// it is on.
+#[cfg(any(test, feature = "testutils"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MerkleSibling {
+    Left([u8; 32]),
+    Right([u8; 32]),
+}
+
+/// Proof of inclusion of a storage entry in the Merkle tree committed to by
+/// [Storage::merkle_root].
+#[cfg(any(test, feature = "testutils"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerkleProof {
+    /// Hash of the leaf corresponding to the entry.
+    pub leaf: [u8; 32],
+    /// Sibling hashes on the path from the leaf to the root.
+    pub path: Vec<MerkleSibling>,
+}
+
+#[cfg(any(test, feature = "testutils"))]
+const MERKLE_LEAF_PREFIX: u8 = 0;
+#[cfg(any(test, feature = "testutils"))]
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+#[cfg(any(test, feature = "testutils"))]
+fn merkle_leaf(
+    key: &LedgerKey,
+    entry: &EntryWithLiveUntil,
+    budget: &Budget,
+) -> Result<[u8; 32], HostError> {
+    let mut buf = vec![MERKLE_LEAF_PREFIX];
+    crate::host::metered_xdr::metered_write_xdr(budget, key, &mut buf)?;
+    crate::host::metered_xdr::metered_write_xdr(budget, entry.0.as_ref(), &mut buf)?;
+    match entry.1 {
+        Some(live_until) => {
+            buf.push(1);
+            buf.extend_from_slice(&live_until.to_be_bytes());
+        }
+        None => buf.push(0),
+    }
+    crate::host::crypto::sha256_hash_from_bytes_raw(&buf, budget)
+}
+
+#[cfg(any(test, feature = "testutils"))]
+fn merkle_node(left: &[u8; 32], right: &[u8; 32], budget: &Budget) -> Result<[u8; 32], HostError> {
+    let mut buf = [0_u8; 65];
+    buf[0] = MERKLE_NODE_PREFIX;
+    buf[1..33].copy_from_slice(left);
+    buf[33..].copy_from_slice(right);
+    crate::host::crypto::sha256_hash_from_bytes_raw(&buf, budget)
+}
+
+#[cfg(any(test, feature = "testutils"))]
+impl MerkleProof {
+    /// Computes the root of the tree that this proof leads to.
+    pub fn root(&self, budget: &Budget) -> Result<[u8; 32], HostError> {
+        let mut hash = self.leaf;
+        for sibling in self.path.iter() {
+            hash = match sibling {
+                MerkleSibling::Left(left) => merkle_node(left, &hash, budget)?,
+                MerkleSibling::Right(right) => merkle_node(&hash, right, budget)?,
+            };
+        }
+        Ok(hash)
+    }
+
+    /// Checks that this proves the inclusion of `entry` under `key` in the
+    /// tree with the given `root`.
+    pub fn verify(
+        &self,
+        key: &LedgerKey,
+        entry: &EntryWithLiveUntil,
+        root: &[u8; 32],
+        budget: &Budget,
+    ) -> Result<bool, HostError> {
+        Ok(merkle_leaf(key, entry, budget)? == self.leaf && self.root(budget)? == *root)
+    }
+}
+
+// Merkle commitment over the storage for light-client and bridge prototyping.
+// The leaves are the hashes of the live entries in the canonical key order,
+// every odd node at the end of a level is promoted to the next level as is.
+#[cfg(any(test, feature = "testutils"))]
+impl Storage {
+    fn merkle_levels(&self, budget: &Budget) -> Result<Vec<Vec<[u8; 32]>>, HostError> {
+        let mut leaves = vec![];
+        for (key, entry) in self.map.iter(budget)? {
+            if let Some(entry) = entry {
+                leaves.push(merkle_leaf(key, entry, budget)?);
+            }
+        }
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let mut next = vec![];
+            for pair in level.chunks(2) {
+                match pair {
+                    [left, right] => next.push(merkle_node(left, right, budget)?),
+                    [single] => next.push(*single),
+                    _ => return Err((ScErrorType::Storage, ScErrorCode::InternalError).into()),
+                }
+            }
+            levels.push(next);
+        }
+        Ok(levels)
+    }
+
+    /// Returns the root of the Merkle tree over all the live entries in the
+    /// storage map (together with their live_until ledgers), or all zeroes if
+    /// there are no live entries.
+    pub fn merkle_root(&self, budget: &Budget) -> Result<[u8; 32], HostError> {
+        let levels = self.merkle_levels(budget)?;
+        Ok(levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// Produces the proof of inclusion of the live entry under `key` in the
+    /// tree committed to by [Storage::merkle_root], or `None` if there is no
+    /// such entry.
+    ///
+    /// This doesn't go through the [Footprint] and hence doesn't affect it.
+    pub fn merkle_proof(
+        &self,
+        key: &Rc<LedgerKey>,
+        budget: &Budget,
+    ) -> Result<Option<MerkleProof>, HostError> {
+        let mut index = None;
+        let mut live_count = 0_usize;
+        for (k, entry) in self.map.iter(budget)? {
+            if entry.is_none() {
+                continue;
+            }
+            if Self::compare_keys(k, key, budget)? == Ordering::Equal {
+                index = Some(live_count);
+            }
+            live_count += 1;
+        }
+        let Some(leaf_index) = index else {
+            return Ok(None);
+        };
+        let levels = self.merkle_levels(budget)?;
+        let mut index = leaf_index;
+        let mut path = vec![];
+        for level in levels.iter() {
+            if index % 2 == 1 {
+                path.push(MerkleSibling::Left(level[index - 1]));
+            } else if index + 1 < level.len() {
+                path.push(MerkleSibling::Right(level[index + 1]));
+            }
+            index /= 2;
+        }
+        Ok(Some(MerkleProof {
+            leaf: levels[0][leaf_index],
+            path,
+        }))
+    }
 }
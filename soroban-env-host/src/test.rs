@@ -3,13 +3,16 @@ pub(crate) mod observe;
 mod address;
 mod auth;
 mod basic;
+mod batch_verify;
 mod budget_metering;
 mod bytes;
 mod complex;
 mod crypto;
+mod debug_render;
 mod depth_limit;
 mod dispatch;
 mod event;
+mod fault_injection;
 mod finish;
 mod frame;
 mod host;
@@ -17,12 +20,14 @@ mod hostile;
 #[cfg(opt_build)]
 mod hostile_opt;
 mod invocation;
+mod json;
 mod ledger;
 mod lifecycle;
 mod linear_memory;
 mod map;
 #[cfg(feature = "testutils")]
 mod metering_benchmark;
+mod mock_contracts;
 mod num;
 mod post_mvp;
 mod prng;
@@ -30,5 +35,7 @@ mod stellar_asset_contract;
 mod storage;
 mod str;
 mod symbol;
+#[cfg(feature = "tracing")]
+mod trace;
 mod tuple;
 mod vec;
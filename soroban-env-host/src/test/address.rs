@@ -1,7 +1,7 @@
 use crate::Host;
 use soroban_env_common::{
     xdr::{AccountId, Hash, PublicKey, ScAddress, ScBytes, ScString, Uint256},
-    Compare, Env, StringObject, Val,
+    Compare, Env, EnvBase, StringObject, Val,
 };
 
 fn extract_string(host: &Host, s: StringObject) -> String {
@@ -168,3 +168,87 @@ fn invalid_strkey_to_address_conversion() {
         ))
         .is_err());
 }
+
+#[test]
+fn test_address_raw_parts_conversions() {
+    let host = Host::default();
+    let key_bytes = [
+        0x3f, 0x0c, 0x34, 0xbf, 0x93, 0xad, 0x0d, 0x99, 0x71, 0xd0, 0x4c, 0xcc, 0x90, 0xf7, 0x05,
+        0x51, 0x1c, 0x83, 0x8a, 0xad, 0x97, 0x34, 0xa4, 0xa2, 0xfb, 0x0d, 0x7a, 0x03, 0xfc, 0x7f,
+        0xe8, 0x9a,
+    ];
+    let key = host.bytes_new_from_slice(&key_bytes).unwrap();
+
+    let account = host.account_address_from_ed25519_public_key(key).unwrap();
+    assert_eq!(
+        extract_string(&host, host.address_to_strkey(account).unwrap()),
+        "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ"
+    );
+    let contract = host.contract_address_from_id(key).unwrap();
+    assert_eq!(
+        extract_string(&host, host.address_to_strkey(contract).unwrap()),
+        "CA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJUWDA"
+    );
+
+    // Each address only yields the part matching its kind.
+    let pk = host.address_to_ed25519_public_key(account).unwrap();
+    assert!(host.compare(&pk, &key.to_val()).unwrap().is_eq());
+    assert!(host.address_to_contract_id(account).unwrap().is_void());
+    let id = host.address_to_contract_id(contract).unwrap();
+    assert!(host.compare(&id, &key.to_val()).unwrap().is_eq());
+    assert!(host
+        .address_to_ed25519_public_key(contract)
+        .unwrap()
+        .is_void());
+
+    // Wrong lengths and keys off the curve are rejected.
+    let short = host.bytes_new_from_slice(&key_bytes[..31]).unwrap();
+    assert!(host.account_address_from_ed25519_public_key(short).is_err());
+    assert!(host.contract_address_from_id(short).is_err());
+    let mut off_curve = [0u8; 32];
+    off_curve[0] = 2;
+    let off_curve = host.bytes_new_from_slice(&off_curve).unwrap();
+    assert!(host
+        .account_address_from_ed25519_public_key(off_curve)
+        .is_err());
+}
+
+#[test]
+fn test_address_generator_is_deterministic() {
+    use crate::testutils::TestAddressGenerator;
+
+    let mut a = TestAddressGenerator::new(1);
+    let mut b = TestAddressGenerator::new(1);
+    let mut c = TestAddressGenerator::new(2);
+    let (a1, a2) = (a.account_id(), a.contract_id());
+    assert_eq!(a1, b.account_id());
+    assert_eq!(a2, b.contract_id());
+    assert_ne!(a1, c.account_id());
+    assert_ne!(a.account_id(), a1);
+    assert_ne!(a.contract_id(), a2);
+
+    // Generated accounts are registered with the host and usable as
+    // addresses, independently of the host's own test PRNG.
+    let host = Host::test_host();
+    let mut gen = TestAddressGenerator::new(1);
+    let (key, address) = gen.account(&host).unwrap();
+    assert_eq!(
+        key.verifying_key().to_bytes(),
+        match &a1.0 {
+            PublicKey::PublicKeyTypeEd25519(Uint256(pk)) => *pk,
+        }
+    );
+    let account = host.load_account(a1.clone()).unwrap();
+    assert_eq!(account.thresholds.0, [1, 0, 0, 0]);
+    host.visit_obj(address, |addr: &ScAddress| {
+        assert_eq!(addr, &ScAddress::Account(a1.clone()));
+        Ok(())
+    })
+    .unwrap();
+    let contract = gen.contract_address(&host).unwrap();
+    host.visit_obj(contract, |addr: &ScAddress| {
+        assert_eq!(addr, &ScAddress::Contract(a2.clone()));
+        Ok(())
+    })
+    .unwrap();
+}
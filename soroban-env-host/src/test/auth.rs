@@ -2,13 +2,14 @@ use ed25519_dalek::SigningKey;
 use rand::Rng;
 use soroban_builtin_sdk_macros::contracttype;
 use soroban_env_common::xdr::{
-    AccountId, ContractDataDurability, HashIdPreimage, HashIdPreimageSorobanAuthorization,
-    InvokeContractArgs, PublicKey, ScAddress, ScBytes, ScErrorCode, ScErrorType, ScNonceKey,
-    ScSymbol, ScVal, SorobanAddressCredentials, SorobanAuthorizationEntry,
-    SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials, Uint256, VecM,
+    AccountId, ContractCostType, HashIdPreimage, HashIdPreimageSorobanAuthorization,
+    InvokeContractArgs, PublicKey, ScAddress, ScBytes, ScErrorCode, ScErrorType, ScSymbol, ScVal,
+    SorobanAddressCredentials, SorobanAuthorizationEntry, SorobanAuthorizedFunction,
+    SorobanAuthorizedInvocation, SorobanCredentials, Uint256, VecM,
 };
 use soroban_test_wasms::{
     AUTH_TEST_CONTRACT, CONDITIONAL_ACCOUNT_TEST_CONTRACT, DELEGATED_ACCOUNT_TEST_CONTRACT,
+    SIMPLE_ACCOUNT_CONTRACT,
 };
 
 use crate::auth::RecordedAuthPayload;
@@ -17,8 +18,8 @@ use crate::builtin_contracts::base_types::Address;
 use crate::builtin_contracts::testutils::{
     create_account, generate_signing_key, sign_payload_for_account, signing_key_to_account_id,
 };
-use crate::{Host, LedgerInfo};
-use soroban_env_common::{AddressObject, Env, Symbol, SymbolStr, TryFromVal, TryIntoVal};
+use crate::{DiagnosticSeverity, Host, HostError, LedgerInfo};
+use soroban_env_common::{AddressObject, Env, EnvBase, Symbol, SymbolStr, TryFromVal, TryIntoVal};
 
 use crate::builtin_contracts::base_types::Vec as HostVec;
 
@@ -241,38 +242,16 @@ impl AuthTest {
                     .with_test_prng(|chacha| Ok(chacha.gen_range(0..=i64::MAX)))
                     .unwrap();
                 curr_nonces.push(nonce);
-                let root_invocation = self.convert_sign_node(sign_root);
-                let payload_preimage =
-                    HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
-                        network_id: self
-                            .host
-                            .with_ledger_info(|li: &LedgerInfo| Ok(li.network_id))
-                            .unwrap()
-                            .try_into()
-                            .unwrap(),
-                        invocation: root_invocation.clone(),
-                        nonce,
-                        signature_expiration_ledger: 1000,
-                    });
-                let payload = self.host.metered_hash_xdr(&payload_preimage).unwrap();
-                let signature_args = test_vec![
-                    &self.host,
-                    sign_payload_for_account(&self.host, &self.keys[address_id], &payload)
-                ];
-                contract_auth.push(SorobanAuthorizationEntry {
-                    credentials: SorobanCredentials::Address(SorobanAddressCredentials {
-                        address: sc_address.clone(),
-                        nonce,
-                        signature: ScVal::Vec(Some(
-                            self.host
-                                .vecobject_to_scval_vec(signature_args.into())
-                                .unwrap()
-                                .into(),
-                        )),
-                        signature_expiration_ledger: 1000,
-                    }),
-                    root_invocation,
-                });
+                let payload = RecordedAuthPayload {
+                    address: Some(sc_address.clone()),
+                    nonce: Some(nonce),
+                    invocation: self.convert_sign_node(sign_root),
+                };
+                contract_auth.push(
+                    self.host
+                        .sign_recorded_auth_payload(&payload, &[&self.keys[address_id]], 1000)
+                        .unwrap(),
+                );
             }
             self.last_nonces.push(curr_nonces);
         }
@@ -291,24 +270,8 @@ impl AuthTest {
     }
 
     fn read_nonce_live_until(&self, address: &Address, nonce: i64) -> Option<u32> {
-        let nonce_key_scval = ScVal::LedgerKeyNonce(ScNonceKey { nonce });
-        let nonce_key = self
-            .host
-            .storage_key_for_address(
-                address.to_sc_address().unwrap(),
-                nonce_key_scval,
-                ContractDataDurability::Temporary,
-            )
-            .unwrap();
         self.host
-            .with_mut_storage(|storage| {
-                if !storage.has(&nonce_key, self.host.budget_ref())? {
-                    return Ok(None);
-                }
-                let (_, live_until_ledger) =
-                    storage.get_with_live_until_ledger(&nonce_key, self.host.budget_ref())?;
-                Ok(live_until_ledger)
-            })
+            .get_nonce_live_until_ledger(address.clone().into(), nonce)
             .unwrap()
     }
 
@@ -475,6 +438,41 @@ impl AuthTest {
     }
 }
 
+#[test]
+fn test_recorded_payload_to_authorization_entry() {
+    let test = AuthTest::setup(1, 1);
+    let setup = SetupNode::new(&test.contracts[0], vec![true], vec![]);
+    let payloads = test.tree_run_recording(&setup, true);
+    assert_eq!(payloads.len(), 1);
+    let entry = payloads[0].to_authorization_entry(1000).unwrap();
+    assert_eq!(
+        entry,
+        SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+                address: test.key_to_sc_address(&test.keys[0]),
+                nonce: payloads[0].nonce.unwrap(),
+                signature_expiration_ledger: 1000,
+                signature: ScVal::Void,
+            }),
+            root_invocation: test.convert_sign_node(&SignNode::tree_fn(&test.contracts[0], vec![])),
+        }
+    );
+
+    // The transaction source account doesn't need address credentials.
+    let source_account = RecordedAuthPayload {
+        address: None,
+        nonce: None,
+        invocation: payloads[0].invocation.clone(),
+    };
+    assert_eq!(
+        source_account
+            .to_authorization_entry(1000)
+            .unwrap()
+            .credentials,
+        SorobanCredentials::SourceAccount
+    );
+}
+
 #[test]
 fn test_single_authorized_call() {
     let mut test = AuthTest::setup(1, 2);
@@ -3210,3 +3208,552 @@ fn test_rollback_with_conditional_custom_account_auth() {
     // Third call still can't succeed and won't consume nonce.
     assert_eq!(test.read_nonce_live_until(&account, 666), None);
 }
+
+#[test]
+fn test_nonce_reuse_and_reset() {
+    let test = AuthTest::setup(0, 2);
+    let account_obj = test
+        .host
+        .register_test_contract_wasm(CONDITIONAL_ACCOUNT_TEST_CONTRACT);
+    let account = Address::try_from_val(&test.host, &account_obj).unwrap();
+    let auth_entry = SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: account.to_sc_address().unwrap(),
+            nonce: 111,
+            signature: ScVal::Void,
+            signature_expiration_ledger: 1000,
+        }),
+        root_invocation: SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                contract_address: test.contracts[1].to_sc_address().unwrap(),
+                function_name: "do_auth".try_into().unwrap(),
+                args: vec![
+                    ScVal::Address(account.to_sc_address().unwrap()),
+                    ScVal::U32(123),
+                ]
+                .try_into()
+                .unwrap(),
+            }),
+            sub_invocations: Default::default(),
+        },
+    };
+    let do_call = || {
+        // Let the `__check_auth` of the account pass.
+        test.host
+            .call(
+                account.clone().into(),
+                Symbol::try_from_small_str("allow").unwrap(),
+                test_vec![&test.host].into(),
+            )
+            .unwrap();
+        test.host
+            .set_authorization_entries(vec![auth_entry.clone()])
+            .unwrap();
+        test.host.call(
+            test.contracts[1].clone().into(),
+            Symbol::try_from_small_str("do_auth").unwrap(),
+            test_vec![&test.host, &account, 123_u32].into(),
+        )
+    };
+
+    assert!(do_call().is_ok());
+    assert_eq!(test.read_nonce_live_until(&account, 111), Some(1000));
+    // The nonce can't be reused.
+    assert!(HostError::result_matches_err(
+        do_call(),
+        (ScErrorType::Auth, ScErrorCode::ExistingValue)
+    ));
+    // Until it is reset.
+    test.host.reset_nonce(account.clone().into(), 111).unwrap();
+    assert_eq!(test.read_nonce_live_until(&account, 111), None);
+    assert!(do_call().is_ok());
+    assert_eq!(test.read_nonce_live_until(&account, 111), Some(1000));
+}
+
+#[test]
+fn test_signature_payload_binds_network_and_expiration() {
+    let test = AuthTest::setup(1, 1);
+    let setup = SetupNode::new(&test.contracts[0], vec![true], vec![]);
+    let root_invocation = test.convert_sign_node(&SignNode::tree_fn(&test.contracts[0], vec![]));
+    let ledger_network_id: [u8; 32] = test
+        .host
+        .with_ledger_info(|li: &LedgerInfo| Ok(li.network_id))
+        .unwrap();
+    let mut nonce = 0;
+    let mut call = |network_id: [u8; 32], signature_expiration_ledger: u32| {
+        nonce += 1;
+        let payload_preimage =
+            HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+                network_id: network_id.into(),
+                invocation: root_invocation.clone(),
+                nonce,
+                signature_expiration_ledger,
+            });
+        let payload = test.host.metered_hash_xdr(&payload_preimage).unwrap();
+        let signature_args = test_vec![
+            &test.host,
+            sign_payload_for_account(&test.host, &test.keys[0], &payload)
+        ];
+        test.host
+            .set_authorization_entries(vec![SorobanAuthorizationEntry {
+                credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+                    address: test.key_to_sc_address(&test.keys[0]),
+                    nonce,
+                    signature: ScVal::Vec(Some(
+                        test.host
+                            .vecobject_to_scval_vec(signature_args.into())
+                            .unwrap()
+                            .into(),
+                    )),
+                    signature_expiration_ledger,
+                }),
+                root_invocation: root_invocation.clone(),
+            }])
+            .unwrap();
+        test.host.call(
+            test.contracts[0].clone().into(),
+            Symbol::try_from_small_str("tree_fn").unwrap(),
+            test_vec![
+                &test.host,
+                test.get_addresses(),
+                test.convert_setup_tree(&setup)
+            ]
+            .into(),
+        )
+    };
+
+    // The ledger sequence is 100 and the max entry TTL is 10000, so the
+    // signature may be valid until ledger 10099 at most.
+    assert!(call(ledger_network_id, 100).is_ok());
+    assert!(call(ledger_network_id, 10099).is_ok());
+    assert!(HostError::result_matches_err(
+        call(ledger_network_id, 99),
+        (ScErrorType::Auth, ScErrorCode::InvalidInput)
+    ));
+    assert!(HostError::result_matches_err(
+        call(ledger_network_id, 10100),
+        (ScErrorType::Auth, ScErrorCode::InvalidInput)
+    ));
+    // A signature for another network doesn't verify.
+    let mut other_network_id = ledger_network_id;
+    other_network_id[0] ^= 1;
+    assert!(call(other_network_id, 1000).is_err());
+    assert!(call(ledger_network_id, 1000).is_ok());
+}
+
+#[test]
+fn test_mock_auths() {
+    let test = AuthTest::setup(2, 2);
+    let setup = SetupNode::new(&test.contracts[0], vec![true, true], vec![]);
+    let call = || {
+        test.host.call(
+            test.contracts[0].clone().into(),
+            Symbol::try_from_small_str("tree_fn").unwrap(),
+            test_vec![
+                &test.host,
+                test.get_addresses(),
+                test.convert_setup_tree(&setup)
+            ]
+            .into(),
+        )
+    };
+    let expected_auths: Vec<_> = test
+        .keys
+        .iter()
+        .map(|key| {
+            (
+                test.key_to_sc_address(key),
+                test.convert_sign_node(&SignNode::tree_fn(&test.contracts[0], vec![])),
+            )
+        })
+        .collect();
+
+    test.host.mock_all_auths().unwrap();
+    assert!(call().is_ok());
+    assert_eq!(
+        test.host.get_authenticated_authorizations().unwrap(),
+        expected_auths
+    );
+
+    test.host.mock_auths(expected_auths.clone()).unwrap();
+    assert!(call().is_ok());
+    assert_eq!(
+        test.host.get_authenticated_authorizations().unwrap(),
+        expected_auths
+    );
+
+    // Every required authorization has to be mocked.
+    test.host
+        .mock_auths(vec![expected_auths[0].clone()])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+    // And it has to match the invocation.
+    test.host
+        .mock_auths(vec![
+            expected_auths[0].clone(),
+            (
+                expected_auths[1].0.clone(),
+                test.convert_sign_node(&SignNode::tree_fn(&test.contracts[1], vec![])),
+            ),
+        ])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+}
+
+#[test]
+fn test_unauthorized_call_diagnostics() {
+    let test = AuthTest::setup(1, 2);
+    let setup = SetupNode::new(
+        &test.contracts[0],
+        vec![false],
+        vec![SetupNode::new(&test.contracts[1], vec![true], vec![])],
+    );
+    let address = test.key_to_sc_address(&test.keys[0]);
+    // Runs the setup with the `sign_root` authorization mocked and returns
+    // the reported call path and the mismatch of the closest authorized
+    // invocation.
+    let run = |sign_root: SignNode| -> (ScVal, ScVal) {
+        test.host
+            .mock_auths(vec![(address.clone(), test.convert_sign_node(&sign_root))])
+            .unwrap();
+        assert!(HostError::result_matches_err(
+            test.host.call(
+                test.contracts[0].clone().into(),
+                Symbol::try_from_small_str("tree_fn").unwrap(),
+                test_vec![
+                    &test.host,
+                    test.get_addresses(),
+                    test.convert_setup_tree(&setup)
+                ]
+                .into(),
+            ),
+            (ScErrorType::Auth, ScErrorCode::InvalidAction)
+        ));
+        let events = test
+            .host
+            .get_diagnostic_events(DiagnosticSeverity::Error)
+            .unwrap();
+        let find = |msg: &str| {
+            events
+                .iter()
+                .rev()
+                .find(
+                    |e| matches!(e.args.first(), Some(ScVal::String(s)) if s.0.to_string() == msg),
+                )
+                .unwrap()
+                .args
+                .clone()
+        };
+        let unmatched = find("no authorization entry matches the invocation at call path");
+        assert_eq!(unmatched[1], ScVal::Address(address.clone()));
+        let closest = find("closest authorized invocation of the address differs in");
+        (unmatched[3].clone(), closest[1].clone())
+    };
+    let symbol = |s: &str| ScVal::Symbol(ScSymbol(s.try_into().unwrap()));
+
+    let (path, mismatch) = run(SignNode::new(
+        &test.contracts[1],
+        Symbol::try_from_small_str("tree_fn").unwrap(),
+        vec![ScVal::U32(1)].try_into().unwrap(),
+        vec![],
+    ));
+    assert_eq!(mismatch, symbol("args"));
+    let ScVal::Vec(Some(path)) = path else {
+        panic!("unexpected call path {:?}", path);
+    };
+    assert_eq!(
+        path.as_slice(),
+        &[
+            ScVal::Vec(Some(
+                vec![
+                    ScVal::Address(test.contracts[0].to_sc_address().unwrap()),
+                    symbol("tree_fn")
+                ]
+                .try_into()
+                .unwrap()
+            )),
+            ScVal::Vec(Some(
+                vec![
+                    ScVal::Address(test.contracts[1].to_sc_address().unwrap()),
+                    symbol("tree_fn")
+                ]
+                .try_into()
+                .unwrap()
+            )),
+        ]
+    );
+
+    let (_, mismatch) = run(SignNode::tree_fn_stored(&test.contracts[1], vec![]));
+    assert_eq!(mismatch, symbol("function"));
+    // The invocation of the second contract is authorized, but only under
+    // the first contract that doesn't require the authorization.
+    let (_, mismatch) = run(SignNode::tree_fn(
+        &test.contracts[0],
+        vec![SignNode::tree_fn(&test.contracts[1], vec![])],
+    ));
+    assert_eq!(mismatch, symbol("call_path"));
+}
+
+#[test]
+fn test_direct_invoker_contract_auth() {
+    let mut test = AuthTest::setup(0, 4);
+    let mut addresses = HostVec::new(&test.host).unwrap();
+    addresses.push(&test.contracts[0]).unwrap();
+
+    // Every contract directly called by the first contract is authorized on
+    // its behalf without any authorization entries.
+    let setup = SetupNode::new(
+        &test.contracts[0],
+        vec![false],
+        vec![
+            SetupNode::new(
+                &test.contracts[1],
+                vec![true],
+                vec![SetupNode::new(&test.contracts[2], vec![false], vec![])],
+            ),
+            SetupNode::new(&test.contracts[3], vec![true], vec![]),
+        ],
+    );
+    test.tree_test_enforcing_with_addresses(addresses.clone(), &setup, vec![], true);
+
+    // But the authorization doesn't propagate further down the call tree.
+    for depth in 2..=3 {
+        let mut setup = SetupNode::new(&test.contracts[depth], vec![true], vec![]);
+        for contract_id in (0..depth).rev() {
+            setup = SetupNode::new(&test.contracts[contract_id], vec![false], vec![setup]);
+        }
+        test.tree_test_enforcing_with_addresses(addresses.clone(), &setup, vec![], false);
+    }
+}
+
+#[test]
+fn test_auth_without_signature_verification() {
+    let test = AuthTest::setup(1, 1);
+    let setup = SetupNode::new(&test.contracts[0], vec![true], vec![]);
+    let address: Address = test
+        .key_to_address(&test.keys[0])
+        .try_into_val(&test.host)
+        .unwrap();
+    // The signature doesn't correspond to the actual payload.
+    let signature_args = test_vec![
+        &test.host,
+        sign_payload_for_account(&test.host, &test.keys[0], &[0; 32])
+    ];
+    let auth_entry = |nonce: i64| SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: test.key_to_sc_address(&test.keys[0]),
+            nonce,
+            signature: ScVal::Vec(Some(
+                test.host
+                    .vecobject_to_scval_vec(signature_args.clone().into())
+                    .unwrap()
+                    .into(),
+            )),
+            signature_expiration_ledger: 1000,
+        }),
+        root_invocation: test.convert_sign_node(&SignNode::tree_fn(&test.contracts[0], vec![])),
+    };
+    let call = || {
+        test.host.call(
+            test.contracts[0].clone().into(),
+            Symbol::try_from_small_str("tree_fn").unwrap(),
+            test_vec![
+                &test.host,
+                test.get_addresses(),
+                test.convert_setup_tree(&setup)
+            ]
+            .into(),
+        )
+    };
+    let verifications = || {
+        test.host
+            .as_budget()
+            .get_tracker(ContractCostType::VerifyEd25519Sig)
+            .unwrap()
+            .iterations
+    };
+
+    test.host
+        .set_authorization_entries(vec![auth_entry(1)])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+
+    let verifications_before = verifications();
+    test.host
+        .set_authorization_entries_without_signature_verification(vec![auth_entry(1)])
+        .unwrap();
+    assert!(call().is_ok());
+    // The verification is still charged for.
+    assert_eq!(verifications(), verifications_before + 1);
+    // The nonces are still consumed and verified.
+    assert_eq!(test.read_nonce_live_until(&address, 1), Some(1000));
+    test.host
+        .set_authorization_entries_without_signature_verification(vec![auth_entry(1)])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::ExistingValue)
+    ));
+    // As well as the invocations.
+    test.host
+        .set_authorization_entries_without_signature_verification(vec![SorobanAuthorizationEntry {
+            root_invocation: test
+                .convert_sign_node(&SignNode::tree_fn_stored(&test.contracts[0], vec![])),
+            ..auth_entry(2)
+        }])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+}
+
+#[test]
+fn test_custom_account_auth_without_signature_verification() {
+    let test = AuthTest::setup(1, 1);
+    let account_obj = test
+        .host
+        .register_test_contract_wasm(SIMPLE_ACCOUNT_CONTRACT);
+    let account = Address::try_from_val(&test.host, &account_obj).unwrap();
+    let public_key = test
+        .host
+        .bytes_new_from_slice(&test.keys[0].verifying_key().to_bytes())
+        .unwrap();
+    test.host
+        .call(
+            account_obj,
+            Symbol::try_from_small_str("init").unwrap(),
+            test_vec![&test.host, public_key].into(),
+        )
+        .unwrap();
+
+    // The signature is a placeholder that `__check_auth` fails to verify.
+    let auth_entry = |nonce: i64| SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: account.to_sc_address().unwrap(),
+            nonce,
+            signature: ScVal::Bytes(ScBytes(vec![0; 64].try_into().unwrap())),
+            signature_expiration_ledger: 1000,
+        }),
+        root_invocation: SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                contract_address: test.contracts[0].to_sc_address().unwrap(),
+                function_name: "do_auth".try_into().unwrap(),
+                args: vec![
+                    ScVal::Address(account.to_sc_address().unwrap()),
+                    ScVal::U32(123),
+                ]
+                .try_into()
+                .unwrap(),
+            }),
+            sub_invocations: Default::default(),
+        },
+    };
+    let call = || {
+        test.host.call(
+            test.contracts[0].clone().into(),
+            Symbol::try_from_small_str("do_auth").unwrap(),
+            test_vec![&test.host, &account, 123_u32].into(),
+        )
+    };
+    let verifications = || {
+        test.host
+            .as_budget()
+            .get_tracker(ContractCostType::VerifyEd25519Sig)
+            .unwrap()
+            .iterations
+    };
+
+    test.host
+        .set_authorization_entries(vec![auth_entry(1)])
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        call(),
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+
+    let verifications_before = verifications();
+    test.host
+        .set_authorization_entries_without_signature_verification(vec![auth_entry(1)])
+        .unwrap();
+    assert!(call().is_ok());
+    // `__check_auth` has still been invoked and has verified the signature.
+    assert_eq!(verifications(), verifications_before + 1);
+    assert_eq!(test.read_nonce_live_until(&account, 1), Some(1000));
+}
+
+#[test]
+fn test_sign_recorded_auth_payload() {
+    let test = AuthTest::setup(2, 1);
+    let account_id = signing_key_to_account_id(&test.keys[0]);
+    // Make the second key a signer of the first account and require both
+    // signatures.
+    create_account(
+        &test.host,
+        &account_id,
+        vec![(&test.keys[1], 1)],
+        100_000_000,
+        1,
+        [1, 0, 2, 0],
+        None,
+        None,
+        0,
+    );
+    let mut setup_addresses = HostVec::new(&test.host).unwrap();
+    setup_addresses
+        .push(&test.key_to_address(&test.keys[0]))
+        .unwrap();
+    let setup = SetupNode::new(&test.contracts[0], vec![true], vec![]);
+    let args = test_vec![&test.host, setup_addresses, test.convert_setup_tree(&setup)];
+    let payloads = test.run_recording(
+        &test.contracts[0],
+        Symbol::try_from_small_str("tree_fn").unwrap(),
+        args.clone(),
+        true,
+    );
+    assert_eq!(payloads.len(), 1);
+    // Recording has already consumed the nonce.
+    test.host
+        .reset_nonce(
+            test.key_to_address(&test.keys[0]),
+            payloads[0].nonce.unwrap(),
+        )
+        .unwrap();
+    let call = |signers: &[&SigningKey]| {
+        let entry = test
+            .host
+            .sign_recorded_auth_payload(&payloads[0], signers, 1000)
+            .unwrap();
+        test.host.set_authorization_entries(vec![entry]).unwrap();
+        test.host.call(
+            test.contracts[0].clone().into(),
+            Symbol::try_from_small_str("tree_fn").unwrap(),
+            args.clone().into(),
+        )
+    };
+    // A single signature doesn't reach the threshold.
+    assert!(call(&[&test.keys[0]]).is_err());
+    // The signatures are ordered by the helper.
+    assert!(call(&[&test.keys[1], &test.keys[0]]).is_ok());
+
+    // Custom accounts can't be signed with keys.
+    let custom_account_payload = RecordedAuthPayload {
+        address: Some(test.contracts[0].to_sc_address().unwrap()),
+        nonce: payloads[0].nonce,
+        invocation: payloads[0].invocation.clone(),
+    };
+    assert!(HostError::result_matches_err(
+        test.host
+            .sign_recorded_auth_payload(&custom_account_payload, &[&test.keys[0]], 1000),
+        (ScErrorType::Auth, ScErrorCode::InvalidInput)
+    ));
+}
@@ -81,3 +81,31 @@ fn f32_does_not_work() -> Result<(), HostError> {
     assert!(crate::vm::Vm::new(&host, hash, soroban_test_wasms::ADD_F32).is_err());
     Ok(())
 }
+
+#[cfg(feature = "testutils")]
+#[test]
+fn arbitrary_valid_scvals_convert_to_host_values() -> Result<(), HostError> {
+    use crate::{xdr::ScVec, Symbol, ValidInvokeContractArgs, ValidScMap, ValidScVal};
+    use arbitrary::{Arbitrary, Unstructured};
+    use rand::{Rng, RngCore, SeedableRng};
+
+    let host = Host::test_host();
+    let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+    let mut data = vec![0u8; 4096];
+    for _ in 0..200 {
+        let len = rng.gen_range(0..data.len());
+        rng.fill_bytes(&mut data[..len]);
+        let mut u = Unstructured::new(&data[..len]);
+        let ValidScVal(v) = ValidScVal::arbitrary(&mut u).unwrap();
+        let val = host.to_host_val(&v)?;
+        assert_eq!(host.from_host_val(val)?, v);
+
+        let ValidScMap(m) = ValidScMap::arbitrary(&mut u).unwrap();
+        host.to_host_val(&ScVal::Map(Some(m)))?;
+
+        let ValidInvokeContractArgs(args) = ValidInvokeContractArgs::arbitrary(&mut u).unwrap();
+        Symbol::try_from_val(&host, &args.function_name)?;
+        host.to_host_val(&ScVal::Vec(Some(ScVec(args.args))))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use soroban_env_common::xdr::{
+    Hash, InvokeContractArgs, ScAddress, ScErrorCode, ScErrorType, ScSymbol, ScVal,
+    SorobanAuthorizationEntry, SorobanAuthorizedFunction, SorobanAuthorizedInvocation,
+    SorobanCredentials,
+};
+
+use crate::{
+    auth::RecordedAuthPayload,
+    batch_verify::{
+        account_signature_checks, verify_auth_entries, verify_signatures, SignatureCheck,
+    },
+    builtin_contracts::testutils::{generate_signing_key, signing_key_to_account_id},
+    Error, Host, HostError, LedgerInfo,
+};
+
+const NETWORK_ID: [u8; 32] = [7; 32];
+
+fn signed_account_entry(host: &Host, nonce: i64) -> Result<SorobanAuthorizationEntry, HostError> {
+    let keys = [generate_signing_key(host), generate_signing_key(host)];
+    let payload = RecordedAuthPayload {
+        address: Some(ScAddress::Account(signing_key_to_account_id(&keys[0]))),
+        nonce: Some(nonce),
+        invocation: SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                contract_address: ScAddress::Contract(Hash([1; 32])),
+                function_name: ScSymbol("transfer".try_into().unwrap()),
+                args: vec![ScVal::U32(1)].try_into().unwrap(),
+            }),
+            sub_invocations: Default::default(),
+        },
+    };
+    host.sign_recorded_auth_payload(&payload, &[&keys[0], &keys[1]], 1000)
+}
+
+fn invalid_signature() -> Error {
+    Error::from_type_and_code(ScErrorType::Crypto, ScErrorCode::InvalidInput)
+}
+
+#[test]
+fn auth_entry_signatures_are_verified() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.set_ledger_info(LedgerInfo {
+        network_id: NETWORK_ID,
+        ..Default::default()
+    })?;
+    let valid = signed_account_entry(&host, 1)?;
+    assert_eq!(account_signature_checks(&NETWORK_ID, &valid)?.len(), 2);
+
+    // Changing the nonce invalidates the signatures.
+    let mut replayed = valid.clone();
+    if let SorobanCredentials::Address(credentials) = &mut replayed.credentials {
+        credentials.nonce += 1;
+    }
+    let mut malformed = valid.clone();
+    if let SorobanCredentials::Address(credentials) = &mut malformed.credentials {
+        credentials.signature = ScVal::U32(0);
+    }
+    let source_account = SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::SourceAccount,
+        root_invocation: valid.root_invocation.clone(),
+    };
+
+    let results = verify_auth_entries(
+        &NETWORK_ID,
+        &[valid.clone(), replayed, malformed, source_account],
+    );
+    assert_eq!(
+        results,
+        vec![
+            Ok(()),
+            Err(invalid_signature()),
+            Err(Error::from_type_and_code(
+                ScErrorType::Auth,
+                ScErrorCode::InvalidInput
+            )),
+            Ok(()),
+        ]
+    );
+    // The signatures are bound to the network.
+    assert_eq!(
+        verify_auth_entries(&[8; 32], &[valid]),
+        vec![Err(invalid_signature())]
+    );
+    Ok(())
+}
+
+#[test]
+fn secp256k1_signatures_are_verified() {
+    let key = k256::ecdsa::SigningKey::from_bytes(&[3; 32].into()).unwrap();
+    let msg_digest = [5; 32];
+    let signature: k256::ecdsa::Signature = key.sign_prehash(&msg_digest).unwrap();
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let check = |msg_digest: [u8; 32]| SignatureCheck::EcdsaSecp256k1 {
+        public_key: key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec(),
+        msg_digest,
+        signature: signature.to_bytes().as_slice().try_into().unwrap(),
+    };
+    assert_eq!(
+        verify_signatures(&[check(msg_digest), check([6; 32])]),
+        vec![Ok(()), Err(invalid_signature())]
+    );
+}
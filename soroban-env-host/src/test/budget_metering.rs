@@ -1,13 +1,14 @@
 use crate::{
     budget::{AsBudget, Budget},
+    events::{DiagnosticEvent, DiagnosticSeverity},
     host::metered_clone::{MeteredClone, MeteredIterator},
     host::metered_xdr::metered_write_xdr,
     xdr::{ContractCostType, ScMap, ScMapEntry, ScVal},
-    Env, Host, HostError, Symbol, Val,
+    Env, EnvBase, Host, HostError, Symbol, Val,
 };
 use expect_test::{self, expect};
 use soroban_env_common::xdr::{ScErrorCode, ScErrorType};
-use soroban_test_wasms::VEC;
+use soroban_test_wasms::{ADD_I32, INVOKE_CONTRACT, VEC};
 
 #[test]
 fn xdr_object_conversion() -> Result<(), HostError> {
@@ -433,3 +434,247 @@ fn total_amount_charged_from_random_inputs() -> Result<(), HostError> {
 
     Ok(())
 }
+
+#[test]
+fn budget_exceeded_post_mortem() -> Result<(), HostError> {
+    let run = |cpu_limit: u64| -> Result<Host, HostError> {
+        let host = Host::test_host_with_recording_footprint();
+        host.enable_debug()?;
+        let id_obj = host.register_test_contract_wasm(VEC);
+        host.as_budget().reset_limits(cpu_limit, 100_000_000)?;
+        let sym = Symbol::try_from_small_str("vec_err").unwrap();
+        let args = host.test_vec_obj::<u32>(&[1])?;
+        // `vec_err` fails on its own, with an `IndexBounds` error.
+        let _ = host.call(id_obj, sym, args);
+        Ok(host)
+    };
+    let post_mortems = |host: &Host| -> Result<Vec<DiagnosticEvent>, HostError> {
+        Ok(host
+            .get_diagnostic_events(DiagnosticSeverity::Error)?
+            .into_iter()
+            .filter(|e| matches!(e.args.first(), Some(ScVal::String(s)) if s.0.to_string() == "budget exceeded"))
+            .collect())
+    };
+    let host = run(100_000_000)?;
+    assert!(post_mortems(&host)?.is_empty());
+
+    // Run out of budget near the end of the call, inside the contract.
+    let cpu_required = host.as_budget().get_cpu_insns_consumed()?;
+    let host = run(cpu_required - 1_000)?;
+    let post_mortems = post_mortems(&host)?;
+    // Reported once, as the error propagates out of the host function.
+    assert_eq!(post_mortems.len(), 1);
+    let args = &post_mortems[0].args;
+    let ScVal::Vec(Some(costs)) = &args[1] else {
+        panic!("expected the top cost types")
+    };
+    assert_eq!(costs.len(), 5);
+    // The ranking depends on the cost model, so only check that the
+    // VM instantiation is among the reported costs.
+    assert!(costs.iter().any(
+        |cost| matches!(cost, ScVal::String(s) if s.0.to_string().starts_with("VmInstantiation"))
+    ));
+    let ScVal::Vec(Some(frames)) = &args[2] else {
+        panic!("expected the frames")
+    };
+    assert_eq!(frames.len(), 1);
+    let ScVal::String(frame) = &frames[0] else {
+        panic!("expected a frame")
+    };
+    assert!(frame.0.to_string().ends_with("fn vec_err"));
+    let ScVal::Vec(Some(calls)) = &args[3] else {
+        panic!("expected the recent host calls")
+    };
+    assert!(!calls.is_empty());
+    Ok(())
+}
+
+#[test]
+fn budget_usage_report() -> Result<(), HostError> {
+    let host = Host::test_host();
+    host.print_budget_usage_on_drop("budget_usage_report")?;
+    let v = host.vec_new()?;
+    host.vec_push_back(v, 1u32.into())?;
+
+    let report = host.budget_usage_report()?;
+    assert_eq!(
+        report.cpu_insns,
+        host.budget_ref().get_cpu_insns_consumed()?
+    );
+    assert_eq!(
+        report.mem_bytes,
+        host.budget_ref().get_mem_bytes_consumed()?
+    );
+    assert!(report
+        .cost_types
+        .iter()
+        .all(|(_, tracker)| tracker.iterations > 0));
+    let (_, visit_object) = report
+        .cost_types
+        .iter()
+        .find(|(ct, _)| *ct == ContractCostType::VisitObject)
+        .unwrap();
+    assert_eq!(
+        visit_object,
+        &host
+            .budget_ref()
+            .get_tracker(ContractCostType::VisitObject)?
+    );
+
+    let csv = report.to_string();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("cost_type,iterations,cpu_insns,mem_bytes")
+    );
+    assert_eq!(
+        lines.last(),
+        Some(format!("total,,{},{}", report.cpu_insns, report.mem_bytes).as_str())
+    );
+    Ok(())
+}
+
+#[test]
+fn wasm_fixture_host_calls_and_memory() -> Result<(), HostError> {
+    use crate::testutils::wasm::WasmFixture;
+    let host = Host::test_host_with_recording_footprint();
+    // vec_new and vec_len, called 10 times each.
+    let wasm = WasmFixture::new(2)
+        .call_host_fn("v", "_", 0)
+        .call_host_fn("v", "3", 1)
+        .loop_iterations(10)
+        .memory_pages(1, 2)
+        .build();
+    let id_obj = host.register_test_contract_wasm(&wasm);
+    let dispatched = |host: &Host| {
+        host.with_budget(|budget| {
+            Ok(budget
+                .get_tracker(ContractCostType::DispatchHostFunction)?
+                .iterations)
+        })
+    };
+    let before = dispatched(&host)?;
+    let mem_before = host.budget_cloned().get_wasm_mem_alloc()?;
+    let res = host.call(
+        id_obj,
+        Symbol::try_from_small_str("test")?,
+        host.vec_new_from_slice(&[host.vec_new()?.to_val(), 2u32.into()])?,
+    )?;
+    assert_eq!(
+        res.get_payload(),
+        Symbol::try_from_small_str("pass")?.to_val().get_payload()
+    );
+    assert_eq!(dispatched(&host)? - before, 20);
+    // The initial page plus the two grown ones.
+    assert_eq!(
+        host.budget_cloned().get_wasm_mem_alloc()? - mem_before,
+        3 * 65536
+    );
+    Ok(())
+}
+
+#[test]
+fn invocation_budgets_share_host_budget() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let invoke_id = host.register_test_contract_wasm(INVOKE_CONTRACT);
+    let add_id = host.register_test_contract_wasm(ADD_I32);
+    let invocations_before = host.invocation_budgets()?.len();
+    let cpu_before = host.as_budget().get_cpu_insns_consumed()?;
+
+    // A cross-contract call only counts as a single top-level invocation.
+    let args = host.test_vec_obj::<i32>(&[5, 6])?;
+    let args = host.vec_push_back(args, add_id.to_val())?;
+    host.call(invoke_id, Symbol::try_from_small_str("add_with")?, args)?;
+    let args = host.test_vec_obj::<i32>(&[5, 6])?;
+    host.call(add_id, Symbol::try_from_small_str("add")?, args)?;
+
+    let budgets = host.invocation_budgets()?;
+    assert_eq!(budgets.len(), invocations_before + 2);
+    let (cross_contract, direct) = (budgets[budgets.len() - 2], budgets[budgets.len() - 1]);
+    assert!(direct.cpu_insns > 0);
+    assert!(cross_contract.cpu_insns > direct.cpu_insns);
+    // Both invocations consume the same budget, which also covers the work
+    // done between them.
+    assert!(
+        cross_contract.cpu_insns + direct.cpu_insns
+            <= host.as_budget().get_cpu_insns_consumed()? - cpu_before
+    );
+    assert_eq!(host.invocation_report()?.invocation_budget, Some(direct));
+    Ok(())
+}
+
+#[test]
+fn budget_resets_are_visible_through_clones() -> Result<(), HostError> {
+    let budget = Budget::default();
+    let clone = budget.clone();
+    budget.reset_limits(1_000_000, 1_000_000)?;
+    budget.charge(ContractCostType::MemCpy, Some(100))?;
+    assert!(clone.get_cpu_insns_consumed()? > 0);
+    assert_eq!(
+        clone.get_tracker(ContractCostType::MemCpy)?.inputs,
+        Some(100)
+    );
+
+    clone.reset_default()?;
+    assert_eq!(budget.get_cpu_insns_consumed()?, 0);
+    assert_eq!(budget.get_tracker(ContractCostType::MemCpy)?.iterations, 0);
+    assert_eq!(
+        budget.get_cpu_insns_remaining()?,
+        Budget::default().get_cpu_insns_remaining()?
+    );
+    Ok(())
+}
+
+#[test]
+fn budget_reset_to_swaps_shadow_budget_and_tracker() -> Result<(), HostError> {
+    // Whether a shadow-mode section gets to run, which it only does while the
+    // shadow budget isn't exhausted.
+    let shadow_runs = |budget: &Budget| {
+        let mut ran = false;
+        budget.with_shadow_mode(|| {
+            ran = true;
+            Ok(())
+        });
+        ran
+    };
+    let exhausted = Budget::default();
+    exhausted.reset_limits(1_000_000, 1_000_000)?;
+    exhausted.set_shadow_limits(1, 1)?;
+    exhausted.with_shadow_mode(|| exhausted.charge(ContractCostType::MemCpy, Some(100)));
+    exhausted.charge(ContractCostType::MemCpy, Some(100))?;
+    // Shadow charges only count towards the shadow budget, not the tracker.
+    assert!(!shadow_runs(&exhausted));
+    assert!(!exhausted.is_in_shadow_mode()?);
+    assert_eq!(
+        exhausted.get_tracker(ContractCostType::MemCpy)?.iterations,
+        1
+    );
+
+    // Resetting a budget to another state carries over the shadow budget and
+    // the tracker, as seen through every clone of the budget.
+    let budget = Budget::default();
+    let clone = budget.clone();
+    assert!(shadow_runs(&budget));
+    budget.0.reset_to((*exhausted.0).clone());
+    assert!(!shadow_runs(&clone));
+    assert!(!clone.is_in_shadow_mode()?);
+    assert_eq!(
+        clone.get_tracker(ContractCostType::MemCpy)?,
+        exhausted.get_tracker(ContractCostType::MemCpy)?
+    );
+    assert_eq!(
+        clone.get_cpu_insns_consumed()?,
+        exhausted.get_cpu_insns_consumed()?
+    );
+
+    // Resetting it to the default restores both, including which cost types
+    // track their inputs, so that charging them keeps working.
+    clone.reset_default()?;
+    assert!(shadow_runs(&budget));
+    let fresh = Budget::default();
+    for ty in ContractCostType::variants() {
+        assert_eq!(budget.get_tracker(ty)?, fresh.get_tracker(ty)?);
+    }
+    budget.charge(ContractCostType::MemCpy, Some(100))?;
+    Ok(())
+}
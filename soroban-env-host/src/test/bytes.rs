@@ -524,3 +524,28 @@ fn instantiate_oversized_bytes_from_linear_memory() -> Result<(), HostError> {
 
     Ok(())
 }
+
+#[test]
+fn test_xdr_decoding_is_charged_before_parsing() -> Result<(), HostError> {
+    let host = Host::test_host();
+    // A `Bytes` value declaring ~4GiB of payload followed by only a few bytes
+    // of it: the declared length is checked against the input length before
+    // anything is allocated for the payload.
+    let mut xdr = (crate::xdr::ScValType::Bytes as i32).to_be_bytes().to_vec();
+    xdr.extend_from_slice(&0xffff_fff0_u32.to_be_bytes());
+    xdr.extend_from_slice(&[1, 2, 3, 4]);
+    let bo = host.add_host_object(ScBytes(xdr.try_into()?))?;
+    let mem_before = host.budget_ref().get_mem_bytes_consumed()?;
+    assert!(host.deserialize_from_bytes(bo).is_err());
+    assert!(host.budget_ref().get_mem_bytes_consumed()? - mem_before < 1024);
+
+    // The cost of decoding the whole input is charged up front, so an input
+    // that is too large for the budget is rejected before being parsed.
+    let big = ScVal::Bytes(vec![0; 100_000].try_into()?).to_xdr(DEFAULT_XDR_RW_LIMITS)?;
+    let bo = host.add_host_object(ScBytes(big.try_into()?))?;
+    host.budget_ref().reset_limits(1_000_000, 100_000)?;
+    let err = host.deserialize_from_bytes(bo).unwrap_err();
+    assert!(err.error.is_type(ScErrorType::Budget));
+    assert!(err.error.is_code(ScErrorCode::ExceededLimit));
+    Ok(())
+}
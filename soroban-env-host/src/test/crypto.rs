@@ -255,3 +255,53 @@ fn recover_ecdsa_secp256k1_key_test() {
     )
         .err().unwrap()));
 }
+
+#[test]
+fn hash_val_test() -> Result<(), HostError> {
+    use crate::{
+        xdr::{ContractCostType, ScVal, WriteXdr},
+        Symbol, DEFAULT_XDR_RW_LIMITS,
+    };
+    use sha2::{Digest, Sha256};
+
+    let host = Host::test_host();
+    let inner = host.vec_new_from_slice(&[1u32.into(), Symbol::try_from_small_str("a")?.into()])?;
+    let map = host.map_new()?;
+    let map = host.map_put(map, Symbol::try_from_small_str("k")?.into(), inner.into())?;
+
+    let scval: ScVal = host.from_host_val(map.into())?;
+    let expected = Sha256::digest(scval.to_xdr(DEFAULT_XDR_RW_LIMITS)?);
+    assert_eq!(host.hash_val(map.into())?.0.as_slice(), expected.as_slice());
+
+    // An equal value under a different handle hashes the same.
+    let map2 = host.to_host_val(&scval)?;
+    assert_eq!(host.hash_val(map2)?, host.hash_val(map.into())?);
+
+    // Small values are hashed as well.
+    let expected = Sha256::digest(ScVal::U32(7).to_xdr(DEFAULT_XDR_RW_LIMITS)?);
+    assert_eq!(
+        host.hash_val(7u32.into())?.0.as_slice(),
+        expected.as_slice()
+    );
+
+    // Hashing the same object again is served from the cache.
+    let budget = host.budget_cloned();
+    let hashes = budget
+        .get_tracker(ContractCostType::ComputeSha256Hash)?
+        .iterations;
+    host.hash_val(map.into())?;
+    assert_eq!(
+        budget
+            .get_tracker(ContractCostType::ComputeSha256Hash)?
+            .iterations,
+        hashes
+    );
+
+    // The host function returns the hash as bytes.
+    let hash_obj = host.compute_hash_sha256_val(map.into())?;
+    assert_eq!(
+        host.hash_from_bytesobj_input("hash", hash_obj)?,
+        host.hash_val(map.into())?
+    );
+    Ok(())
+}
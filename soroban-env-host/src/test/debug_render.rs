@@ -0,0 +1,64 @@
+use crate::{DebugRenderLimits, Env, Host, HostError, TryIntoVal, Val};
+
+#[test]
+fn debug_render_val_shows_object_contents() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let inner = host.test_vec_obj::<u32>(&[1, 2])?;
+    let outer = host.vec_new()?;
+    let outer = host.vec_push_back(outer, inner.to_val())?;
+    let outer = host.vec_push_back(outer, u64::MAX.try_into_val(&host)?)?;
+    let rendered = host.debug_render_val(outer.to_val(), DebugRenderLimits::default());
+    assert_eq!(rendered, "[[U32(1), U32(2)], U64(18446744073709551615)]");
+    Ok(())
+}
+
+#[test]
+fn debug_render_val_respects_limits() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let limits = DebugRenderLimits {
+        max_depth: 2,
+        max_elements: 3,
+        max_len: 64,
+    };
+
+    let long = host.test_vec_obj::<u32>(&[1, 2, 3, 4, 5])?;
+    assert_eq!(
+        host.debug_render_val(long.to_val(), limits),
+        "[U32(1), U32(2), U32(3), ...]"
+    );
+
+    let mut deep: Val = long.to_val();
+    for _ in 0..10 {
+        deep = host.vec_push_back(host.vec_new()?, deep)?.to_val();
+    }
+    assert_eq!(host.debug_render_val(deep, limits), "[[[...]]]");
+
+    // Output past `max_len` is cut off and marked as truncated.
+    let wide = host.test_vec_obj::<u32>(&[1_000_000; 100])?;
+    let limits = DebugRenderLimits {
+        max_elements: 100,
+        ..limits
+    };
+    let rendered = host.debug_render_val(wide.to_val(), limits);
+    assert_eq!(rendered.len(), 64 + 3);
+    assert!(rendered.ends_with("..."));
+    Ok(())
+}
+
+#[test]
+fn debug_render_is_charged_to_shadow_budget() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let vec = host.test_vec_obj::<u32>(&[1, 2, 3])?;
+    let budget = host.budget_cloned();
+    let cpu = budget.get_cpu_insns_consumed()?;
+    let mem = budget.get_mem_bytes_consumed()?;
+
+    let rendered = host.debug_render(DebugRenderLimits::default());
+    assert!(rendered.starts_with("Host(objects: 1, frames: 0, events: 0, "));
+    assert!(rendered.ends_with("Storage(Enforcing, footprint: [], entries: 0))"));
+    host.debug_render_val(vec.to_val(), DebugRenderLimits::default());
+
+    assert_eq!(budget.get_cpu_insns_consumed()?, cpu);
+    assert_eq!(budget.get_mem_bytes_consumed()?, mem);
+    Ok(())
+}
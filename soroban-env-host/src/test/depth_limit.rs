@@ -2,11 +2,23 @@ use soroban_env_common::xdr::{Limits, ReadXdr, WriteXdr};
 
 use crate::{
     budget::AsBudget,
-    host::metered_clone::MeteredClone,
+    host::{conversion::CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION, metered_clone::MeteredClone},
     xdr::{ScErrorCode, ScErrorType, ScVal, ScVec},
-    Env, Host, HostError, DEFAULT_XDR_RW_LIMITS,
+    Env, Host, HostError, LedgerInfo, DEFAULT_XDR_RW_LIMITS,
 };
 
+// Returns a host that reports the limit errors hit by the conversions, see
+// `CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION`.
+fn host_reporting_conversion_limit_errors() -> Result<Host, HostError> {
+    let host = Host::default();
+    host.set_ledger_info(LedgerInfo {
+        protocol_version: CONVERSION_LIMIT_ERRORS_PROTOCOL_VERSION,
+        ..Default::default()
+    })?;
+    host.as_budget().reset_unlimited()?;
+    Ok(host)
+}
+
 #[test]
 fn deep_scval_to_host_val() -> Result<(), HostError> {
     let host = observe_host!(Host::default());
@@ -50,6 +62,73 @@ fn deep_host_val_to_scval() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn deep_conversion_reports_depth_limit_error() -> Result<(), HostError> {
+    let host = host_reporting_conversion_limit_errors()?;
+    let code = (ScErrorType::Context, ScErrorCode::ExceededLimit);
+
+    let mut v = ScVec::default();
+    for _ in 0..300 {
+        v = ScVec::try_from(vec![ScVal::from(v)])?;
+    }
+    let res = host.to_host_val(&ScVal::from(v));
+    assert!(HostError::result_matches_err(res, code));
+
+    let mut hv = host.test_vec_obj::<u32>(&[])?;
+    for _ in 0..1000 {
+        let vv = host.test_vec_obj::<u32>(&[])?;
+        hv = host.vec_push_back(vv, hv.to_val())?;
+    }
+    let res = host.from_host_obj(hv);
+    assert!(HostError::result_matches_err(res, code));
+    Ok(())
+}
+
+#[test]
+fn configured_depth_limit() -> Result<(), HostError> {
+    let host = host_reporting_conversion_limit_errors()?;
+    host.as_budget().set_depth_limit(10)?;
+
+    let nested = |depth: usize| -> Result<ScVal, HostError> {
+        let mut v = ScVec::default();
+        for _ in 0..depth {
+            v = ScVec::try_from(vec![ScVal::from(v)])?;
+        }
+        Ok(ScVal::from(v))
+    };
+    assert!(host.to_host_val(&nested(8)?).is_ok());
+    let res = host.to_host_val(&nested(10)?);
+    let code = (ScErrorType::Context, ScErrorCode::ExceededLimit);
+    assert!(HostError::result_matches_err(res, code));
+    Ok(())
+}
+
+#[test]
+fn conversion_element_limit() -> Result<(), HostError> {
+    let host = host_reporting_conversion_limit_errors()?;
+    host.as_budget().set_conversion_element_limit(10)?;
+    let code = (ScErrorType::Value, ScErrorCode::ExceededLimit);
+
+    // The elements are counted across all the nested containers.
+    let inner = ScVal::from(ScVec::try_from(vec![ScVal::U32(1); 4])?);
+    let fits = ScVal::from(ScVec::try_from(vec![inner.clone(); 2])?);
+    let exceeds = ScVal::from(ScVec::try_from(vec![inner; 3])?);
+    let hv = host.to_host_val(&fits)?;
+    assert_eq!(host.from_host_val(hv)?, fits);
+    assert!(HostError::result_matches_err(
+        host.to_host_val(&exceeds),
+        code
+    ));
+
+    // The same applies to the conversion back from the host values.
+    host.as_budget()
+        .set_conversion_element_limit(crate::DEFAULT_HOST_CONVERSION_ELEMENT_LIMIT)?;
+    let hv = host.to_host_val(&exceeds)?;
+    host.as_budget().set_conversion_element_limit(10)?;
+    assert!(HostError::result_matches_err(host.from_host_val(hv), code));
+    Ok(())
+}
+
 #[test]
 fn deep_host_obj_clone() -> Result<(), HostError> {
     let host = observe_host!(Host::default());
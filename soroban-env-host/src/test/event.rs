@@ -1,20 +1,23 @@
 use crate::{
     budget::AsBudget,
     events::{
-        Events, InternalContractEvent, InternalDiagnosticArg, InternalDiagnosticEvent,
-        InternalEvent,
+        ContractEventFilter, DiagnosticSeverity, EventLimits, Events, HostEvent,
+        InternalContractEvent, InternalDiagnosticArg, InternalDiagnosticEvent, InternalEvent,
+        FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION,
     },
     testutils::AsScVal,
     xdr::{
         ContractCostType, ContractEvent, ContractEventBody, ContractEventType, ContractEventV0,
-        ExtensionPoint, Hash, ScAddress, ScErrorCode, ScErrorType, ScMap, ScMapEntry, ScVal,
+        ExtensionPoint, Hash, ScAddress, ScErrorCode, ScErrorType, ScMap, ScMapEntry, ScString,
+        ScSymbol, ScVal,
     },
-    ContractFunctionSet, Env, Error, Host, HostError, Symbol, SymbolSmall, Val, VecObject,
+    AddressObject, ContractFunctionSet, Env, Error, Host, HostError, Symbol, SymbolSmall, Val,
+    VecObject,
 };
 use expect_test::expect;
 use more_asserts::assert_le;
-use soroban_env_common::EnvBase;
-use std::rc::Rc;
+use soroban_env_common::{EnvBase, TryIntoVal};
+use std::{cell::RefCell, rc::Rc};
 
 pub struct ContractWithSingleEvent;
 
@@ -99,13 +102,261 @@ fn test_event_rollback() -> Result<(), HostError> {
         Val::from_void().to_val().get_payload()
     );
     host.try_borrow_events_mut()?.rollback(1)?;
-    // run `UPDATE_EXPECT=true cargo test` to update this.
-    let expected = expect!["[HostEvent { event: ContractEvent { ext: V0, contract_id: Some(Hash(0000000000000000000000000000000000000000000000000000000000000000)), type_: Contract, body: V0(ContractEventV0 { topics: VecM([I32(0), I32(1)]), data: U32(0) }) }, failed_call: false }, HostEvent { event: ContractEvent { ext: V0, contract_id: Some(Hash(0000000000000000000000000000000000000000000000000000000000000000)), type_: System, body: V0(ContractEventV0 { topics: VecM([I32(0), I32(1)]), data: U32(0) }) }, failed_call: true }]"];
+    // run `UPDATE_EXPECT=true cargo test` to update this. From
+    // FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION the rolled back event
+    // is dropped outside of debug mode, see `test_event_rollback_as_diagnostics`.
+    let expected = if host.get_ledger_protocol_version()?
+        >= FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION
+    {
+        expect!["[HostEvent { event: ContractEvent { ext: V0, contract_id: Some(Hash(0000000000000000000000000000000000000000000000000000000000000000)), type_: Contract, body: V0(ContractEventV0 { topics: VecM([I32(0), I32(1)]), data: U32(0) }) }, failed_call: false, sequence: 0 }]"]
+    } else {
+        expect!["[HostEvent { event: ContractEvent { ext: V0, contract_id: Some(Hash(0000000000000000000000000000000000000000000000000000000000000000)), type_: Contract, body: V0(ContractEventV0 { topics: VecM([I32(0), I32(1)]), data: U32(0) }) }, failed_call: false, sequence: 0 }, HostEvent { event: ContractEvent { ext: V0, contract_id: Some(Hash(0000000000000000000000000000000000000000000000000000000000000000)), type_: System, body: V0(ContractEventV0 { topics: VecM([I32(0), I32(1)]), data: U32(0) }) }, failed_call: true, sequence: 1 }]"]
+    };
     let actual = format!("{:?}", host.try_borrow_events()?.externalize(&host)?.0);
     expected.assert_eq(&actual);
     Ok(())
 }
 
+#[test]
+fn test_event_rollback_as_diagnostics() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| {
+        li.protocol_version = FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION
+    })?;
+    let dummy_address = ScAddress::Contract(Hash([0; 32]));
+    let id = host.add_host_object(dummy_address)?;
+    let test_contract = Rc::new(ContractWithMultipleEvents {});
+    let sym = Symbol::try_from_small_str("add").unwrap();
+    let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    host.register_test_contract(id, test_contract)?;
+    host.call(id, sym, args)?;
+    host.try_borrow_events_mut()?.rollback(1)?;
+
+    // The rolled back event is dropped from the event stream.
+    let cpu_before = host.budget_ref().get_cpu_insns_consumed()?;
+    let events = host.get_events()?.0;
+    let cpu_charged = host.budget_ref().get_cpu_insns_consumed()? - cpu_before;
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| (e.event.type_, e.failed_call))
+            .collect::<Vec<_>>(),
+        vec![(ContractEventType::Contract, false)]
+    );
+
+    // In debug mode, it surfaces as a failed call, charged to the shadow
+    // budget.
+    host.enable_debug()?;
+    let cpu_before = host.budget_ref().get_cpu_insns_consumed()?;
+    let events = host.get_events()?.0;
+    assert_eq!(
+        host.budget_ref().get_cpu_insns_consumed()? - cpu_before,
+        cpu_charged
+    );
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| (e.event.type_, e.failed_call))
+            .collect::<Vec<_>>(),
+        vec![
+            (ContractEventType::Contract, false),
+            (ContractEventType::System, true),
+        ]
+    );
+    Ok(())
+}
+
+pub struct ContractWithFailingSubCall;
+
+impl ContractFunctionSet for ContractWithFailingSubCall {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        let topics = test_vec![host, *func].into();
+        host.record_contract_event(ContractEventType::Contract, topics, Val::VOID.into())
+            .unwrap();
+        let outer = Symbol::try_from_small_str("outer").unwrap();
+        if func.to_val().get_payload() == outer.to_val().get_payload() {
+            // Call the other contract with `inner`, which emits an event then
+            // fails.
+            let res = host
+                .try_call(
+                    AddressObject::try_from(args[0]).unwrap(),
+                    Symbol::try_from_small_str("inner").unwrap(),
+                    test_vec![host].into(),
+                )
+                .unwrap();
+            assert!(Error::try_from(res).is_ok());
+            Some(Val::VOID.into())
+        } else {
+            Some(Error::from_contract_error(1).to_val())
+        }
+    }
+}
+
+fn failing_sub_call_events(protocol_version: u32, debug: bool) -> Result<Events, HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| li.protocol_version = protocol_version)?;
+    if debug {
+        host.enable_debug()?;
+    }
+    let outer_id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+    let inner_id = host.add_host_object(ScAddress::Contract(Hash([1; 32])))?;
+    host.register_test_contract(outer_id, Rc::new(ContractWithFailingSubCall {}))?;
+    host.register_test_contract(inner_id, Rc::new(ContractWithFailingSubCall {}))?;
+    host.call(
+        outer_id,
+        Symbol::try_from_small_str("outer")?,
+        test_vec![&host, inner_id].into(),
+    )?;
+    host.get_events()
+}
+
+#[test]
+fn test_failed_sub_call_events_are_rolled_back() -> Result<(), HostError> {
+    let contract_topics = |events: &Events, failed_call: bool| {
+        events
+            .0
+            .iter()
+            .filter(|e| {
+                e.event.type_ == ContractEventType::Contract && e.failed_call == failed_call
+            })
+            .map(|e| match &e.event.body {
+                ContractEventBody::V0(ContractEventV0 { topics, .. }) => topics.to_vec(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let outer = vec![ScVal::Symbol(ScSymbol("outer".try_into().unwrap()))];
+    let inner = vec![ScVal::Symbol(ScSymbol("inner".try_into().unwrap()))];
+
+    // Before FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION, the event of
+    // the failed sub-call is part of the event stream, marked as failed.
+    let events = failing_sub_call_events(
+        FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION - 1,
+        false,
+    )?;
+    assert_eq!(contract_topics(&events, false), vec![outer.clone()]);
+    assert_eq!(contract_topics(&events, true), vec![inner.clone()]);
+
+    // From then on, it is dropped.
+    let events =
+        failing_sub_call_events(FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION, false)?;
+    assert_eq!(events.0.len(), 1);
+    assert_eq!(contract_topics(&events, false), vec![outer.clone()]);
+
+    // In debug mode, it is retained as a failed call, next to the diagnostics.
+    let events = failing_sub_call_events(FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION, true)?;
+    assert_eq!(contract_topics(&events, false), vec![outer]);
+    assert_eq!(contract_topics(&events, true), vec![inner]);
+    Ok(())
+}
+
+#[test]
+fn test_diagnostic_events_with_severity() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let dummy_address = ScAddress::Contract(Hash([0; 32]));
+    let id = host.add_host_object(dummy_address)?;
+    host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+    let sym = Symbol::try_from_small_str("add").unwrap();
+    let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    host.call(id, sym, args)?;
+    let _ = host.err(
+        ScErrorType::Value,
+        ScErrorCode::InvalidInput,
+        "some error",
+        &[],
+    );
+
+    // fn_call, log, fn_return and error events.
+    let all = host.get_diagnostic_events(DiagnosticSeverity::Debug)?;
+    assert_eq!(
+        all.iter().map(|e| e.severity).collect::<Vec<_>>(),
+        vec![
+            DiagnosticSeverity::Debug,
+            DiagnosticSeverity::Info,
+            DiagnosticSeverity::Debug,
+            DiagnosticSeverity::Error
+        ]
+    );
+
+    let info = host.get_diagnostic_events(DiagnosticSeverity::Info)?;
+    assert_eq!(info.len(), 2);
+    assert_eq!(info[0].contract_id, Some(Hash([0; 32])));
+    assert_eq!(
+        info[0].topics,
+        vec![ScVal::Symbol(ScSymbol("log".try_into().unwrap()))]
+    );
+    assert_eq!(
+        info[0].args,
+        vec![ScVal::String(ScString("debug event 0".try_into().unwrap()))]
+    );
+    assert!(!info[0].failed_call);
+    assert_eq!(info[1].severity, DiagnosticSeverity::Error);
+    assert_eq!(info[1].contract_id, None);
+
+    // Only the error event has at least the `Warn` severity.
+    assert_eq!(
+        host.get_diagnostic_events(DiagnosticSeverity::Warn)?.len(),
+        1
+    );
+    // Contract events are not included.
+    let events = host.get_events()?.0;
+    let diagnostic_count = events
+        .iter()
+        .filter(|e| e.event.type_ == ContractEventType::Diagnostic)
+        .count();
+    assert!(events.len() > diagnostic_count);
+    assert_eq!(all.len(), diagnostic_count);
+
+    // Running out of the shadow budget fails the conversion rather than
+    // returning a partial list.
+    host.as_budget().set_shadow_limits(0, 0)?;
+    assert!(HostError::result_matches_err(
+        host.get_diagnostic_events(DiagnosticSeverity::Debug),
+        (ScErrorType::Budget, ScErrorCode::ExceededLimit)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_get_contract_events_with_filter() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+    host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+    let sym = Symbol::try_from_small_str("add").unwrap();
+    let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    host.call(id, sym, args)?;
+
+    // Diagnostic events are never returned.
+    let events = host.get_contract_events(&ContractEventFilter::default())?;
+    assert_eq!(
+        events.iter().map(|e| e.type_).collect::<Vec<_>>(),
+        vec![ContractEventType::Contract, ContractEventType::System]
+    );
+    assert_eq!(events[0].contract_id, Some(Hash([0; 32])));
+
+    let events = host.get_contract_events(&ContractEventFilter {
+        contract_id: Some(Hash([0; 32])),
+        event_types: Some(vec![ContractEventType::System]),
+        ..Default::default()
+    })?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].type_, ContractEventType::System);
+
+    let events = host.get_contract_events(&ContractEventFilter {
+        contract_id: Some(Hash([1; 32])),
+        ..Default::default()
+    })?;
+    assert!(events.is_empty());
+
+    // Nor are the events of failed calls.
+    host.try_borrow_events_mut()?.rollback(0)?;
+    assert!(host
+        .get_contract_events(&ContractEventFilter::default())?
+        .is_empty());
+    Ok(())
+}
+
 #[test]
 fn test_internal_contract_events_metering_not_free() -> Result<(), HostError> {
     let host = observe_host!(Host::test_host());
@@ -143,6 +394,7 @@ fn test_internal_diagnostic_event_metering_free() -> Result<(), HostError> {
     ];
     let args = vec![InternalDiagnosticArg::XdrVal(1_i32.as_scval())];
     let de = Rc::new(InternalDiagnosticEvent {
+        severity: DiagnosticSeverity::Error,
         contract_id,
         topics,
         args,
@@ -326,6 +578,52 @@ fn too_big_event_data() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn event_limits() -> Result<(), HostError> {
+    let host = Host::test_host();
+    host.set_event_limits(EventLimits {
+        max_events: Some(2),
+        max_topics_per_event: Some(2),
+        max_topic_size_bytes: Some(16),
+        max_data_size_bytes: Some(16),
+    })?;
+    let small = Val::from_u32(0).to_val();
+    let big = host.bytes_new_from_slice(&[0; 32])?.to_val();
+    let topics = host.vec_new_from_slice(&[small, small])?;
+
+    let res = host.contract_event(host.vec_new_from_slice(&[small, small, small])?, small);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Events, ScErrorCode::IndexBounds)
+    ));
+    let res = host.contract_event(host.vec_new_from_slice(&[small, big])?, small);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Events, ScErrorCode::InvalidInput)
+    ));
+    let res = host.contract_event(topics, big);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Events, ScErrorCode::UnexpectedSize)
+    ));
+
+    host.contract_event(topics, small)?;
+    host.contract_event(topics, small)?;
+    let res = host.contract_event(topics, small);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Events, ScErrorCode::ExceededLimit)
+    ));
+
+    // Rolled back events don't count towards the limit.
+    host.try_borrow_events_mut()?.rollback(1)?;
+    host.contract_event(topics, small)?;
+    let events = host.get_events()?.0;
+    assert_eq!(events.len(), 3);
+    assert_eq!(events.iter().filter(|e| !e.failed_call).count(), 2);
+    Ok(())
+}
+
 #[test]
 fn too_many_events_in_loop() -> Result<(), HostError> {
     // We don't observe this test: it makes way too big a trace.
@@ -347,3 +645,220 @@ fn too_many_events_in_loop() -> Result<(), HostError> {
     }
     Ok(())
 }
+
+#[test]
+fn event_callback() -> Result<(), HostError> {
+    let run = |with_callback: bool| -> Result<(Host, Vec<String>, u64, u64), HostError> {
+        let host = Host::test_host_with_recording_footprint();
+        host.enable_debug()?;
+        let streamed = Rc::new(RefCell::new(vec![]));
+        if with_callback {
+            let streamed = Rc::clone(&streamed);
+            host.set_event_callback(Some(Rc::new(move |_: &Host, e: &HostEvent| {
+                streamed.borrow_mut().push(e.to_string());
+                Ok(())
+            })))?;
+        }
+        let id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+        host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+        let sym = Symbol::try_from_small_str("add").unwrap();
+        let args = host.test_vec_obj::<i32>(&[1, 2])?;
+        host.call(id, sym, args)?;
+        let cpu = host.as_budget().get_cpu_insns_consumed()?;
+        let mem = host.as_budget().get_mem_bytes_consumed()?;
+        let streamed = streamed.borrow().clone();
+        Ok((host, streamed, cpu, mem))
+    };
+
+    // Streaming the events does not change the metering.
+    let (host, streamed, cpu, mem) = run(true)?;
+    let (_, _, cpu_without_callback, mem_without_callback) = run(false)?;
+    assert_eq!(cpu, cpu_without_callback);
+    assert_eq!(mem, mem_without_callback);
+
+    // Every event is streamed, in the order it is recorded in.
+    let events: Vec<String> = host.get_events()?.0.iter().map(|e| e.to_string()).collect();
+    assert!(events.len() > 2);
+    assert_eq!(streamed, events);
+
+    // The callback can reject contract events, failing their emission.
+    host.set_event_callback(Some(Rc::new(|host: &Host, e: &HostEvent| {
+        if e.event.type_ == ContractEventType::Contract {
+            return Err(host.err(
+                ScErrorType::Events,
+                ScErrorCode::InvalidAction,
+                "rejected by policy",
+                &[],
+            ));
+        }
+        Ok(())
+    })))?;
+    let topics = host.test_vec_obj(&[0, 1])?;
+    let res = host.contract_event(topics, Val::from(0u32));
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Events, ScErrorCode::InvalidAction)
+    ));
+    Ok(())
+}
+
+#[test]
+fn event_callback_runs_after_recording() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let streamed = Rc::new(RefCell::new(vec![]));
+    {
+        let streamed = Rc::clone(&streamed);
+        host.set_event_callback(Some(Rc::new(move |host: &Host, e: &HostEvent| {
+            let last = host.get_events()?.0.last().map(|last| last.sequence);
+            streamed
+                .borrow_mut()
+                .push((e.event.type_, e.sequence, last));
+            Ok(())
+        })))?;
+    }
+    host.contract_event(host.test_vec_obj(&[0, 1])?, Val::from(0u32))?;
+    host.log_diagnostics("diagnostic", &[]);
+    let _ = host.err(
+        ScErrorType::Value,
+        ScErrorCode::InvalidInput,
+        "error diagnostic",
+        &[],
+    );
+
+    // Contract, diagnostic and error events are all reported once they have
+    // been recorded.
+    let streamed = streamed.borrow();
+    assert_eq!(
+        *streamed,
+        vec![
+            (ContractEventType::Contract, 0, Some(0)),
+            (ContractEventType::Diagnostic, 1, Some(1)),
+            (ContractEventType::Diagnostic, 2, Some(2)),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn event_sequence_numbers() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let streamed = Rc::new(RefCell::new(vec![]));
+    {
+        let streamed = Rc::clone(&streamed);
+        host.set_event_callback(Some(Rc::new(move |_: &Host, e: &HostEvent| {
+            streamed.borrow_mut().push(e.sequence);
+            Ok(())
+        })))?;
+    }
+    let id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+    host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+    let sym = Symbol::try_from_small_str("add").unwrap();
+    let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    host.call(id, sym, args)?;
+    host.log_diagnostics("after the call", &[]);
+
+    // Contract and diagnostic events share a single, gapless sequence
+    // reflecting the order they were emitted in.
+    let events = host.get_events()?.0;
+    let sequences: Vec<u32> = events.iter().map(|e| e.sequence).collect();
+    let expected: Vec<u32> = (0..events.len() as u32).collect();
+    assert_eq!(sequences, expected);
+    assert!(events
+        .iter()
+        .any(|e| e.event.type_ == ContractEventType::Contract));
+    assert!(events
+        .iter()
+        .any(|e| e.event.type_ == ContractEventType::Diagnostic));
+
+    // The callback observes the same numbers as the events are emitted.
+    assert_eq!(*streamed.borrow(), sequences);
+
+    // Structured diagnostics report the sequence of the matching event.
+    let diagnostics = host.get_diagnostic_events(DiagnosticSeverity::Debug)?;
+    assert!(!diagnostics.is_empty());
+    for d in diagnostics.iter() {
+        let e = &events[d.sequence as usize];
+        assert_eq!(e.event.type_, ContractEventType::Diagnostic);
+        assert_eq!(e.failed_call, d.failed_call);
+    }
+    Ok(())
+}
+
+#[test]
+fn event_sequence_numbers_are_not_reused_after_rollback() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| {
+        li.protocol_version = FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION
+    })?;
+    let streamed = Rc::new(RefCell::new(vec![]));
+    {
+        let streamed = Rc::clone(&streamed);
+        host.set_event_callback(Some(Rc::new(move |_: &Host, e: &HostEvent| {
+            streamed.borrow_mut().push(e.sequence);
+            Ok(())
+        })))?;
+    }
+    let id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+    host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+    let func = Symbol::try_from_small_str("emit")?;
+    let emit = |fail: bool| {
+        host.with_test_contract_frame(Hash([0; 32]), func, || {
+            host.contract_event(host.test_vec_obj(&[0, 1])?, Val::from(0u32))?;
+            if fail {
+                return Err(Error::from_contract_error(1).into());
+            }
+            Ok(Val::VOID.into())
+        })
+    };
+    emit(false)?;
+    assert!(emit(true).is_err());
+    emit(false)?;
+
+    // The event of the failed frame is dropped, but its number is not given
+    // to the event emitted after it.
+    let events = host.get_events()?.0;
+    let sequences: Vec<u32> = events.iter().map(|e| e.sequence).collect();
+    assert_eq!(sequences, vec![0, 2]);
+    assert_eq!(*streamed.borrow(), vec![0, 1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_contract_event_assertions() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let id = host.add_host_object(ScAddress::Contract(Hash([0; 32])))?;
+    host.register_test_contract(id, Rc::new(ContractWithMultipleEvents {}))?;
+    let sym = Symbol::try_from_small_str("add").unwrap();
+    let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    host.call(id, sym, args)?;
+
+    let event = host.assert_contract_event(&ContractEventFilter {
+        event_types: Some(vec![ContractEventType::Contract]),
+        topic_prefix: Some(vec![ScVal::I32(0)]),
+        ..Default::default()
+    });
+    assert_eq!(event.contract_id, Some(Hash([0; 32])));
+    host.assert_no_contract_event(&ContractEventFilter {
+        topic_prefix: Some(vec![ScVal::I32(1)]),
+        ..Default::default()
+    });
+    host.assert_no_contract_event(&ContractEventFilter {
+        topic_prefix: Some(vec![ScVal::I32(0), ScVal::I32(1), ScVal::I32(2)]),
+        ..Default::default()
+    });
+
+    // Both the contract and the system event match the prefix.
+    let res =
+        crate::testutils::call_with_suppressed_panic_hook(std::panic::AssertUnwindSafe(|| {
+            host.assert_contract_event(&ContractEventFilter {
+                topic_prefix: Some(vec![ScVal::I32(0), ScVal::I32(1)]),
+                ..Default::default()
+            })
+        }));
+    let msg = res.unwrap_err().downcast::<String>().unwrap();
+    assert!(msg.contains("found 2"));
+    assert!(msg.contains("[System Event] contract:"));
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::{
+    xdr::{ScErrorCode, ScErrorType},
+    Env, EnvBase, Error, Host, HostError, StorageType, Symbol, Val,
+};
+use soroban_test_wasms::CONTRACT_STORAGE;
+
+#[test]
+fn inject_storage_read_fault() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key: Val = Symbol::try_from_small_str("key")?.into();
+    let func = Symbol::try_from_small_str("")?;
+    let error = Error::from_type_and_code(ScErrorType::Storage, ScErrorCode::InternalError);
+
+    host.with_test_contract_frame(contract_hash, func, || {
+        host.put_contract_data(key, 1_u32.into(), StorageType::Persistent)?;
+        host.inject_fault("storage_read", 2, error)?;
+        // Only the 2nd read fails.
+        host.get_contract_data(key, StorageType::Persistent)?;
+        let res = host.get_contract_data(key, StorageType::Persistent);
+        assert!(HostError::result_matches_err(res, error));
+        host.has_contract_data(key, StorageType::Persistent)?;
+        // Instance storage is not affected.
+        host.inject_fault("storage_read", 1, error)?;
+        host.put_contract_data(key, 1_u32.into(), StorageType::Instance)?;
+        host.get_contract_data(key, StorageType::Instance)?;
+        host.clear_injected_faults()?;
+        host.get_contract_data(key, StorageType::Persistent)?;
+        Ok(Val::VOID.into())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn inject_faults_at_the_same_point() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let first = Error::from_type_and_code(ScErrorType::Crypto, ScErrorCode::InvalidInput);
+    let second = Error::from_type_and_code(ScErrorType::Crypto, ScErrorCode::InternalError);
+    host.inject_fault("ed25519_verify", 1, first)?;
+    host.inject_fault("ed25519_verify", 1, second)?;
+
+    // From https://datatracker.ietf.org/doc/html/rfc8032#section-7.1
+    let public_key =
+        hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a").unwrap();
+    let signature = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+    let verify = || -> Result<(), HostError> {
+        let public_key = host.bytes_new_from_slice(&public_key)?;
+        let message = host.bytes_new_from_slice(&[])?;
+        let signature = host.bytes_new_from_slice(&signature)?;
+        host.verify_sig_ed25519(public_key, message, signature)
+            .map(|_| ())
+    };
+    // Failures due at the same time fire one after another, in the order they
+    // were injected in.
+    assert!(HostError::result_matches_err(verify(), first));
+    assert!(HostError::result_matches_err(verify(), second));
+    verify()
+}
+
+#[test]
+fn inject_fault_invalid_inputs() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let error = Error::from_type_and_code(ScErrorType::Storage, ScErrorCode::InternalError);
+    let code = (ScErrorType::Context, ScErrorCode::InvalidInput);
+    assert!(HostError::result_matches_err(
+        host.inject_fault("no_such_point", 1, error),
+        code
+    ));
+    assert!(HostError::result_matches_err(
+        host.inject_fault("storage_read", 0, error),
+        code
+    ));
+    Ok(())
+}
@@ -1,10 +1,10 @@
 use soroban_env_common::xdr::{ScBytes, ScErrorCode, ScErrorType};
-use soroban_env_common::Val;
+use soroban_env_common::{Symbol, Val};
 
 use crate::{
     budget::Budget,
     storage::{Footprint, Storage, StorageMap},
-    Env, Host, HostError, LedgerInfo,
+    Env, EnvBase, Host, HostError, LedgerInfo,
 };
 
 #[test]
@@ -58,3 +58,49 @@ fn invalid_val() -> Result<(), HostError> {
 
     Ok(())
 }
+
+#[test]
+fn render_val() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let map = host.map_new()?;
+    let map = host.map_put(map, 1u32.into(), host.string_new_from_slice(b"str")?.into())?;
+    let vec = host.vec_new_from_slice(&[
+        Symbol::try_from_small_str("sym")?.to_val(),
+        map.to_val(),
+        Val::VOID.to_val(),
+    ])?;
+    let budget = host.budget_cloned();
+    let cpu_before = budget.get_cpu_insns_consumed()?;
+    assert_eq!(host.render_val(vec.to_val()), r#"[sym, {1: "str"}, Void]"#);
+    assert_eq!(budget.get_cpu_insns_consumed()?, cpu_before);
+
+    // Values that can't be converted are rendered as is.
+    let bad = Val::from_payload(u64::MAX);
+    assert_eq!(host.render_val(bad), format!("{:?}", bad));
+    Ok(())
+}
+
+#[test]
+fn std_collection_conversions() -> Result<(), HostError> {
+    use std::collections::BTreeMap;
+
+    let host = Host::test_host();
+    let vec = host.to_vec_obj(&[1u64, u64::MAX])?;
+    assert_eq!(host.from_vec_obj::<u64>(vec)?, vec![1u64, u64::MAX]);
+    assert!(host.from_vec_obj::<u32>(vec).is_err());
+
+    let map = BTreeMap::from([(3u32, -1i64), (1, 5), (2, i64::MIN)]);
+    let obj = host.to_map_obj(&map)?;
+    assert_eq!(u32::from(host.map_len(obj)?), 3);
+    assert_eq!(host.from_map_obj::<u32, i64>(obj)?, map);
+
+    let s = host.to_string_obj("hello")?;
+    assert_eq!(host.from_string_obj(s)?, "hello");
+    let not_utf8 = host.string_new_from_slice(&[0xff])?;
+    assert!(host.from_string_obj(not_utf8).is_err());
+
+    let b = host.to_bytes_obj(&[1u8, 2, 3])?;
+    assert_eq!(host.from_bytes_obj::<3>(b)?, [1, 2, 3]);
+    assert!(host.from_bytes_obj::<4>(b).is_err());
+    Ok(())
+}
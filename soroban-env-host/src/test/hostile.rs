@@ -3,6 +3,7 @@ use soroban_test_wasms::HOSTILE;
 
 use crate::{
     budget::{AsBudget, Budget},
+    host::frame::CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION,
     host_object::HostVec,
     meta,
     storage::Storage,
@@ -370,12 +371,26 @@ fn instantiate_with_mem_and_table_sizes(
     )
 }
 
+// From CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION creating the contract
+// instantiates it once more to look for a constructor, allocating its initial
+// memory a second time.
+fn instantiations_on_creation(host: &Host) -> Result<u64, HostError> {
+    if host.get_ledger_protocol_version()? >= CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION {
+        Ok(2)
+    } else {
+        Ok(1)
+    }
+}
+
 #[test]
 fn moderate_sized_initial_memory_request_ok() -> Result<(), HostError> {
     let host = Host::test_host_with_recording_footprint();
     let res = instantiate_with_mem_and_table_sizes(&host, 10, 0);
     assert!(res.is_ok());
-    assert_eq!(host.as_budget().get_wasm_mem_alloc()?, 0x10_000 * 10);
+    assert_eq!(
+        host.as_budget().get_wasm_mem_alloc()?,
+        instantiations_on_creation(&host)? * 0x10_000 * 10
+    );
     Ok(())
 }
 
@@ -431,7 +446,10 @@ fn data_segment_smaller_than_a_page_fits_in_one_page_memory() -> Result<(), Host
     host.as_budget().reset_unlimited_cpu()?;
     let res = instantiate_with_data_segment(&host, 1, 0, 5000);
     assert!(res.is_ok());
-    assert_eq!(host.as_budget().get_wasm_mem_alloc()?, 0x10_000);
+    assert_eq!(
+        host.as_budget().get_wasm_mem_alloc()?,
+        instantiations_on_creation(&host)? * 0x10_000
+    );
     Ok(())
 }
 
@@ -454,7 +472,10 @@ fn data_segment_larger_than_a_page_fits_in_two_page_memory() -> Result<(), HostE
     host.as_budget().reset_unlimited_cpu()?;
     let res = instantiate_with_data_segment(&host, 2, 0, 100_000);
     assert!(res.is_ok());
-    assert_eq!(host.as_budget().get_wasm_mem_alloc()?, 2 * 0x10_000);
+    assert_eq!(
+        host.as_budget().get_wasm_mem_alloc()?,
+        instantiations_on_creation(&host)? * 2 * 0x10_000
+    );
     Ok(())
 }
 
@@ -850,6 +871,42 @@ fn test_corrupt_custom_section() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn test_host_function_from_later_protocol_fails_to_link() -> Result<(), HostError> {
+    // `take_contract_data` (export `l.c`) is only available since protocol 21.
+    let module_for_protocol = |proto: u32| -> Vec<u8> {
+        let iv =
+            (proto as u64) << 32 | meta::get_pre_release_version(meta::INTERFACE_VERSION) as u64;
+        let entry = ScEnvMetaEntry::ScEnvMetaKindInterfaceVersion(iv);
+        let mut w = Limited::new(Vec::<u8>::new(), Limits::none());
+        entry.write_xdr(&mut w).unwrap();
+        wasm_util::wasm_module_calling_host_function_with_meta(&w.inner, "l", "c", 2)
+    };
+
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| li.protocol_version = 20)?;
+    let res = host.register_test_contract_wasm_from_source_account(
+        module_for_protocol(20).as_slice(),
+        generate_account_id(&host),
+        generate_bytes_array(&host),
+    );
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::WasmVm, ScErrorCode::InvalidAction)
+    ));
+
+    #[cfg(feature = "next")]
+    {
+        host.with_mut_ledger_info(|li| li.protocol_version = 21)?;
+        host.register_test_contract_wasm_from_source_account(
+            module_for_protocol(21).as_slice(),
+            generate_account_id(&host),
+            generate_bytes_array(&host),
+        )?;
+    }
+    Ok(())
+}
+
 #[test]
 fn test_floating_point() -> Result<(), HostError> {
     let wasm = wasm_util::wasm_module_with_floating_point_ops();
@@ -7,8 +7,13 @@ use soroban_env_common::{
 };
 
 use crate::{
-    budget::AsBudget, events::HostEvent, test::observe::ObservedHost, xdr::ScErrorType,
-    ContractFunctionSet, Error, Host, HostError, Symbol, Tag,
+    budget::AsBudget,
+    events::HostEvent,
+    host::frame::CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION,
+    test::observe::ObservedHost,
+    xdr::{ScErrorType, ScVal},
+    AddressObject, CallLimits, ContractFunctionSet, DiagnosticSeverity, Error, ErrorContext, Host,
+    HostError, Symbol, Tag, U32Val, VecObject,
 };
 use soroban_test_wasms::{ADD_I32, ALLOC, ERR, INVOKE_CONTRACT, VEC};
 
@@ -67,9 +72,15 @@ fn invoke_alloc() -> Result<(), HostError> {
     // pages or about 1.3 MiB, plus the initial 17 pages (1.1MiB) plus some more
     // slop from general host machinery allocations, plus allocating a VM once
     // during upload and once during execution we get around 2.5MiB. Call
-    // is "less than 4MiB".
+    // is "less than 4MiB". From CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION
+    // the creation instantiates the VM once more to look for a constructor,
+    // which adds the initial 17 pages and the VM allocations again.
     assert!(used_bytes > (128 * 4096));
-    assert!(used_bytes < 0x40_0000);
+    if host.get_ledger_protocol_version()? >= CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION {
+        assert!(used_bytes < 0x60_0000);
+    } else {
+        assert!(used_bytes < 0x40_0000);
+    }
     Ok(())
 }
 
@@ -83,10 +94,25 @@ fn invoke_cross_contract(diagnostics: bool, testname: &'static str) -> Result<()
     // prepare arguments
     let sym = Symbol::try_from_small_str("add").unwrap();
     let args = host.test_vec_obj::<i32>(&[1, 2])?;
+    #[cfg(feature = "testutils")]
+    host.enable_host_call_log()?;
     let res = host.call(id_obj, sym, args)?;
     assert_eq!(res.get_tag(), Tag::I32Val);
     let i: i32 = res.try_into()?;
     assert_eq!(i, 3);
+
+    // The host functions called by the contract are logged nested in the call
+    // to it, and every call is logged with the budget it consumed.
+    #[cfg(feature = "testutils")]
+    {
+        let calls = host.get_host_call_log()?.render();
+        assert!(calls.starts_with("call call(Address(obj#"));
+        assert!(calls.contains("\n  call contract_event(Vec(obj#"));
+        assert!(calls.contains("\n  ret contract_event -> Ok(Void) [cpu:"));
+        let last = calls.lines().last().unwrap();
+        assert!(last.starts_with("ret call -> Ok(I32(3)) [cpu:"));
+        assert!(!last.ends_with("[cpu:0, mem:0]"));
+    }
     Ok(())
 }
 
@@ -144,6 +170,36 @@ fn invoke_cross_contract_with_err() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn error_context_chain() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let id_obj = host.register_test_contract_wasm(VEC);
+    let id = host.contract_id_from_address(id_obj)?;
+    let sym = Symbol::try_from_small_str("vec_err").unwrap();
+    let args = host.test_vec_obj::<u32>(&[1])?;
+
+    let err = host.call(id_obj, sym, args).err().unwrap();
+    let context = err.context();
+    assert_eq!(context.len(), 3);
+    assert!(matches!(context[0], ErrorContext::HostLocation(_)));
+    assert_eq!(context[1], ErrorContext::HostFunction("vec_insert"));
+    assert_eq!(
+        context[2],
+        ErrorContext::ContractFunction {
+            contract: id,
+            function: "vec_err".to_string(),
+        }
+    );
+    assert!(format!("{:?}", err).contains("in host function vec_insert"));
+
+    // Without debug mode no context is recorded.
+    host.set_diagnostic_level(crate::DiagnosticLevel::None)?;
+    let err = host.call(id_obj, sym, args).err().unwrap();
+    assert!(err.context().is_empty());
+    Ok(())
+}
+
 #[test]
 fn invoke_cross_contract_indirect() -> Result<(), HostError> {
     let host = observe_host!(Host::test_host_with_recording_footprint());
@@ -247,6 +303,48 @@ fn contract_failure_with_debug_on_off_affects_no_metering() -> Result<(), HostEr
     Ok(())
 }
 
+#[test]
+fn diagnostic_level_switchable_between_invocations() -> Result<(), HostError> {
+    use crate::DiagnosticLevel;
+    let host = Host::test_host_with_recording_footprint();
+    let id0_obj = host.register_test_contract_wasm(INVOKE_CONTRACT);
+    let sym = Symbol::try_from_small_str("add_with").unwrap();
+    let args = host.test_vec_obj::<i32>(&[i32::MAX, 1])?;
+    let args = host.vec_push_back(args, host.bytes_new()?.to_val())?;
+
+    let mut runs = vec![];
+    for level in [
+        DiagnosticLevel::None,
+        DiagnosticLevel::DebugEvents,
+        DiagnosticLevel::FullTrace,
+        DiagnosticLevel::DebugEvents,
+        DiagnosticLevel::None,
+    ] {
+        host.set_diagnostic_level(level)?;
+        let num_events = host.get_events()?.0.len();
+        host.as_budget().reset_default()?;
+        let res = host.try_call(id0_obj, sym, args)?;
+        let cost = (
+            host.as_budget().get_cpu_insns_consumed()?,
+            host.as_budget().get_mem_bytes_consumed()?,
+        );
+        let recorded_events = host.get_events()?.0.len() > num_events;
+        assert_eq!(recorded_events, level != DiagnosticLevel::None);
+        let err = host.call(id0_obj, sym, args).err().unwrap();
+        assert_eq!(
+            !err.context().is_empty(),
+            level == DiagnosticLevel::FullTrace
+        );
+        runs.push((res.get_payload(), cost));
+    }
+    // The results and the metering are the same at every level.
+    assert!(runs.windows(2).all(|w| w[0] == w[1]));
+
+    // The `Debug` level keeps recording everything it used to.
+    assert_eq!(DiagnosticLevel::Debug, DiagnosticLevel::FullTrace);
+    Ok(())
+}
+
 #[test]
 fn invoke_contract_with_reentry() -> Result<(), HostError> {
     let host = observe_host!(Host::test_host_with_recording_footprint());
@@ -270,6 +368,268 @@ fn invoke_contract_with_reentry() -> Result<(), HostError> {
     Ok(())
 }
 
+// Calls its own `leaf` function `calls` times, first allowing reentry for
+// the next call if `allow` is set, and returns the results in a vector.
+struct SelfCaller;
+impl ContractFunctionSet for SelfCaller {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        if func
+            .to_val()
+            .shallow_eq(&Symbol::try_from_small_str("leaf").ok()?.to_val())
+        {
+            return Some(7_u32.into());
+        }
+        let allow = bool::try_from_val(host, &args[0]).ok()?;
+        let calls = u32::try_from_val(host, &args[1]).ok()?;
+        let this = host.get_current_contract_address().ok()?;
+        let leaf = Symbol::try_from_small_str("leaf").ok()?;
+        if allow {
+            host.allow_reentry_for_next_call().ok()?;
+        }
+        let mut results = host.vec_new().ok()?;
+        for _ in 0..calls {
+            let res = host.try_call(this, leaf, host.vec_new().ok()?).ok()?;
+            results = host.vec_push_back(results, res).ok()?;
+        }
+        Some(results.into())
+    }
+}
+
+#[test]
+fn reentry_is_allowed_only_for_the_next_call_when_requested() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let addr = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([0; 32])))?;
+    host.register_test_contract(addr, Rc::new(SelfCaller))?;
+    let go = Symbol::try_from_small_str("go")?;
+    let reentry_err: Val =
+        Error::from_type_and_code(ScErrorType::Context, ScErrorCode::InvalidAction).into();
+
+    let run = |allow: bool| -> Result<Vec<Val>, HostError> {
+        let args = host.vec_new()?;
+        let args = host.vec_push_back(args, allow.into())?;
+        let args = host.vec_push_back(args, 2_u32.into())?;
+        let res = host.call(addr, go, args)?;
+        let res = VecObject::try_from(res)?;
+        (0..2_u32).map(|i| host.vec_get(res, i.into())).collect()
+    };
+
+    // By default, the self-call is rejected as reentry.
+    let res = run(false)?;
+    assert!(res.iter().all(|v| v.shallow_eq(&reentry_err)));
+
+    // Once allowed, only the next call may reenter.
+    let res = run(true)?;
+    assert!(res[0].shallow_eq(&7_u32.into()));
+    assert!(res[1].shallow_eq(&reentry_err));
+    Ok(())
+}
+
+// Returns the call stack, after first calling into the contract passed as an
+// argument (if any) and returning its result instead.
+struct CallStackReporter;
+impl ContractFunctionSet for CallStackReporter {
+    fn call(&self, _func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        match args.first() {
+            Some(callee) => {
+                let callee = AddressObject::try_from(*callee).ok()?;
+                let func = Symbol::try_from_small_str("inner").ok()?;
+                host.call(callee, func, host.vec_new().ok()?).ok()
+            }
+            None => Some(host.get_current_call_stack().ok()?.into()),
+        }
+    }
+}
+
+#[test]
+fn get_current_call_stack_lists_contract_frames() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let outer = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([1; 32])))?;
+    let inner = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([2; 32])))?;
+    host.register_test_contract(outer, Rc::new(CallStackReporter))?;
+    host.register_test_contract(inner, Rc::new(CallStackReporter))?;
+
+    let args = host.vec_push_back(host.vec_new()?, inner.to_val())?;
+    let stack = host.call(outer, Symbol::try_from_small_str("outer")?, args)?;
+    let stack = VecObject::try_from(stack)?;
+    assert_eq!(u32::from(host.vec_len(stack)?), 2);
+    for (i, (addr, func)) in [(outer, "outer"), (inner, "inner")].iter().enumerate() {
+        let entry = VecObject::try_from(host.vec_get(stack, (i as u32).into())?)?;
+        let entry_addr = host.vec_get(entry, 0_u32.into())?;
+        assert!(host.compare(&entry_addr, &addr.to_val())?.is_eq());
+        let entry_func = host.vec_get(entry, 1_u32.into())?;
+        assert!(entry_func.shallow_eq(&Symbol::try_from_small_str(func)?.to_val()));
+    }
+
+    // Outside of any contract the stack is empty.
+    let stack = host.get_current_call_stack()?;
+    assert_eq!(u32::from(host.vec_len(stack)?), 0);
+    Ok(())
+}
+
+// Returns its invoker, after first calling into the contract passed as an
+// argument (if any) and returning its result instead.
+struct InvokerReporter;
+impl ContractFunctionSet for InvokerReporter {
+    fn call(&self, _func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        match args.first() {
+            Some(callee) => {
+                let callee = AddressObject::try_from(*callee).ok()?;
+                let func = Symbol::try_from_small_str("inner").ok()?;
+                host.call(callee, func, host.vec_new().ok()?).ok()
+            }
+            None => Some(host.get_invoker().ok()?.into()),
+        }
+    }
+}
+
+#[test]
+fn get_invoker_distinguishes_transaction_and_contract_invokers() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let source = crate::testutils::generate_account_id(&host);
+    host.set_source_account(source.clone())?;
+    let outer = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([1; 32])))?;
+    let inner = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([2; 32])))?;
+    host.register_test_contract(outer, Rc::new(InvokerReporter))?;
+    host.register_test_contract(inner, Rc::new(InvokerReporter))?;
+    let func = Symbol::try_from_small_str("outer")?;
+    let check = |res: Val, kind: u32, addr: AddressObject| -> Result<(), HostError> {
+        let res = VecObject::try_from(res)?;
+        assert!(host
+            .vec_get(res, 0_u32.into())?
+            .shallow_eq(&U32Val::from(kind).to_val()));
+        let invoker = host.vec_get(res, 1_u32.into())?;
+        assert!(host.compare(&invoker, &addr.to_val())?.is_eq());
+        Ok(())
+    };
+
+    let res = host.call(outer, func, host.vec_new()?)?;
+    check(
+        res,
+        0,
+        host.add_host_object(xdr::ScAddress::Account(source))?,
+    )?;
+    let res = host.call(outer, func, host.vec_new_from_slice(&[inner.to_val()])?)?;
+    check(res, 1, outer)?;
+
+    // There is no invoker outside of any contract.
+    assert!(HostError::result_matches_err(
+        host.get_invoker(),
+        (ScErrorType::Context, ScErrorCode::InvalidAction)
+    ));
+    Ok(())
+}
+
+// "outer" puts `a` in its scratch space and calls "inner" on the contract
+// passed as an argument, returning `[inner result, whether "b" is visible]`.
+// "inner" puts `b` in its own scratch space and returns whether `a` is
+// visible.
+struct ScratchUser;
+impl ContractFunctionSet for ScratchUser {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        let a = Symbol::try_from_small_str("a").ok()?.to_val();
+        let b = Symbol::try_from_small_str("b").ok()?.to_val();
+        if func
+            .to_val()
+            .shallow_eq(&Symbol::try_from_small_str("outer").ok()?.to_val())
+        {
+            host.scratch_put(a, 1_u32.into()).ok()?;
+            let callee = AddressObject::try_from(*args.first()?).ok()?;
+            host.allow_reentry_for_next_call().ok()?;
+            let inner = host
+                .call(
+                    callee,
+                    Symbol::try_from_small_str("inner").ok()?,
+                    host.vec_new().ok()?,
+                )
+                .ok()?;
+            let res = [inner, host.scratch_has(b).ok()?.to_val()];
+            Some(host.vec_new_from_slice(&res).ok()?.into())
+        } else {
+            host.scratch_put(b, 2_u32.into()).ok()?;
+            Some(host.scratch_has(a).ok()?.into())
+        }
+    }
+}
+
+#[test]
+fn scratch_space_is_frame_local_and_visible_to_own_helpers() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let addr = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([1; 32])))?;
+    let other = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([2; 32])))?;
+    host.register_test_contract(addr, Rc::new(ScratchUser))?;
+    host.register_test_contract(other, Rc::new(ScratchUser))?;
+    let outer = Symbol::try_from_small_str("outer")?;
+    let expect = |res: Val, inner_sees_a: bool| -> Result<(), HostError> {
+        let res = VecObject::try_from(res)?;
+        let inner = host.vec_get(res, 0_u32.into())?;
+        assert!(inner.shallow_eq(&Val::from_bool(inner_sees_a).to_val()));
+        // The scratch space of the callee is gone once it returns.
+        let outer_sees_b = host.vec_get(res, 1_u32.into())?;
+        assert!(outer_sees_b.shallow_eq(&Val::from_bool(false).to_val()));
+        Ok(())
+    };
+
+    // A self-call sees the scratch space of its caller, another contract
+    // doesn't.
+    let res = host.call(addr, outer, host.vec_new_from_slice(&[addr.to_val()])?)?;
+    expect(res, true)?;
+    let res = host.call(addr, outer, host.vec_new_from_slice(&[other.to_val()])?)?;
+    expect(res, false)?;
+
+    // Nothing is left behind after the invocation.
+    let a = Symbol::try_from_small_str("a")?.to_val();
+    assert!(host
+        .scratch_has(a)?
+        .to_val()
+        .shallow_eq(&Val::from_bool(false).to_val()));
+    Ok(())
+}
+
+// Returns its first argument, if any.
+struct Echo;
+impl ContractFunctionSet for Echo {
+    fn call(&self, _func: &Symbol, _host: &Host, args: &[Val]) -> Option<Val> {
+        Some(args.first().copied().unwrap_or_default())
+    }
+}
+
+#[test]
+fn call_limits_bound_args_and_return_values() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let addr = host.add_host_object(xdr::ScAddress::Contract(xdr::Hash([0; 32])))?;
+    host.register_test_contract(addr, Rc::new(Echo))?;
+    host.set_call_limits(CallLimits {
+        max_args: Some(2),
+        max_args_size_bytes: Some(64),
+        max_return_size_bytes: Some(32),
+    })?;
+    let sym = Symbol::try_from_small_str("echo")?;
+    let small = Val::from_u32(1).to_val();
+    // 56 bytes once serialized.
+    let big = host.bytes_new_from_slice(&[0; 48])?.to_val();
+
+    let res = host.call(addr, sym, host.vec_new_from_slice(&[small, small, small])?);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Context, ScErrorCode::ExceededLimit)
+    ));
+    let res = host.call(addr, sym, host.vec_new_from_slice(&[big, big])?);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Context, ScErrorCode::ExceededLimit)
+    ));
+    // The argument fits, but the same value returned doesn't.
+    let res = host.call(addr, sym, host.vec_new_from_slice(&[big])?);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Context, ScErrorCode::ExceededLimit)
+    ));
+
+    let res = host.call(addr, sym, host.vec_new_from_slice(&[small, big])?)?;
+    assert!(res.shallow_eq(&small));
+    Ok(())
+}
+
 struct ReturnContractError;
 impl ReturnContractError {
     const ERR: Error = Error::from_contract_error(12345);
@@ -330,6 +690,33 @@ fn wasm_invoke_return_err_variants() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn contract_error_named_from_spec() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let addr = host.register_test_contract_wasm(ERR);
+    // Diagnostics are not affected by how the contract signals the error.
+    for fname in ["err_eek", "err"] {
+        let sym = Symbol::try_from_val(&host, &fname)?;
+        let args = host.vec_new_from_slice(&[])?;
+        let num_events = host.get_diagnostic_events(DiagnosticSeverity::Error)?.len();
+        assert!(host.try_call(addr, sym, args).is_ok());
+        // The error is reported by a single event from the contract, which
+        // names the error code as declared in the spec of the contract.
+        let events: Vec<_> = host.get_diagnostic_events(DiagnosticSeverity::Error)?[num_events..]
+            .iter()
+            .filter(|e| e.contract_id.is_some())
+            .cloned()
+            .collect();
+        assert_eq!(events.len(), 1);
+        match events[0].args.last() {
+            Some(ScVal::String(name)) => assert_eq!(name.0.to_string(), "Eek::BADNESS"),
+            _ => panic!("contract error not named"),
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn error_spoof_rejected() -> Result<(), HostError> {
     let host = observe_host!(Host::test_host_with_recording_footprint());
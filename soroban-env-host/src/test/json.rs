@@ -0,0 +1,175 @@
+use serde_json::json;
+
+use crate::{
+    json::{scval_from_json, scval_to_json},
+    xdr::{
+        AccountId, ContractExecutable, Duration, Hash, Int128Parts, Int256Parts, PublicKey,
+        ScAddress, ScContractInstance, ScError, ScErrorCode, ScErrorType, ScMap, ScMapEntry,
+        ScNonceKey, ScString, ScSymbol, ScVal, ScVec, TimePoint, UInt128Parts, UInt256Parts,
+        Uint256,
+    },
+    Host, HostError,
+};
+
+fn sample_values() -> Vec<ScVal> {
+    vec![
+        ScVal::Bool(true),
+        ScVal::Void,
+        ScVal::Error(ScError::Contract(7)),
+        ScVal::Error(ScError::Auth(ScErrorCode::InvalidAction)),
+        ScVal::U32(u32::MAX),
+        ScVal::I32(i32::MIN),
+        ScVal::U64(u64::MAX),
+        ScVal::I64(i64::MIN),
+        ScVal::Timepoint(TimePoint(1234)),
+        ScVal::Duration(Duration(5678)),
+        ScVal::U128(UInt128Parts {
+            hi: u64::MAX,
+            lo: 1,
+        }),
+        ScVal::I128(Int128Parts { hi: -1, lo: 5 }),
+        ScVal::U256(UInt256Parts {
+            hi_hi: u64::MAX,
+            hi_lo: 2,
+            lo_hi: 3,
+            lo_lo: 4,
+        }),
+        ScVal::I256(Int256Parts {
+            hi_hi: i64::MIN,
+            hi_lo: 2,
+            lo_hi: 3,
+            lo_lo: 4,
+        }),
+        ScVal::Bytes(vec![0, 1, 0xff].try_into().unwrap()),
+        ScVal::String(ScString("hello".try_into().unwrap())),
+        ScVal::String(ScString(vec![0xff, 0xfe].try_into().unwrap())),
+        ScVal::Symbol(ScSymbol("sym".try_into().unwrap())),
+        ScVal::Vec(None),
+        ScVal::Vec(Some(ScVec(
+            vec![ScVal::U32(1), ScVal::Void].try_into().unwrap(),
+        ))),
+        ScVal::Map(None),
+        ScVal::Map(Some(ScMap(
+            vec![ScMapEntry {
+                key: ScVal::Symbol(ScSymbol("a".try_into().unwrap())),
+                val: ScVal::I64(-3),
+            }]
+            .try_into()
+            .unwrap(),
+        ))),
+        ScVal::Address(ScAddress::Account(AccountId(
+            PublicKey::PublicKeyTypeEd25519(Uint256([1; 32])),
+        ))),
+        ScVal::Address(ScAddress::Contract(Hash([2; 32]))),
+        ScVal::LedgerKeyContractInstance,
+        ScVal::LedgerKeyNonce(ScNonceKey { nonce: -9 }),
+        ScVal::ContractInstance(ScContractInstance {
+            executable: ContractExecutable::Wasm(Hash([3; 32])),
+            storage: None,
+        }),
+        ScVal::ContractInstance(ScContractInstance {
+            executable: ContractExecutable::StellarAsset,
+            storage: Some(ScMap(
+                vec![ScMapEntry {
+                    key: ScVal::U32(1),
+                    val: ScVal::U32(2),
+                }]
+                .try_into()
+                .unwrap(),
+            )),
+        }),
+    ]
+}
+
+#[test]
+fn scval_json_roundtrip() -> Result<(), HostError> {
+    for v in sample_values() {
+        let json = scval_to_json(&v)?;
+        // The rendering must survive being serialized as text.
+        let text = serde_json::to_string(&json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(scval_from_json(&parsed)?, v);
+    }
+    Ok(())
+}
+
+#[test]
+fn scval_json_format() -> Result<(), HostError> {
+    assert_eq!(scval_to_json(&ScVal::U32(5))?, json!({"u32": 5}));
+    assert_eq!(
+        scval_to_json(&ScVal::U64(u64::MAX))?,
+        json!({"u64": "18446744073709551615"})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::I128(Int128Parts { hi: -1, lo: 0 }))?,
+        json!({"i128": "-18446744073709551616"})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::Error(ScError::Auth(ScErrorCode::InvalidAction)))?,
+        json!({"error": {"type": "Auth", "code": "InvalidAction"}})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::Bytes(vec![0xab, 0x01].try_into().unwrap()))?,
+        json!({"bytes": "ab01"})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::String(ScString(vec![0xff].try_into().unwrap())))?,
+        json!({"string": {"hex": "ff"}})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::Address(ScAddress::Contract(Hash([0; 32]))))?,
+        json!({"address": stellar_strkey::Contract([0; 32]).to_string()})
+    );
+    assert_eq!(
+        scval_to_json(&ScVal::Map(Some(ScMap(
+            vec![ScMapEntry {
+                key: ScVal::U32(1),
+                val: ScVal::Bool(false),
+            }]
+            .try_into()
+            .unwrap()
+        ))))?,
+        json!({"map": [{"key": {"u32": 1}, "val": {"bool": false}}]})
+    );
+    Ok(())
+}
+
+#[test]
+fn scval_json_rejects_malformed_input() {
+    for json in [
+        json!(5),
+        json!({}),
+        json!({"u32": 1, "i32": 1}),
+        json!({"u32": -1}),
+        json!({"u32": 4294967296u64}),
+        json!({"u64": 1}),
+        json!({"i128": "1.5"}),
+        json!({"bytes": "abc"}),
+        json!({"bytes": "zz"}),
+        json!({"symbol": "not a symbol"}),
+        json!({"address": "GABC"}),
+        json!({"error": {"type": "Auth", "code": 3}}),
+        json!({"error": {"type": "Contract", "code": "InvalidAction"}}),
+        json!({"unknown": null}),
+    ] {
+        let err = scval_from_json(&json).unwrap_err();
+        assert!(
+            err.error.is_type(ScErrorType::Value) && err.error.is_code(ScErrorCode::InvalidInput),
+            "{json}"
+        );
+    }
+}
+
+#[test]
+fn host_val_json_roundtrip() -> Result<(), HostError> {
+    let host = Host::test_host();
+    for v in sample_values() {
+        if !crate::Val::can_represent_scval(&v) {
+            assert!(host.val_from_json(&scval_to_json(&v)?).is_err());
+            continue;
+        }
+        let val = host.val_from_json(&scval_to_json(&v)?)?;
+        assert_eq!(host.val_to_json(val)?, scval_to_json(&v)?);
+    }
+    Ok(())
+}
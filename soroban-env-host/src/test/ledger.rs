@@ -1,8 +1,17 @@
-use soroban_env_common::{xdr::ScBytes, Env};
+use soroban_env_common::{
+    xdr::{
+        ContractIdPreimage, Hash, HashIdPreimage, HashIdPreimageContractId,
+        HashIdPreimageSorobanAuthorization, ScBytes, SorobanAuthorizedFunction,
+        SorobanAuthorizedInvocation,
+    },
+    Env,
+};
 
 use crate::{
     budget::Budget,
+    host::crypto::NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION,
     storage::{Footprint, Storage, StorageMap},
+    xdr::{Asset, ContractExecutable, CreateContractArgs},
     Host, HostError, LedgerInfo,
 };
 
@@ -28,3 +37,50 @@ fn ledger_network_id() -> Result<(), HostError> {
     assert_eq!(np, vec![7; 32],);
     Ok(())
 }
+
+#[test]
+fn network_preimage_hashes_follow_ledger_network_id() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let invocation = SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::CreateContractHostFn(CreateContractArgs {
+            contract_id_preimage: ContractIdPreimage::Asset(Asset::Native),
+            executable: ContractExecutable::StellarAsset,
+        }),
+        sub_invocations: Default::default(),
+    };
+    host.set_ledger_info(Default::default())?;
+    for (protocol_version, network_id) in [
+        (NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION - 1, [1; 32]),
+        (NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION, [1; 32]),
+        (NETWORK_PREIMAGE_PREFIXES_PROTOCOL_VERSION, [2; 32]),
+    ] {
+        host.with_mut_ledger_info(|li| {
+            li.protocol_version = protocol_version;
+            li.network_id = network_id;
+        })?;
+        // The hashes computed from the cached prefixes match the hashes of
+        // the full preimages that earlier protocols compute.
+        let full_payload = host.metered_hash_xdr(&HashIdPreimage::SorobanAuthorization(
+            HashIdPreimageSorobanAuthorization {
+                network_id: Hash(network_id),
+                nonce: 123,
+                signature_expiration_ledger: 456,
+                invocation: invocation.clone(),
+            },
+        ))?;
+        assert_eq!(
+            host.soroban_authorization_payload(123, 456, invocation.clone())?,
+            full_payload
+        );
+        let full_contract_id =
+            host.metered_hash_xdr(&HashIdPreimage::ContractId(HashIdPreimageContractId {
+                network_id: Hash(network_id),
+                contract_id_preimage: ContractIdPreimage::Asset(Asset::Native),
+            }))?;
+        assert_eq!(
+            host.contract_id_from_preimage(ContractIdPreimage::Asset(Asset::Native))?,
+            Hash(full_contract_id)
+        );
+    }
+    Ok(())
+}
@@ -1,13 +1,16 @@
 use crate::auth::RecordedAuthPayload;
 use crate::{
     budget::{AsBudget, Budget},
+    events::FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION,
+    host::frame::CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION,
     storage::{AccessType, Footprint, Storage, StorageMap},
     xdr::{
-        self, ContractEvent, ContractEventBody, ContractEventType, ContractEventV0,
+        self, Asset, ContractEvent, ContractEventBody, ContractEventType, ContractEventV0,
         ContractExecutable, CreateContractArgs, ExtensionPoint, Hash, HashIdPreimage,
         HashIdPreimageContractId, LedgerEntryData, ScSymbol, ScVal, ScVec, Uint256,
     },
-    Env, Host, LedgerInfo, Symbol, DEFAULT_XDR_RW_LIMITS,
+    AddressObject, BytesObject, Env, Host, HostError, LedgerInfo, Symbol, TryFromVal,
+    DEFAULT_XDR_RW_LIMITS,
 };
 use sha2::{Digest, Sha256};
 use soroban_env_common::xdr::{
@@ -19,7 +22,11 @@ use soroban_env_common::{xdr::ScBytes, EnvBase, TryIntoVal, Val};
 use soroban_env_common::{StorageType, VecObject};
 use soroban_test_wasms::{ADD_I32, CREATE_CONTRACT, UPDATEABLE_CONTRACT};
 
-use crate::testutils::{generate_account_id, generate_bytes_array};
+use crate::testutils::{
+    generate_account_id, generate_bytes_array, wasm::wasm_module_with_constructor,
+    MockSnapshotSource,
+};
+use std::rc::Rc;
 
 fn get_contract_wasm_ref(host: &Host, contract_id: Hash) -> Hash {
     let storage_key = host.contract_instance_ledger_key(&contract_id).unwrap();
@@ -251,6 +258,31 @@ pub(crate) fn sha256_hash_id_preimage<T: xdr::WriteXdr>(pre_image: T) -> xdr::Ha
     )
 }
 
+// Checks that the only event is the failed system event of a contract update
+// that got rolled back, and returns the number of events retained for it.
+// From FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION the events of failed
+// calls are only retained in debug mode.
+fn check_failed_call_system_event(host: &Host) -> usize {
+    let failed_call_events = host.get_events().unwrap().0;
+    if host.get_ledger_protocol_version().unwrap()
+        >= FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION
+    {
+        assert!(failed_call_events.is_empty());
+        return 0;
+    }
+    assert_eq!(failed_call_events.len(), 1);
+    match failed_call_events.last() {
+        Some(he) => {
+            assert!(he.failed_call);
+            assert_eq!(he.event.type_, ContractEventType::System);
+        }
+        _ => {
+            panic!("unexpected event");
+        }
+    }
+    1
+}
+
 #[test]
 fn test_contract_wasm_update() {
     let host = observe_host!(Host::test_host_with_recording_footprint());
@@ -300,17 +332,7 @@ fn test_contract_wasm_update() {
     assert!(failed_call_err.is_code(ScErrorCode::InvalidAction));
     // The update now has happened, but then got rolled back. Make sure
     // that it got converted to a failed system event.
-    let failed_call_events = host.get_events().unwrap().0;
-    assert_eq!(failed_call_events.len(), 1);
-    match failed_call_events.last() {
-        Some(he) => {
-            assert!(he.failed_call);
-            assert_eq!(he.event.type_, ContractEventType::System);
-        }
-        _ => {
-            panic!("unexpected event");
-        }
-    }
+    let failed_call_events = check_failed_call_system_event(&host);
 
     let res: i32 = host
         .call(
@@ -354,7 +376,7 @@ fn test_contract_wasm_update() {
             updated_wasm_hash_obj.try_into_val(&*host).unwrap(),
         )
         .unwrap();
-    assert_eq!(events.len(), 2);
+    assert_eq!(events.len(), failed_call_events + 1);
     match events.last() {
         Some(he) => {
             assert!(!he.failed_call);
@@ -442,17 +464,7 @@ fn test_contract_wasm_update_with_try_call() {
     assert_eq!(failed_call_res, None);
 
     // Make sure failure event is recorded.
-    let failed_call_events = host.get_events().unwrap().0;
-    assert_eq!(failed_call_events.len(), 1);
-    match failed_call_events.last() {
-        Some(he) => {
-            assert!(he.failed_call);
-            assert_eq!(he.event.type_, ContractEventType::System);
-        }
-        _ => {
-            panic!("unexpected event");
-        }
-    }
+    let failed_call_events = check_failed_call_system_event(&host);
 
     let res: Option<i32> = host
         .call(
@@ -471,7 +483,7 @@ fn test_contract_wasm_update_with_try_call() {
         .unwrap();
     assert_eq!(res, Some(123));
     let success_call_events = host.get_events().unwrap().0;
-    assert_eq!(success_call_events.len(), 2);
+    assert_eq!(success_call_events.len(), failed_call_events + 1);
     // Make sure event is recorded.
     match success_call_events.last() {
         Some(he) => {
@@ -539,6 +551,298 @@ fn test_create_contract_from_source_account_recording_auth() {
     );
 }
 
+#[test]
+fn test_preflight_create_contract() {
+    let host = Host::test_host_with_recording_footprint();
+    let source_account = generate_account_id(&host);
+    host.set_source_account(source_account.clone()).unwrap();
+
+    let res = host
+        .preflight_invocation(HostFunction::UploadContractWasm(
+            CREATE_CONTRACT.try_into().unwrap(),
+        ))
+        .unwrap();
+    let wasm_hash: Val = res.invoke_result.unwrap().try_into_val(&host).unwrap();
+    let wasm_hash = host
+        .hash_from_bytesobj_input("wasm_hash", wasm_hash.try_into().unwrap())
+        .unwrap();
+    let code_key = host
+        .contract_code_ledger_key(&wasm_hash)
+        .unwrap()
+        .as_ref()
+        .clone();
+    assert!(res.resources.footprint.read_only.is_empty());
+    assert_eq!(
+        res.resources.footprint.read_write.as_slice(),
+        std::slice::from_ref(&code_key)
+    );
+    assert_eq!(res.resources.read_bytes, 0);
+    assert!(res.resources.write_bytes as usize > CREATE_CONTRACT.len());
+    assert!(res.resources.instructions > 0);
+    assert!(res.memory_bytes > 0);
+    assert_eq!(res.contract_events_size_bytes, 0);
+    assert!(res.auth_entries.is_empty());
+
+    // Every preflight runs on a fresh host, with the uploaded code in the
+    // ledger.
+    let snapshot_source = Rc::new(MockSnapshotSource::new());
+    host.try_borrow_storage()
+        .unwrap()
+        .commit_to_backend(snapshot_source.as_ref(), host.as_budget())
+        .unwrap();
+    let host = Host::test_host_with_snapshot_source(snapshot_source);
+    host.set_source_account(source_account.clone()).unwrap();
+    let create_contract_args = CreateContractArgs {
+        contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+            address: ScAddress::Account(source_account),
+            salt: Uint256(generate_bytes_array(&host)),
+        }),
+        executable: ContractExecutable::Wasm(wasm_hash),
+    };
+    let res = host
+        .preflight_invocation(HostFunction::CreateContract(create_contract_args.clone()))
+        .unwrap();
+    let ScVal::Address(ScAddress::Contract(contract_id)) = res.invoke_result.unwrap() else {
+        panic!("expected a contract address");
+    };
+    // The code is only read, the new instance is written.
+    assert_eq!(res.resources.footprint.read_only.as_slice(), &[code_key]);
+    assert_eq!(
+        res.resources.footprint.read_write.as_slice(),
+        &[host
+            .contract_instance_ledger_key(&contract_id)
+            .unwrap()
+            .as_ref()
+            .clone()]
+    );
+    assert!(res.resources.read_bytes as usize > CREATE_CONTRACT.len());
+    assert_eq!(
+        res.auth_entries,
+        vec![SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::SourceAccount,
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::CreateContractHostFn(create_contract_args),
+                sub_invocations: VecM::default(),
+            },
+        }]
+    );
+}
+
+#[test]
+fn test_simulate_transaction() {
+    use crate::fees::{compute_transaction_resource_fee, FeeConfiguration, RentFeeConfiguration};
+    let fee_config = FeeConfiguration {
+        fee_per_instruction_increment: 100,
+        fee_per_read_entry: 1000,
+        fee_per_write_entry: 2000,
+        fee_per_read_1kb: 100,
+        fee_per_write_1kb: 500,
+        fee_per_historical_1kb: 300,
+        fee_per_contract_event_1kb: 200,
+        fee_per_transaction_size_1kb: 900,
+    };
+    let rent_fee_config = RentFeeConfiguration {
+        fee_per_write_1kb: 500,
+        fee_per_write_entry: 2000,
+        persistent_rent_rate_denominator: 10,
+        temporary_rent_rate_denominator: 100,
+    };
+    let host = Host::test_host_with_recording_footprint();
+    let source_account = generate_account_id(&host);
+
+    let res = host
+        .simulate_transaction(
+            HostFunction::UploadContractWasm(CREATE_CONTRACT.try_into().unwrap()),
+            source_account.clone(),
+            None,
+            1000,
+            &fee_config,
+            &rent_fee_config,
+        )
+        .unwrap();
+    let wasm_hash: Val = res
+        .preflight
+        .invoke_result
+        .unwrap()
+        .try_into_val(&host)
+        .unwrap();
+    let wasm_hash = host
+        .hash_from_bytesobj_input("wasm_hash", wasm_hash.try_into().unwrap())
+        .unwrap();
+    let resources = &res.transaction_resources;
+    assert_eq!((resources.read_entries, resources.write_entries), (0, 1));
+    assert_eq!(resources.instructions, res.preflight.resources.instructions);
+    assert_eq!(resources.write_bytes, res.preflight.resources.write_bytes);
+    assert_eq!(resources.transaction_size_bytes, 1000);
+    assert_eq!(
+        (res.non_refundable_fee, res.refundable_fee),
+        compute_transaction_resource_fee(resources, &fee_config)
+    );
+    // The new contract code entry pays rent.
+    assert!(res.rent_fee > 0);
+
+    // The provided authorization entries are enforced instead of recorded.
+    // Every simulation runs on a fresh host, with the uploaded code in the
+    // ledger.
+    let snapshot_source = Rc::new(MockSnapshotSource::new());
+    host.try_borrow_storage()
+        .unwrap()
+        .commit_to_backend(snapshot_source.as_ref(), host.as_budget())
+        .unwrap();
+    let host = Host::test_host_with_snapshot_source(snapshot_source.clone());
+    let create_contract_args = CreateContractArgs {
+        contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+            address: ScAddress::Account(source_account.clone()),
+            salt: Uint256([0; 32]),
+        }),
+        executable: ContractExecutable::Wasm(wasm_hash),
+    };
+    let auth_entries = vec![SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::SourceAccount,
+        root_invocation: SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::CreateContractHostFn(create_contract_args.clone()),
+            sub_invocations: VecM::default(),
+        },
+    }];
+    let res = host
+        .simulate_transaction(
+            HostFunction::CreateContract(create_contract_args.clone()),
+            source_account.clone(),
+            Some(auth_entries.clone()),
+            1000,
+            &fee_config,
+            &rent_fee_config,
+        )
+        .unwrap();
+    assert!(res.preflight.invoke_result.is_ok());
+    assert_eq!(res.preflight.auth_entries, auth_entries);
+    // The code is read and the new instance is written.
+    let resources = &res.transaction_resources;
+    assert_eq!((resources.read_entries, resources.write_entries), (1, 1));
+
+    // The entries don't authorize the creation of another contract.
+    let host = Host::test_host_with_snapshot_source(snapshot_source);
+    let res = host
+        .simulate_transaction(
+            HostFunction::CreateContract(CreateContractArgs {
+                contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                    address: ScAddress::Account(source_account.clone()),
+                    salt: Uint256([1; 32]),
+                }),
+                ..create_contract_args
+            }),
+            source_account,
+            Some(auth_entries),
+            1000,
+            &fee_config,
+            &rent_fee_config,
+        )
+        .unwrap();
+    assert!(HostError::result_matches_err(
+        res.preflight.invoke_result,
+        (ScErrorType::Auth, ScErrorCode::InvalidAction)
+    ));
+}
+
+#[test]
+fn test_replay_invocation() {
+    use crate::e2e_invoke::{replay_invocation, RecordedInvocation, ReplayDivergence};
+    use crate::testutils::LedgerSnapshot;
+    use crate::xdr::{
+        InvokeHostFunctionOp, LedgerEntry, LedgerFootprint, LedgerKey, LedgerKeyContractCode,
+        ReadXdr, SorobanResources,
+    };
+
+    let host = Host::test_host();
+    let wasm_hash = Hash(Sha256::digest(CREATE_CONTRACT).try_into().unwrap());
+    let code_key = LedgerKey::ContractCode(LedgerKeyContractCode {
+        hash: wasm_hash.clone(),
+    });
+    let mut invocation = RecordedInvocation {
+        op: InvokeHostFunctionOp {
+            host_function: HostFunction::UploadContractWasm(CREATE_CONTRACT.try_into().unwrap()),
+            auth: VecM::default(),
+        },
+        source_account: generate_account_id(&host),
+        resources: SorobanResources {
+            footprint: LedgerFootprint {
+                read_only: VecM::default(),
+                read_write: vec![code_key.clone()].try_into().unwrap(),
+            },
+            instructions: 10_000_000,
+            read_bytes: 0,
+            write_bytes: 10_000,
+        },
+        base_prng_seed: *Host::TEST_PRNG_SEED,
+        recorded_result: Some(ScVal::Bytes(wasm_hash.0.to_vec().try_into().unwrap())),
+    };
+    let mut snapshot = LedgerSnapshot {
+        ledger_info: LedgerInfo {
+            protocol_version: 0,
+            sequence_number: 100,
+            min_temp_entry_ttl: 16,
+            min_persistent_entry_ttl: 4096,
+            max_entry_ttl: 6_312_000,
+            ..Default::default()
+        },
+        entries: vec![],
+    };
+    let protocol_version = crate::meta::get_ledger_protocol_version(crate::meta::INTERFACE_VERSION);
+
+    let res =
+        replay_invocation(&Budget::default(), &snapshot, &invocation, protocol_version).unwrap();
+    assert!(res.invoke_result.encoded_invoke_result.is_ok());
+    assert!(res.divergence.is_none());
+
+    // Replay on top of the state left by the first invocation, against a
+    // different recorded value.
+    let change = &res.invoke_result.ledger_changes[0];
+    let entry = LedgerEntry::from_xdr(
+        change.encoded_new_value.as_ref().unwrap(),
+        DEFAULT_XDR_RW_LIMITS,
+    )
+    .unwrap();
+    let live_until = change.ttl_change.as_ref().unwrap().new_live_until_ledger;
+    snapshot
+        .entries
+        .push((Rc::new(code_key), (Rc::new(entry), Some(live_until))));
+    invocation.recorded_result = Some(ScVal::Void);
+    let res =
+        replay_invocation(&Budget::default(), &snapshot, &invocation, protocol_version).unwrap();
+    assert!(matches!(
+        res.divergence,
+        Some(ReplayDivergence::ResultMismatch {
+            recorded: ScVal::Void,
+            replayed: ScVal::Bytes(_),
+        })
+    ));
+    // The entry was provided by the snapshot, so it is not created again.
+    assert_eq!(
+        res.invoke_result.ledger_changes[0].old_entry_size_bytes,
+        change.encoded_new_value.as_ref().unwrap().len() as u32
+    );
+
+    invocation.recorded_result = None;
+    let res =
+        replay_invocation(&Budget::default(), &snapshot, &invocation, protocol_version).unwrap();
+    assert!(matches!(
+        res.divergence,
+        Some(ReplayDivergence::UnexpectedSuccess { .. })
+    ));
+}
+
+#[test]
+fn test_preflight_requires_recording_footprint() {
+    let host = Host::test_host();
+    let res = host.preflight_invocation(HostFunction::UploadContractWasm(
+        CREATE_CONTRACT.try_into().unwrap(),
+    ));
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Storage, ScErrorCode::InvalidAction)
+    ));
+}
+
 #[test]
 fn test_invalid_contract() {
     let host = observe_host!(Host::test_host_with_recording_footprint());
@@ -566,3 +870,147 @@ fn test_large_contract() {
     assert!(err.error.is_type(ScErrorType::Budget));
     assert!(err.error.is_code(ScErrorCode::ExceededLimit));
 }
+
+fn create_contract_with_constructor_args(
+    host: &Host,
+    deployer: AddressObject,
+    wasm_hash: BytesObject,
+    salt: BytesObject,
+    args: &[Val],
+) -> Result<AddressObject, HostError> {
+    let args = host.vec_new_from_slice(args)?;
+    host.create_contract_with_constructor(deployer, wasm_hash, salt, args)
+}
+
+#[test]
+fn test_create_contract_with_constructor() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    // The failing creations below run in a contract frame, so the auth is
+    // not required at the root.
+    host.switch_to_recording_auth(false)?;
+    // Deploy on behalf of the source account, so that the auth doesn't need
+    // the deployer account entry.
+    let account = generate_account_id(&host);
+    host.set_source_account(account.clone())?;
+    let deployer = host.add_host_object(ScAddress::Account(account))?;
+    let wasm = wasm_module_with_constructor();
+    let wasm_hash = host.upload_wasm(host.bytes_new_from_slice(&wasm)?)?;
+
+    // The constructor runs on creation, with the provided arguments.
+    let salt = host.bytes_new_from_slice(&[1; 32])?;
+    let contract =
+        create_contract_with_constructor_args(&host, deployer, wasm_hash, salt, &[7_u32.into()])?;
+    let stored = host.call(
+        contract,
+        Symbol::try_from_small_str("get")?,
+        host.vec_new()?,
+    )?;
+    assert_eq!(u32::try_from_val(&host, &stored)?, 7);
+    // It can't be invoked again afterwards.
+    let res = host.call(
+        contract,
+        Symbol::try_from_val(&host, &"__constructor")?,
+        host.test_vec_obj::<u32>(&[8])?,
+    );
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Context, ScErrorCode::InvalidAction)
+    ));
+
+    // From protocol 21 the other creation functions run the constructor too,
+    // with no arguments, which this one doesn't accept. Earlier protocols
+    // don't run it at all.
+    let salt = host.bytes_new_from_slice(&[4; 32])?;
+    let res = host.create_contract(deployer, wasm_hash, salt);
+    if host.get_ledger_protocol_version()? >= CONSTRUCTOR_ON_ALL_CREATIONS_PROTOCOL_VERSION {
+        assert!(res.is_err());
+    } else {
+        let res = host.call(res?, Symbol::try_from_small_str("get")?, host.vec_new()?);
+        assert!(HostError::result_matches_err(
+            res,
+            (ScErrorType::Storage, ScErrorCode::MissingValue)
+        ));
+    }
+
+    // A failing constructor fails the creation, and the frame of the caller
+    // rolls back the contract instance.
+    let caller_id = host.get_contract_id_hash(deployer, salt)?;
+    let create_in_frame = |wasm_hash: BytesObject, salt: BytesObject, args: &[Val]| {
+        host.with_test_contract_frame(
+            caller_id.clone(),
+            Symbol::try_from_small_str("deploy")?,
+            || {
+                create_contract_with_constructor_args(&host, deployer, wasm_hash, salt, args)?;
+                Ok(Val::VOID.into())
+            },
+        )
+    };
+    let salt = host.bytes_new_from_slice(&[2; 32])?;
+    assert!(create_in_frame(wasm_hash, salt, &[]).is_err());
+    let contract_id = host.get_contract_id_hash(deployer, salt)?;
+    let instance_key = host.contract_instance_ledger_key(&contract_id)?;
+    assert!(!host.with_mut_storage(|s| s.has(&instance_key, host.as_budget()))?);
+
+    // Contracts without a constructor can only be created without arguments.
+    let wasm_hash = host.upload_wasm(host.bytes_new_from_slice(ADD_I32)?)?;
+    let salt = host.bytes_new_from_slice(&[3; 32])?;
+    assert!(HostError::result_matches_err(
+        create_in_frame(wasm_hash, salt, &[7_u32.into()]),
+        (ScErrorType::Context, ScErrorCode::InvalidAction)
+    ));
+    create_in_frame(wasm_hash, salt, &[])?;
+    Ok(())
+}
+
+#[test]
+fn test_contract_id_from_preimage_matches_host_derivations() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.switch_to_recording_auth(true)?;
+    let network_id = host.hash_from_bytesobj_input("network_id", host.get_ledger_network_id()?)?;
+    let salt = generate_bytes_array(&host);
+
+    // Account deployer: the id matches the one of the created contract.
+    let account = generate_account_id(&host);
+    host.set_source_account(account.clone())?;
+    let deployer = host.add_host_object(ScAddress::Account(account.clone()))?;
+    let wasm_hash = host.upload_wasm(host.bytes_new_from_slice(ADD_I32)?)?;
+    let created = host.create_contract(deployer, wasm_hash, host.bytes_new_from_slice(&salt)?)?;
+    let id = host.contract_id_from_preimage(ContractIdPreimage::Address(
+        ContractIdPreimageFromAddress {
+            address: ScAddress::Account(account),
+            salt: Uint256(salt),
+        },
+    ))?;
+    assert_eq!(host.contract_id_from_address(created)?, id);
+
+    // Contract deployer: the id matches `get_contract_id` and the hash of
+    // the full preimage.
+    let preimage = ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+        address: ScAddress::Contract(id.clone()),
+        salt: Uint256(salt),
+    });
+    let child_id = host.contract_id_from_preimage(preimage.clone())?;
+    let child_address = host.get_contract_id(
+        host.add_host_object(ScAddress::Contract(id))?,
+        host.bytes_new_from_slice(&salt)?,
+    )?;
+    assert_eq!(host.contract_id_from_address(child_address)?, child_id);
+    assert_eq!(
+        child_id,
+        sha256_hash_id_preimage(HashIdPreimage::ContractId(HashIdPreimageContractId {
+            network_id: network_id.clone(),
+            contract_id_preimage: preimage,
+        }))
+    );
+
+    // Stellar asset: the id matches the hash of the full preimage.
+    let asset_id = host.contract_id_from_preimage(ContractIdPreimage::Asset(Asset::Native))?;
+    assert_eq!(
+        asset_id,
+        sha256_hash_id_preimage(HashIdPreimage::ContractId(HashIdPreimageContractId {
+            network_id,
+            contract_id_preimage: ContractIdPreimage::Asset(Asset::Native),
+        }))
+    );
+    Ok(())
+}
@@ -9,3 +9,53 @@ use soroban_synth_wasm::{Arity, LocalRef, ModEmitter, Operand};
 use soroban_env_macros::generate_linear_memory_host_fn_tests;
 
 generate_linear_memory_host_fn_tests!("../soroban-env-common/env.json");
+
+#[test]
+fn log_from_linear_memory_emits_diagnostic_event() -> Result<(), HostError> {
+    let wasm = wasm_module_calling_log_from_linear_memory();
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let contract_id_obj = host.register_test_contract_wasm(wasm.as_slice());
+    // 16 bytes of the chars at the end of the linear memory, followed by three
+    // of the U32Vals preceding them.
+    let args = host.test_vec_obj::<u32>(&[0xFF00, 16, 0xFE00, 3])?;
+    host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("loadmem1")?,
+        args,
+    )?;
+
+    let events = host.get_events()?.0;
+    let log_events: Vec<String> = events
+        .iter()
+        .map(|e| format!("{}", e))
+        .filter(|e| e.contains("topics:[log]"))
+        .collect();
+    assert_eq!(log_events.len(), 1);
+    assert!(log_events[0].ends_with(r#"topics:[log], data:["1111111122222222", 100, 101, 102]"#));
+    Ok(())
+}
+
+#[test]
+fn symbol_new_from_linear_memory_checks_chars() -> Result<(), HostError> {
+    let wasm = wasm_module_calling_symbol_new_from_linear_memory();
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id_obj = host.register_test_contract_wasm(wasm.as_slice());
+    // The last 256 bytes hold encoded U32Vals, which are not symbol chars.
+    let args = host.test_vec_obj::<u32>(&[0xFF00, 32])?;
+    let res = host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("loadmem2")?,
+        args,
+    );
+    // They are only rejected from protocol 21 on.
+    if host.get_ledger_protocol_version()? >= 21 {
+        assert!(HostError::result_matches_err(
+            res,
+            (ScErrorType::Value, ScErrorCode::InvalidInput)
+        ));
+    } else {
+        assert!(res.is_ok());
+    }
+    Ok(())
+}
@@ -607,3 +607,37 @@ fn linear_memory_operations() -> Result<(), HostError> {
     }
     Ok(())
 }
+
+#[test]
+fn scmap_key_order_policy() -> Result<(), HostError> {
+    use crate::ScMapKeyOrderPolicy;
+
+    let host = Host::test_host();
+    let scmap = |keys: &[u32]| {
+        ScVal::Map(Some(ScMap(
+            keys.iter()
+                .map(|k| ScMapEntry {
+                    key: ScVal::U32(*k),
+                    val: ScVal::U32(*k * 10),
+                })
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        )))
+    };
+    // By default, unsorted maps are rejected.
+    assert!(host.to_host_val(&scmap(&[2, 1, 3])).is_err());
+    assert!(host.to_host_val(&scmap(&[1, 1])).is_err());
+
+    // Canonicalization sorts the entries, but still rejects duplicate keys.
+    host.set_scmap_key_order_policy(ScMapKeyOrderPolicy::Canonicalize)?;
+    let map = host.to_host_val(&scmap(&[2, 1, 3]))?;
+    assert_eq!(host.from_host_val(map)?, scmap(&[1, 2, 3]));
+    let map = MapObject::try_from(map)?;
+    assert_eq!(
+        host.map_get(map, U32Val::from(2).to_val())?.get_payload(),
+        U32Val::from(20).to_val().get_payload()
+    );
+    assert!(host.to_host_val(&scmap(&[1, 2, 1])).is_err());
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::{
+    testutils::{generate_bytes_array, mock_contracts::MockTokenError},
+    xdr::{Hash, ScAddress},
+    AddressObject, Env, EnvBase, Error, Host, HostError, Symbol, TryFromVal, TryIntoVal, Val,
+};
+
+fn call(host: &Host, contract: AddressObject, func: &str, args: &[Val]) -> Result<Val, HostError> {
+    let args = host.vec_new_from_slice(args)?;
+    host.call(contract, Symbol::try_from_val(host, &func)?, args)
+}
+
+#[test]
+fn mock_price_oracle() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let oracle = host.register_mock_price_oracle(14)?;
+    let xlm = Symbol::try_from_small_str("XLM")?.to_val();
+
+    let decimals: u32 = call(&host, oracle, "decimals", &[])?.try_into_val(&host)?;
+    assert_eq!(decimals, 14);
+    assert!(call(&host, oracle, "lastprice", &[xlm])?.is_void());
+    let price = Val::try_from_val(&host, &12_345_i128)?;
+    call(&host, oracle, "set_price", &[xlm, price])?;
+    let last_price: i128 = call(&host, oracle, "lastprice", &[xlm])?.try_into_val(&host)?;
+    assert_eq!(last_price, 12_345);
+    Ok(())
+}
+
+#[test]
+fn mock_token() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.switch_to_recording_auth(true)?;
+    let token = host.register_mock_token("Test Token", "TEST", 7)?;
+    // Use contract addresses, as emulating the authentication of accounts in
+    // the recording mode needs their ledger entries.
+    let alice = host
+        .add_host_object(ScAddress::Contract(Hash(generate_bytes_array(&host))))?
+        .to_val();
+    let bob = host
+        .add_host_object(ScAddress::Contract(Hash(generate_bytes_array(&host))))?
+        .to_val();
+    let balance = |id: Val| -> Result<i128, HostError> {
+        Ok(call(&host, token, "balance", &[id])?.try_into_val(&host)?)
+    };
+    let amount = |amount: i128| Val::try_from_val(&host, &amount);
+
+    let decimals: u32 = call(&host, token, "decimals", &[])?.try_into_val(&host)?;
+    assert_eq!(decimals, 7);
+    call(&host, token, "mint", &[alice, amount(100)?])?;
+    call(&host, token, "transfer", &[alice, bob, amount(30)?])?;
+    assert_eq!((balance(alice)?, balance(bob)?), (70, 30));
+
+    // Failed transfers leave the balances unchanged.
+    assert!(HostError::result_matches_err(
+        call(&host, token, "transfer", &[bob, alice, amount(31)?]),
+        Error::from(MockTokenError::InsufficientBalance)
+    ));
+    assert!(HostError::result_matches_err(
+        call(&host, token, "mint", &[bob, amount(-1)?]),
+        Error::from(MockTokenError::NegativeAmount)
+    ));
+    assert_eq!((balance(alice)?, balance(bob)?), (70, 30));
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use soroban_env_common::{
     num::*,
     xdr::{ScErrorCode, ScErrorType, ScVal},
-    Compare, Env, EnvBase, TryFromVal, TryIntoVal, I256,
+    Compare, Env, EnvBase, I256Val, TryFromVal, TryIntoVal, U256Val, I256, U256,
 };
 
 use crate::{budget::AsBudget, Host, HostError, Val};
@@ -437,3 +437,53 @@ fn test_u256_bytes_roundtrip() -> Result<(), HostError> {
     assert_eq!(num, num_back);
     Ok(())
 }
+
+#[test]
+fn test_256_scval_and_string_conversions() -> Result<(), HostError> {
+    let host = Host::test_host();
+
+    let u = U256::from_words(0x0123_4567_89ab_cdef, u128::MAX);
+    let i = I256::from_words(-0x0123_4567_89ab_cdef, 42);
+    assert_eq!(u256_from_scval(&u256_into_scval(u))?, u);
+    assert_eq!(i256_from_scval(&i256_into_scval(i))?, i);
+    assert!(u256_from_scval(&i256_into_scval(i)).is_err());
+
+    // ScVals built with the helpers agree with the host's conversions.
+    let v: Val = u.try_into_val(&host)?;
+    assert_eq!(ScVal::try_from_val(&host, &v)?, u256_into_scval(u));
+    let v: Val = i.try_into_val(&host)?;
+    assert_eq!(ScVal::try_from_val(&host, &v)?, i256_into_scval(i));
+    let v: U256Val = u.try_into_val(&host)?;
+    assert_eq!(U256::try_from_val(&host, &v)?, u);
+    let v: I256Val = i.try_into_val(&host)?;
+    assert_eq!(I256::try_from_val(&host, &v)?, i);
+
+    for u in [U256::ZERO, U256::ONE, MAX_SMALL_U256 + 1, U256::MAX] {
+        assert_eq!(u256_from_str(&u.to_string())?, u);
+        assert_eq!(u256_from_str(&U256Hex(u).to_string())?, u);
+    }
+    for i in [
+        I256::ZERO,
+        I256::MINUS_ONE,
+        MIN_SMALL_I256 - 1,
+        I256::MIN,
+        I256::MAX,
+    ] {
+        assert_eq!(i256_from_str(&i.to_string())?, i);
+        assert_eq!(i256_from_str(&I256Hex(i).to_string())?, i);
+    }
+    assert_eq!(U256Hex(U256::new(255)).to_string(), "0xff");
+    assert_eq!(I256Hex(I256::new(-255)).to_string(), "-0xff");
+    assert_eq!(i256_from_str("-0x80")?, I256::new(-128));
+
+    for bad in ["", "0x", "-1", "+1", "0x-1", " 1", "1_000", "0xg"] {
+        assert!(u256_from_str(bad).is_err(), "{bad}");
+    }
+    let too_big = U256::MAX.to_string();
+    assert!(u256_from_str(&(too_big.clone() + "0")).is_err());
+    assert!(i256_from_str(&too_big).is_err());
+    assert!(i256_from_str(&format!("-{}", I256::MIN.unsigned_abs() + 1)).is_err());
+    assert!(i256_from_str(&format!("{}", I256::MIN.unsigned_abs())).is_err());
+    assert!(i256_from_str("--1").is_err());
+    Ok(())
+}
@@ -348,7 +348,7 @@ impl Host {
         if let Ok(evts) = self.try_borrow_events() {
             evts.vec
                 .iter()
-                .filter(|(e, _)| match e {
+                .filter(|(e, _, _)| match e {
                     InternalEvent::Contract(_) => true,
                     InternalEvent::Diagnostic(_) => false,
                 })
@@ -359,10 +359,15 @@ impl Host {
     }
     fn events_hash(&self) -> u64 {
         if let Ok(evts) = self.try_borrow_events() {
-            hash_iter(evts.vec.iter().filter(|(e, _)| match e {
-                InternalEvent::Contract(_) => true,
-                InternalEvent::Diagnostic(_) => false,
-            }))
+            hash_iter(
+                evts.vec
+                    .iter()
+                    .filter(|(e, _, _)| match e {
+                        InternalEvent::Contract(_) => true,
+                        InternalEvent::Diagnostic(_) => false,
+                    })
+                    .map(|(e, status, _)| (e, status)),
+            )
         } else {
             0
         }
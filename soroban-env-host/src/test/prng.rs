@@ -4,9 +4,10 @@ use rand_chacha::ChaCha20Rng;
 
 use crate::{
     test::observe::ObservedHost,
-    xdr::{Hash, ScAddress, ScVal, ScVec},
+    xdr::{Hash, ScAddress, ScErrorCode, ScErrorType, ScVal, ScVec},
     AddressObject, BytesObject, ContractFunctionSet, Env, EnvBase, Host, HostError, Symbol,
     SymbolSmall, TryFromVal, TryIntoVal, U32Val, U64Object, U64Val, Val, VecObject,
+    FRAME_PRNG_DERIVATION_PROTOCOL_VERSION,
 };
 
 /// prng tests
@@ -140,6 +141,197 @@ fn prng_test() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn frame_prng_seeds_are_derived_from_base_seed() -> Result<(), HostError> {
+    let base_seed = [7; 32];
+    let host = Host::test_host_with_recording_footprint();
+    host.set_base_prng_seed(base_seed)?;
+    host.with_mut_ledger_info(|li| li.protocol_version = FRAME_PRNG_DERIVATION_PROTOCOL_VERSION)?;
+    let ids = [Hash([1; 32]), Hash([2; 32])];
+    for id in ids.iter() {
+        let addr = host.add_host_object(ScAddress::Contract(id.clone()))?;
+        host.register_test_contract(addr, std::rc::Rc::new(PRNGUsingTest))?;
+    }
+
+    let frame_bytes = |id: &Hash| -> Result<[u8; SEED_LEN as usize], HostError> {
+        let mut buf = [0u8; SEED_LEN as usize];
+        host.with_test_contract_frame(id.clone(), BYTES_NEW.into(), || {
+            let bytes = host.prng_bytes_new(U32Val::from(SEED_LEN))?;
+            host.bytes_copy_to_slice(bytes, U32Val::from(0), &mut buf)?;
+            Ok(Val::VOID.into())
+        })?;
+        Ok(buf)
+    };
+    let expected_bytes = |id: &Hash, counter: u64| {
+        let seed = crate::derive_frame_prng_seed(&base_seed, Some(id), counter);
+        let mut buf = [0u8; SEED_LEN as usize];
+        ChaCha20Rng::from_seed(seed).fill_bytes(&mut buf);
+        buf
+    };
+
+    // Each frame's stream is reproducible from the base seed, its contract
+    // and the number of frame PRNGs derived before it, so repeated
+    // invocations of the same contract see different streams.
+    let first = frame_bytes(&ids[0])?;
+    let second = frame_bytes(&ids[1])?;
+    let third = frame_bytes(&ids[0])?;
+    assert_eq!(first, expected_bytes(&ids[0], 0));
+    assert_eq!(second, expected_bytes(&ids[1], 1));
+    assert_eq!(third, expected_bytes(&ids[0], 2));
+    assert_ne!(first, third);
+    assert_ne!(
+        crate::derive_frame_prng_seed(&base_seed, Some(&ids[0]), 0),
+        crate::derive_frame_prng_seed(&base_seed, Some(&ids[1]), 0)
+    );
+
+    // Earlier protocols keep drawing the frame seeds from the base PRNG.
+    host.set_base_prng_seed(base_seed)?;
+    host.with_mut_ledger_info(|li| {
+        li.protocol_version = FRAME_PRNG_DERIVATION_PROTOCOL_VERSION - 1
+    })?;
+    assert_ne!(frame_bytes(&ids[0])?, expected_bytes(&ids[0], 0));
+    Ok(())
+}
+
+#[test]
+fn base_prng_hash_covers_frame_prng_derivations() -> Result<(), HostError> {
+    use std::hash::{Hash as _, Hasher};
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| li.protocol_version = FRAME_PRNG_DERIVATION_PROTOCOL_VERSION)?;
+    host.set_base_prng_seed([7; 32])?;
+    let id = Hash([1; 32]);
+    let addr = host.add_host_object(ScAddress::Contract(id.clone()))?;
+    host.register_test_contract(addr, std::rc::Rc::new(PRNGUsingTest))?;
+    let base_prng_hash = || -> Result<u64, HostError> {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (*host.try_borrow_base_prng()?).hash(&mut hasher);
+        Ok(hasher.finish())
+    };
+
+    // Deriving a frame PRNG doesn't touch the base stream, but it still
+    // changes the hashed state.
+    let before = base_prng_hash()?;
+    host.with_test_contract_frame(id, BYTES_NEW.into(), || {
+        host.prng_bytes_new(U32Val::from(SEED_LEN))?;
+        Ok(Val::VOID.into())
+    })?;
+    assert_ne!(before, base_prng_hash()?);
+    Ok(())
+}
+
+#[test]
+fn frame_prng_digest_tracks_draws() -> Result<(), HostError> {
+    // The digest before the first draw, after it, and after a second one.
+    type Digests = (Option<[u8; 32]>, [u8; 32], [u8; 32]);
+    let id = Hash([3; 32]);
+    let run = |seed: [u8; 32]| -> Result<Digests, HostError> {
+        let host = Host::test_host_with_recording_footprint();
+        host.set_base_prng_seed(seed)?;
+        host.with_mut_ledger_info(|li| {
+            li.protocol_version = FRAME_PRNG_DERIVATION_PROTOCOL_VERSION
+        })?;
+        let addr = host.add_host_object(ScAddress::Contract(id.clone()))?;
+        host.register_test_contract(addr, std::rc::Rc::new(PRNGUsingTest))?;
+        let mut digests = (None, [0; 32], [0; 32]);
+        host.with_test_contract_frame(id.clone(), U64_RANGE.into(), || {
+            digests.0 = host.current_frame_prng_digest()?;
+            host.prng_u64_in_inclusive_range(LO, HI)?;
+            digests.1 = host.current_frame_prng_digest()?.unwrap();
+            host.prng_u64_in_inclusive_range(LO, HI)?;
+            digests.2 = host.current_frame_prng_digest()?.unwrap();
+            Ok(Val::VOID.into())
+        })?;
+        Ok(digests)
+    };
+
+    let (unused, first, second) = run([5; 32])?;
+    assert_eq!(unused, None);
+    assert_ne!(first, second);
+    assert_eq!(run([5; 32])?, (None, first, second));
+    assert_ne!(run([6; 32])?.1, first);
+    Ok(())
+}
+
+#[test]
+fn prng_weighted_pick() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.set_base_prng_seed([0; 32])?;
+    let id = Hash([0; 32]);
+    let addr = host.add_host_object(ScAddress::Contract(id.clone()))?;
+    host.register_test_contract(addr, std::rc::Rc::new(PRNGUsingTest))?;
+
+    host.with_test_contract_frame(id, U64_RANGE.into(), || {
+        let weights = host.test_vec_obj::<u64>(&[0, 1, 0, 3])?;
+        let mut counts = [0u32; 4];
+        for _ in 0..200 {
+            let idx: u32 = host.prng_weighted_pick(weights)?.into();
+            counts[idx as usize] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[2], 0);
+        assert!(counts[1] > 0 && counts[3] > counts[1]);
+
+        for bad in [
+            host.test_vec_obj::<u64>(&[])?,
+            host.test_vec_obj::<u64>(&[0, 0])?,
+        ] {
+            assert!(HostError::result_matches_err(
+                host.prng_weighted_pick(bad),
+                (ScErrorType::Value, ScErrorCode::InvalidInput)
+            ));
+        }
+        let not_u64s = host.test_vec_obj::<u32>(&[1, 2])?;
+        assert!(HostError::result_matches_err(
+            host.prng_weighted_pick(not_u64s),
+            (ScErrorType::Value, ScErrorCode::UnexpectedType)
+        ));
+        Ok(Val::VOID.into())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn prng_128_bit_ranges() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.set_base_prng_seed([0; 32])?;
+    let id = Hash([0; 32]);
+    let addr = host.add_host_object(ScAddress::Contract(id.clone()))?;
+    host.register_test_contract(addr, std::rc::Rc::new(PRNGUsingTest))?;
+
+    host.with_test_contract_frame(id, U64_RANGE.into(), || {
+        // Ranges that don't fit in 64 bits, including negative ones.
+        let (ulo, uhi) = (u64::MAX as u128, u128::MAX - 1);
+        let (ilo, ihi) = (i128::MIN + 1, -(u64::MAX as i128));
+        for _ in 0..16 {
+            let u: u128 = host
+                .prng_u128_in_inclusive_range(ulo.try_into_val(&host)?, uhi.try_into_val(&host)?)?
+                .to_val()
+                .try_into_val(&host)?;
+            assert!(ulo <= u && u <= uhi);
+            let i: i128 = host
+                .prng_i128_in_inclusive_range(ilo.try_into_val(&host)?, ihi.try_into_val(&host)?)?
+                .to_val()
+                .try_into_val(&host)?;
+            assert!(ilo <= i && i <= ihi);
+        }
+
+        // Single-value ranges are allowed, empty ones are not.
+        let seven: u128 = host
+            .prng_u128_in_inclusive_range(7_u128.try_into_val(&host)?, 7_u128.try_into_val(&host)?)?
+            .to_val()
+            .try_into_val(&host)?;
+        assert_eq!(seven, 7);
+        let res = host
+            .prng_i128_in_inclusive_range(1_i128.try_into_val(&host)?, 0_i128.try_into_val(&host)?);
+        assert!(HostError::result_matches_err(
+            res,
+            (ScErrorType::Value, ScErrorCode::InvalidInput)
+        ));
+        Ok(Val::VOID.into())
+    })?;
+    Ok(())
+}
+
 // This test checks that setting the base seed to two different values
 // produces _frame_ PRNG behaviour that differs; and that setting it
 // to the same value twice produces the same behaviour both times.
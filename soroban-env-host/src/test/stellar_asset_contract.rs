@@ -15,6 +15,7 @@ use crate::{
             AccountContractSigner, AccountSigner, TestSigner,
         },
     },
+    events::FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION,
     host::{frame::TestContractFrame, Frame},
     testutils::generate_bytes_array,
     xdr::{
@@ -3181,22 +3182,25 @@ fn verify_nested_try_call_rollback() -> Result<(), HostError> {
 
     let events = test.host.get_events()?.0;
 
-    // Make sure the event emitted in "fail_after_updates" is marked as failed
-    assert!(
-        events
-            .iter()
-            .find(|e| {
-                if e.event.type_ == ContractEventType::Contract && e.event.contract_id.is_some() {
-                    let address = ScAddress::Contract(e.event.contract_id.clone().unwrap());
-                    let id = test.host.add_host_object(address).unwrap();
-                    test.host.obj_cmp(err_id_obj.to_val(), id.to_val()).unwrap() == 0
-                } else {
-                    false
-                }
-            })
-            .unwrap()
-            .failed_call
-    );
+    // Make sure the event emitted in "fail_after_updates" is marked as failed.
+    // From FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION it is only
+    // retained in debug mode.
+    let failed_call_event = events.iter().find(|e| {
+        if e.event.type_ == ContractEventType::Contract && e.event.contract_id.is_some() {
+            let address = ScAddress::Contract(e.event.contract_id.clone().unwrap());
+            let id = test.host.add_host_object(address).unwrap();
+            test.host.obj_cmp(err_id_obj.to_val(), id.to_val()).unwrap() == 0
+        } else {
+            false
+        }
+    });
+    if test.host.get_ledger_protocol_version()?
+        >= FAILED_CALL_EVENTS_AS_DIAGNOSTICS_PROTOCOL_VERSION
+    {
+        assert!(failed_call_event.is_none());
+    } else {
+        assert!(failed_call_event.unwrap().failed_call);
+    }
 
     Ok(())
 }
@@ -1,13 +1,22 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
 use crate::budget::{AsBudget, Budget};
-use crate::storage::{AccessType, Footprint, Storage};
+use crate::storage::{
+    AccessType, CrossContractStoragePolicy, Footprint, SnapshotSource, Storage, StorageAccessEvent,
+    StorageAccessKind,
+};
+use crate::testutils::{HostSnapshot, LedgerSnapshot, MockSnapshotSource};
 use crate::xdr::{
-    ContractDataDurability, LedgerKey, LedgerKeyContractData, ScAddress, ScErrorCode, ScErrorType,
-    ScVal,
+    ContractDataDurability, ContractDataEntry, ExtensionPoint, Hash, LedgerEntry, LedgerEntryData,
+    LedgerEntryExt, LedgerKey, LedgerKeyContractData, ScAddress, ScErrorCode, ScErrorType,
+    ScSymbol, ScVal,
+};
+use crate::{
+    CallLimits, Compare, DetachedHostState, DiagnosticLevel, Host, HostError, MeteredOrdMap,
+};
+use soroban_env_common::{
+    AddressObject, Env, StorageType, Symbol, TryFromVal, TryIntoVal, Val, VecObject,
 };
-use crate::{Host, HostError, MeteredOrdMap};
-use soroban_env_common::{AddressObject, Env, Symbol, TryFromVal, TryIntoVal};
 use soroban_test_wasms::{CONTRACT_STORAGE, INVOKE_CONTRACT};
 
 #[test]
@@ -107,6 +116,49 @@ fn footprint_attempt_to_write_readonly_entry() -> Result<(), HostError> {
     Ok(())
 }
 
+#[test]
+fn storage_commit_to_backend() -> Result<(), HostError> {
+    let budget = Budget::default();
+    budget.reset_unlimited()?;
+    let backend = Rc::new(MockSnapshotSource::new());
+    let key = Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract([0; 32].into()),
+        key: ScVal::I32(0),
+        durability: ContractDataDurability::Persistent,
+    }));
+    let entry = Rc::new(LedgerEntry {
+        last_modified_ledger_seq: 0,
+        data: LedgerEntryData::ContractData(ContractDataEntry {
+            contract: ScAddress::Contract([0; 32].into()),
+            key: ScVal::I32(0),
+            val: ScVal::I32(1),
+            durability: ContractDataDurability::Persistent,
+            ext: ExtensionPoint::V0,
+        }),
+        ext: LedgerEntryExt::V0,
+    });
+
+    // Write the entry and commit it to the backend.
+    let mut storage = Storage::with_recording_footprint(backend.clone());
+    storage.put(&key, &entry, Some(100), &budget)?;
+    assert!(!backend.has(&key)?);
+    storage.commit_to_backend(backend.as_ref(), &budget)?;
+    assert!(backend.has(&key)?);
+
+    // A new storage on top of the same backend observes the committed entry.
+    let mut storage = Storage::with_recording_footprint(backend.clone());
+    assert_eq!(
+        storage.get_with_live_until_ledger(&key, &budget)?,
+        (entry, Some(100))
+    );
+
+    // Deletions are committed as well.
+    storage.del(&key, &budget)?;
+    storage.commit_to_backend(backend.as_ref(), &budget)?;
+    assert!(!backend.has(&key)?);
+    Ok(())
+}
+
 fn storage_fn_name(host: &Host, fn_name: &str, storage: &str) -> Symbol {
     Symbol::try_from_val(host, &format!("{}_{}", fn_name, storage).as_str()).unwrap()
 }
@@ -477,3 +529,866 @@ fn test_large_instance_key() {
         test_vec![&*host, key, 1_u64].into(),
     );
 }
+
+#[test]
+fn test_advance_time_and_ledgers() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let key = Symbol::try_from_small_str("key_1")?;
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "temporary"),
+        test_vec![&host, key, 1234_u64].into(),
+    )?;
+    let ledger = || host.with_ledger_info(|li| Ok((li.sequence_number, li.timestamp)));
+
+    // Time advances close ledgers as they add up to the ledger close time.
+    assert!(host.advance_time(7)?.is_empty());
+    assert_eq!(ledger()?, (1, 7));
+    assert!(host.advance_time(3)?.is_empty());
+    assert_eq!(ledger()?, (2, 10));
+    assert!(host.advance_ledgers(3)?.is_empty());
+    assert_eq!(ledger()?, (5, 25));
+
+    // Moving past the TTL of the temporary entry removes it; nothing is
+    // archived since the persistent entries are still live.
+    assert!(host.advance_ledgers(20)?.is_empty());
+    assert_eq!(ledger()?, (25, 125));
+    let has = host.call(
+        contract_id,
+        storage_fn_name(&host, "has", "temporary"),
+        test_vec![&host, key].into(),
+    )?;
+    assert!(!bool::try_from_val(&host, &has)?);
+    Ok(())
+}
+
+#[test]
+fn test_archived_entry_access_and_restore() {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let key = Symbol::try_from_small_str("key_1").unwrap();
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 1234_u64].into(),
+    )
+    .unwrap();
+
+    // Nothing is expired yet.
+    assert!(host.archive_expired_entries().unwrap().is_empty());
+
+    // Move past the TTL of all the entries: the contract instance, its code
+    // and the data entry all get archived.
+    host.with_mut_ledger_info(|li| li.sequence_number += 10_000)
+        .unwrap();
+    let archived = host.archive_expired_entries().unwrap();
+    assert_eq!(archived.len(), 3);
+    host.with_mut_storage(|s: &mut Storage| {
+        for key in archived.iter() {
+            assert!(s.is_archived(key, host.as_budget())?);
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // Any access to the contract now fails.
+    let res = host.call(
+        contract_id,
+        storage_fn_name(&host, "get", "persistent"),
+        test_vec![&host, key].into(),
+    );
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Storage, ScErrorCode::InvalidAction)
+    ));
+
+    // Restoring the entries brings them back with the minimum persistent TTL.
+    let ledger_seq: u32 = host.get_ledger_sequence().unwrap().into();
+    for key in archived.iter() {
+        assert_eq!(
+            host.restore_archived_entry(key).unwrap(),
+            ledger_seq + 4096 - 1
+        );
+    }
+    let res = host
+        .call(
+            contract_id,
+            storage_fn_name(&host, "get", "persistent"),
+            test_vec![&host, key].into(),
+        )
+        .unwrap();
+    assert_eq!(u64::try_from_val(&host, &res).unwrap(), 1234);
+
+    // Restoring a non-archived entry is an error.
+    assert!(HostError::result_matches_err(
+        host.restore_archived_entry(&archived[0]),
+        (ScErrorType::Storage, ScErrorCode::MissingValue)
+    ));
+}
+
+#[test]
+fn test_storage_access_hook() {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id).unwrap();
+    let key = Symbol::try_from_small_str("key_1").unwrap();
+
+    let accesses = Rc::new(RefCell::new(vec![]));
+    let accesses_clone = Rc::clone(&accesses);
+    host.set_storage_access_hook(Some(Rc::new(move |_, event: StorageAccessEvent| {
+        if let LedgerKey::ContractData(LedgerKeyContractData {
+            key: ScVal::Symbol(_),
+            ..
+        }) = event.key.as_ref()
+        {
+            accesses_clone.borrow_mut().push((
+                event.kind,
+                event.old_value.is_some(),
+                event.new_value.is_some(),
+                event.contract,
+            ));
+        }
+    })))
+    .unwrap();
+
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 1234_u64].into(),
+    )
+    .unwrap();
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "has", "persistent"),
+        test_vec![&host, key].into(),
+    )
+    .unwrap();
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "del", "persistent"),
+        test_vec![&host, key].into(),
+    )
+    .unwrap();
+    assert_eq!(
+        *accesses.borrow(),
+        vec![
+            (
+                StorageAccessKind::Read,
+                false,
+                false,
+                Some(contract_hash.clone())
+            ),
+            (
+                StorageAccessKind::Write,
+                false,
+                true,
+                Some(contract_hash.clone())
+            ),
+            (
+                StorageAccessKind::Read,
+                true,
+                true,
+                Some(contract_hash.clone())
+            ),
+            (StorageAccessKind::Delete, true, false, Some(contract_hash)),
+        ]
+    );
+
+    // Removing the hook stops the notifications.
+    host.set_storage_access_hook(None).unwrap();
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 1234_u64].into(),
+    )
+    .unwrap();
+    assert_eq!(accesses.borrow().len(), 4);
+}
+
+#[test]
+fn test_canonical_key_ordering() -> Result<(), HostError> {
+    let host = Host::default();
+    let budget = host.as_budget().clone();
+    let key = |contract: u8, key: ScVal, durability| {
+        Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract([contract; 32].into()),
+            key,
+            durability,
+        }))
+    };
+    let sym = |s: &str| ScVal::Symbol(s.try_into().unwrap());
+    let mut keys = vec![
+        key(1, ScVal::U32(1), ContractDataDurability::Persistent),
+        key(0, sym("b"), ContractDataDurability::Temporary),
+        key(0, ScVal::U64(5), ContractDataDurability::Persistent),
+        key(0, sym("b"), ContractDataDurability::Persistent),
+        key(0, sym("a"), ContractDataDurability::Persistent),
+        key(1, ScVal::U32(1), ContractDataDurability::Persistent),
+    ];
+
+    // The ordering must agree with the one used by the footprint map.
+    let mut fp = Footprint::default();
+    for k in keys.iter() {
+        fp.record_access(k, AccessType::ReadOnly, &budget)?;
+    }
+    let cpu_before = budget.get_cpu_insns_consumed()?;
+    Storage::sort_keys(&mut keys, &budget)?;
+    assert!(budget.get_cpu_insns_consumed()? > cpu_before);
+    assert_eq!(keys.len(), 5);
+    let fp_keys: Vec<_> = fp.0.map.iter().map(|(k, _)| k.clone()).collect();
+    assert_eq!(keys, fp_keys);
+
+    // ScVal keys compare the same way as their host object counterparts.
+    let vals = [
+        sym("abc"),
+        sym("abd"),
+        ScVal::U64(u64::MAX),
+        ScVal::I128(crate::xdr::Int128Parts { hi: -1, lo: 7 }),
+        ScVal::Bytes(vec![1, 2, 3].try_into().unwrap()),
+    ];
+    for a in vals.iter() {
+        for b in vals.iter() {
+            let host_ord = host.compare(&host.to_host_val(a)?, &host.to_host_val(b)?)?;
+            assert_eq!(Storage::compare_scval_keys(a, b, &budget)?, host_ord);
+        }
+    }
+
+    // Unsupported key types are rejected.
+    let ttl_key = LedgerKey::Ttl(crate::xdr::LedgerKeyTtl {
+        key_hash: [0; 32].into(),
+    });
+    let res = Storage::compare_keys(&ttl_key, &keys[0], &budget);
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Storage, ScErrorCode::InternalError)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_storage_keys_by_contract() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_a = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_b = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let hash_a = host.contract_id_from_address(contract_a)?;
+    let hash_b = host.contract_id_from_address(contract_b)?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    host.enable_storage_keys_tracking()?;
+    let data_key = |hash: &crate::xdr::Hash| {
+        Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(hash.clone()),
+            key: ScVal::Symbol("key_1".try_into().unwrap()),
+            durability: ContractDataDurability::Persistent,
+        }))
+    };
+
+    host.call(
+        contract_a,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 1234_u64].into(),
+    )?;
+    host.call(
+        contract_b,
+        storage_fn_name(&host, "has", "persistent"),
+        test_vec![&host, key].into(),
+    )?;
+
+    let keys_by_contract = host.get_storage_keys_by_contract()?;
+    assert_eq!(keys_by_contract.len(), 2);
+    let keys_a = keys_by_contract.get(&hash_a).unwrap();
+    let keys_b = keys_by_contract.get(&hash_b).unwrap();
+    assert_eq!(
+        keys_a.written.iter().cloned().collect::<Vec<_>>(),
+        vec![data_key(&hash_a)]
+    );
+    assert!(keys_b.written.is_empty());
+    assert!(keys_b.read.contains(&data_key(&hash_b)));
+    assert!(!keys_b.read.contains(&data_key(&hash_a)));
+
+    // The tracking survives resetting the host.
+    host.reset()?;
+    let contract_c = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let hash_c = host.contract_id_from_address(contract_c)?;
+    host.call(
+        contract_c,
+        storage_fn_name(&host, "has", "persistent"),
+        test_vec![&host, key].into(),
+    )?;
+    let keys_by_contract = host.get_storage_keys_by_contract()?;
+    assert_eq!(keys_by_contract.len(), 1);
+    assert!(keys_by_contract
+        .get(&hash_c)
+        .unwrap()
+        .read
+        .contains(&data_key(&hash_c)));
+
+    Ok(())
+}
+
+#[test]
+fn test_take_contract_data() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key: Val = Symbol::try_from_small_str("key")?.into();
+    let func = Symbol::try_from_small_str("")?;
+
+    host.with_test_contract_frame(contract_hash.clone(), func, || {
+        for t in [
+            StorageType::Persistent,
+            StorageType::Temporary,
+            StorageType::Instance,
+        ] {
+            host.put_contract_data(key, 1234_u32.into(), t)?;
+            let v: u32 = host.take_contract_data(key, t)?.try_into_val(&host)?;
+            assert_eq!(v, 1234);
+            assert!(!bool::try_from(host.has_contract_data(key, t)?)?);
+            assert!(HostError::result_matches_err(
+                host.take_contract_data(key, t),
+                (ScErrorType::Storage, ScErrorCode::MissingValue)
+            ));
+        }
+        Ok(Val::VOID.into())
+    })?;
+
+    // Taking an entry only requires the read-write access to it.
+    let storage_key = Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract(contract_hash),
+        key: ScVal::Symbol("key".try_into().unwrap()),
+        durability: ContractDataDurability::Persistent,
+    }));
+    let storage = host.try_borrow_storage()?;
+    assert_eq!(
+        storage
+            .footprint
+            .0
+            .get::<Rc<LedgerKey>>(&storage_key, host.as_budget())?,
+        Some(&AccessType::ReadWrite)
+    );
+    Ok(())
+}
+
+#[test]
+fn storage_merkle_proofs() -> Result<(), HostError> {
+    let budget = Budget::default();
+    budget.reset_unlimited()?;
+    let data_key = |i: i32| {
+        Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract([0; 32].into()),
+            key: ScVal::I32(i),
+            durability: ContractDataDurability::Persistent,
+        }))
+    };
+    let data_entry = |i: i32, v: i32| {
+        Rc::new(LedgerEntry {
+            last_modified_ledger_seq: 0,
+            data: LedgerEntryData::ContractData(ContractDataEntry {
+                contract: ScAddress::Contract([0; 32].into()),
+                key: ScVal::I32(i),
+                val: ScVal::I32(v),
+                durability: ContractDataDurability::Persistent,
+                ext: ExtensionPoint::V0,
+            }),
+            ext: LedgerEntryExt::V0,
+        })
+    };
+
+    let mut storage = Storage::with_recording_footprint(Rc::new(MockSnapshotSource::new()));
+    assert_eq!(storage.merkle_root(&budget)?, [0; 32]);
+    for i in 0..6 {
+        storage.put(&data_key(i), &data_entry(i, i * 10), Some(100), &budget)?;
+    }
+    storage.del(&data_key(3), &budget)?;
+    let root = storage.merkle_root(&budget)?;
+
+    // Every live entry has a valid proof, including the odd one out.
+    for i in [0, 1, 2, 4, 5] {
+        let proof = storage.merkle_proof(&data_key(i), &budget)?.unwrap();
+        let entry = (data_entry(i, i * 10), Some(100));
+        assert!(proof.verify(&data_key(i), &entry, &root, &budget)?);
+        // The proof doesn't hold for a different value or TTL.
+        let wrong_value = (data_entry(i, 1), Some(100));
+        assert!(!proof.verify(&data_key(i), &wrong_value, &root, &budget)?);
+        let wrong_ttl = (data_entry(i, i * 10), Some(101));
+        assert!(!proof.verify(&data_key(i), &wrong_ttl, &root, &budget)?);
+    }
+    assert!(storage.merkle_proof(&data_key(3), &budget)?.is_none());
+    assert!(storage.merkle_proof(&data_key(10), &budget)?.is_none());
+
+    // Modifying an entry changes the root and invalidates old proofs.
+    let old_proof = storage.merkle_proof(&data_key(0), &budget)?.unwrap();
+    storage.put(&data_key(1), &data_entry(1, 11), Some(100), &budget)?;
+    let new_root = storage.merkle_root(&budget)?;
+    assert_ne!(root, new_root);
+    let entry = (data_entry(0, 0), Some(100));
+    assert!(!old_proof.verify(&data_key(0), &entry, &new_root, &budget)?);
+    let new_proof = storage.merkle_proof(&data_key(0), &budget)?.unwrap();
+    assert!(new_proof.verify(&data_key(0), &entry, &new_root, &budget)?);
+    Ok(())
+}
+
+#[test]
+fn test_cross_contract_storage_policy() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_a = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_b = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let hash_a = host.contract_id_from_address(contract_a)?;
+    let key: Val = Symbol::try_from_small_str("key")?.into();
+    let func = Symbol::try_from_small_str("")?;
+
+    // By default a contract may extend the TTL of another contract instance.
+    host.with_test_contract_frame(hash_a.clone(), func, || {
+        host.extend_contract_instance_and_code_ttl(contract_b, 100.into(), 1000.into())?;
+        Ok(Val::VOID.into())
+    })?;
+
+    host.set_cross_contract_storage_policy(CrossContractStoragePolicy {
+        allow_extend_ttl: false,
+        ..Default::default()
+    })?;
+    let res = host.with_test_contract_frame(hash_a.clone(), func, || {
+        host.extend_contract_instance_and_code_ttl(contract_b, 100.into(), 1000.into())?;
+        Ok(Val::VOID.into())
+    });
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Storage, ScErrorCode::InvalidAction)
+    ));
+
+    // A contract can still access its own storage.
+    host.with_test_contract_frame(hash_a, func, || {
+        host.extend_contract_instance_and_code_ttl(contract_a, 100.into(), 1000.into())?;
+        host.put_contract_data(key, 1_u32.into(), StorageType::Persistent)?;
+        host.extend_contract_data_ttl(key, StorageType::Persistent, 100.into(), 1000.into())?;
+        assert!(bool::try_from(
+            host.has_contract_data(key, StorageType::Persistent)?
+        )?);
+        host.del_contract_data(key, StorageType::Persistent)?;
+        Ok(Val::VOID.into())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_cross_contract_storage_read_policy() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_a = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_b = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let hash_a = host.contract_id_from_address(contract_a)?;
+    let hash_b = host.contract_id_from_address(contract_b)?;
+    let key: Val = Symbol::try_from_small_str("key")?.into();
+    let func = Symbol::try_from_small_str("")?;
+    let key_b = Rc::new(LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract(hash_b.clone()),
+        key: ScVal::Symbol("key".try_into().unwrap()),
+        durability: ContractDataDurability::Persistent,
+    }));
+    host.with_test_contract_frame(hash_b, func, || {
+        host.put_contract_data(key, 1_u32.into(), StorageType::Persistent)?;
+        Ok(Val::VOID.into())
+    })?;
+
+    // By default a read of the data of another contract is allowed.
+    host.with_test_contract_frame(hash_a.clone(), func, || {
+        assert!(host.has_contract_data_entry(&key_b)?);
+        host.get_contract_data_entry(&key_b)?;
+        Ok(Val::VOID.into())
+    })?;
+
+    host.set_cross_contract_storage_policy(CrossContractStoragePolicy {
+        allow_read: false,
+        ..Default::default()
+    })?;
+    for res in [
+        host.with_test_contract_frame(hash_a.clone(), func, || {
+            host.get_contract_data_entry(&key_b)?;
+            Ok(Val::VOID.into())
+        }),
+        host.with_test_contract_frame(hash_a.clone(), func, || {
+            host.has_contract_data_entry(&key_b)?;
+            Ok(Val::VOID.into())
+        }),
+    ] {
+        assert!(HostError::result_matches_err(
+            res,
+            (ScErrorType::Storage, ScErrorCode::InvalidAction)
+        ));
+    }
+
+    // A contract can still read its own data.
+    host.with_test_contract_frame(hash_a, func, || {
+        host.put_contract_data(key, 2_u32.into(), StorageType::Persistent)?;
+        assert!(bool::try_from(
+            host.has_contract_data(key, StorageType::Persistent)?
+        )?);
+        assert_eq!(
+            u32::try_from_val(
+                &host,
+                &host.get_contract_data(key, StorageType::Persistent)?
+            )?,
+            2
+        );
+        Ok(Val::VOID.into())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_ledger_snapshot_file_roundtrip() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 1234_u64].into(),
+    )?;
+    host.with_mut_ledger_info(|li| {
+        li.sequence_number = 100;
+        li.network_id = [7; 32];
+    })?;
+
+    let snapshot = host.ledger_snapshot()?;
+    let path = std::env::temp_dir().join(format!(
+        "soroban-ledger-snapshot-{}.xdr",
+        std::process::id()
+    ));
+    snapshot.write_file(&path)?;
+    let loaded = LedgerSnapshot::read_file(&path)?;
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.entries, snapshot.entries);
+
+    // The fresh host observes the same ledger and contract state.
+    let host = Host::test_host_from_ledger_snapshot(&loaded)?;
+    host.with_ledger_info(|li| {
+        assert_eq!(li.sequence_number, 100);
+        assert_eq!(li.network_id, [7; 32]);
+        Ok(())
+    })?;
+    let contract_id = host.add_host_object(ScAddress::Contract(contract_hash))?;
+    let v: u64 = host
+        .call(
+            contract_id,
+            storage_fn_name(&host, "get", "persistent"),
+            test_vec![&host, key].into(),
+        )?
+        .try_into_val(&host)?;
+    assert_eq!(v, 1234);
+    Ok(())
+}
+
+#[test]
+fn test_host_snapshot_fork_and_rewind() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    let put = |host: &Host, v: u64| -> Result<(), HostError> {
+        let contract_id = host.add_host_object(ScAddress::Contract(contract_hash.clone()))?;
+        host.call(
+            contract_id,
+            storage_fn_name(host, "put", "persistent"),
+            test_vec![host, key, v].into(),
+        )?;
+        Ok(())
+    };
+    let get = |host: &Host| -> Result<u64, HostError> {
+        let contract_id = host.add_host_object(ScAddress::Contract(contract_hash.clone()))?;
+        Ok(host
+            .call(
+                contract_id,
+                storage_fn_name(host, "get", "persistent"),
+                test_vec![host, key].into(),
+            )?
+            .try_into_val(host)?)
+    };
+    put(&host, 1)?;
+    host.with_mut_ledger_info(|li| li.sequence_number = 100)?;
+    let snapshot = host.to_snapshot()?;
+
+    // The original host and two forks of it evolve independently.
+    put(&host, 2)?;
+    let fork1 = Host::from_snapshot(&snapshot)?;
+    let fork2 = Host::from_snapshot(&snapshot)?;
+    assert_eq!(get(&fork1)?, 1);
+    put(&fork2, 3)?;
+    assert_eq!(get(&host)?, 2);
+    assert_eq!(get(&fork1)?, 1);
+    assert_eq!(get(&fork2)?, 3);
+
+    // Rewinding restores the ledger info and budget configuration, with
+    // nothing consumed.
+    let rewound = Host::from_snapshot(&snapshot)?;
+    rewound.with_ledger_info(|li| {
+        assert_eq!(li.sequence_number, 100);
+        Ok(())
+    })?;
+    assert_eq!(
+        rewound.budget_cloned().to_configs()?,
+        host.budget_cloned().to_configs()?
+    );
+    assert_eq!(rewound.budget_cloned().get_cpu_insns_consumed()?, 0);
+    assert_eq!(get(&rewound)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_host_snapshot_state_xdr_roundtrip() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    host.call(
+        contract_id,
+        storage_fn_name(&host, "put", "persistent"),
+        test_vec![&host, key, 7_u64].into(),
+    )?;
+    host.set_call_limits(CallLimits {
+        max_args: Some(10),
+        ..Default::default()
+    })?;
+    let snapshot = host.to_snapshot()?;
+
+    let mut bytes = vec![];
+    snapshot.state.write_xdr(&mut bytes)?;
+    let state = DetachedHostState::read_xdr(bytes.as_slice())?;
+    let mut rewritten = vec![];
+    state.write_xdr(&mut rewritten)?;
+    assert_eq!(bytes, rewritten);
+
+    // The rest of the snapshot is provided again when restoring it.
+    let restored = Host::from_snapshot(&HostSnapshot {
+        state,
+        snapshot_source: snapshot.snapshot_source.clone(),
+        test_contracts: vec![],
+    })?;
+    let contract_id = restored.add_host_object(ScAddress::Contract(contract_hash))?;
+    let v: u64 = restored
+        .call(
+            contract_id,
+            storage_fn_name(&restored, "get", "persistent"),
+            test_vec![&restored, key].into(),
+        )?
+        .try_into_val(&restored)?;
+    assert_eq!(v, 7);
+    assert_eq!(
+        restored.detach_state()?.call_limits,
+        snapshot.state.call_limits
+    );
+
+    // Truncated states are rejected.
+    assert!(DetachedHostState::read_xdr(&bytes[..bytes.len() - 1]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_host_detach_and_attach_state() -> Result<(), HostError> {
+    fn put(host: &Host, contract_hash: &Hash, v: u64) -> Result<(), HostError> {
+        let contract_id = host.add_host_object(ScAddress::Contract(contract_hash.clone()))?;
+        let key = Symbol::try_from_small_str("key_1")?;
+        host.call(
+            contract_id,
+            storage_fn_name(host, "put", "persistent"),
+            test_vec![host, key, v].into(),
+        )?;
+        Ok(())
+    }
+    fn get(host: &Host, contract_hash: &Hash) -> Result<u64, HostError> {
+        let contract_id = host.add_host_object(ScAddress::Contract(contract_hash.clone()))?;
+        let key = Symbol::try_from_small_str("key_1")?;
+        Ok(host
+            .call(
+                contract_id,
+                storage_fn_name(host, "get", "persistent"),
+                test_vec![host, key].into(),
+            )?
+            .try_into_val(host)?)
+    }
+
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    put(&host, &contract_hash, 1)?;
+    host.with_mut_ledger_info(|li| li.sequence_number = 100)?;
+    let call_limits = CallLimits {
+        max_args: Some(10),
+        ..Default::default()
+    };
+    host.set_call_limits(call_limits)?;
+    host.budget_ref().set_depth_limit(50)?;
+    host.set_diagnostic_level(DiagnosticLevel::FullTrace)?;
+    let state = host.detach_state()?;
+
+    // The state of a recording host can only be attached with a snapshot
+    // source.
+    assert!(HostError::result_matches_err(
+        Host::attach_state(state.clone(), None),
+        (ScErrorType::Storage, ScErrorCode::InvalidInput)
+    ));
+
+    // The state moves to another thread and back.
+    let worker_contract_hash = contract_hash.clone();
+    let state = std::thread::spawn(move || {
+        let host = Host::attach_state(state, Some(Rc::new(MockSnapshotSource::new()))).unwrap();
+        assert_eq!(get(&host, &worker_contract_hash).unwrap(), 1);
+        put(&host, &worker_contract_hash, 2).unwrap();
+        host.detach_state().unwrap()
+    })
+    .join()
+    .unwrap();
+
+    let attached = Host::attach_state(state, Some(Rc::new(MockSnapshotSource::new())))?;
+    attached.with_ledger_info(|li| {
+        assert_eq!(li.sequence_number, 100);
+        Ok(())
+    })?;
+    assert_eq!(
+        attached.budget_cloned().to_configs()?,
+        host.budget_cloned().to_configs()?
+    );
+    assert_eq!(get(&attached, &contract_hash)?, 2);
+    // The configuration is carried over.
+    let state = attached.detach_state()?;
+    assert_eq!(state.call_limits, call_limits);
+    assert_eq!(state.depth_limit, 50);
+    assert_eq!(state.diagnostic_level, DiagnosticLevel::FullTrace);
+    // The original host is left unchanged.
+    assert_eq!(get(&host, &contract_hash)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_host_reset() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    host.with_mut_ledger_info(|li| li.sequence_number = 100)?;
+    let configs = host.budget_cloned().to_configs()?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    let put_and_get = |host: &Host, v: u64| -> Result<u64, HostError> {
+        let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+        host.call(
+            contract_id,
+            storage_fn_name(host, "put", "persistent"),
+            test_vec![host, key, v].into(),
+        )?;
+        Ok(host
+            .call(
+                contract_id,
+                storage_fn_name(host, "get", "persistent"),
+                test_vec![host, key].into(),
+            )?
+            .try_into_val(host)?)
+    };
+    assert_eq!(put_and_get(&host, 1)?, 1);
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    assert!(host.budget_cloned().get_cpu_insns_consumed()? > 0);
+
+    host.reset()?;
+    assert_eq!(host.budget_cloned().get_cpu_insns_consumed()?, 0);
+    assert_eq!(host.budget_cloned().get_mem_bytes_consumed()?, 0);
+    assert_eq!(host.budget_cloned().to_configs()?, configs);
+    assert!(host.get_events()?.0.is_empty());
+    assert!(host.try_borrow_storage()?.map.map.is_empty());
+    assert!(host.try_borrow_storage()?.footprint.0.map.is_empty());
+    assert!(host.try_borrow_objects()?.is_empty());
+    host.with_ledger_info(|li| {
+        assert_eq!(li.sequence_number, 100);
+        Ok(())
+    })?;
+    // The previously registered contract is gone, along with its handle.
+    assert!(host.contract_id_from_address(contract_id).is_err());
+
+    // The host is usable again, in the same footprint mode.
+    assert_eq!(put_and_get(&host, 2)?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_metering_is_transparent() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let contract_hash = host.contract_id_from_address(contract_id)?;
+    let key = Symbol::try_from_small_str("key_1")?;
+    let put_and_get = |host: &Host| -> Result<Val, HostError> {
+        let contract_id = host.add_host_object(ScAddress::Contract(contract_hash.clone()))?;
+        host.call(
+            contract_id,
+            storage_fn_name(host, "put", "persistent"),
+            test_vec![host, key, 7_u64].into(),
+        )?;
+        host.call(
+            contract_id,
+            storage_fn_name(host, "get", "persistent"),
+            test_vec![host, key].into(),
+        )
+    };
+    let cpu = host.budget_cloned().get_cpu_insns_consumed()?;
+    let effects = host.assert_metering_is_transparent(put_and_get);
+    assert_eq!(effects.result, Ok(ScVal::U64(7)));
+    assert!(effects.storage.iter().any(|(k, _)| matches!(
+        k.as_ref(),
+        LedgerKey::ContractData(cd) if cd.key == ScVal::Symbol(ScSymbol("key_1".try_into().unwrap()))
+    )));
+    // The original host is left untouched.
+    assert_eq!(host.budget_cloned().get_cpu_insns_consumed()?, cpu);
+
+    // Running out of budget in the metered run is a divergence.
+    host.budget_cloned().reset_limits(100_000, 100_000)?;
+    let res =
+        crate::testutils::call_with_suppressed_panic_hook(std::panic::AssertUnwindSafe(|| {
+            host.assert_metering_is_transparent(put_and_get)
+        }));
+    let msg = res.unwrap_err().downcast::<String>().unwrap();
+    assert!(msg.contains("the metered (left) and unmetered (right) runs diverged"));
+    Ok(())
+}
+
+#[test]
+fn test_invocation_report() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let key = Symbol::try_from_small_str("key_1")?;
+    let call = |op: &str, args: VecObject| {
+        let _ = host.call(contract_id, storage_fn_name(&host, op, "persistent"), args);
+        let cpu = host.as_budget().get_cpu_insns_consumed()?;
+        let report = host.invocation_report()?;
+        // Building the report is not metered.
+        assert_eq!(host.as_budget().get_cpu_insns_consumed()?, cpu);
+        assert_eq!(report.budget.cpu_insns_consumed, cpu);
+        Ok::<_, HostError>(report)
+    };
+
+    let report = call("put", test_vec![&host, key, 1234_u64].into())?;
+    assert!(matches!(report.result, Some(Ok(ScVal::Void))));
+    // Only the new entry has changed.
+    assert_eq!(report.storage_changes.len(), 1);
+    let change = &report.storage_changes[0];
+    assert!(change.old_value.is_none());
+    let Some((entry, Some(_))) = &change.new_value else {
+        panic!("missing new entry");
+    };
+    let LedgerEntryData::ContractData(data) = &entry.data else {
+        panic!("unexpected entry type");
+    };
+    assert_eq!(data.val, ScVal::U64(1234));
+    assert_eq!(
+        report
+            .footprint
+            .0
+            .get::<Rc<LedgerKey>>(&change.key, host.as_budget())?,
+        Some(&AccessType::ReadWrite)
+    );
+
+    // Reads don't change the storage.
+    let report = call("get", test_vec![&host, key].into())?;
+    assert!(matches!(report.result, Some(Ok(ScVal::U64(1234)))));
+    assert!(report.storage_changes.is_empty());
+
+    // Errors are reported as the result.
+    let missing = Symbol::try_from_small_str("key_2")?;
+    let report = call("get", test_vec![&host, missing].into())?;
+    assert!(matches!(report.result, Some(Err(_))));
+    assert!(report.storage_changes.is_empty());
+    Ok(())
+}
@@ -1,5 +1,9 @@
-use crate::{Host, HostError};
-use soroban_env_common::{Symbol, TryFromVal, Val};
+use crate::{
+    xdr::{ScSymbol, ScVal},
+    Host, HostError,
+};
+use soroban_env_common::{Compare, Env, Symbol, Tag, TryFromVal, Val};
+use std::cmp::Ordering;
 
 #[test]
 fn invalid_chars() -> Result<(), HostError> {
@@ -61,3 +65,34 @@ fn invalid_symbol_scval_to_val() {
 
     assert!(Val::try_from_val(&*host, &symbol).is_err())
 }
+
+#[test]
+fn small_and_object_symbols_are_consistent() -> Result<(), HostError> {
+    let host = Host::test_host();
+
+    // The same characters compare equal whatever their representation.
+    let small = Symbol::try_from_val(&host, &"abc")?;
+    assert!(small.to_val().get_tag() == Tag::SymbolSmall);
+    let obj: Symbol = host.add_host_object(ScSymbol("abc".try_into()?))?.into();
+    assert_eq!(host.compare(&small, &obj)?, Ordering::Equal);
+    assert_eq!(host.compare(&obj, &small)?, Ordering::Equal);
+    assert_eq!(u32::from(host.symbol_val_len(small)?), 3);
+    assert_eq!(u32::from(host.symbol_val_len(obj)?), 3);
+
+    // Symbols are ordered by their characters, not by their representation,
+    // which agrees with the order of their ScVals.
+    let short = Symbol::try_from_val(&host, &"zzz")?;
+    let long = Symbol::try_from_val(&host, &"aaaaaaaaaaaa")?;
+    assert!(long.to_val().get_tag() == Tag::SymbolObject);
+    assert_eq!(host.compare(&short, &long)?, Ordering::Greater);
+    assert_eq!(host.compare(&long, &short)?, Ordering::Less);
+    assert_eq!(
+        host.compare(&short.to_val(), &long.to_val())?,
+        Ordering::Greater
+    );
+    let short_scv = ScVal::try_from_val(&host, &short.to_val())?;
+    let long_scv = ScVal::try_from_val(&host, &long.to_val())?;
+    assert_eq!(short_scv.cmp(&long_scv), Ordering::Greater);
+    assert_eq!(u32::from(host.symbol_val_len(long)?), 12);
+    Ok(())
+}
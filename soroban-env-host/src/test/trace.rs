@@ -0,0 +1,99 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use soroban_env_common::{Env, TryFromVal, TryIntoVal};
+use soroban_test_wasms::CONTRACT_STORAGE;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+use crate::{
+    host::trace::{FRAME_TARGET, HOST_FN_TARGET},
+    Host, HostError, Symbol,
+};
+
+// Collects the `kind` of every frame span and the fields of every host
+// function event.
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    frames: Mutex<Vec<String>>,
+    host_fns: Mutex<Vec<BTreeMap<String, String>>>,
+}
+
+#[derive(Default)]
+struct FieldsVisitor(BTreeMap<String, String>);
+
+impl Visit for FieldsVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        if span.metadata().target() == FRAME_TARGET {
+            let mut visitor = FieldsVisitor::default();
+            span.record(&mut visitor);
+            self.frames
+                .lock()
+                .unwrap()
+                .push(visitor.0.remove("kind").unwrap_or_default());
+        }
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        if event.metadata().target() == HOST_FN_TARGET {
+            let mut visitor = FieldsVisitor::default();
+            event.record(&mut visitor);
+            self.host_fns.lock().unwrap().push(visitor.0);
+        }
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn frames_and_host_fn_calls_are_traced() -> Result<(), HostError> {
+    let recorder = Arc::new(Recorder::default());
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id = host.register_test_contract_wasm(CONTRACT_STORAGE);
+    let key = Symbol::try_from_small_str("key")?;
+    let args = test_vec![&host, key].into();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        host.call(
+            contract_id,
+            Symbol::try_from_val(&host, &"has_persistent")?,
+            args,
+        )
+    })?;
+
+    assert_eq!(*recorder.frames.lock().unwrap(), vec!["\"contract_vm\""]);
+    let host_fns = recorder.host_fns.lock().unwrap();
+    let has = host_fns
+        .iter()
+        .find(|f| f.get("host_fn").map(String::as_str) == Some("\"has_contract_data\""))
+        .expect("has_contract_data was not traced");
+    assert_eq!(has.get("ok").map(String::as_str), Some("true"));
+    assert!(has.get("cpu_insns").unwrap().parse::<u64>().unwrap() > 0);
+    assert!(has.contains_key("mem_bytes"));
+    Ok(())
+}
@@ -1,6 +1,6 @@
-use soroban_env_common::TryIntoVal;
+use soroban_env_common::{TryFromVal, TryIntoVal};
 
-use crate::{Env, Host, HostError, Val};
+use crate::{Env, EnvBase, Host, HostError, Val, VecObject};
 
 #[test]
 fn tuple_conversions() -> Result<(), HostError> {
@@ -33,3 +33,50 @@ fn tuple_array_conversions() -> Result<(), HostError> {
 
     Ok(())
 }
+
+type Tuple13 = (
+    u32,
+    i32,
+    u64,
+    i64,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    bool,
+);
+
+#[test]
+fn tuple_max_arity_conversions() -> Result<(), HostError> {
+    let host = Host::test_host();
+    let t = (
+        0u32, 1i32, 2u64, 3i64, 4u32, 5u32, 6u32, 7u32, 8u32, 9u32, 10u32, 11u32, true,
+    );
+    let val: Val = t.try_into_val(&host)?;
+    let roundtrip: Tuple13 = val.try_into_val(&host)?;
+    let roundtrip_val: Val = roundtrip.try_into_val(&host)?;
+    assert_eq!(host.obj_cmp(val, roundtrip_val)?, 0);
+    assert_eq!((roundtrip.2, roundtrip.12), (2u64, true));
+    Ok(())
+}
+
+#[test]
+fn array_conversions() -> Result<(), HostError> {
+    let host = Host::test_host();
+
+    let vec: VecObject = [1u64, u64::MAX, 3].try_into_val(&host)?;
+    assert_eq!(u32::from(host.vec_len(vec)?), 3);
+    let roundtrip: [u64; 3] = vec.try_into_val(&host)?;
+    assert_eq!(roundtrip, [1u64, u64::MAX, 3]);
+
+    // The length must match and every element must convert.
+    assert!(<[u64; 2]>::try_from_val(&host, &vec).is_err());
+    assert!(<[u64; 4]>::try_from_val(&host, &vec).is_err());
+    let vec = host.vec_new_from_slice(&[1u32.into(), Val::VOID.to_val()])?;
+    assert!(<[u32; 2]>::try_from_val(&host, &vec).is_err());
+    Ok(())
+}
@@ -1,18 +1,41 @@
-use rand::RngCore;
+use ed25519_dalek::SigningKey;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use std::panic::{catch_unwind, set_hook, take_hook, UnwindSafe};
-use std::{cell::Cell, collections::BTreeMap, rc::Rc, sync::Once};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    rc::Rc,
+    sync::Once,
+};
 
 use crate::{
-    budget::Budget,
-    storage::{SnapshotSource, Storage},
+    budget::{Budget, CostTracker},
+    events::{ContractEventFilter, EventLimits},
+    host::{ledger_info_helper::get_key_durability, BasePrng},
+    host_object::{HostMap, HostVec},
+    storage::{
+        AccessType, CrossContractStoragePolicy, EntryWithLiveUntil, FootprintMode, SnapshotSource,
+        Storage, StorageBackend,
+    },
     xdr::{
-        AccountId, ContractCostType, LedgerEntry, LedgerKey, PublicKey, ScAddress, ScErrorCode,
-        ScErrorType, ScVal, ScVec, Uint256,
+        AccountEntry, AccountEntryExt, AccountId, ContractCostParams, ContractCostType,
+        ContractDataDurability, ContractEvent, Hash, LedgerEntry, LedgerEntryData, LedgerEntryExt,
+        LedgerKey, Limited, Limits, PublicKey, ReadXdr, ScAddress, ScBytes, ScErrorCode,
+        ScErrorType, ScString, ScVal, ScVec, SequenceNumber, Thresholds, UInt128Parts, Uint256,
+        WriteXdr,
     },
-    AddressObject, BytesObject, Env, EnvBase, Error, Host, HostError, LedgerInfo, StorageType,
-    SymbolSmall, Val, VecObject,
+    AddressObject, BytesObject, CallLimits, ContractFunctionSet, DetachedHostState,
+    DiagnosticLevel, Env, EnvBase, Error, Host, HostError, LedgerInfo, MapObject,
+    ScMapKeyOrderPolicy, StorageType, StringObject, SymbolSmall, TryFromVal, TryIntoVal, Val,
+    VecObject,
 };
 
+pub mod mock_contracts;
+
 /// Catch panics while suppressing the default panic hook that prints to the
 /// console.
 ///
@@ -113,16 +136,93 @@ pub fn generate_bytes_array(host: &Host) -> [u8; 32] {
     bytes
 }
 
-pub struct MockSnapshotSource(BTreeMap<Rc<LedgerKey>, (Rc<LedgerEntry>, Option<u32>)>);
+/// Generates deterministic sequences of ed25519 keys, account ids and
+/// contract ids from a seed, so that tests involving several parties are
+/// reproducible: the n-th key of a generator created from a given seed is
+/// always the same, regardless of what else the test or the host does.
+pub struct TestAddressGenerator {
+    rng: ChaCha20Rng,
+}
+
+impl TestAddressGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha20Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn signing_key(&mut self) -> SigningKey {
+        SigningKey::generate(&mut self.rng)
+    }
+
+    /// Returns the account id of the next [TestAddressGenerator::signing_key].
+    pub fn account_id(&mut self) -> AccountId {
+        let key = self.signing_key();
+        AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            key.verifying_key().to_bytes(),
+        )))
+    }
+
+    pub fn contract_id(&mut self) -> Hash {
+        let mut bytes = [0u8; 32];
+        self.rng.fill_bytes(&mut bytes);
+        Hash(bytes)
+    }
+
+    pub fn account_address(&mut self, host: &Host) -> Result<AddressObject, HostError> {
+        let account_id = self.account_id();
+        host.add_host_object(ScAddress::Account(account_id))
+    }
+
+    pub fn contract_address(&mut self, host: &Host) -> Result<AddressObject, HostError> {
+        let contract_id = self.contract_id();
+        host.add_host_object(ScAddress::Contract(contract_id))
+    }
+
+    /// Generates the next signing key and registers an account for it in
+    /// `host`'s storage, with the key as its only signer, so that it can
+    /// authorize invocations. Returns the key and the account address.
+    pub fn account(&mut self, host: &Host) -> Result<(SigningKey, AddressObject), HostError> {
+        let key = self.signing_key();
+        let account_id = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            key.verifying_key().to_bytes(),
+        )));
+        let ledger_key = host.to_account_key(account_id.clone())?;
+        let entry = AccountEntry {
+            account_id: account_id.clone(),
+            balance: 0,
+            seq_num: SequenceNumber(0),
+            num_sub_entries: 0,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: Default::default(),
+            thresholds: Thresholds([1, 0, 0, 0]),
+            signers: Default::default(),
+            ext: AccountEntryExt::V0,
+        };
+        let entry = Rc::new(LedgerEntry {
+            last_modified_ledger_seq: 0,
+            data: LedgerEntryData::Account(entry),
+            ext: LedgerEntryExt::V0,
+        });
+        host.setup_storage_entry(ledger_key, Some((entry, None)), AccessType::ReadWrite)?;
+        let address = host.add_host_object(ScAddress::Account(account_id))?;
+        Ok((key, address))
+    }
+}
+
+pub struct MockSnapshotSource(RefCell<BTreeMap<Rc<LedgerKey>, EntryWithLiveUntil>>);
 
 impl MockSnapshotSource {
     pub fn new() -> Self {
-        Self(BTreeMap::<Rc<LedgerKey>, (Rc<LedgerEntry>, Option<u32>)>::new())
+        Self(RefCell::new(
+            BTreeMap::<Rc<LedgerKey>, EntryWithLiveUntil>::new(),
+        ))
     }
 }
 impl SnapshotSource for MockSnapshotSource {
     fn get(&self, key: &Rc<LedgerKey>) -> Result<(Rc<LedgerEntry>, Option<u32>), HostError> {
-        if let Some(val) = self.0.get(key) {
+        if let Some(val) = self.0.borrow().get(key) {
             Ok((Rc::clone(&val.0), val.1))
         } else {
             Err(Error::from_type_and_code(ScErrorType::Storage, ScErrorCode::MissingValue).into())
@@ -130,10 +230,448 @@ impl SnapshotSource for MockSnapshotSource {
     }
 
     fn has(&self, key: &Rc<LedgerKey>) -> Result<bool, HostError> {
-        Ok(self.0.contains_key(key))
+        Ok(self.0.borrow().contains_key(key))
+    }
+}
+
+impl StorageBackend for MockSnapshotSource {
+    fn put(&self, key: &Rc<LedgerKey>, entry: &EntryWithLiveUntil) -> Result<(), HostError> {
+        self.0
+            .borrow_mut()
+            .insert(Rc::clone(key), (Rc::clone(&entry.0), entry.1));
+        Ok(())
+    }
+
+    fn del(&self, key: &Rc<LedgerKey>) -> Result<(), HostError> {
+        self.0.borrow_mut().remove(key);
+        Ok(())
+    }
+}
+
+fn write_len_xdr<W: Write>(len: usize, w: &mut Limited<W>) -> Result<(), HostError> {
+    let len: u32 = len
+        .try_into()
+        .map_err(|_| HostError::from((ScErrorType::Storage, ScErrorCode::ExceededLimit)))?;
+    len.write_xdr(w)?;
+    Ok(())
+}
+
+fn write_ledger_info_xdr<W: Write>(info: &LedgerInfo, w: &mut Limited<W>) -> Result<(), HostError> {
+    info.protocol_version.write_xdr(w)?;
+    info.sequence_number.write_xdr(w)?;
+    info.timestamp.write_xdr(w)?;
+    Hash(info.network_id).write_xdr(w)?;
+    info.base_reserve.write_xdr(w)?;
+    info.min_temp_entry_ttl.write_xdr(w)?;
+    info.min_persistent_entry_ttl.write_xdr(w)?;
+    info.max_entry_ttl.write_xdr(w)?;
+    Ok(())
+}
+
+fn read_ledger_info_xdr<R: Read>(r: &mut Limited<R>) -> Result<LedgerInfo, HostError> {
+    Ok(LedgerInfo {
+        protocol_version: u32::read_xdr(r)?,
+        sequence_number: u32::read_xdr(r)?,
+        timestamp: u64::read_xdr(r)?,
+        network_id: Hash::read_xdr(r)?.0,
+        base_reserve: u32::read_xdr(r)?,
+        min_temp_entry_ttl: u32::read_xdr(r)?,
+        min_persistent_entry_ttl: u32::read_xdr(r)?,
+        max_entry_ttl: u32::read_xdr(r)?,
+    })
+}
+
+/// Ledger state that can be shared between tests and tools as a fixture: the
+/// ledger info and the ledger entries together with their live_until ledgers.
+///
+/// The snapshot is stored in a file as a sequence of XDR values: a format
+/// version, the fields of [LedgerInfo], the number of entries and then every
+/// entry as a `LedgerKey`, `LedgerEntry` and optional live_until ledger.
+#[derive(Clone, Default)]
+pub struct LedgerSnapshot {
+    pub ledger_info: LedgerInfo,
+    pub entries: Vec<(Rc<LedgerKey>, EntryWithLiveUntil)>,
+}
+
+impl LedgerSnapshot {
+    const FORMAT_VERSION: u32 = 1;
+
+    pub fn write_xdr<W: Write>(&self, w: W) -> Result<(), HostError> {
+        let mut w = Limited::new(w, Limits::none());
+        Self::FORMAT_VERSION.write_xdr(&mut w)?;
+        write_ledger_info_xdr(&self.ledger_info, &mut w)?;
+        write_len_xdr(self.entries.len(), &mut w)?;
+        for (key, (entry, live_until)) in self.entries.iter() {
+            key.write_xdr(&mut w)?;
+            entry.write_xdr(&mut w)?;
+            live_until.write_xdr(&mut w)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_xdr<R: Read>(r: R) -> Result<Self, HostError> {
+        let mut r = Limited::new(r, Limits::none());
+        if u32::read_xdr(&mut r)? != Self::FORMAT_VERSION {
+            return Err((ScErrorType::Storage, ScErrorCode::InvalidInput).into());
+        }
+        let ledger_info = read_ledger_info_xdr(&mut r)?;
+        let len = u32::read_xdr(&mut r)?;
+        let mut entries = vec![];
+        for _ in 0..len {
+            let key = LedgerKey::read_xdr(&mut r)?;
+            let entry = LedgerEntry::read_xdr(&mut r)?;
+            let live_until = Option::<u32>::read_xdr(&mut r)?;
+            entries.push((Rc::new(key), (Rc::new(entry), live_until)));
+        }
+        Ok(Self {
+            ledger_info,
+            entries,
+        })
     }
+
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), HostError> {
+        let mut w = BufWriter::new(File::create(path).map_err(crate::xdr::Error::Io)?);
+        self.write_xdr(&mut w)?;
+        w.flush().map_err(crate::xdr::Error::Io)?;
+        Ok(())
+    }
+
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Self, HostError> {
+        Self::read_xdr(BufReader::new(
+            File::open(path).map_err(crate::xdr::Error::Io)?,
+        ))
+    }
+}
+
+/// The state of a [Host] between invocations, captured via
+/// [Host::to_snapshot]. Every host created from it via [Host::from_snapshot]
+/// starts in that state independently of the original host and of the other
+/// ones, so tests can fork, branch and rewind environments.
+///
+/// This is the [DetachedHostState] of the host, along with the parts that
+/// can't be detached from it but can be shared between the hosts of a single
+/// thread: the [SnapshotSource] of a storage in recording mode and the native
+/// test contracts. Only the detached state can be saved, via
+/// [DetachedHostState::write_xdr]; the other parts have to be provided again
+/// when restoring a snapshot from it.
+#[derive(Clone)]
+pub struct HostSnapshot {
+    pub state: DetachedHostState,
+    /// The source of the storage in recording mode, `None` in enforcing mode.
+    pub snapshot_source: Option<Rc<dyn SnapshotSource>>,
+    pub test_contracts: Vec<(Hash, Rc<dyn ContractFunctionSet>)>,
 }
 
+/// The detached state is stored as a sequence of XDR values, in the order of
+/// the fields of [DetachedHostState], so that the state of a [HostSnapshot]
+/// can be saved along with the test that uses it. Optional values are XDR
+/// optionals, lists are prefixed with their length and the enums are stored
+/// as the `u32` index of their variant.
+impl DetachedHostState {
+    const FORMAT_VERSION: u32 = 1;
+
+    pub fn write_xdr<W: Write>(&self, w: W) -> Result<(), HostError> {
+        let mut w = Limited::new(w, Limits::none());
+        Self::FORMAT_VERSION.write_xdr(&mut w)?;
+        self.ledger_info.is_some().write_xdr(&mut w)?;
+        if let Some(info) = &self.ledger_info {
+            write_ledger_info_xdr(info, &mut w)?;
+        }
+        write_len_xdr(self.footprint.len(), &mut w)?;
+        for (key, access) in self.footprint.iter() {
+            key.write_xdr(&mut w)?;
+            (*access == AccessType::ReadWrite).write_xdr(&mut w)?;
+        }
+        write_len_xdr(self.entries.len(), &mut w)?;
+        for (key, entry) in self.entries.iter() {
+            key.write_xdr(&mut w)?;
+            entry.is_some().write_xdr(&mut w)?;
+            if let Some((entry, live_until)) = entry {
+                entry.write_xdr(&mut w)?;
+                live_until.write_xdr(&mut w)?;
+            }
+        }
+        write_len_xdr(self.archived.len(), &mut w)?;
+        for (key, entry) in self.archived.iter() {
+            key.write_xdr(&mut w)?;
+            entry.write_xdr(&mut w)?;
+        }
+        self.recording_footprint.write_xdr(&mut w)?;
+        self.cpu_limit.write_xdr(&mut w)?;
+        self.mem_limit.write_xdr(&mut w)?;
+        self.cpu_cost_params.write_xdr(&mut w)?;
+        self.mem_cost_params.write_xdr(&mut w)?;
+        self.depth_limit.write_xdr(&mut w)?;
+        self.conversion_element_limit.write_xdr(&mut w)?;
+        let diagnostic_level: u32 = match self.diagnostic_level {
+            DiagnosticLevel::None => 0,
+            DiagnosticLevel::DebugEvents => 1,
+            DiagnosticLevel::FullTrace => 2,
+        };
+        diagnostic_level.write_xdr(&mut w)?;
+        self.cross_contract_storage_policy
+            .allow_extend_ttl
+            .write_xdr(&mut w)?;
+        self.cross_contract_storage_policy
+            .allow_read
+            .write_xdr(&mut w)?;
+        let scmap_key_order_policy: u32 = match self.scmap_key_order_policy {
+            ScMapKeyOrderPolicy::Strict => 0,
+            ScMapKeyOrderPolicy::Canonicalize => 1,
+        };
+        scmap_key_order_policy.write_xdr(&mut w)?;
+        let limits = &self.event_limits;
+        limits.max_events.write_xdr(&mut w)?;
+        limits.max_topics_per_event.write_xdr(&mut w)?;
+        limits.max_topic_size_bytes.write_xdr(&mut w)?;
+        limits.max_data_size_bytes.write_xdr(&mut w)?;
+        let limits = &self.call_limits;
+        limits.max_args.write_xdr(&mut w)?;
+        limits.max_args_size_bytes.write_xdr(&mut w)?;
+        limits.max_return_size_bytes.write_xdr(&mut w)?;
+        self.source_account.write_xdr(&mut w)?;
+        self.recording_auth.is_some().write_xdr(&mut w)?;
+        if let Some(disable_non_root_auth) = self.recording_auth {
+            disable_non_root_auth.write_xdr(&mut w)?;
+        }
+        self.base_prng.is_some().write_xdr(&mut w)?;
+        if let Some(base_prng) = &self.base_prng {
+            let (seed, frame_prng_count, stream_seed, stream, word_pos, protocol_version) =
+                base_prng.to_parts();
+            Hash(seed).write_xdr(&mut w)?;
+            frame_prng_count.write_xdr(&mut w)?;
+            Hash(stream_seed).write_xdr(&mut w)?;
+            stream.write_xdr(&mut w)?;
+            UInt128Parts {
+                hi: (word_pos >> 64) as u64,
+                lo: word_pos as u64,
+            }
+            .write_xdr(&mut w)?;
+            protocol_version.write_xdr(&mut w)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_xdr<R: Read>(r: R) -> Result<Self, HostError> {
+        fn invalid_input() -> HostError {
+            (ScErrorType::Storage, ScErrorCode::InvalidInput).into()
+        }
+        let mut r = Limited::new(r, Limits::none());
+        if u32::read_xdr(&mut r)? != Self::FORMAT_VERSION {
+            return Err(invalid_input());
+        }
+        let ledger_info = if bool::read_xdr(&mut r)? {
+            Some(read_ledger_info_xdr(&mut r)?)
+        } else {
+            None
+        };
+        let mut footprint = vec![];
+        for _ in 0..u32::read_xdr(&mut r)? {
+            let key = LedgerKey::read_xdr(&mut r)?;
+            let access = if bool::read_xdr(&mut r)? {
+                AccessType::ReadWrite
+            } else {
+                AccessType::ReadOnly
+            };
+            footprint.push((key, access));
+        }
+        let mut entries = vec![];
+        for _ in 0..u32::read_xdr(&mut r)? {
+            let key = LedgerKey::read_xdr(&mut r)?;
+            let entry = if bool::read_xdr(&mut r)? {
+                let entry = LedgerEntry::read_xdr(&mut r)?;
+                Some((entry, Option::<u32>::read_xdr(&mut r)?))
+            } else {
+                None
+            };
+            entries.push((key, entry));
+        }
+        let mut archived = vec![];
+        for _ in 0..u32::read_xdr(&mut r)? {
+            let key = LedgerKey::read_xdr(&mut r)?;
+            archived.push((key, LedgerEntry::read_xdr(&mut r)?));
+        }
+        let recording_footprint = bool::read_xdr(&mut r)?;
+        let cpu_limit = u64::read_xdr(&mut r)?;
+        let mem_limit = u64::read_xdr(&mut r)?;
+        let cpu_cost_params = ContractCostParams::read_xdr(&mut r)?;
+        let mem_cost_params = ContractCostParams::read_xdr(&mut r)?;
+        let depth_limit = u32::read_xdr(&mut r)?;
+        let conversion_element_limit = u32::read_xdr(&mut r)?;
+        let diagnostic_level = match u32::read_xdr(&mut r)? {
+            0 => DiagnosticLevel::None,
+            1 => DiagnosticLevel::DebugEvents,
+            2 => DiagnosticLevel::FullTrace,
+            _ => return Err(invalid_input()),
+        };
+        let cross_contract_storage_policy = CrossContractStoragePolicy {
+            allow_extend_ttl: bool::read_xdr(&mut r)?,
+            allow_read: bool::read_xdr(&mut r)?,
+        };
+        let scmap_key_order_policy = match u32::read_xdr(&mut r)? {
+            0 => ScMapKeyOrderPolicy::Strict,
+            1 => ScMapKeyOrderPolicy::Canonicalize,
+            _ => return Err(invalid_input()),
+        };
+        let event_limits = EventLimits {
+            max_events: Option::<u32>::read_xdr(&mut r)?,
+            max_topics_per_event: Option::<u32>::read_xdr(&mut r)?,
+            max_topic_size_bytes: Option::<u32>::read_xdr(&mut r)?,
+            max_data_size_bytes: Option::<u32>::read_xdr(&mut r)?,
+        };
+        let call_limits = CallLimits {
+            max_args: Option::<u32>::read_xdr(&mut r)?,
+            max_args_size_bytes: Option::<u32>::read_xdr(&mut r)?,
+            max_return_size_bytes: Option::<u32>::read_xdr(&mut r)?,
+        };
+        let source_account = Option::<AccountId>::read_xdr(&mut r)?;
+        let recording_auth = if bool::read_xdr(&mut r)? {
+            Some(bool::read_xdr(&mut r)?)
+        } else {
+            None
+        };
+        let base_prng = if bool::read_xdr(&mut r)? {
+            let seed = Hash::read_xdr(&mut r)?.0;
+            let frame_prng_count = u64::read_xdr(&mut r)?;
+            let stream_seed = Hash::read_xdr(&mut r)?.0;
+            let stream = u64::read_xdr(&mut r)?;
+            let word_pos = UInt128Parts::read_xdr(&mut r)?;
+            let protocol_version = Option::<u32>::read_xdr(&mut r)?;
+            Some(BasePrng::from_parts(
+                seed,
+                frame_prng_count,
+                stream_seed,
+                stream,
+                (u128::from(word_pos.hi) << 64) | u128::from(word_pos.lo),
+                protocol_version,
+            ))
+        } else {
+            None
+        };
+        Ok(Self {
+            ledger_info,
+            footprint,
+            entries,
+            archived,
+            recording_footprint,
+            cpu_limit,
+            mem_limit,
+            cpu_cost_params,
+            mem_cost_params,
+            depth_limit,
+            conversion_element_limit,
+            diagnostic_level,
+            cross_contract_storage_policy,
+            scmap_key_order_policy,
+            event_limits,
+            call_limits,
+            source_account,
+            recording_auth,
+            base_prng,
+        })
+    }
+}
+
+/// The budget consumed by a host, per cost type, see
+/// [Host::budget_usage_report].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BudgetUsageReport {
+    /// The usage of every cost type charged at least once, in cost type
+    /// order.
+    pub cost_types: Vec<(ContractCostType, CostTracker)>,
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+}
+
+impl BudgetUsageReport {
+    fn from_budget(budget: &Budget) -> Result<Self, HostError> {
+        let mut cost_types = vec![];
+        for ct in ContractCostType::variants() {
+            let tracker = budget.get_tracker(ct)?;
+            if tracker.iterations != 0 {
+                cost_types.push((ct, tracker));
+            }
+        }
+        Ok(Self {
+            cost_types,
+            cpu_insns: budget.get_cpu_insns_consumed()?,
+            mem_bytes: budget.get_mem_bytes_consumed()?,
+        })
+    }
+}
+
+/// Renders the report as CSV, one line per cost type followed by a line with
+/// the totals, suitable for storing in CI and diffing between runs.
+impl std::fmt::Display for BudgetUsageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "cost_type,iterations,cpu_insns,mem_bytes")?;
+        for (ct, tracker) in self.cost_types.iter() {
+            writeln!(
+                f,
+                "{:?},{},{},{}",
+                ct, tracker.iterations, tracker.cpu, tracker.mem
+            )?;
+        }
+        writeln!(f, "total,,{},{}", self.cpu_insns, self.mem_bytes)
+    }
+}
+
+/// Prints the budget usage of a host when it is dropped, see
+/// [Host::print_budget_usage_on_drop]. Shared between clones of the host so
+/// that it prints once, when the last clone is dropped.
+pub(crate) struct BudgetUsagePrinter {
+    label: String,
+    budget: Budget,
+}
+
+impl Drop for BudgetUsagePrinter {
+    fn drop(&mut self) {
+        if let Ok(report) = BudgetUsageReport::from_budget(&self.budget) {
+            println!("budget usage of {}:\n{}", self.label, report);
+        }
+    }
+}
+
+/// The observable effects of an invocation, compared by
+/// [Host::assert_metering_is_transparent].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvocationEffects {
+    /// The value returned by the invocation, or the error it failed with.
+    pub result: Result<ScVal, Error>,
+    /// The contract and system events emitted, see [Host::get_contract_events].
+    pub events: Vec<ContractEvent>,
+    /// The storage entries after the invocation, in key order.
+    pub storage: Vec<(Rc<LedgerKey>, Option<EntryWithLiveUntil>)>,
+}
+
+/// The named points of the host at which failures can be injected, see
+/// [Host::inject_fault]:
+///
+///   - `storage_read`: reading or checking the presence of a temporary or
+///     persistent contract data entry.
+///   - `storage_write`: writing, deleting or taking a temporary or persistent
+///     contract data entry.
+///   - `ed25519_verify`: verifying an ed25519 signature.
+///   - `secp256k1_recover`: recovering an ECDSA secp256k1 public key.
+pub const FAULT_POINTS: &[&str] = &[
+    "storage_read",
+    "storage_write",
+    "ed25519_verify",
+    "secp256k1_recover",
+];
+
+#[derive(Clone, Debug)]
+struct InjectedFault {
+    point: &'static str,
+    // Number of times the point still has to be reached before failing.
+    remaining: u32,
+    error: Error,
+}
+
+/// The failures pending injection, see [Host::inject_fault].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FaultInjector(Vec<InjectedFault>);
+
 impl Host {
     pub const TEST_PRNG_SEED: &'static [u8; 32] = b"12345678901234567890123456789012";
 
@@ -144,7 +682,10 @@ impl Host {
     }
 
     pub fn test_host_with_recording_footprint() -> Self {
-        let snapshot_source = Rc::<MockSnapshotSource>::new(MockSnapshotSource::new());
+        Self::test_host_with_snapshot_source(Rc::new(MockSnapshotSource::new()))
+    }
+
+    pub fn test_host_with_snapshot_source(snapshot_source: Rc<dyn SnapshotSource>) -> Self {
         let storage = Storage::with_recording_footprint(snapshot_source);
         let host = Host::with_storage_and_budget(storage, Budget::default());
         host.set_base_prng_seed(*Host::TEST_PRNG_SEED).unwrap();
@@ -214,6 +755,105 @@ impl Host {
         Ok(val.try_into()?)
     }
 
+    /// Converts the elements of `vals` to host values and returns a vector of
+    /// them.
+    pub fn to_vec_obj<T: TryIntoVal<Host, Val>>(&self, vals: &[T]) -> Result<VecObject, HostError> {
+        let vals = vals
+            .iter()
+            .map(|v| v.try_into_val(self).map_err(|e| HostError::from(e.into())))
+            .collect::<Result<Vec<Val>, HostError>>()?;
+        self.vec_new_from_slice(&vals)
+    }
+
+    /// Converts the elements of the vector `vec` from host values.
+    pub fn from_vec_obj<T: TryFromVal<Host, Val>>(
+        &self,
+        vec: VecObject,
+    ) -> Result<Vec<T>, HostError> {
+        let vals = self.visit_obj(vec, |hv: &HostVec| {
+            Ok(hv.iter().cloned().collect::<Vec<Val>>())
+        })?;
+        vals.iter()
+            .map(|v| T::try_from_val(self, v).map_err(|e| HostError::from(e.into())))
+            .collect()
+    }
+
+    /// Converts the entries of `map` to host values and returns a map of them.
+    /// The entries are inserted one by one, as the order of the keys in
+    /// `map` may differ from their order as host values.
+    pub fn to_map_obj<K, V>(&self, map: &BTreeMap<K, V>) -> Result<MapObject, HostError>
+    where
+        K: TryIntoVal<Host, Val>,
+        V: TryIntoVal<Host, Val>,
+    {
+        let mut obj = self.map_new()?;
+        for (k, v) in map {
+            let k = k
+                .try_into_val(self)
+                .map_err(|e| HostError::from(e.into()))?;
+            let v = v
+                .try_into_val(self)
+                .map_err(|e| HostError::from(e.into()))?;
+            obj = self.map_put(obj, k, v)?;
+        }
+        Ok(obj)
+    }
+
+    /// Converts the entries of the map `map` from host values.
+    pub fn from_map_obj<K, V>(&self, map: MapObject) -> Result<BTreeMap<K, V>, HostError>
+    where
+        K: TryFromVal<Host, Val> + Ord,
+        V: TryFromVal<Host, Val>,
+    {
+        let entries = self.visit_obj(map, |hm: &HostMap| {
+            Ok(hm.iter(self)?.cloned().collect::<Vec<(Val, Val)>>())
+        })?;
+        entries
+            .iter()
+            .map(|(k, v)| {
+                Ok((
+                    K::try_from_val(self, k).map_err(|e| HostError::from(e.into()))?,
+                    V::try_from_val(self, v).map_err(|e| HostError::from(e.into()))?,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn to_string_obj(&self, s: &str) -> Result<StringObject, HostError> {
+        self.string_new_from_slice(s.as_bytes())
+    }
+
+    /// Returns the content of the string `s`, which must be valid UTF-8.
+    pub fn from_string_obj(&self, s: StringObject) -> Result<String, HostError> {
+        let bytes = self.visit_obj(s, |s: &ScString| Ok(s.to_vec()))?;
+        String::from_utf8(bytes).map_err(|_| {
+            self.err(
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+                "string is not valid UTF-8",
+                &[s.to_val()],
+            )
+        })
+    }
+
+    pub fn to_bytes_obj<const N: usize>(&self, bytes: &[u8; N]) -> Result<BytesObject, HostError> {
+        self.bytes_new_from_slice(bytes)
+    }
+
+    /// Returns the content of the bytes `b`, which must be exactly `N` long.
+    pub fn from_bytes_obj<const N: usize>(&self, b: BytesObject) -> Result<[u8; N], HostError> {
+        self.visit_obj(b, |bytes: &ScBytes| {
+            <[u8; N]>::try_from(bytes.as_slice()).map_err(|_| {
+                self.err(
+                    ScErrorType::Value,
+                    ScErrorCode::InvalidInput,
+                    "unexpected bytes length",
+                    &[b.to_val()],
+                )
+            })
+        })
+    }
+
     // Registers a contract with provided Wasm code and returns the registered
     // contract's address.
     // The contract address deterministically depends on the input account and
@@ -296,6 +936,385 @@ impl Host {
         (host, contract_addresses)
     }
 
+    // Simulates the state archival performed by the network at the current
+    // ledger: persistent entries in storage whose TTL has run out are
+    // archived (see `Storage::archive`), and expired temporary entries are
+    // removed. Returns the keys of the newly archived entries.
+    pub fn archive_expired_entries(&self) -> Result<Vec<Rc<LedgerKey>>, HostError> {
+        let ledger_seq = self.with_ledger_info(|li| Ok(li.sequence_number))?;
+        let budget = self.budget_cloned();
+        self.with_mut_storage(|storage| {
+            let mut expired = vec![];
+            for (key, entry) in storage.map.iter(&budget)? {
+                if let Some((_, Some(live_until))) = entry {
+                    if *live_until < ledger_seq {
+                        expired.push(Rc::clone(key));
+                    }
+                }
+            }
+            let mut archived = vec![];
+            for key in expired {
+                if matches!(
+                    get_key_durability(&key),
+                    Some(ContractDataDurability::Persistent)
+                ) {
+                    storage.archive(&key, &budget)?;
+                    archived.push(key);
+                } else {
+                    storage.map = storage.map.insert(key, None, &budget)?;
+                }
+            }
+            Ok(archived)
+        })
+    }
+
+    // Restores an archived entry with the minimum persistent TTL, the same
+    // way a restore operation does on-chain. Returns the new live_until
+    // ledger of the entry.
+    pub fn restore_archived_entry(&self, key: &Rc<LedgerKey>) -> Result<u32, HostError> {
+        let live_until = self.get_min_live_until_ledger(ContractDataDurability::Persistent)?;
+        let budget = self.budget_cloned();
+        self.with_mut_storage(|storage| storage.restore(key, live_until, &budget))?;
+        Ok(live_until)
+    }
+
+    /// The ledger close time assumed by [Host::advance_ledgers] and
+    /// [Host::advance_time].
+    pub const TEST_LEDGER_CLOSE_TIME_SECS: u64 = 5;
+
+    /// Advances the ledger sequence number by `n` and the timestamp by `n`
+    /// ledger close times, then archives the entries whose TTL has run out
+    /// via [Host::archive_expired_entries], returning the archived keys.
+    pub fn advance_ledgers(&self, n: u32) -> Result<Vec<Rc<LedgerKey>>, HostError> {
+        let secs = (n as u64).saturating_mul(Self::TEST_LEDGER_CLOSE_TIME_SECS);
+        self.advance_ledger_info(n, secs)?;
+        self.archive_expired_entries()
+    }
+
+    /// Advances the timestamp by `secs` and the ledger sequence number by the
+    /// number of ledgers that close in that time, so that repeated small
+    /// advances add up, then archives the entries whose TTL has run out via
+    /// [Host::archive_expired_entries], returning the archived keys.
+    pub fn advance_time(&self, secs: u64) -> Result<Vec<Rc<LedgerKey>>, HostError> {
+        let timestamp = self.with_ledger_info(|li| Ok(li.timestamp))?;
+        let close = Self::TEST_LEDGER_CLOSE_TIME_SECS;
+        let closed = timestamp.saturating_add(secs) / close - timestamp / close;
+        let n = u32::try_from(closed).map_err(|_| {
+            self.err(
+                ScErrorType::Context,
+                ScErrorCode::ArithDomain,
+                "ledger sequence number overflow",
+                &[],
+            )
+        })?;
+        self.advance_ledger_info(n, secs)?;
+        self.archive_expired_entries()
+    }
+
+    fn advance_ledger_info(&self, ledgers: u32, secs: u64) -> Result<(), HostError> {
+        let (sequence_number, timestamp) = self.with_ledger_info(|li| {
+            match (
+                li.sequence_number.checked_add(ledgers),
+                li.timestamp.checked_add(secs),
+            ) {
+                (Some(sequence_number), Some(timestamp)) => Ok((sequence_number, timestamp)),
+                _ => Err(self.err(
+                    ScErrorType::Context,
+                    ScErrorCode::ArithDomain,
+                    "ledger sequence number or timestamp overflow",
+                    &[],
+                )),
+            }
+        })?;
+        self.with_mut_ledger_info(|li| {
+            li.sequence_number = sequence_number;
+            li.timestamp = timestamp;
+        })
+    }
+
+    /// Captures the ledger info and all the live entries in the storage map
+    /// of this host, i.e. the state that would be committed if the host
+    /// finished now. Entries of the underlying [SnapshotSource] that haven't
+    /// been accessed are not included, nor are the native test contracts.
+    pub fn ledger_snapshot(&self) -> Result<LedgerSnapshot, HostError> {
+        let ledger_info = self.with_ledger_info(|li| Ok(li.clone()))?;
+        let storage = self.try_borrow_storage()?;
+        let entries = storage
+            .map
+            .map
+            .iter()
+            .filter_map(|(key, entry)| {
+                entry
+                    .as_ref()
+                    .map(|(entry, live_until)| (Rc::clone(key), (Rc::clone(entry), *live_until)))
+            })
+            .collect();
+        Ok(LedgerSnapshot {
+            ledger_info,
+            entries,
+        })
+    }
+
+    /// Constructs a test host in recording mode on top of the ledger state
+    /// from `snapshot`, e.g. the one previously captured via
+    /// [Host::ledger_snapshot] and stored in a file.
+    pub fn test_host_from_ledger_snapshot(snapshot: &LedgerSnapshot) -> Result<Self, HostError> {
+        let snapshot_source = Rc::new(MockSnapshotSource::new());
+        for (key, entry) in snapshot.entries.iter() {
+            snapshot_source.put(key, entry)?;
+        }
+        let storage = Storage::with_recording_footprint(snapshot_source);
+        let host = Host::with_storage_and_budget(storage, Budget::default());
+        host.set_base_prng_seed(*Host::TEST_PRNG_SEED)?;
+        host.set_ledger_info(snapshot.ledger_info.clone())?;
+        Ok(host)
+    }
+
+    /// Captures the state of this host, see [HostSnapshot]. Fails with
+    /// `(Context, InvalidAction)` if an invocation is in progress.
+    pub fn to_snapshot(&self) -> Result<HostSnapshot, HostError> {
+        let state = self.detach_state()?;
+        Ok(HostSnapshot {
+            state,
+            snapshot_source: match &self.try_borrow_storage()?.mode {
+                FootprintMode::Recording(src) => Some(Rc::clone(src)),
+                FootprintMode::Enforcing => None,
+            },
+            test_contracts: self
+                .try_borrow_contracts()?
+                .iter()
+                .map(|(id, contract)| (id.clone(), Rc::clone(contract)))
+                .collect(),
+        })
+    }
+
+    /// Constructs a host in the state captured by `snapshot` via
+    /// [Host::to_snapshot], with nothing consumed from its budget.
+    pub fn from_snapshot(snapshot: &HostSnapshot) -> Result<Self, HostError> {
+        let host = Host::attach_state(snapshot.state.clone(), snapshot.snapshot_source.clone())?;
+        *host.try_borrow_contracts_mut()? = snapshot.test_contracts.iter().cloned().collect();
+        Ok(host)
+    }
+
+    /// Returns the host to the state it was in right after construction, so
+    /// that it can be reused across test cases instead of constructing a new
+    /// host for each of them.
+    ///
+    /// Storage (keeping its footprint mode and snapshot source), events,
+    /// objects, the authorization manager (keeping its mode, but not its
+    /// entries), registered test contracts, the budget consumption and all
+    /// the per-invocation test state are cleared, and the base PRNG is
+    /// re-seeded with its seed. Configuration is preserved: the budget limits
+    /// and cost parameters, the ledger info, the source account, the
+    /// diagnostic level, the limits and policies and the installed hooks.
+    pub fn reset(&self) -> Result<(), HostError> {
+        if !self.try_borrow_context_stack()?.is_empty() {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidAction,
+                "cannot reset a host during an invocation",
+                &[],
+            ));
+        }
+        {
+            let mut storage = self.try_borrow_storage_mut()?;
+            // Keep the storage access callback, so that the storage hook and
+            // the storage keys tracking survive the reset.
+            let access_callback = storage.access_callback.take();
+            *storage = match &storage.mode {
+                FootprintMode::Recording(src) => Storage::with_recording_footprint(Rc::clone(src)),
+                FootprintMode::Enforcing => Storage::default(),
+            };
+            storage.access_callback = access_callback;
+        }
+        *self.try_borrow_objects_mut()? = vec![];
+        *self.try_borrow_events_mut()? = Default::default();
+        *self.try_borrow_val_hash_cache_mut()? = Default::default();
+        *self.try_borrow_budget_post_mortem_mut()? = Default::default();
+        self.try_borrow_invocation_budgets_mut()?.clear();
+        let recording_auth = self
+            .try_borrow_authorization_manager()?
+            .recording_mode_disables_non_root_auth();
+        match recording_auth {
+            Some(disable_non_root_auth) => self.switch_to_recording_auth(disable_non_root_auth)?,
+            None => self.set_authorization_entries(vec![])?,
+        }
+        *self.try_borrow_previous_authorization_manager_mut()? = None;
+        self.try_borrow_contracts_mut()?.clear();
+        if let Some(keys_by_contract) = self.try_borrow_storage_keys_by_contract_mut()?.as_mut() {
+            keys_by_contract.clear();
+        }
+        *self.try_borrow_invocation_snapshot_mut()? = None;
+        self.clear_injected_faults()?;
+        let seed = self
+            .try_borrow_base_prng()?
+            .as_ref()
+            .map(|prng| prng.seed());
+        if let Some(seed) = seed {
+            self.set_base_prng_seed(seed)?;
+        }
+        let (cpu_limit, mem_limit, _, _) = self.budget_ref().to_configs()?;
+        self.budget_ref().reset_limits(cpu_limit, mem_limit)
+    }
+
+    /// Runs `invoke` on two forks of this host (see [Host::to_snapshot]), one
+    /// with the budget of this host enforced and one with an unlimited
+    /// budget, and panics if the [InvocationEffects] of the two runs differ,
+    /// which means that metering changed the semantics of the invocation.
+    /// This host is left untouched.
+    ///
+    /// The budget of this host must be large enough for the invocation, as
+    /// running out of it in the metered run is reported as a divergence.
+    /// Returns the effects of the invocation.
+    pub fn assert_metering_is_transparent<F>(&self, invoke: F) -> InvocationEffects
+    where
+        F: Fn(&Host) -> Result<Val, HostError>,
+    {
+        let snapshot = self.to_snapshot().unwrap();
+        let run = |unmetered: bool| -> InvocationEffects {
+            let host = Host::from_snapshot(&snapshot).unwrap();
+            if unmetered {
+                host.budget_ref().reset_unlimited().unwrap();
+            }
+            let result = invoke(&host);
+            // Collecting the effects must not run out of budget either.
+            host.budget_ref().reset_unlimited().unwrap();
+            let effects = InvocationEffects {
+                result: result
+                    .and_then(|val| host.from_host_val(val))
+                    .map_err(|e| e.error),
+                events: host
+                    .get_contract_events(&ContractEventFilter::default())
+                    .unwrap(),
+                storage: host.try_borrow_storage().unwrap().map.map.clone(),
+            };
+            effects
+        };
+        let metered = run(false);
+        let unmetered = run(true);
+        assert_eq!(
+            metered, unmetered,
+            "the metered (left) and unmetered (right) runs diverged"
+        );
+        metered
+    }
+
+    /// Asserts that exactly one of the contract events returned by
+    /// [Host::get_contract_events] matches `filter`, and returns it. On
+    /// failure, panics with the filter and all the events recorded so far.
+    pub fn assert_contract_event(&self, filter: &ContractEventFilter) -> ContractEvent {
+        let mut matching = self.get_contract_events(filter).unwrap();
+        if matching.len() != 1 {
+            panic!(
+                "expected exactly one contract event matching {:?}, found {}\n{}",
+                filter,
+                matching.len(),
+                self.render_events_for_assertion()
+            );
+        }
+        matching.pop().unwrap()
+    }
+
+    /// Asserts that none of the contract events returned by
+    /// [Host::get_contract_events] match `filter`. On failure, panics with
+    /// the filter and all the events recorded so far.
+    pub fn assert_no_contract_event(&self, filter: &ContractEventFilter) {
+        let matching = self.get_contract_events(filter).unwrap();
+        if !matching.is_empty() {
+            panic!(
+                "expected no contract event matching {:?}, found {}\n{}",
+                filter,
+                matching.len(),
+                self.render_events_for_assertion()
+            );
+        }
+    }
+
+    fn render_events_for_assertion(&self) -> String {
+        let events = self.get_events().unwrap();
+        let mut out = format!("recorded events ({}):\n", events.0.len());
+        for event in events.0.iter() {
+            out.push_str(&format!("  {}: {}\n", event.sequence, event));
+        }
+        out
+    }
+
+    /// Returns the budget consumed by this host so far, per cost type.
+    pub fn budget_usage_report(&self) -> Result<BudgetUsageReport, HostError> {
+        BudgetUsageReport::from_budget(self.budget_ref())
+    }
+
+    /// Prints the [BudgetUsageReport] of this host under `label` once the
+    /// host is dropped, e.g. at the end of a test, so that the costs of the
+    /// contract functions the test exercises can be tracked over time.
+    pub fn print_budget_usage_on_drop(&self, label: impl Into<String>) -> Result<(), HostError> {
+        *self.try_borrow_budget_usage_printer_mut()? = Some(Rc::new(BudgetUsagePrinter {
+            label: label.into(),
+            budget: self.budget_cloned(),
+        }));
+        Ok(())
+    }
+
+    /// Injects a failure at the named host `point` (one of [FAULT_POINTS]):
+    /// the `nth` time the point is reached from now on (starting at 1), the
+    /// host fails with `error` instead of performing the operation.
+    ///
+    /// Every injected failure fires once. Several failures can be pending at
+    /// the same time, including at the same point, and each of them counts
+    /// the times the point is reached independently. Injection is not
+    /// metered, so it does not affect the budget consumed up to the failure.
+    pub fn inject_fault(&self, point: &str, nth: u32, error: Error) -> Result<(), HostError> {
+        let Some(point) = FAULT_POINTS.iter().find(|p| **p == point) else {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidInput,
+                "unknown fault injection point",
+                &[],
+            ));
+        };
+        if nth == 0 {
+            return Err(self.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidInput,
+                "faults are injected starting at the 1st time a point is reached",
+                &[],
+            ));
+        }
+        self.try_borrow_fault_injector_mut()?.0.push(InjectedFault {
+            point,
+            remaining: nth,
+            error,
+        });
+        Ok(())
+    }
+
+    /// Removes all the failures injected via [Host::inject_fault] that have
+    /// not fired yet.
+    pub fn clear_injected_faults(&self) -> Result<(), HostError> {
+        self.try_borrow_fault_injector_mut()?.0.clear();
+        Ok(())
+    }
+
+    // Called when `point` is reached, fails if an injected failure fires.
+    pub(crate) fn check_injected_fault(&self, point: &'static str) -> Result<(), HostError> {
+        let fired = {
+            let mut injector = self.try_borrow_fault_injector_mut()?;
+            for fault in injector.0.iter_mut().filter(|f| f.point == point) {
+                fault.remaining = fault.remaining.saturating_sub(1);
+            }
+            // Failures due at the same time fire one after another.
+            injector
+                .0
+                .iter()
+                .position(|f| f.point == point && f.remaining == 0)
+                .map(|i| injector.0.remove(i).error)
+        };
+        match fired {
+            Some(error) => Err(self.error(error, &format!("injected fault at {point}"), &[])),
+            None => Ok(()),
+        }
+    }
+
     #[cfg(all(test, feature = "testutils"))]
     pub(crate) fn measured_call(
         &self,
@@ -370,9 +1389,96 @@ impl Host {
 
 #[cfg(test)]
 pub(crate) mod wasm {
-    use crate::{Symbol, Tag, U32Val, Val};
+    use crate::{StorageType, Symbol, Tag, U32Val, Val};
     use soroban_synth_wasm::{Arity, FuncRef, LocalRef, ModEmitter, Operand};
 
+    /// Builds a module exporting a single function `test` with `n_args`
+    /// arguments that grows the memory by `mem_grow_pages` and then calls
+    /// each of the chosen host functions, in order, `loop_iterations` times,
+    /// passing them the leading arguments of `test` and dropping their
+    /// results. `test` returns the symbol `pass`.
+    pub(crate) struct WasmFixture {
+        n_args: u32,
+        host_fns: Vec<(&'static str, &'static str, Arity)>,
+        loop_iterations: u32,
+        mem_pages: u32,
+        mem_grow_pages: u32,
+    }
+
+    impl WasmFixture {
+        pub(crate) fn new(n_args: u32) -> Self {
+            Self {
+                n_args,
+                host_fns: vec![],
+                loop_iterations: 1,
+                mem_pages: 1,
+                mem_grow_pages: 0,
+            }
+        }
+
+        /// Calls the host function `name` of module `module` (their export
+        /// names in `env.json`), which takes `arity` arguments.
+        pub(crate) fn call_host_fn(
+            mut self,
+            module: &'static str,
+            name: &'static str,
+            arity: u32,
+        ) -> Self {
+            assert!(arity <= self.n_args, "not enough arguments to pass on");
+            self.host_fns.push((module, name, Arity(arity)));
+            self
+        }
+
+        pub(crate) fn loop_iterations(mut self, n: u32) -> Self {
+            self.loop_iterations = n;
+            self
+        }
+
+        /// Sets the initial memory size and the number of pages the memory
+        /// is grown by when `test` is called.
+        pub(crate) fn memory_pages(mut self, initial: u32, grow: u32) -> Self {
+            self.mem_pages = initial;
+            self.mem_grow_pages = grow;
+            self
+        }
+
+        pub(crate) fn build(&self) -> Vec<u8> {
+            let mut me = ModEmitter::from_configs(self.mem_pages, 128);
+            let funcs: Vec<(FuncRef, Arity)> = self
+                .host_fns
+                .iter()
+                .map(|(module, name, arity)| (me.import_func(module, name, *arity), *arity))
+                .collect();
+            let mut fe = me.func(Arity(self.n_args), 1);
+            let counter = fe.locals[0].0;
+            if self.mem_grow_pages > 0 {
+                fe.push(Operand::Const32(self.mem_grow_pages as i32));
+                fe.memory_grow();
+                fe.drop();
+            }
+            if self.loop_iterations > 0 && !funcs.is_empty() {
+                fe.loop_block();
+                for (f, arity) in funcs.iter() {
+                    for i in 0..arity.0 {
+                        fe.local_get(LocalRef(i));
+                    }
+                    fe.call_func(*f);
+                    fe.drop();
+                }
+                fe.local_get(counter);
+                fe.i64_const(1);
+                fe.i64_add();
+                fe.local_tee(counter);
+                fe.i64_const(self.loop_iterations as i64);
+                fe.i64_lt_u();
+                fe.br_if(0);
+                fe.end();
+            }
+            fe.push(Symbol::try_from_small_str("pass").unwrap());
+            fe.finish_and_export("test").finish()
+        }
+    }
+
     pub(crate) fn wasm_module_with_4n_insns(n: usize) -> Vec<u8> {
         let mut fe = ModEmitter::default().func(Arity(1), 0);
         let arg = fe.args[0];
@@ -654,6 +1760,26 @@ pub(crate) mod wasm {
         me.finish()
     }
 
+    // Returns a module with the given env meta section, exporting a `test`
+    // function that calls the host function `fn_str` of module `mod_str` with
+    // its own arguments.
+    pub fn wasm_module_calling_host_function_with_meta(
+        meta: &[u8],
+        mod_str: &str,
+        fn_str: &str,
+        arity: u32,
+    ) -> Vec<u8> {
+        let mut me = ModEmitter::new();
+        me.custom_section(soroban_env_common::meta::ENV_META_V0_SECTION_NAME, meta);
+        let f0 = me.import_func(mod_str, fn_str, Arity(arity));
+        let mut fe = me.func(Arity(arity), 0);
+        for i in 0..arity {
+            fe.push(Operand::Local(LocalRef(i)));
+        }
+        fe.call_func(f0);
+        fe.finish_and_export("test").finish()
+    }
+
     pub fn wasm_module_with_floating_point_ops() -> Vec<u8> {
         let me = ModEmitter::default();
         let mut fe = me.func(Arity(0), 0);
@@ -753,6 +1879,24 @@ pub(crate) mod wasm {
         me.finish()
     }
 
+    // A contract whose `__constructor(v)` stores `v` in instance storage under
+    // `init`, and whose `get()` returns the stored value.
+    pub(crate) fn wasm_module_with_constructor() -> Vec<u8> {
+        let key = Symbol::try_from_small_str("init").unwrap();
+        let mut me = ModEmitter::default();
+        // put_contract_data and get_contract_data
+        let put = me.import_func("l", "_", Arity(3));
+        let get = me.import_func("l", "1", Arity(2));
+        let mut fe = me.func(Arity(1), 0);
+        let arg = fe.args[0].0;
+        fe.push(key).push(arg).push(StorageType::Instance);
+        fe.call_func(put);
+        let mut fe = fe.finish_and_export("__constructor").func(Arity(0), 0);
+        fe.push(key).push(StorageType::Instance);
+        fe.call_func(get);
+        fe.finish_and_export("get").finish()
+    }
+
     pub(crate) fn wasm_module_large_globals(n: u32) -> Vec<u8> {
         let mut me = ModEmitter::default();
         for i in 0..n {
@@ -0,0 +1,233 @@
+//! Native mock contracts for the dependencies that many contracts share, such
+//! as price oracles and tokens, so that tests can register them on a [Host]
+//! with a single call instead of maintaining their own Wasm fixtures.
+//!
+//! The mocks keep their state in the instance storage of the contract, so it
+//! is rolled back along with the rest of the storage when a call fails.
+
+use std::rc::Rc;
+
+use crate::{
+    xdr::{Hash, ScAddress, ScErrorCode, ScErrorType},
+    AddressObject, ContractFunctionSet, Env, EnvBase, Error, Host, HostError, StorageType, Symbol,
+    SymbolStr, TryFromVal, Val,
+};
+
+use super::generate_bytes_array;
+
+/// Errors returned by the functions of [MockToken].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MockTokenError {
+    NegativeAmount = 1,
+    InsufficientBalance = 2,
+}
+
+impl From<MockTokenError> for Error {
+    fn from(e: MockTokenError) -> Self {
+        Error::from_contract_error(e as u32)
+    }
+}
+
+/// A price oracle that reports the prices configured by the tests.
+///
+/// Functions:
+/// - `decimals() -> u32`: the number of decimals of the prices.
+/// - `set_price(asset: Val, price: i128)`: sets the price of `asset`, which
+///   can be any value (e.g. the address of a token or a symbol).
+/// - `lastprice(asset: Val) -> i128`: the price of `asset`, or `void` if it
+///   has not been set.
+pub struct MockPriceOracle {
+    pub decimals: u32,
+}
+
+/// A token that anyone can mint, with a subset of the token interface.
+///
+/// Functions:
+/// - `name() -> String`, `symbol() -> String`, `decimals() -> u32`.
+/// - `mint(to: Address, amount: i128)`: mints `amount` to `to`, without any
+///   authorization.
+/// - `balance(id: Address) -> i128`.
+/// - `transfer(from: Address, to: Address, amount: i128)`: requires the
+///   authorization of `from`.
+pub struct MockToken {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+impl ContractFunctionSet for MockPriceOracle {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        unwrap_or_escalate(host, self.dispatch(func, host, args))
+    }
+}
+
+impl MockPriceOracle {
+    fn dispatch(&self, func: &Symbol, host: &Host, args: &[Val]) -> Result<Option<Val>, HostError> {
+        let res = match SymbolStr::try_from_val(host, func)?.to_string().as_str() {
+            "decimals" => {
+                check_args(host, args, 0)?;
+                self.decimals.into()
+            }
+            "set_price" => {
+                check_args(host, args, 2)?;
+                i128::try_from_val(host, &args[1])?;
+                let key = storage_key(host, "price", args[0])?;
+                host.put_contract_data(key, args[1], StorageType::Instance)?;
+                Val::VOID.into()
+            }
+            "lastprice" => {
+                check_args(host, args, 1)?;
+                let key = storage_key(host, "price", args[0])?;
+                if bool::try_from(host.has_contract_data(key, StorageType::Instance)?)? {
+                    host.get_contract_data(key, StorageType::Instance)?
+                } else {
+                    Val::VOID.into()
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(res))
+    }
+}
+
+impl ContractFunctionSet for MockToken {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        unwrap_or_escalate(host, self.dispatch(func, host, args))
+    }
+}
+
+impl MockToken {
+    fn dispatch(&self, func: &Symbol, host: &Host, args: &[Val]) -> Result<Option<Val>, HostError> {
+        let res = match SymbolStr::try_from_val(host, func)?.to_string().as_str() {
+            "name" => {
+                check_args(host, args, 0)?;
+                host.string_new_from_slice(self.name.as_bytes())?.into()
+            }
+            "symbol" => {
+                check_args(host, args, 0)?;
+                host.string_new_from_slice(self.symbol.as_bytes())?.into()
+            }
+            "decimals" => {
+                check_args(host, args, 0)?;
+                self.decimals.into()
+            }
+            "balance" => {
+                check_args(host, args, 1)?;
+                Val::try_from_val(host, &balance(host, args[0])?)?
+            }
+            "mint" => {
+                check_args(host, args, 2)?;
+                let amount = amount(host, args[1])?;
+                let to_balance = balance(host, args[0])?;
+                set_balance(host, args[0], to_balance.saturating_add(amount))?;
+                Val::VOID.into()
+            }
+            "transfer" => {
+                check_args(host, args, 3)?;
+                let amount = amount(host, args[2])?;
+                host.require_auth(AddressObject::try_from_val(host, &args[0])?)?;
+                let from_balance = balance(host, args[0])?;
+                if from_balance < amount {
+                    return Err(Error::from(MockTokenError::InsufficientBalance).into());
+                }
+                set_balance(host, args[0], from_balance - amount)?;
+                let to_balance = balance(host, args[1])?;
+                set_balance(host, args[1], to_balance.saturating_add(amount))?;
+                Val::VOID.into()
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(res))
+    }
+}
+
+// Returns contract errors as the `Error` values of the contract function, and
+// escalates any other error to a panic, as the errors of the host functions
+// are escalated for the native contracts built with the SDK.
+fn unwrap_or_escalate(host: &Host, res: Result<Option<Val>, HostError>) -> Option<Val> {
+    match res {
+        Ok(val) => val,
+        Err(e) if e.error.is_type(ScErrorType::Contract) => Some(e.error.to_val()),
+        Err(e) => {
+            host.record_test_contract_panic(e.error);
+            panic!("{:?}", e)
+        }
+    }
+}
+
+fn check_args(host: &Host, args: &[Val], expected: usize) -> Result<(), HostError> {
+    if args.len() != expected {
+        return Err(host.err(
+            ScErrorType::Context,
+            ScErrorCode::UnexpectedSize,
+            "unexpected number of arguments of a mock contract function",
+            &[],
+        ));
+    }
+    Ok(())
+}
+
+fn storage_key(host: &Host, prefix: &str, key: Val) -> Result<Val, HostError> {
+    let prefix = Symbol::try_from_val(host, &prefix)?;
+    Ok(host.vec_new_from_slice(&[prefix.to_val(), key])?.into())
+}
+
+fn amount(host: &Host, amount: Val) -> Result<i128, HostError> {
+    let amount = i128::try_from_val(host, &amount)?;
+    if amount < 0 {
+        return Err(Error::from(MockTokenError::NegativeAmount).into());
+    }
+    Ok(amount)
+}
+
+fn balance(host: &Host, id: Val) -> Result<i128, HostError> {
+    AddressObject::try_from_val(host, &id)?;
+    let key = storage_key(host, "balance", id)?;
+    if bool::try_from(host.has_contract_data(key, StorageType::Instance)?)? {
+        Ok(i128::try_from_val(
+            host,
+            &host.get_contract_data(key, StorageType::Instance)?,
+        )?)
+    } else {
+        Ok(0)
+    }
+}
+
+fn set_balance(host: &Host, id: Val, balance: i128) -> Result<(), HostError> {
+    let key = storage_key(host, "balance", id)?;
+    let balance = Val::try_from_val(host, &balance)?;
+    host.put_contract_data(key, balance, StorageType::Instance)?;
+    Ok(())
+}
+
+impl Host {
+    /// Registers a [MockPriceOracle] reporting prices with `decimals`
+    /// decimals at a new contract address.
+    pub fn register_mock_price_oracle(&self, decimals: u32) -> Result<AddressObject, HostError> {
+        self.register_mock_contract(Rc::new(MockPriceOracle { decimals }))
+    }
+
+    /// Registers a [MockToken] at a new contract address.
+    pub fn register_mock_token(
+        &self,
+        name: &str,
+        symbol: &str,
+        decimals: u32,
+    ) -> Result<AddressObject, HostError> {
+        self.register_mock_contract(Rc::new(MockToken {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+        }))
+    }
+
+    fn register_mock_contract(
+        &self,
+        contract: Rc<dyn ContractFunctionSet>,
+    ) -> Result<AddressObject, HostError> {
+        let address =
+            self.add_host_object(ScAddress::Contract(Hash(generate_bytes_array(self))))?;
+        self.register_test_contract(address, contract)?;
+        Ok(address)
+    }
+}
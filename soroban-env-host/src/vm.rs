@@ -18,9 +18,13 @@ pub(crate) use dispatch::dummy0;
 use crate::{
     budget::AsBudget,
     err,
+    events::DiagnosticSeverity,
     host::{error::TryBorrowOrErr, metered_clone::MeteredContainer},
     meta::{self, get_ledger_protocol_version},
-    xdr::{ContractCostType, Hash, Limited, ReadXdr, ScEnvMetaEntry, ScErrorCode, ScErrorType},
+    xdr::{
+        ContractCostType, Hash, Limited, ReadXdr, ScEnvMetaEntry, ScErrorCode, ScErrorType,
+        ScSpecEntry,
+    },
     ConversionError, Host, HostError, Symbol, SymbolStr, TryIntoVal, Val, WasmiMarshal,
     DEFAULT_XDR_RW_LIMITS,
 };
@@ -38,6 +42,7 @@ use wasmi::{Caller, StoreContextMut};
 impl wasmi::core::HostError for HostError {}
 
 const MAX_VM_ARGS: usize = 32;
+const CONTRACT_SPEC_SECTION_NAME: &str = "contractspecv0";
 
 /// A [Vm] is a thin wrapper around an instance of [wasmi::Module]. Multiple
 /// [Vm]s may be held in a single [Host], and each contains a single WASM module
@@ -258,7 +263,16 @@ impl Vm {
 
         {
             let _span0 = tracy_span!("define host functions");
+            let ledger_proto = host.get_ledger_protocol_version()?;
             for hf in HOST_FUNCTIONS {
+                // Functions introduced after the ledger protocol are left
+                // undefined, so that linking a contract importing them fails.
+                if hf
+                    .min_proto
+                    .is_some_and(|min_proto| ledger_proto < min_proto)
+                {
+                    continue;
+                }
                 let func = (hf.wrap)(&mut store);
                 host.map_err(
                     linker
@@ -402,7 +416,8 @@ impl Vm {
                         return Err(host.error(err, &msg, &[func_sym.to_val()]));
                     }
                     if let Some(he) = trap.downcast::<HostError>() {
-                        host.log_diagnostics(
+                        host.log_diagnostics_with_severity(
+                            DiagnosticSeverity::Warn,
                             "VM call trapped with HostError",
                             &[func_sym.to_val(), he.error.to_val()],
                         );
@@ -430,6 +445,16 @@ impl Vm {
         )
     }
 
+    /// Returns whether the instantiated module exports a function named
+    /// `name`.
+    pub(crate) fn has_exported_function(&self, name: &str) -> Result<bool, HostError> {
+        Ok(self
+            .instance
+            .get_export(&*self.store.try_borrow_or_err()?, name)
+            .and_then(|e| e.into_func())
+            .is_some())
+    }
+
     pub(crate) fn invoke_function_raw(
         self: &Rc<Self>,
         host: &Host,
@@ -461,6 +486,36 @@ impl Vm {
         Self::module_custom_section(&self.module, name)
     }
 
+    /// Returns the name of the contract error `code` as declared in the spec
+    /// of the contract (its `contractspecv0` custom section), formatted as
+    /// `Enum::Case`, or `None` if the spec is missing or doesn't declare it.
+    pub(crate) fn contract_error_name(
+        &self,
+        host: &Host,
+        code: u32,
+    ) -> Result<Option<String>, HostError> {
+        let Some(spec) = self.custom_section(CONTRACT_SPEC_SECTION_NAME) else {
+            return Ok(None);
+        };
+        host.charge_budget(ContractCostType::ValDeser, Some(spec.len() as u64))?;
+        let mut limits = DEFAULT_XDR_RW_LIMITS;
+        limits.len = spec.len();
+        let mut cursor = Limited::new(Cursor::new(spec), limits);
+        for entry in ScSpecEntry::read_xdr_iter(&mut cursor) {
+            // The spec is not validated by the host, so a malformed spec is
+            // only used up to the first entry that fails to decode.
+            let Ok(entry) = entry else {
+                break;
+            };
+            if let ScSpecEntry::UdtErrorEnumV0(e) = entry {
+                if let Some(case) = e.cases.iter().find(|c| c.value == code) {
+                    return Ok(Some(format!("{}::{}", e.name, case.name)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Utility function that synthesizes a `VmCaller<Host>` configured to point
     /// to this VM's `Store` and `Instance`, and calls the provided function
     /// back with it. Mainly used for testing.
@@ -1,12 +1,13 @@
 use super::FuelRefillable;
 use crate::{
     xdr::{ContractCostType, ScErrorCode, ScErrorType},
-    CheckedEnvArg, EnvBase, Host, HostError, VmCaller, VmCallerEnv,
+    CheckedEnvArg, EnvBase, ErrorContext, Host, HostError, VmCaller, VmCallerEnv,
 };
 use crate::{
-    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I256Object, I256Val,
-    I64Object, MapObject, StorageType, StringObject, Symbol, SymbolObject, TimepointObject,
-    U128Object, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
+    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I128Val, I256Object,
+    I256Val, I64Object, MapObject, StorageType, StringObject, Symbol, SymbolObject,
+    TimepointObject, U128Object, U128Val, U256Object, U256Val, U32Val, U64Object, U64Val, Val,
+    VecObject, Void,
 };
 use soroban_env_common::{call_macro_with_all_host_functions, WasmiMarshal};
 use wasmi::{
@@ -76,7 +77,9 @@ impl_relative_object_conversion!(U128Object);
 impl_relative_object_conversion!(U256Object);
 
 impl_relative_object_conversion!(U64Val);
+impl_relative_object_conversion!(U128Val);
 impl_relative_object_conversion!(U256Val);
+impl_relative_object_conversion!(I128Val);
 impl_relative_object_conversion!(I256Val);
 
 // Trivial / non-relativizing impls are ok for types that can't carry objects.
@@ -112,7 +115,7 @@ macro_rules! generate_dispatch_functions {
                     // pattern-repetition matcher so that it will match all such
                     // descriptions.
                     $(#[$fn_attr:meta])*
-                    { $fn_str:literal, fn $fn_id:ident ($($arg:ident:$type:ty),*) -> $ret:ty }
+                    { $fn_str:literal, $($min_proto:literal)?, fn $fn_id:ident ($($arg:ident:$type:ty),*) -> $ret:ty }
                 )*
             }
         )*
@@ -180,6 +183,12 @@ macro_rules! generate_dispatch_functions {
                     // the host maintains control of the budget.
                     FuelRefillable::return_fuel_to_host(&mut caller, &host).map_err(|he| Trap::from(he))?;
 
+                    // In debug mode, remember the call in case it runs out of budget.
+                    host.record_recent_host_call(core::stringify!($fn_id));
+
+                    #[cfg(feature = "tracing")]
+                    let trace = crate::host::trace::HostFnTrace::start(&host, core::stringify!($fn_id));
+
                     // Charge for the host function dispatching: conversion between VM fuel and
                     // host budget, marshalling values. This does not account for the actual work
                     // being done in those functions, which are metered individually by the implementation.
@@ -196,6 +205,9 @@ macro_rules! generate_dispatch_functions {
                     // wasmi::Value.
                     let res: Result<_, HostError> = host.$fn_id(&mut vmcaller, $(<$type>::check_env_arg(<$type>::try_marshal_from_relative_value(Value::I64($arg), &host)?, &host)?),*);
 
+                    #[cfg(feature = "tracing")]
+                    trace.finish(&host, res.is_ok());
+
                     #[cfg(feature = "testutils")]
                     {
                         let res_str: Result<String,&HostError> = match &res {
@@ -221,12 +233,15 @@ macro_rules! generate_dispatch_functions {
                                 Err(BadSignature.into())
                             }
                         },
-                        Err(hosterr) => {
-                            // We make a new HostError here to capture the escalation event itself.
-                            let escalation: HostError =
+                        Err(mut hosterr) => {
+                            hosterr.push_context(ErrorContext::HostFunction(stringify!($fn_id)));
+                            // We make a new HostError here to capture the escalation event itself,
+                            // carrying over the context of the error it escalates.
+                            let mut escalation: HostError =
                                 host.error(hosterr.error,
                                            concat!("escalating error to VM trap from failed host function call: ",
                                                    stringify!($fn_id)), &[]);
+                            escalation.inherit_context(&hosterr);
                             let trap: Trap = escalation.into();
                             Err(trap)
                         }
@@ -11,12 +11,28 @@ pub(crate) struct HostFuncInfo {
     /// as.
     pub(crate) fn_str: &'static str,
 
+    /// The first ledger protocol version providing this host function, or
+    /// `None` if it has been available since the first protocol. Contracts
+    /// importing it can't be instantiated on a ledger at an earlier protocol.
+    pub(crate) min_proto: Option<u32>,
+
     /// Function that takes a wasmi::Store and _wraps_ a dispatch function
     /// for this host function, with the specific type of the dispatch function,
     /// into a Func in the Store.
     pub(crate) wrap: fn(&mut Store<Host>) -> Func,
 }
 
+// Turns the optional minimum protocol of a function description into an
+// `Option<u32>`.
+macro_rules! min_proto_helper {
+    () => {
+        None
+    };
+    ($min_proto:literal) => {
+        Some($min_proto)
+    };
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// X-macro use: static HOST_FUNCTIONS array of HostFuncInfo
 ///////////////////////////////////////////////////////////////////////////////
@@ -41,7 +57,7 @@ macro_rules! generate_host_function_infos {
                     // pattern-repetition matcher so that it will match all such
                     // descriptions.
                     $(#[$fn_attr:meta])*
-                    { $fn_id:literal, fn $func_id:ident $args:tt -> $ret:ty }
+                    { $fn_id:literal, $($min_proto:literal)?, fn $func_id:ident $args:tt -> $ret:ty }
                 )*
             }
         )*
@@ -77,6 +93,7 @@ macro_rules! generate_host_function_infos {
                     HostFuncInfo {
                         mod_str: $mod_str,
                         fn_str: $fn_id,
+                        min_proto: min_proto_helper!($($min_proto)?),
                         wrap: |store| Func::wrap(store, dispatch::$func_id),
                     },
                 )*
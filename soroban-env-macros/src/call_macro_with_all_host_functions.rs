@@ -1,5 +1,5 @@
 use itertools::iproduct;
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 use std::{
     collections::{btree_map::Entry, BTreeMap},
@@ -78,9 +78,16 @@ pub fn generate(file_lit: LitStr) -> Result<TokenStream, Error> {
 
         // Build the 'fn' sections within the 'mod'.
         let functions = m.functions.iter().map(|f| {
-            let docs = f.docs.as_deref().unwrap_or_default();
+            let docs = match f.min_supported_protocol {
+                Some(p) => format!(
+                    "{}\n\nAvailable since protocol {p}.",
+                    f.docs.as_deref().unwrap_or_default()
+                ),
+                None => f.docs.clone().unwrap_or_default(),
+            };
             let export = &f.export;
             let name = format_ident!("{}", &f.name);
+            let min_proto = f.min_supported_protocol.map(Literal::u32_unsuffixed);
 
             // Build the args for use within the 'fn'.
             let args = f.args.iter().map(|a| {
@@ -93,7 +100,7 @@ pub fn generate(file_lit: LitStr) -> Result<TokenStream, Error> {
 
             quote! {
                 #[doc = #docs]
-                { #export, fn #name(#(#args),*) -> #r#return }
+                { #export, #min_proto, fn #name(#(#args),*) -> #r#return }
             }
         });
 
@@ -120,12 +127,15 @@ pub fn generate(file_lit: LitStr) -> Result<TokenStream, Error> {
                     //
                     //  mod $mod_id:ident $mod_str:literal {
                     //     ...
-                    //     { $fn_str:literal, fn $fn_id:ident $args:tt -> $ret:ty }
+                    //     { $fn_str:literal, $($min_proto:literal)?, fn $fn_id:ident $args:tt -> $ret:ty }
                     //     ...
                     //  }
                     //
                     // Where the sub token-tree $args:tt is a normal parenthesized
-                    // argument list of comma-separated arg:type pairs
+                    // argument list of comma-separated arg:type pairs, and the
+                    // optional $min_proto is the first ledger protocol version
+                    // providing the function, if it hasn't been available since
+                    // the first one.
 
                     #(#modules)*
                 }
@@ -0,0 +1,54 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, LitStr};
+
+// Generates a static description of the env interface from env.json, as a
+// `&'static [HostModuleDescription]` expression. The description types
+// themselves are defined in soroban-env-common, which is where this is
+// expanded; this module only has to turn the parsed JSON into literals.
+pub fn generate(file_lit: LitStr) -> Result<TokenStream, Error> {
+    let root: crate::Root = crate::load_env_file(file_lit)?;
+
+    let modules = root.modules.iter().map(|m| {
+        let name = &m.name;
+        let export = &m.export;
+
+        let functions = m.functions.iter().map(|f| {
+            let name = &f.name;
+            let export = &f.export;
+            let r#return = &f.r#return;
+            let docs = f.docs.as_deref().unwrap_or_default();
+            let min_supported_protocol = match f.min_supported_protocol {
+                Some(p) => quote! { Some(#p) },
+                None => quote! { None },
+            };
+            let args = f.args.iter().map(|a| {
+                let name = &a.name;
+                let r#type = &a.r#type;
+                quote! { HostFunctionArgDescription { name: #name, r#type: #r#type } }
+            });
+            quote! {
+                HostFunctionDescription {
+                    name: #name,
+                    export: #export,
+                    args: &[#(#args),*],
+                    r#return: #r#return,
+                    docs: #docs,
+                    min_supported_protocol: #min_supported_protocol,
+                }
+            }
+        });
+
+        quote! {
+            HostModuleDescription {
+                name: #name,
+                export: #export,
+                functions: &[#(#functions),*],
+            }
+        }
+    });
+
+    Ok(quote! {
+        &[#(#modules),*]
+    })
+}
@@ -1,4 +1,5 @@
 mod call_macro_with_all_host_functions;
+mod env_interface_description;
 mod path;
 mod synth_dispatch_host_fn_tests;
 mod synth_linear_memory_tests;
@@ -99,6 +100,10 @@ pub(crate) struct Function {
     pub(crate) args: Vec<Arg>,
     pub(crate) r#return: String,
     pub(crate) docs: Option<String>,
+    // The first ledger protocol version that provides this function; absent
+    // for functions that have been available since soroban's first protocol.
+    #[serde(default)]
+    pub(crate) min_supported_protocol: Option<u32>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -143,6 +148,15 @@ pub fn generate_call_macro_with_all_host_functions(input: TokenStream) -> TokenS
     }
 }
 
+#[proc_macro]
+pub fn generate_env_interface_description(input: TokenStream) -> TokenStream {
+    let file = parse_macro_input!(input as LitStr);
+    match env_interface_description::generate(file) {
+        Ok(t) => t.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[proc_macro]
 pub fn generate_synth_wasm_expr_type(input: TokenStream) -> TokenStream {
     let file = parse_macro_input!(input as LitStr);
@@ -1,4 +1,4 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 use syn::{Error, LitStr};
 
@@ -80,6 +80,23 @@ fn dfs(edges: &BTreeMap<String, BTreeSet<String>>, ty: &String, set: &mut BTreeS
     }
 }
 
+// Returns the statement ending a test of a host function early when the ledger
+// protocol of the test host predates the function, as contracts importing it
+// then fail to link.
+fn return_if_unsupported(min_supported_protocol: Option<u32>) -> TokenStream {
+    match min_supported_protocol {
+        Some(proto) => {
+            let proto = Literal::u32_unsuffixed(proto);
+            quote! {
+                if crate::meta::get_ledger_protocol_version(crate::meta::INTERFACE_VERSION) < #proto {
+                    return Ok(());
+                }
+            }
+        }
+        None => quote! {},
+    }
+}
+
 // This requires the input to be a valid signature
 const SPECIAL_CASES: [&str; 1] = ["recover_key_ecdsa_secp256k1"];
 
@@ -241,6 +258,7 @@ pub fn generate_hostfn_call_with_wrong_types(file_lit: LitStr) -> Result<TokenSt
             let wasm_module = format_ident!("wasm_module_calling_{}", &target_fn.name);
             let test_wrong_arg_type =
                 format_ident!("dispatch_with_wrong_arg_type_{}", &target_fn.name);
+            let skip = return_if_unsupported(target_fn.min_supported_protocol);
 
             // There are three types of possibilities between two types "target" and "input":
             // 1. Compatible -- the target type is a parent (say `Val`), then passing in any child type value is fine.
@@ -293,6 +311,7 @@ pub fn generate_hostfn_call_with_wrong_types(file_lit: LitStr) -> Result<TokenSt
             quote! {
                 #[test]
                 fn #test_wrong_arg_type() -> Result<(), HostError> {
+                    #skip
                     let wasm = #wasm_module();
                     let host = observe_host!(Host::test_host_with_recording_footprint());
                     host.as_budget().reset_unlimited()?;
@@ -325,18 +344,20 @@ pub fn generate_hostfn_call_with_invalid_obj_handles(
         .iter()
         .flat_map(|m| m.functions.clone().into_iter())
         .flat_map(|f| {
-            f.args
-                .clone()
-                .into_iter()
-                .enumerate()
-                .map(move |(i, a)| ((f.name.clone(), f.args.clone()), (i, a)))
+            f.args.clone().into_iter().enumerate().map(move |(i, a)| {
+                (
+                    (f.name.clone(), f.args.clone(), f.min_supported_protocol),
+                    (i, a),
+                )
+            })
         })
-        .filter(|((f_name, _), (_, arg))| {
+        .filter(|((f_name, _, _), (_, arg))| {
             !special_case_fns.contains(f_name) && arg.r#type.ends_with("Object")
         })
         .map(|(f_info, (pos, _))| {
             let wasm_module = format_ident!("wasm_module_calling_{}", f_info.0);
             let fn_ident = format_ident!("invalid_object_handle_{}_arg_{}", f_info.0, pos);
+            let skip = return_if_unsupported(f_info.2);
 
             let args = f_info.1.iter().enumerate().map(|(i, a)| {
                 let ty_ident = format_ident!("{}", a.r#type);
@@ -362,6 +383,7 @@ pub fn generate_hostfn_call_with_invalid_obj_handles(
             quote! {
                 #[test]
                 fn #fn_ident() -> Result<(), HostError> {
+                    #skip
                     let wasm = #wasm_module();
                     let host = observe_host!(Host::test_host_with_recording_footprint());
                     host.as_budget().reset_unlimited()?;
@@ -313,6 +313,14 @@ impl FuncEmitter {
     pub fn br(&mut self, loc: u32) -> &mut Self {
         self.insn(&Instruction::Br(loc))
     }
+    /// Emit an [`Instruction::Loop`]
+    pub fn loop_block(&mut self) -> &mut Self {
+        self.insn(&Instruction::Loop(BlockType::Empty))
+    }
+    /// Emit an [`Instruction::BrIf`]
+    pub fn br_if(&mut self, loc: u32) -> &mut Self {
+        self.insn(&Instruction::BrIf(loc))
+    }
     /// Emit an [`Instruction::Call`]
     pub fn call_func(&mut self, fun: FuncRef) -> &mut Self {
         self.insn(&Instruction::Call(fun.0))